@@ -13,10 +13,12 @@ extern crate serde_json;
 extern crate serde_yaml;
 
 mod graphql;
+mod link_resolver;
 mod log;
 mod subgraph;
 
 pub use graphql::GraphQlRunner;
+pub use link_resolver::{CompositeLinkResolver, FileLinkResolver, HttpLinkResolver};
 pub use log::elastic::{elastic_logger, ElasticDrainConfig, ElasticLoggingConfig};
 pub use log::split::split_logger;
 pub use subgraph::{SubgraphInstanceManager, SubgraphProvider, SubgraphProviderWithNames};
@@ -0,0 +1,284 @@
+use reqwest;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use graph::components::link_resolver::DEFAULT_MAX_FILE_BYTES;
+use graph::prelude::*;
+use graph::serde_json;
+
+/// Resolves links to files on the local filesystem.
+///
+/// Links are either absolute paths, paths relative to the base directory of the resolver
+/// (typically the directory containing the subgraph manifest) or `file:` URLs such as
+/// `file:///abs/schema.graphql` or `file:./schema.graphql`.
+#[derive(Clone, Debug)]
+pub struct FileLinkResolver {
+    base_dir: PathBuf,
+}
+
+impl FileLinkResolver {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        FileLinkResolver {
+            base_dir: base_dir.into(),
+        }
+    }
+
+    fn path(&self, link: &Link) -> PathBuf {
+        let link = link.link.as_str();
+        let path = if link.starts_with("file://") {
+            Path::new(&link["file://".len()..])
+        } else if link.starts_with("file:") {
+            Path::new(&link["file:".len()..])
+        } else {
+            Path::new(link)
+        };
+
+        if path.is_absolute() {
+            path.to_owned()
+        } else {
+            self.base_dir.join(path)
+        }
+    }
+}
+
+impl LinkResolver for FileLinkResolver {
+    fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        let path = self.path(link);
+
+        Box::new(future::result(fs::read(&path).map_err(|e| {
+            format_err!("failed to read file `{}`: {}", path.display(), e)
+        })))
+    }
+}
+
+/// Resolves `http://` and `https://` links.
+///
+/// Responses that are larger than the maximum file size are rejected while they are
+/// downloaded, like files on IPFS.
+#[derive(Clone, Debug)]
+pub struct HttpLinkResolver {
+    client: reqwest::async::Client,
+    max_file_size: usize,
+}
+
+impl HttpLinkResolver {
+    pub fn new(max_file_size: usize) -> Self {
+        HttpLinkResolver {
+            client: reqwest::async::Client::new(),
+            max_file_size,
+        }
+    }
+}
+
+impl Default for HttpLinkResolver {
+    fn default() -> Self {
+        HttpLinkResolver::new(DEFAULT_MAX_FILE_BYTES)
+    }
+}
+
+impl LinkResolver for HttpLinkResolver {
+    fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        let url = link.link.clone();
+        let max_file_size = self.max_file_size;
+
+        Box::new(
+            self.client
+                .get(&url)
+                .send()
+                .and_then(|response| response.error_for_status())
+                .map_err(Error::from)
+                .and_then(move |response| {
+                    response.into_body().map_err(Error::from).fold(
+                        vec![],
+                        move |mut data, chunk| {
+                            if data.len() + chunk.len() > max_file_size {
+                                return Err(format_err!(
+                                    "response is larger than the maximum of {} bytes",
+                                    max_file_size
+                                ));
+                            }
+                            data.extend_from_slice(&chunk);
+                            Ok(data)
+                        },
+                    )
+                })
+                // Guard against unresponsive servers, like the IPFS resolver does.
+                .timeout(Duration::from_secs(10))
+                .map_err(move |e| {
+                    let e = e
+                        .into_inner()
+                        .unwrap_or_else(|| format_err!("request timed out"));
+                    format_err!("failed to fetch `{}`: {}", url, e)
+                }),
+        )
+    }
+}
+
+/// Dispatches links to other resolvers based on their scheme:
+///
+/// - `/ipfs/...` links are resolved through IPFS,
+/// - `http://` and `https://` links are fetched over HTTP,
+/// - everything else is treated as a path on the local filesystem.
+///
+/// Since this gives access to local files and the network, it must only be used for
+/// manifests that the node operator passed in, never for subgraphs deployed by others.
+pub struct CompositeLinkResolver<I> {
+    ipfs: Arc<I>,
+    http: HttpLinkResolver,
+    file: FileLinkResolver,
+}
+
+impl<I> CompositeLinkResolver<I>
+where
+    I: LinkResolver,
+{
+    pub fn new(ipfs: Arc<I>, file: FileLinkResolver) -> Self {
+        CompositeLinkResolver {
+            ipfs,
+            http: HttpLinkResolver::default(),
+            file,
+        }
+    }
+}
+
+impl<I> LinkResolver for CompositeLinkResolver<I>
+where
+    I: LinkResolver,
+{
+    fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        if link.link.starts_with("/ipfs/") {
+            self.ipfs.cat(link)
+        } else if link.link.starts_with("http://") || link.link.starts_with("https://") {
+            self.http.cat(link)
+        } else {
+            self.file.cat(link)
+        }
+    }
+
+    fn json_stream(
+        &self,
+        link: &Link,
+    ) -> Box<Stream<Item = serde_json::Value, Error = Error> + Send> {
+        if link.link.starts_with("/ipfs/") {
+            self.ipfs.json_stream(link)
+        } else if link.link.starts_with("http://") || link.link.starts_with("https://") {
+            self.http.json_stream(link)
+        } else {
+            self.file.json_stream(link)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    const SCHEMA: &str = "tests/subgraphs/dummy/schema.graphql";
+
+    fn link(link: impl Into<String>) -> Link {
+        Link { link: link.into() }
+    }
+
+    fn schema() -> Vec<u8> {
+        fs::read(SCHEMA).unwrap()
+    }
+
+    /// Serves a single HTTP request with the given status line and body, and returns
+    /// the URL of the server.
+    fn serve_once(status: &'static str, body: Vec<u8>) -> String {
+        format!("http://{}/file", ::graph_mock::serve_once(status, body))
+    }
+
+    fn run<F>(future: F) -> Result<F::Item, F::Error>
+    where
+        F: Future + Send + 'static,
+        F::Item: Send,
+        F::Error: Send,
+    {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    /// Stands in for IPFS by returning the link itself.
+    struct EchoResolver;
+
+    impl LinkResolver for EchoResolver {
+        fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+            Box::new(future::ok(link.link.clone().into_bytes()))
+        }
+    }
+
+    #[test]
+    fn file_resolver_resolves_absolute_and_relative_paths() {
+        let resolver = FileLinkResolver::new("tests/subgraphs/dummy");
+        let absolute = env::current_dir().unwrap().join(SCHEMA);
+
+        for link_str in &[
+            absolute.to_string_lossy().into_owned(),
+            format!("file://{}", absolute.display()),
+            "schema.graphql".to_owned(),
+            "./schema.graphql".to_owned(),
+            "file:schema.graphql".to_owned(),
+            "file:./schema.graphql".to_owned(),
+        ] {
+            assert_eq!(
+                resolver.cat(&link(link_str.as_str())).wait().unwrap(),
+                schema(),
+                "link: {}",
+                link_str
+            );
+        }
+    }
+
+    #[test]
+    fn file_resolver_fails_for_missing_files() {
+        let resolver = FileLinkResolver::new("tests/subgraphs/dummy");
+        let error = resolver.cat(&link("missing.graphql")).wait().unwrap_err();
+        assert!(error.to_string().contains("missing.graphql"));
+    }
+
+    #[test]
+    fn http_resolver_fetches_files() {
+        let url = serve_once("200 OK", schema());
+        let data = run(HttpLinkResolver::default().cat(&link(url))).unwrap();
+        assert_eq!(data, schema());
+    }
+
+    #[test]
+    fn http_resolver_fails_on_error_status() {
+        let url = serve_once("404 Not Found", vec![]);
+        assert!(run(HttpLinkResolver::default().cat(&link(url))).is_err());
+    }
+
+    #[test]
+    fn http_resolver_rejects_large_responses() {
+        let url = serve_once("200 OK", vec![b'x'; 100]);
+        let error = run(HttpLinkResolver::new(99).cat(&link(url))).unwrap_err();
+        assert!(error.to_string().contains("maximum of 99 bytes"));
+    }
+
+    #[test]
+    fn composite_resolver_dispatches_by_scheme() {
+        let resolver = Arc::new(CompositeLinkResolver::new(
+            Arc::new(EchoResolver),
+            FileLinkResolver::new("tests/subgraphs/dummy"),
+        ));
+
+        assert_eq!(
+            resolver.cat(&link("/ipfs/QmHash")).wait().unwrap(),
+            b"/ipfs/QmHash".to_vec()
+        );
+        assert_eq!(
+            resolver.cat(&link("schema.graphql")).wait().unwrap(),
+            schema()
+        );
+
+        let url = serve_once("200 OK", b"{\"a\": 1}\n2\n".to_vec());
+        let values = run(future::lazy(move || {
+            resolver.json_stream(&link(url)).collect()
+        }))
+        .unwrap();
+        assert_eq!(values, vec![json!({ "a": 1 }), json!(2)]);
+    }
+}
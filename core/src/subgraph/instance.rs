@@ -26,23 +26,35 @@ where
         // we use the same order here as in the subgraph manifest to make the
         // event processing behavior predictable
         let manifest_id = manifest.id;
+        let schema = manifest.schema;
         let (hosts, errors): (_, Vec<_>) = manifest
             .data_sources
             .into_iter()
-            .map(|d| host_builder.build(&logger, manifest_id.clone(), d))
+            .map(|d| host_builder.build(&logger, manifest_id.clone(), d, schema.clone()))
             .partition(|res| res.is_ok());
 
         if !errors.is_empty() {
-            let joined_errors = errors
+            let errors = errors
                 .into_iter()
                 .map(Result::unwrap_err)
+                .collect::<Vec<_>>();
+
+            // Loading the subgraph fails again on restart only if every data source
+            // failed to load because of its manifest entry or mapping
+            let deterministic = errors.iter().all(|e| {
+                e.downcast_ref::<SubgraphError>()
+                    .map_or(false, |e| e.deterministic)
+            });
+            let joined_errors = errors
+                .iter()
                 .map(|e| e.to_string())
                 .collect::<Vec<_>>()
                 .join(", ");
-            return Err(format_err!(
-                "Errors loading data sources: {}",
-                joined_errors
-            ));
+            return Err(SubgraphError::new(
+                format!("Errors loading data sources: {}", joined_errors),
+                deterministic,
+            )
+            .into());
         }
 
         Ok(SubgraphInstance {
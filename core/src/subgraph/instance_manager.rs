@@ -2,7 +2,9 @@ use futures::sync::mpsc::{channel, Receiver, Sender};
 use graph::components::subgraph::SubgraphProviderEvent;
 use graph::data::subgraph::schema::SubgraphEntity;
 use graph::prelude::{SubgraphInstance as SubgraphInstanceTrait, *};
+use graph::util::env::positive_number_from_env;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::RwLock;
 use std::time::Duration;
 
@@ -98,16 +100,30 @@ impl SubgraphInstanceManager {
 
                     info!(logger, "Start subgraph");
 
-                    Self::start_subgraph(
+                    let id = manifest.id.clone();
+                    let failure_ops = match Self::start_subgraph(
                         logger.clone(),
                         instances.clone(),
                         host_builder.clone(),
                         block_stream_builder.clone(),
                         store.clone(),
                         manifest,
-                    )
-                    .map_err(|err| error!(logger, "Failed to start subgraph: {}", err))
-                    .ok();
+                    ) {
+                        Ok(()) => SubgraphEntity::clear_failure_operations(&id),
+                        Err(err) => {
+                            error!(logger, "Failed to start subgraph: {}", err);
+
+                            // Errors caused by the manifest or mappings are marked as
+                            // deterministic; anything else may not happen again
+                            let error = err
+                                .downcast::<SubgraphError>()
+                                .unwrap_or_else(|e| SubgraphError::new(e.to_string(), false));
+                            SubgraphEntity::write_failure_operations(&id, error)
+                        }
+                    };
+                    if let Err(e) = store.apply_entity_operations(failure_ops, EventSource::None) {
+                        error!(logger, "Failed to update subgraph failure: {}", e);
+                    }
                 }
                 SubgraphStop(id) => {
                     info!(logger, "Stopping subgraph"; "subgraph_id" => id.to_string());
@@ -135,120 +151,173 @@ impl SubgraphInstanceManager {
         let id = manifest.id.clone();
         let id_for_block = manifest.id.clone();
         let id_for_err = manifest.id.clone();
-        let store_for_events = store.clone();
         let store_for_errors = store.clone();
 
-        // Request a block stream for this subgraph
-        let block_stream_canceler = CancelGuard::new();
-        let block_stream = block_stream_builder
-            .from_subgraph(&manifest, logger.clone())
-            .from_err()
-            .cancelable(&block_stream_canceler, || CancelableError::Cancel);
+        // Transient failures are retried this many times in a row before the subgraph is
+        // marked as failed; retry delays back off exponentially up to 30 seconds
+        let max_restarts: usize = positive_number_from_env("GRAPH_SUBGRAPH_MAX_RESTARTS", 20)?;
+
+        // Transient failures since the last block that was processed successfully
+        let consecutive_failures = Arc::new(AtomicUsize::new(0));
+        let failures_for_retry = consecutive_failures.clone();
 
         // Load the subgraph
         let instance = Arc::new(SubgraphInstance::from_manifest(
             &logger,
-            manifest,
+            manifest.clone(),
             host_builder,
         )?);
 
         // Prepare loggers for different parts of the async processing
-        let block_logger = logger.clone();
+        let stream_logger = logger.clone();
         let error_logger = logger.clone();
 
-        // Forward block stream events to the subgraph for processing
+        // Every block stream requested for this subgraph is canceled through
+        // the same guard, so stopping the subgraph also stops pending restarts
+        let block_stream_canceler = CancelGuard::new();
+        let block_stream_cancel_handle = block_stream_canceler.handle();
+
+        // Forward block stream events to the subgraph for processing; a new block
+        // stream picks up after the last block that was written to the store, so
+        // transient failures are retried by requesting a fresh stream
         tokio::spawn(
-            block_stream
-                .for_each(move |block| {
-                    let id = id_for_block.clone();
+            retry("process subgraph blocks", &logger)
+                .when(
+                    move |result: &Result<(), CancelableError<Error>>| match result {
+                        Err(CancelableError::Error(e)) if !is_deterministic(e) => {
+                            failures_for_retry.fetch_add(1, Ordering::SeqCst) < max_restarts
+                        }
+                        _ => false,
+                    },
+                )
+                .no_limit()
+                .no_timeout()
+                .run(move || {
+                    let id_for_block = id_for_block.clone();
+                    let consecutive_failures = consecutive_failures.clone();
                     let instance = instance.clone();
-                    let store = store_for_events.clone();
-                    let logger = block_logger.new(o!(
-                        "block_number" => format!("{:?}", block.block.number.unwrap()),
-                        "block_hash" => format!("{:?}", block.block.hash.unwrap())
-                    ));
-
-                    info!(logger, "Processing events from block");
-
-                    // Extract logs relevant to the subgraph
-                    let logs: Vec<_> = block
-                        .transaction_receipts
-                        .iter()
-                        .flat_map(|receipt| {
-                            receipt.logs.iter().filter(|log| instance.matches_log(&log))
-                        })
-                        .cloned()
-                        .collect();
-
-                    if logs.len() == 0 {
-                        info!(logger, "No events found in this block for this subgraph");
-                    } else if logs.len() == 1 {
-                        info!(logger, "1 event found in this block for this subgraph");
-                    } else {
-                        info!(
-                            logger,
-                            "{} events found in this block for this subgraph",
-                            logs.len()
-                        );
-                    }
+                    let store_for_events = store.clone();
+                    let block_logger = stream_logger.clone();
+                    let restart_logger = stream_logger.clone();
 
-                    // Process events one after the other, passing in entity operations
-                    // collected previously to every new event being processed
-                    let block_for_process = Arc::new(block);
-                    let block_for_transact = block_for_process.clone();
-                    let logger_for_process = logger;
-                    let logger_for_transact = logger_for_process.clone();
-                    stream::iter_ok::<_, CancelableError<Error>>(logs)
-                        .fold(vec![], move |entity_operations, log| {
-                            let logger = logger_for_process.clone();
+                    // Request a block stream for this subgraph
+                    block_stream_builder
+                        .from_subgraph(&manifest, stream_logger.clone())
+                        .from_err()
+                        .cancelable(&block_stream_cancel_handle, || CancelableError::Cancel)
+                        .for_each(move |block| {
+                            let id = id_for_block.clone();
                             let instance = instance.clone();
-                            let block = block_for_process.clone();
-
-                            let transaction = block
-                                .transaction_for_log(&log)
-                                .map(Arc::new)
-                                .ok_or_else(|| format_err!("Found no transaction for event"));
-
-                            future::result(transaction).and_then(move |transaction| {
-                                instance
-                                    .process_log(
-                                        &logger,
-                                        block,
-                                        transaction,
-                                        log,
-                                        entity_operations,
-                                    )
-                                    .map_err(|e| format_err!("Failed to process event: {}", e))
-                            })
-                        })
-                        .and_then(move |entity_operations| {
-                            let block = block_for_transact.clone();
-                            let logger = logger_for_transact.clone();
+                            let store = store_for_events.clone();
+                            let consecutive_failures = consecutive_failures.clone();
+                            let logger = block_logger.new(o!(
+                                "block_number" => format!("{:?}", block.block.number.unwrap()),
+                                "block_hash" => format!("{:?}", block.block.hash.unwrap())
+                            ));
 
-                            let block_ptr_now = EthereumBlockPointer::to_parent(&block);
-                            let block_ptr_after = EthereumBlockPointer::from(&*block);
+                            info!(logger, "Processing events from block");
 
-                            info!(
-                                logger,
-                                "Applying {} entity operation(s)",
-                                entity_operations.len()
-                            );
+                            // Extract logs relevant to the subgraph
+                            let logs: Vec<_> = block
+                                .transaction_receipts
+                                .iter()
+                                .flat_map(|receipt| {
+                                    receipt.logs.iter().filter(|log| instance.matches_log(&log))
+                                })
+                                .cloned()
+                                .collect();
+
+                            if logs.len() == 0 {
+                                info!(logger, "No events found in this block for this subgraph");
+                            } else if logs.len() == 1 {
+                                info!(logger, "1 event found in this block for this subgraph");
+                            } else {
+                                info!(
+                                    logger,
+                                    "{} events found in this block for this subgraph",
+                                    logs.len()
+                                );
+                            }
+
+                            // Process events one after the other, passing in entity operations
+                            // collected previously to every new event being processed
+                            let block_for_process = Arc::new(block);
+                            let block_for_transact = block_for_process.clone();
+                            let logger_for_process = logger;
+                            let logger_for_transact = logger_for_process.clone();
+                            stream::iter_ok::<_, CancelableError<Error>>(logs)
+                                .fold(vec![], move |entity_operations, log| {
+                                    let logger = logger_for_process.clone();
+                                    let instance = instance.clone();
+                                    let block = block_for_process.clone();
+
+                                    let transaction =
+                                        block.transaction_for_log(&log).map(Arc::new).ok_or_else(
+                                            || format_err!("Found no transaction for event"),
+                                        );
+
+                                    future::result(transaction).and_then(move |transaction| {
+                                        instance.process_log(
+                                            &logger,
+                                            block,
+                                            transaction,
+                                            log,
+                                            entity_operations,
+                                        )
+                                    })
+                                })
+                                .and_then(move |entity_operations| {
+                                    let block = block_for_transact.clone();
+                                    let logger = logger_for_transact.clone();
+
+                                    let block_ptr_now = EthereumBlockPointer::to_parent(&block);
+                                    let block_ptr_after = EthereumBlockPointer::from(&*block);
+
+                                    info!(
+                                        logger,
+                                        "Applying {} entity operation(s)",
+                                        entity_operations.len()
+                                    );
 
-                            // Transact entity operations into the store and update the
-                            // subgraph's block stream pointer
-                            future::result(store.transact_block_operations(
-                                id.clone(),
-                                block_ptr_now,
-                                block_ptr_after,
-                                entity_operations,
-                            ))
-                            .map_err(|e| {
-                                format_err!(
-                                    "Error while processing block stream for a subgraph: {}",
-                                    e
-                                )
-                            })
-                            .from_err()
+                                    // Transact entity operations into the store and update the
+                                    // subgraph's block stream pointer
+                                    future::result(store.transact_block_operations(
+                                        id.clone(),
+                                        block_ptr_now,
+                                        block_ptr_after,
+                                        entity_operations,
+                                    ))
+                                    .map_err(move |e| {
+                                        let message = format!(
+                                            "Error while processing block stream \
+                                             for a subgraph: {}",
+                                            e
+                                        );
+                                        Error::from(SubgraphError {
+                                            message,
+                                            block_ptr: Some(block_ptr_after),
+                                            handler: None,
+                                            data_source: None,
+                                            deterministic: false,
+                                        })
+                                    })
+                                    .from_err()
+                                })
+                                // A processed block starts a new run of restarts
+                                .map(move |()| consecutive_failures.store(0, Ordering::SeqCst))
+                        })
+                        .map_err(move |e| {
+                            if let CancelableError::Error(ref e) = e {
+                                if !is_deterministic(e) {
+                                    warn!(
+                                        restart_logger,
+                                        "Subgraph failed on a transient error, \
+                                         restarting from the last processed block: {}",
+                                        e
+                                    );
+                                }
+                            }
+                            e
                         })
                 })
                 .map_err(move |e| match e {
@@ -266,13 +335,17 @@ impl SubgraphInstanceManager {
                             "id" => id_for_err.to_string()
                         );
 
-                        // Set subgraph status to Failed
-                        let status_ops = SubgraphEntity::write_status_operations(
-                            &id_for_err,
-                            SubgraphStatus::Failed,
-                        );
+                        // Errors raised while processing a block already know where they
+                        // occurred; anything else comes from the block stream itself
+                        let error = e
+                            .downcast::<SubgraphError>()
+                            .unwrap_or_else(|e| SubgraphError::new(e.to_string(), false));
+
+                        // Set subgraph status to Failed and record the error
+                        let failure_ops =
+                            SubgraphEntity::write_failure_operations(&id_for_err, error);
                         if let Err(e) =
-                            store_for_errors.apply_entity_operations(status_ops, EventSource::None)
+                            store_for_errors.apply_entity_operations(failure_ops, EventSource::None)
                         {
                             error!(
                                 error_logger,
@@ -305,3 +378,11 @@ impl EventConsumer<SubgraphProviderEvent> for SubgraphInstanceManager {
         }))
     }
 }
+
+/// Whether an error would occur again if the subgraph was restarted from the
+/// same block. Only errors explicitly marked as deterministic are treated as
+/// such; everything else is assumed to be transient.
+fn is_deterministic(e: &Error) -> bool {
+    e.downcast_ref::<SubgraphError>()
+        .map_or(false, |e| e.deterministic)
+}
@@ -1,10 +1,14 @@
 use futures::sync::mpsc::{channel, Receiver, Sender};
-use std::collections::HashSet;
-use std::sync::Mutex;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::{Mutex, RwLock};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use graph::data::subgraph::schema::SubgraphEntity;
 use graph::prelude::{SubgraphProvider as SubgraphProviderTrait, *};
+use graph_runtime_wasm::provides_import;
+
+use link_resolver::{CompositeLinkResolver, FileLinkResolver};
 
 pub struct SubgraphProvider<L, S> {
     logger: Logger,
@@ -13,6 +17,8 @@ pub struct SubgraphProvider<L, S> {
     resolver: Arc<L>,
     subgraphs_running: Arc<Mutex<HashSet<SubgraphId>>>,
     store: Arc<S>,
+    /// Manifests on the local filesystem, with the resolvers for the files they link to.
+    local_manifests: Arc<RwLock<HashMap<SubgraphId, (Link, Arc<CompositeLinkResolver<L>>)>>>,
 }
 
 impl<L, S> SubgraphProvider<L, S>
@@ -31,6 +37,66 @@ where
             resolver,
             subgraphs_running: Arc::new(Mutex::new(HashSet::new())),
             store,
+            local_manifests: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Makes the subgraph whose manifest is at `path` on the local filesystem available
+    /// under the ID derived from the path. Returns that ID.
+    ///
+    /// Unlike manifests on IPFS, local manifests may link to other local files, with
+    /// relative paths being resolved against the directory of the manifest, and to files
+    /// served over HTTP. Local manifests are not persisted, so they have to be registered
+    /// again whenever the node is restarted.
+    pub fn register_local_manifest(&self, path: &Path) -> Result<SubgraphId, Error> {
+        let path = path
+            .canonicalize()
+            .map_err(|e| format_err!("failed to find subgraph manifest {:?}: {}", path, e))?;
+        let link = Link {
+            link: path.to_string_lossy().into_owned(),
+        };
+        let id = SubgraphId::for_link(&link)
+            .map_err(|()| format_err!("link does not identify a subgraph: {}", link.link))?;
+
+        // Canonical paths to files always have a parent directory
+        let file_resolver = FileLinkResolver::new(path.parent().unwrap());
+        let resolver = Arc::new(CompositeLinkResolver::new(
+            self.resolver.clone(),
+            file_resolver,
+        ));
+
+        self.local_manifests
+            .write()
+            .unwrap()
+            .insert(id.clone(), (link, resolver));
+        Ok(id)
+    }
+
+    /// Resolves the manifest of a subgraph, either from the local filesystem, if it was
+    /// registered as a local manifest, or from IPFS.
+    fn resolve(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = SubgraphManifest, Error = SubgraphProviderError> + Send> {
+        let local_manifest = self.local_manifests.read().unwrap().get(&id).cloned();
+
+        match local_manifest {
+            Some((link, resolver)) => Box::new(
+                SubgraphManifest::resolve(link, resolver)
+                    .map_err(SubgraphProviderError::ResolveError),
+            ),
+            None if id.is_local() => Box::new(future::err(
+                SubgraphProviderError::LocalManifestUnavailable(id),
+            )),
+            None => Box::new(
+                SubgraphManifest::resolve(
+                    Link {
+                        link: format!("/ipfs/{}", id),
+                    },
+                    self.resolver.clone(),
+                )
+                .map_err(SubgraphProviderError::ResolveError),
+            ),
         }
     }
 
@@ -43,6 +109,7 @@ where
             resolver: self.resolver.clone(),
             subgraphs_running: self.subgraphs_running.clone(),
             store: self.store.clone(),
+            local_manifests: self.local_manifests.clone(),
         }
     }
 }
@@ -58,54 +125,50 @@ where
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
         let self_clone = self.clone();
 
-        let link = format!("/ipfs/{}", id);
-
-        Box::new(
-            SubgraphManifest::resolve(Link { link }, self.resolver.clone())
-                .map_err(SubgraphProviderError::ResolveError)
-                .and_then(move |subgraph| -> Box<Future<Item = _, Error = _> + Send> {
-                    // If subgraph ID already in set
-                    if !self_clone
-                        .subgraphs_running
-                        .lock()
-                        .unwrap()
-                        .insert(subgraph.id.clone())
-                    {
-                        return Box::new(future::err(SubgraphProviderError::AlreadyRunning(
-                            subgraph.id,
-                        )));
-                    }
-
-                    // Place subgraph info into store
-                    let created_at = SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs();
-                    let entity_ops =
-                        SubgraphEntity::new(&subgraph, SubgraphStatus::Syncing, 0, 0, created_at)
-                            .write_operations();
+        Box::new(self.resolve(id).and_then(
+            move |subgraph| -> Box<Future<Item = _, Error = _> + Send> {
+                // If subgraph ID already in set
+                if !self_clone
+                    .subgraphs_running
+                    .lock()
+                    .unwrap()
+                    .insert(subgraph.id.clone())
+                {
+                    return Box::new(future::err(SubgraphProviderError::AlreadyRunning(
+                        subgraph.id,
+                    )));
+                }
+
+                // Place subgraph info into store
+                let created_at = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap()
+                    .as_secs();
+                let entity_ops =
+                    SubgraphEntity::new(&subgraph, SubgraphStatus::Syncing, 0, 0, created_at)
+                        .write_operations();
+                self_clone
+                    .store
+                    .apply_entity_operations(entity_ops, EventSource::None)
+                    .map_err(|err| {
+                        error!(
+                            self_clone.logger,
+                            "Failed to write subgraph to store: {}", err
+                        )
+                    })
+                    .ok();
+
+                // Send events to trigger subgraph processing
+                Box::new(
                     self_clone
-                        .store
-                        .apply_entity_operations(entity_ops, EventSource::None)
-                        .map_err(|err| {
-                            error!(
-                                self_clone.logger,
-                                "Failed to write subgraph to store: {}", err
-                            )
-                        })
-                        .ok();
-
-                    // Send events to trigger subgraph processing
-                    Box::new(
-                        self_clone
-                            .event_sink
-                            .clone()
-                            .send(SubgraphProviderEvent::SubgraphStart(subgraph))
-                            .map_err(|e| panic!("failed to forward subgraph: {}", e))
-                            .map(|_| ()),
-                    )
-                }),
-        )
+                        .event_sink
+                        .clone()
+                        .send(SubgraphProviderEvent::SubgraphStart(subgraph))
+                        .map_err(|e| panic!("failed to forward subgraph: {}", e))
+                        .map(|_| ()),
+                )
+            },
+        ))
     }
 
     fn stop(
@@ -126,6 +189,17 @@ where
             Box::new(future::err(SubgraphProviderError::NotRunning(id)))
         }
     }
+
+    fn validate(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        Box::new(self.resolve(id).and_then(|subgraph| {
+            subgraph
+                .validate(provides_import)
+                .map_err(SubgraphProviderError::ManifestValidationErrors)
+        }))
+    }
 }
 
 impl<L, S> EventProducer<SubgraphProviderEvent> for SubgraphProvider<L, S> {
@@ -1,9 +1,11 @@
 use std::collections::HashSet;
 
+use graph::data::subgraph::schema::SubgraphEntity;
 use graph::prelude::{
     SubgraphProvider as SubgraphProviderTrait,
     SubgraphProviderWithNames as SubgraphProviderWithNamesTrait, *,
 };
+use graph::web3::types::H256;
 
 pub struct SubgraphProviderWithNames<P, S> {
     logger: Logger,
@@ -16,7 +18,7 @@ pub struct SubgraphProviderWithNames<P, S> {
 impl<P, S> SubgraphProviderWithNames<P, S>
 where
     P: SubgraphProviderTrait,
-    S: SubgraphDeploymentStore,
+    S: SubgraphDeploymentStore + Store + ChainStore,
 {
     pub fn new(logger: Logger, provider: Arc<P>, store: Arc<S>, node_id: NodeId) -> Self {
         let logger = logger.new(o!("component" => "SubgraphProviderWithNames"));
@@ -34,6 +36,7 @@ where
         let logger_clone1 = self.logger.clone();
         let logger_clone2 = self.logger.clone();
         let provider = self.provider.clone();
+        let store = self.store.clone();
         let node_id = self.node_id.clone();
         let deployment_event_stream_cancel_handle =
             self.deployment_event_stream_cancel_guard.handle();
@@ -75,7 +78,12 @@ where
                     })
                     .for_each(move |deployment_event| {
                         assert_eq!(deployment_event.node_id(), &node_id);
-                        handle_deployment_event(deployment_event, provider.clone(), &logger_clone1)
+                        handle_deployment_event(
+                            deployment_event,
+                            provider.clone(),
+                            store.clone(),
+                            &logger_clone1,
+                        )
                     })
                     .map_err(move |e| match e {
                         CancelableError::Cancel => {}
@@ -91,18 +99,38 @@ where
     }
 
     fn start_deployed_subgraphs(&self) -> impl Future<Item = (), Error = Error> {
+        let logger = self.logger.clone();
         let provider = self.provider.clone();
 
-        future::result(self.store.read_by_node_id(self.node_id.clone())).and_then(
-            move |names_and_subgraph_ids| {
+        future::result(self.store.read_versions_by_node_id(self.node_id.clone())).and_then(
+            move |versions| {
                 let provider = provider.clone();
 
-                let subgraph_ids = names_and_subgraph_ids
+                // Paused versions stay deployed but are not indexed
+                let subgraph_ids = versions
                     .into_iter()
-                    .map(|(_name, id)| id)
+                    .filter(|version| version.is_indexed())
+                    .map(|version| version.subgraph_id)
                     .collect::<HashSet<SubgraphId>>();
 
-                stream::iter_ok(subgraph_ids).for_each(move |id| provider.start(id).from_err())
+                stream::iter_ok(subgraph_ids).for_each(move |id| {
+                    let logger = logger.clone();
+
+                    provider.start(id).then(move |result| match result {
+                        // Local manifests are forgotten when the node restarts, unless they
+                        // are passed in again; this must not keep other subgraphs from starting
+                        Err(SubgraphProviderError::LocalManifestUnavailable(id)) => {
+                            warn!(
+                                logger,
+                                "Not starting subgraph deployed from a local manifest \
+                                 that is no longer available";
+                                "subgraph_id" => id.to_string()
+                            );
+                            Ok(())
+                        }
+                        result => result.map_err(Error::from),
+                    })
+                })
             },
         )
     }
@@ -111,7 +139,7 @@ where
 impl<P, S> SubgraphProviderWithNamesTrait for SubgraphProviderWithNames<P, S>
 where
     P: SubgraphProviderTrait,
-    S: SubgraphDeploymentStore,
+    S: SubgraphDeploymentStore + Store + ChainStore,
 {
     fn deploy(
         &self,
@@ -126,7 +154,13 @@ where
             id.to_string()
         );
 
-        Box::new(future::result(self.store.write(name, id, node_id)).from_err())
+        // Refuse to deploy subgraphs that would fail as soon as they are indexed
+        let store = self.store.clone();
+        Box::new(
+            self.provider
+                .validate(id.clone())
+                .and_then(move |()| future::result(store.write(name, id, node_id)).from_err()),
+        )
     }
 
     fn remove(
@@ -142,28 +176,258 @@ where
         Box::new(
             future::result(self.store.remove(name.clone()))
                 .from_err()
-                .and_then(move |did_remove| {
-                    if did_remove {
+                .and_then(move |did_remove| name_found(did_remove, name)),
+        )
+    }
+
+    fn list(&self) -> Result<Vec<(SubgraphDeploymentName, SubgraphId)>, Error> {
+        self.store.read_by_node_id(self.node_id.clone())
+    }
+
+    fn reassign(
+        &self,
+        name: SubgraphDeploymentName,
+        node_id: NodeId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        debug!(
+            self.logger,
+            "Reassigning subgraph name to node: name = {:?}, node ID = {:?}",
+            name.to_string(),
+            node_id.to_string()
+        );
+
+        Box::new(
+            future::result(self.store.reassign(name.clone(), node_id))
+                .from_err()
+                .and_then(move |did_update| name_found(did_update, name)),
+        )
+    }
+
+    fn pause(
+        &self,
+        name: SubgraphDeploymentName,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        debug!(self.logger, "Pausing subgraph name: {:?}", name.to_string());
+
+        Box::new(
+            future::result(self.store.set_paused(name.clone(), true))
+                .from_err()
+                .and_then(move |did_update| name_found(did_update, name)),
+        )
+    }
+
+    fn resume(
+        &self,
+        name: SubgraphDeploymentName,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        debug!(
+            self.logger,
+            "Resuming subgraph name: {:?}",
+            name.to_string()
+        );
+
+        Box::new(
+            future::result(self.store.set_paused(name.clone(), false))
+                .from_err()
+                .and_then(move |did_update| name_found(did_update, name)),
+        )
+    }
+
+    fn rewind(
+        &self,
+        name: SubgraphDeploymentName,
+        block_hash: H256,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        let logger = self.logger.new(o!("name" => name.to_string()));
+        let provider = self.provider.clone();
+        let store = self.store.clone();
+        let node_id = self.node_id.clone();
+
+        info!(logger, "Rewinding subgraph"; "block_hash" => format!("{:?}", block_hash));
+
+        // Find the current version and the blocks to revert
+        let version = match self.store.read_versions(name.clone()).map(|versions| {
+            versions
+                .into_iter()
+                .find(|version| version.status == SubgraphVersionStatus::Current)
+        }) {
+            Ok(Some(version)) => version,
+            Ok(None) => {
+                return Box::new(future::err(SubgraphProviderError::NameNotFound(
+                    name.to_string(),
+                )))
+            }
+            Err(e) => return Box::new(future::err(SubgraphProviderError::from(e))),
+        };
+        let block_ptrs = match blocks_to_revert(&*self.store, &version.subgraph_id, block_hash) {
+            Ok(block_ptrs) => block_ptrs,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        // Pause the subgraph so that indexing does not continue while blocks are reverted.
+        // If the subgraph is indexed on this node, stop it right away; other nodes stop it
+        // when they receive the deployment event, and any block they process in the meantime
+        // will make reverting fail because the subgraph block pointer changed.
+        let stop = if let Err(e) = self.store.set_paused(name.clone(), true) {
+            Box::new(future::err(SubgraphProviderError::from(e)))
+                as Box<Future<Item = (), Error = SubgraphProviderError> + Send>
+        } else if version.node_id == node_id && !version.paused {
+            Box::new(
+                provider
+                    .stop(version.subgraph_id.clone())
+                    .then(|result| match result {
+                        Err(SubgraphProviderError::NotRunning(_)) => Ok(()),
+                        result => result,
+                    }),
+            )
+        } else {
+            Box::new(future::ok(()))
+        };
+
+        Box::new(
+            stop.and_then(move |()| -> Result<(), SubgraphProviderError> {
+                let result = block_ptrs
+                    .into_iter()
+                    .try_for_each(|(block_ptr_from, block_ptr_to)| {
+                        debug!(logger, "Reverting block"; "block_number" => block_ptr_from.number);
+                        store.revert_block_operations(
+                            version.subgraph_id.clone(),
+                            block_ptr_from,
+                            block_ptr_to,
+                        )
+                    })
+                    .and_then(|()| {
+                        // The subgraph has to catch up with the chain again
+                        let mut ops = SubgraphEntity::write_status_operations(
+                            &version.subgraph_id,
+                            SubgraphStatus::Syncing,
+                        );
+                        let processed = store.block_ptr(version.subgraph_id.clone())?.number;
+                        let total = store
+                            .chain_head_ptr()?
+                            .map_or(processed, |head_ptr| head_ptr.number);
+                        ops.append(&mut SubgraphEntity::write_ethereum_block_counts_operations(
+                            &version.subgraph_id,
+                            processed,
+                            total,
+                        ));
+                        store.apply_entity_operations(ops, EventSource::None)
+                    });
+
+                // Resume indexing unless the subgraph was already paused, even if reverting failed
+                if !version.paused {
+                    store.set_paused(name, false)?;
+                }
+
+                result?;
+                info!(logger, "Rewound subgraph");
+                Ok(())
+            }),
+        )
+    }
+
+    fn versions(&self, name: SubgraphDeploymentName) -> Result<Vec<SubgraphVersion>, Error> {
+        self.store.read_versions(name)
+    }
+
+    fn rollback(
+        &self,
+        name: SubgraphDeploymentName,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static> {
+        debug!(
+            self.logger,
+            "Rolling back subgraph name to previous version: name = {:?}, subgraph ID = {:?}",
+            name.to_string(),
+            id.to_string()
+        );
+
+        Box::new(
+            future::result(self.store.rollback(name.clone(), id.clone()))
+                .from_err()
+                .and_then(move |did_rollback| {
+                    if did_rollback {
                         Ok(())
                     } else {
-                        Err(SubgraphProviderError::NameNotFound(name.to_string()))
+                        Err(SubgraphProviderError::VersionNotFound(name.to_string(), id))
                     }
                 }),
         )
     }
+}
 
-    fn list(&self) -> Result<Vec<(SubgraphDeploymentName, SubgraphId)>, Error> {
-        self.store.read_by_node_id(self.node_id.clone())
+/// Returns the `(from, to)` block pointer pairs that need to be reverted to move the block
+/// pointer of a subgraph back to `block_hash`, starting at the latest processed block.
+fn blocks_to_revert<S>(
+    store: &S,
+    subgraph_id: &SubgraphId,
+    block_hash: H256,
+) -> Result<Vec<(EthereumBlockPointer, EthereumBlockPointer)>, SubgraphProviderError>
+where
+    S: Store + ChainStore,
+{
+    let target = store.block(block_hash)?.ok_or_else(|| {
+        SubgraphProviderError::InvalidRewindTarget(format!(
+            "block {:?} is not in the chain store",
+            block_hash
+        ))
+    })?;
+    let target_number = target
+        .block
+        .number
+        .ok_or_else(|| format_err!("block {:?} in the chain store has no number", block_hash))?
+        .as_u64();
+
+    let mut block_ptr = store.block_ptr(subgraph_id.clone())?;
+    if target_number > block_ptr.number {
+        return Err(SubgraphProviderError::InvalidRewindTarget(format!(
+            "block #{} is ahead of the latest block processed by the subgraph (#{})",
+            target_number, block_ptr.number
+        )));
+    }
+
+    // Walk back from the subgraph block pointer to the target block
+    let mut block_ptrs = vec![];
+    while block_ptr.number > target_number {
+        let block = store.block(block_ptr.hash)?.ok_or_else(|| {
+            SubgraphProviderError::InvalidRewindTarget(format!(
+                "ancestor block {:?} is not in the chain store",
+                block_ptr.hash
+            ))
+        })?;
+        let parent_ptr =
+            EthereumBlockPointer::from((block.block.parent_hash, block_ptr.number - 1));
+        block_ptrs.push((block_ptr, parent_ptr));
+        block_ptr = parent_ptr;
+    }
+
+    if block_ptr.hash != block_hash {
+        return Err(SubgraphProviderError::InvalidRewindTarget(format!(
+            "block {:?} is not an ancestor of the latest block processed by the subgraph",
+            block_hash
+        )));
+    }
+
+    Ok(block_ptrs)
+}
+
+fn name_found(found: bool, name: SubgraphDeploymentName) -> Result<(), SubgraphProviderError> {
+    if found {
+        Ok(())
+    } else {
+        Err(SubgraphProviderError::NameNotFound(name.to_string()))
     }
 }
 
-fn handle_deployment_event<P>(
+fn handle_deployment_event<P, S>(
     event: DeploymentEvent,
     provider: Arc<P>,
+    store: Arc<S>,
     logger: &Logger,
 ) -> Box<Future<Item = (), Error = CancelableError<SubgraphProviderError>> + Send>
 where
     P: SubgraphProviderTrait,
+    S: SubgraphDeploymentStore,
 {
     let logger = logger.to_owned();
 
@@ -200,16 +464,33 @@ where
         DeploymentEvent::Remove {
             deployment_name: _,
             subgraph_id,
-            node_id: _,
-        } => Box::new(
-            provider
-                .stop(subgraph_id)
-                .then(|result| match result {
-                    Ok(()) => Ok(()),
-                    Err(SubgraphProviderError::NotRunning(_)) => Ok(()),
-                    Err(e) => Err(e),
-                })
-                .map_err(CancelableError::Error),
-        ),
+            node_id,
+        } => {
+            // The subgraph keeps running while it is deployed under another name
+            let still_indexed = match store.read_versions_by_node_id(node_id) {
+                Ok(versions) => versions
+                    .iter()
+                    .any(|version| version.subgraph_id == subgraph_id && version.is_indexed()),
+                Err(e) => {
+                    return Box::new(future::err(CancelableError::Error(
+                        SubgraphProviderError::Unknown(e),
+                    )))
+                }
+            };
+            if still_indexed {
+                return Box::new(future::ok(()));
+            }
+
+            Box::new(
+                provider
+                    .stop(subgraph_id)
+                    .then(|result| match result {
+                        Ok(()) => Ok(()),
+                        Err(SubgraphProviderError::NotRunning(_)) => Ok(()),
+                        Err(e) => Err(e),
+                    })
+                    .map_err(CancelableError::Error),
+            )
+        }
     }
 }
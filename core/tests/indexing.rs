@@ -0,0 +1,498 @@
+extern crate graph;
+extern crate graph_core;
+extern crate graph_datasource_ethereum;
+extern crate graph_store_postgres;
+extern crate jsonrpc_core;
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use graph::prelude::EthereumAdapter as EthereumAdapterTrait;
+use graph::prelude::*;
+use graph::serde_json;
+use graph::util::ethereum::string_to_h256;
+use graph::web3;
+use graph::web3::helpers::build_request;
+use graph::web3::types::*;
+use graph::web3::{BatchTransport, RequestId, Transport};
+use graph_core::{FileLinkResolver, SubgraphInstanceManager};
+use graph_datasource_ethereum::{
+    BlockStreamBuilder, EthereumAdapter, EthereumFixture, RecordingTransport, ReplayTransport,
+};
+use graph_store_postgres::{Store as DieselStore, StoreConfig};
+
+/// Helper function to ensure and obtain the Postgres URL to use for testing.
+fn postgres_test_url() -> String {
+    std::env::var_os("THEGRAPH_STORE_POSTGRES_DIESEL_URL")
+        .expect("The THEGRAPH_STORE_POSTGRES_DIESEL_URL environment variable is not set")
+        .into_string()
+        .unwrap()
+}
+
+fn block_hash(number: u64) -> H256 {
+    H256::from(0x1dec_0000 + number)
+}
+
+fn transaction_hash(number: u64) -> H256 {
+    H256::from(0x1dec_1000 + number)
+}
+
+/// Builds a chain of four blocks in which every block but the genesis block contains a
+/// single transaction. The transaction in block 2 emits an event from `contract`, the one
+/// in block 1 emits the same event from a different contract.
+fn test_chain(contract: H160, event_signature: H256) -> Vec<EthereumBlock> {
+    (0..4)
+        .map(|number| {
+            let transaction = Transaction {
+                hash: transaction_hash(number),
+                nonce: U256::from(number),
+                block_hash: Some(block_hash(number)),
+                block_number: Some(U256::from(number)),
+                transaction_index: Some(U128::from(0)),
+                from: H160::default(),
+                to: Some(contract),
+                value: U256::from(0),
+                gas_price: U256::from(0),
+                gas: U256::from(0),
+                input: Bytes(vec![]),
+            };
+
+            let logs = match number {
+                1 => vec![H160::from(1)],
+                2 => vec![contract],
+                _ => vec![],
+            }
+            .into_iter()
+            .map(|address| Log {
+                address,
+                topics: vec![event_signature],
+                data: Bytes(vec![]),
+                block_hash: Some(block_hash(number)),
+                block_number: Some(U256::from(number)),
+                transaction_hash: Some(transaction_hash(number)),
+                transaction_index: Some(U128::from(0)),
+                log_index: Some(U256::from(0)),
+                transaction_log_index: Some(U256::from(0)),
+                log_type: None,
+                removed: None,
+            })
+            .collect::<Vec<_>>();
+
+            let receipt = serde_json::Value::Object(
+                vec![
+                    ("transactionHash", serde_json::to_value(transaction.hash)),
+                    ("transactionIndex", serde_json::to_value(U128::from(0))),
+                    ("blockHash", serde_json::to_value(block_hash(number))),
+                    ("blockNumber", serde_json::to_value(U256::from(number))),
+                    ("cumulativeGasUsed", serde_json::to_value(U256::from(0))),
+                    ("gasUsed", serde_json::to_value(U256::from(0))),
+                    ("logs", serde_json::to_value(logs)),
+                ]
+                .into_iter()
+                .map(|(key, value)| (key.to_owned(), value.unwrap()))
+                .collect(),
+            );
+
+            EthereumBlock {
+                block: Block {
+                    hash: Some(block_hash(number)),
+                    parent_hash: if number == 0 {
+                        H256::default()
+                    } else {
+                        block_hash(number - 1)
+                    },
+                    uncles_hash: H256::default(),
+                    author: H160::default(),
+                    state_root: H256::default(),
+                    transactions_root: H256::default(),
+                    receipts_root: H256::default(),
+                    number: Some(U128::from(number)),
+                    gas_used: U256::from(0),
+                    gas_limit: U256::from(1000),
+                    extra_data: Bytes(vec![]),
+                    logs_bloom: H2048::default(),
+                    timestamp: U256::from(100000 + number),
+                    difficulty: U256::from(10),
+                    total_difficulty: U256::from(10 * (number + 1)),
+                    seal_fields: vec![],
+                    uncles: vec![],
+                    transactions: if number == 0 {
+                        vec![]
+                    } else {
+                        vec![transaction]
+                    },
+                    size: Some(U256::from(500)),
+                },
+                transaction_receipts: if number == 0 {
+                    vec![]
+                } else {
+                    vec![serde_json::from_value(receipt).unwrap()]
+                },
+            }
+        })
+        .collect()
+}
+
+/// Answers requests for blocks, receipts and logs the way an Ethereum node with the
+/// given blocks would.
+#[derive(Clone)]
+struct ChainTransport {
+    blocks: Arc<Vec<EthereumBlock>>,
+    next_id: Arc<AtomicUsize>,
+    pending: Arc<Mutex<HashMap<RequestId, (String, Vec<serde_json::Value>)>>>,
+}
+
+impl ChainTransport {
+    fn new(blocks: Vec<EthereumBlock>) -> Self {
+        ChainTransport {
+            blocks: Arc::new(blocks),
+            next_id: Arc::new(AtomicUsize::new(1)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn respond(&self, id: RequestId) -> serde_json::Value {
+        let (method, params) = self.pending.lock().unwrap().remove(&id).unwrap();
+
+        let number = |value: &serde_json::Value| {
+            u64::from_str_radix(value.as_str().unwrap().trim_left_matches("0x"), 16).unwrap()
+        };
+        let block_json = |block: &EthereumBlock| {
+            let mut value = serde_json::to_value(&block.block).unwrap();
+            if params[1] == serde_json::Value::Bool(false) {
+                value["transactions"] = serde_json::to_value(
+                    block
+                        .block
+                        .transactions
+                        .iter()
+                        .map(|transaction| transaction.hash)
+                        .collect::<Vec<_>>(),
+                )
+                .unwrap();
+            }
+            value
+        };
+
+        match method.as_str() {
+            "eth_getBlockByHash" => {
+                let hash: H256 = serde_json::from_value(params[0].clone()).unwrap();
+                self.blocks
+                    .iter()
+                    .find(|block| block.block.hash == Some(hash))
+                    .map_or(serde_json::Value::Null, block_json)
+            }
+            "eth_getBlockByNumber" => self
+                .blocks
+                .iter()
+                .find(|block| block.block.number == Some(U128::from(number(&params[0]))))
+                .map_or(serde_json::Value::Null, block_json),
+            "eth_getTransactionReceipt" => {
+                let hash: H256 = serde_json::from_value(params[0].clone()).unwrap();
+                self.blocks
+                    .iter()
+                    .flat_map(|block| block.transaction_receipts.iter())
+                    .find(|receipt| receipt.transaction_hash == hash)
+                    .map_or(serde_json::Value::Null, |receipt| {
+                        serde_json::to_value(receipt).unwrap()
+                    })
+            }
+            "eth_getLogs" => {
+                let from = number(&params[0]["fromBlock"]);
+                let to = number(&params[0]["toBlock"]);
+                serde_json::to_value(
+                    self.blocks
+                        .iter()
+                        .filter(|block| {
+                            let number = block.block.number.unwrap().as_u64();
+                            number >= from && number <= to
+                        })
+                        .flat_map(|block| block.transaction_receipts.iter())
+                        .flat_map(|receipt| receipt.logs.iter())
+                        .collect::<Vec<_>>(),
+                )
+                .unwrap()
+            }
+            _ => panic!("unexpected request: {}({:?})", method, params),
+        }
+    }
+}
+
+impl Transport for ChainTransport {
+    type Out = Box<Future<Item = serde_json::Value, Error = web3::error::Error> + Send>;
+
+    fn prepare(
+        &self,
+        method: &str,
+        params: Vec<serde_json::Value>,
+    ) -> (RequestId, jsonrpc_core::Call) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(id, (method.to_owned(), params.clone()));
+        (id, build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, _: jsonrpc_core::Call) -> Self::Out {
+        Box::new(future::ok(self.respond(id)))
+    }
+}
+
+impl BatchTransport for ChainTransport {
+    type Batch = Box<
+        Future<
+                Item = Vec<Result<serde_json::Value, web3::error::Error>>,
+                Error = web3::error::Error,
+            > + Send,
+    >;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, jsonrpc_core::Call)>,
+    {
+        Box::new(future::ok(
+            requests
+                .into_iter()
+                .map(|(id, _)| Ok(self.respond(id)))
+                .collect(),
+        ))
+    }
+}
+
+/// Stores an `ExampleEntity` for every event from the data source's contract, identified
+/// by the hash of the transaction that emitted the event.
+#[derive(Debug)]
+struct ExampleRuntimeHost {
+    subgraph_id: SubgraphId,
+    contract: H160,
+}
+
+impl RuntimeHost for ExampleRuntimeHost {
+    fn matches_log(&self, log: &Log) -> bool {
+        log.address == self.contract
+    }
+
+    fn process_log(
+        &self,
+        _: &Logger,
+        block: Arc<EthereumBlock>,
+        transaction: Arc<Transaction>,
+        _: Arc<Log>,
+        mut entity_operations: Vec<EntityOperation>,
+    ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+        entity_operations.push(EntityOperation::Set {
+            key: EntityKey {
+                subgraph_id: self.subgraph_id.clone(),
+                entity_type: "ExampleEntity".to_owned(),
+                entity_id: format!("{:x}", transaction.hash),
+            },
+            data: Entity::from(vec![(
+                "exampleAttribute",
+                Value::from(format!("block {}", block.block.number.unwrap())),
+            )]),
+        });
+        Box::new(future::ok(entity_operations))
+    }
+}
+
+#[derive(Clone)]
+struct ExampleRuntimeHostBuilder {}
+
+impl RuntimeHostBuilder for ExampleRuntimeHostBuilder {
+    type Host = ExampleRuntimeHost;
+
+    fn build(
+        &self,
+        _: &Logger,
+        subgraph_id: SubgraphId,
+        data_source: DataSource,
+        _: Schema,
+    ) -> Result<Self::Host, Error> {
+        Ok(ExampleRuntimeHost {
+            subgraph_id,
+            contract: data_source.source.address,
+        })
+    }
+}
+
+/// Creates a store whose chain store holds the blocks of the Ethereum network `network_name`.
+fn create_store(logger: &Logger, network_name: &str) -> Arc<DieselStore> {
+    Arc::new(DieselStore::new(
+        StoreConfig {
+            postgres_url: postgres_test_url(),
+            network_name: network_name.to_owned(),
+        },
+        logger,
+        EthereumNetworkIdentifier {
+            net_version: "graph test suite".to_owned(),
+            genesis_block_hash: block_hash(0),
+        },
+    ))
+}
+
+/// Indexes the subgraph from scratch with a `SubgraphInstanceManager`, using the blocks
+/// in the chain store of `store` and the Ethereum node behind `transport`. Returns once
+/// the subgraph has caught up with the chain head.
+fn index_subgraph<T>(
+    runtime: &mut tokio::runtime::Runtime,
+    logger: &Logger,
+    store: Arc<DieselStore>,
+    transport: T,
+    manifest: SubgraphManifest,
+) where
+    T: BatchTransport + Send + Sync + 'static,
+    T::Batch: Send,
+    T::Out: Send,
+{
+    let id = manifest.id.clone();
+    store
+        .reset_subgraph(id.clone(), store.genesis_block_ptr().unwrap())
+        .unwrap();
+
+    // Don't wait for reorgs or confirmations, so that the subgraph is indexed through
+    // the RPC calls of the Ethereum adapter rather than from the chain store alone
+    let block_stream_builder = BlockStreamBuilder::new(
+        store.clone(),
+        store.clone(),
+        Arc::new(EthereumAdapter::new(transport)),
+        0,
+        0,
+    );
+    let manager = SubgraphInstanceManager::new(
+        logger,
+        store.clone(),
+        ExampleRuntimeHostBuilder {},
+        block_stream_builder,
+        None,
+    );
+
+    let head_ptr = store.chain_head_ptr().unwrap().unwrap();
+    let start_id = id.clone();
+    let manager = runtime
+        .block_on(future::lazy(move || {
+            manager
+                .event_sink()
+                .send(SubgraphProviderEvent::SubgraphStart(manifest))
+                .map(move |_| manager)
+        }))
+        .unwrap();
+
+    let start_time = Instant::now();
+    while store.block_ptr(start_id.clone()).unwrap() != head_ptr {
+        if start_time.elapsed() > Duration::from_secs(30) {
+            panic!(
+                "Subgraph did not catch up with the chain head, got stuck at {:?}",
+                store.block_ptr(start_id.clone()).unwrap()
+            );
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+
+    runtime
+        .block_on(
+            manager
+                .event_sink()
+                .send(SubgraphProviderEvent::SubgraphStop(id)),
+        )
+        .unwrap();
+}
+
+fn assert_indexed(store: &DieselStore, id: &SubgraphId) {
+    assert_eq!(
+        store.block_ptr(id.clone()).unwrap(),
+        (block_hash(3), 3u64).into()
+    );
+    assert_eq!(store.count_entities(id.clone()).unwrap(), 1);
+
+    let entity = store
+        .get(EntityKey {
+            subgraph_id: id.clone(),
+            entity_type: "ExampleEntity".to_owned(),
+            entity_id: format!("{:x}", transaction_hash(2)),
+        })
+        .unwrap()
+        .expect("event in block 2 was not indexed");
+    assert_eq!(
+        entity.get("exampleAttribute"),
+        Some(&Value::from("block 2"))
+    );
+}
+
+#[test]
+fn index_subgraph_from_recorded_chain() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let resolver = Arc::new(FileLinkResolver::new("tests/subgraphs/replay"));
+    let manifest = runtime
+        .block_on(SubgraphManifest::resolve(
+            Link {
+                link: "replay.yaml".to_owned(),
+            },
+            resolver,
+        ))
+        .unwrap();
+    let id = manifest.id.clone();
+
+    let data_source = &manifest.data_sources[0];
+    let chain = test_chain(
+        data_source.source.address,
+        string_to_h256(&data_source.mapping.event_handlers[0].event),
+    );
+
+    // Record the blocks like the block ingestor would fetch them and index the
+    // subgraph while recording everything else the Ethereum node is asked
+    let recording_transport = RecordingTransport::new(ChainTransport::new(chain));
+    let record_logger = logger.clone();
+    let (record_store, recording_transport) = runtime
+        .block_on(future::lazy(move || {
+            let store = create_store(&record_logger, "replay_test_recording");
+            let adapter = EthereumAdapter::new(recording_transport.clone());
+            stream::iter_ok::<_, Error>((0..4).map(block_hash))
+                .and_then(move |hash| adapter.block_by_hash(&record_logger, hash))
+                .collect()
+                .and_then(move |_| {
+                    recording_transport
+                        .fixture()
+                        .feed_chain_store(store.clone(), 3)
+                        .map(move |()| (store, recording_transport))
+                })
+        }))
+        .unwrap();
+    index_subgraph(
+        &mut runtime,
+        &logger,
+        record_store.clone(),
+        recording_transport.clone(),
+        manifest.clone(),
+    );
+    assert_indexed(&record_store, &id);
+
+    let path = env::temp_dir().join("graph-node-indexing-test-fixture.json");
+    recording_transport.save(&path).unwrap();
+
+    // Index the subgraph again from the fixture alone, on a fresh chain store
+    let fixture = EthereumFixture::load(&path).unwrap();
+    let replay_logger = logger.clone();
+    let replay_fixture = fixture.clone();
+    let replay_store = runtime
+        .block_on(future::lazy(move || {
+            let store = create_store(&replay_logger, "replay_test_replaying");
+            replay_fixture
+                .feed_chain_store(store.clone(), 3)
+                .map(move |()| store)
+        }))
+        .unwrap();
+    index_subgraph(
+        &mut runtime,
+        &logger,
+        replay_store.clone(),
+        ReplayTransport::new(fixture),
+        manifest,
+    );
+    assert_indexed(&replay_store, &id);
+}
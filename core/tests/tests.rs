@@ -9,11 +9,15 @@ use ipfs_api::IpfsClient;
 use walkdir::WalkDir;
 
 use graph::components::ethereum::*;
+use graph::data::subgraph::schema::{
+    SUBGRAPHS_ID, SUBGRAPH_ENTITY_TYPENAME, SUBGRAPH_ERROR_ENTITY_TYPENAME,
+};
 use graph::prelude::*;
 use graph::web3::types::*;
 use graph_core::SubgraphInstanceManager;
-use graph_mock::{FakeStore, MockBlockStreamBuilder, MockStore};
+use graph_mock::{chain_block, FakeStore, MockBlockStreamBuilder, MockStore};
 use std::collections::HashSet;
+use std::env;
 use std::fs::read_to_string;
 use std::io::Cursor;
 use std::sync::Mutex;
@@ -112,6 +116,7 @@ fn multiple_data_sources_per_subgraph() {
             _: &Logger,
             _: SubgraphId,
             data_source: DataSource,
+            _: Schema,
         ) -> Result<Self::Host, Error> {
             self.data_sources_received.lock().unwrap().push(data_source);
 
@@ -431,3 +436,489 @@ fn subgraph_list() {
         }))
         .unwrap();
 }
+
+#[test]
+fn subgraph_pause_and_resume() {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(future::lazy(|| {
+            let logger = Logger::root(slog::Discard, o!());
+            let resolver = Arc::new(IpfsClient::default());
+            let store = Arc::new(MockStore::new(vec![]));
+            let mut provider =
+                graph_core::SubgraphProvider::new(logger.clone(), resolver.clone(), store.clone());
+            let provider_events = provider.take_event_stream().unwrap();
+            let node_id = NodeId::new("test").unwrap();
+
+            let named_provider = Arc::new(graph_core::SubgraphProviderWithNames::new(
+                logger.clone(),
+                Arc::new(provider),
+                store,
+                node_id.clone(),
+            ));
+            let subgraph_name = SubgraphDeploymentName::new("subgraph").unwrap();
+
+            // Gives some time for events to be picked up.
+            let delay = || {
+                Delay::new(Instant::now() + Duration::from_secs(2))
+                    .map_err(|_| panic!("time error"))
+            };
+
+            let named_provider_clone1 = named_provider.clone();
+            let named_provider_clone2 = named_provider.clone();
+            let named_provider_clone3 = named_provider.clone();
+            let named_provider_clone4 = named_provider.clone();
+            let subgraph_name_clone1 = subgraph_name.clone();
+            let subgraph_name_clone2 = subgraph_name.clone();
+            let subgraph_name_clone3 = subgraph_name;
+
+            named_provider
+                .start()
+                .and_then(move |()| add_subgraph_to_ipfs(resolver, "dummy"))
+                .and_then(move |subgraph_link| {
+                    let subgraph_id =
+                        SubgraphId::new(subgraph_link.trim_left_matches("/ipfs/")).unwrap();
+
+                    named_provider_clone1
+                        .deploy(subgraph_name_clone1, subgraph_id.clone(), node_id)
+                        .and_then(move |()| delay())
+                        .and_then(move |()| named_provider_clone2.pause(subgraph_name_clone2))
+                        .and_then(move |()| delay())
+                        .and_then(move |()| {
+                            // Paused subgraphs are still listed
+                            assert_eq!(
+                                named_provider_clone3.list().unwrap(),
+                                vec![(subgraph_name_clone3.clone(), subgraph_id.clone())]
+                            );
+
+                            named_provider_clone3.resume(subgraph_name_clone3)
+                        })
+                        .and_then(move |()| {
+                            // Pausing a name that is not deployed is an error
+                            named_provider_clone4
+                                .pause(SubgraphDeploymentName::new("unknown").unwrap())
+                                .then(|result| {
+                                    assert!(result.is_err());
+                                    Ok(())
+                                })
+                        })
+                        .and_then(move |()| {
+                            provider_events
+                                .take(3)
+                                .collect()
+                                .then(|result| Ok(result.unwrap()))
+                        })
+                        .map(move |provider_events| {
+                            // Keep named provider alive until after events have been collected
+                            let _ = named_provider;
+
+                            // Pausing stops the subgraph, resuming starts it again
+                            assert!(added_subgraph_id_eq(&provider_events[0], &subgraph_id));
+                            assert_eq!(
+                                provider_events[1],
+                                SubgraphProviderEvent::SubgraphStop(subgraph_id.clone())
+                            );
+                            assert!(added_subgraph_id_eq(&provider_events[2], &subgraph_id));
+                        })
+                        .from_err()
+                })
+                .then(|result: Result<(), Error>| -> Result<(), ()> { Ok(result.unwrap()) })
+        }))
+        .unwrap();
+}
+
+#[test]
+fn subgraph_rewind() {
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    runtime
+        .block_on(future::lazy(|| -> Result<(), ()> {
+            let logger = Logger::root(slog::Discard, o!());
+            let store = Arc::new(MockStore::new(vec![]));
+            let resolver = Arc::new(IpfsClient::default());
+            let provider =
+                graph_core::SubgraphProvider::new(logger.clone(), resolver, store.clone());
+            let node_id = NodeId::new("test").unwrap();
+            let named_provider = graph_core::SubgraphProviderWithNames::new(
+                logger,
+                Arc::new(provider),
+                store.clone(),
+                node_id.clone(),
+            );
+
+            // The subgraph has processed blocks 0 to 4 of a chain of 6 blocks with a fork
+            // at block 2
+            let name = SubgraphDeploymentName::new("subgraph").unwrap();
+            let subgraph_id = SubgraphId::new("rewound").unwrap();
+            let blocks = (0..6)
+                .map(|number| chain_block(number, 0))
+                .chain(vec![chain_block(2, 1)])
+                .collect::<Vec<_>>();
+            store
+                .upsert_blocks(stream::iter_ok::<_, Error>(blocks))
+                .wait()
+                .unwrap();
+            store.attempt_chain_head_update(5).unwrap();
+            store
+                .add_subgraph_if_missing(subgraph_id.clone(), (&chain_block(4, 0)).into())
+                .unwrap();
+            store
+                .write(name.clone(), subgraph_id.clone(), node_id)
+                .unwrap();
+
+            let rewind = |name: &SubgraphDeploymentName, block: EthereumBlock| {
+                named_provider
+                    .rewind(name.clone(), block.block.hash.unwrap())
+                    .wait()
+            };
+            let assert_invalid_target = |result: Result<(), SubgraphProviderError>| match result {
+                Err(SubgraphProviderError::InvalidRewindTarget(_)) => (),
+                result => panic!("unexpected rewind result: {:?}", result),
+            };
+
+            // Blocks that are unknown, ahead of the subgraph or not its ancestors are rejected
+            assert_invalid_target(rewind(&name, chain_block(6, 0)));
+            assert_invalid_target(rewind(&name, chain_block(5, 0)));
+            assert_invalid_target(rewind(&name, chain_block(2, 1)));
+            assert_eq!(
+                store.block_ptr(subgraph_id.clone()).unwrap(),
+                (&chain_block(4, 0)).into()
+            );
+
+            match rewind(
+                &SubgraphDeploymentName::new("unknown").unwrap(),
+                chain_block(2, 0),
+            ) {
+                Err(SubgraphProviderError::NameNotFound(_)) => (),
+                result => panic!("unexpected rewind result: {:?}", result),
+            }
+
+            // Rewinding moves the block pointer back and resets the sync progress
+            rewind(&name, chain_block(2, 0)).unwrap();
+            assert_eq!(
+                store.block_ptr(subgraph_id.clone()).unwrap(),
+                (&chain_block(2, 0)).into()
+            );
+            let subgraph_entity = store
+                .get(EntityKey {
+                    subgraph_id: SUBGRAPHS_ID.clone(),
+                    entity_type: SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+                    entity_id: subgraph_id.to_string(),
+                })
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                subgraph_entity.get("status"),
+                Some(&Value::from(SubgraphStatus::Syncing.to_string()))
+            );
+            assert_eq!(
+                subgraph_entity.get("processedEthereumBlocksCount"),
+                Some(&Value::from(2 as u64))
+            );
+            assert_eq!(
+                subgraph_entity.get("totalEthereumBlocksCount"),
+                Some(&Value::from(5 as u64))
+            );
+
+            // Indexing resumes afterwards
+            assert!(!store.read_versions(name).unwrap()[0].paused);
+
+            Ok(())
+        }))
+        .unwrap();
+}
+
+/// Block stream builder whose streams fail immediately for the first
+/// `failures` requests and end without any blocks afterwards.
+#[derive(Clone)]
+struct FailingBlockStreamBuilder {
+    attempts: Arc<Mutex<usize>>,
+    failures: usize,
+    deterministic: bool,
+}
+
+struct FailingBlockStream {
+    error: Option<Error>,
+}
+
+impl Stream for FailingBlockStream {
+    type Item = EthereumBlock;
+    type Error = Error;
+
+    fn poll(&mut self) -> Result<Async<Option<EthereumBlock>>, Error> {
+        match self.error.take() {
+            Some(e) => Err(e),
+            None => Ok(Async::Ready(None)),
+        }
+    }
+}
+
+impl EventConsumer<ChainHeadUpdate> for FailingBlockStream {
+    fn event_sink(&self) -> Box<Sink<SinkItem = ChainHeadUpdate, SinkError = ()> + Send> {
+        unimplemented!()
+    }
+}
+
+impl BlockStream for FailingBlockStream {}
+
+impl BlockStreamBuilder for FailingBlockStreamBuilder {
+    type Stream = FailingBlockStream;
+
+    fn from_subgraph(&self, _: &SubgraphManifest, _: Logger) -> Self::Stream {
+        let mut attempts = self.attempts.lock().unwrap();
+        *attempts += 1;
+
+        FailingBlockStream {
+            error: if *attempts <= self.failures {
+                Some(SubgraphError::new("block stream failed", self.deterministic).into())
+            } else {
+                None
+            },
+        }
+    }
+}
+
+/// Transient failures are retried this many times in these tests.
+const MAX_RESTARTS: &str = "5";
+
+/// Starts the "two-datasources" subgraph on top of block streams that fail
+/// `failures` times, waits for `wait` and returns how often a block stream
+/// was requested for the subgraph.
+fn block_stream_attempts(failures: usize, deterministic: bool, wait: Duration) -> usize {
+    // Every test sets the same value, so tests running in parallel agree on it
+    env::set_var("GRAPH_SUBGRAPH_MAX_RESTARTS", MAX_RESTARTS);
+
+    #[derive(Debug)]
+    struct MockRuntimeHost {}
+
+    impl RuntimeHost for MockRuntimeHost {
+        fn matches_log(&self, _: &Log) -> bool {
+            true
+        }
+
+        fn process_log(
+            &self,
+            _: &Logger,
+            _: Arc<EthereumBlock>,
+            _: Arc<Transaction>,
+            _: Arc<Log>,
+            _: Vec<EntityOperation>,
+        ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+            unimplemented!();
+        }
+    }
+
+    #[derive(Clone)]
+    struct MockRuntimeHostBuilder {}
+
+    impl RuntimeHostBuilder for MockRuntimeHostBuilder {
+        type Host = MockRuntimeHost;
+
+        fn build(
+            &self,
+            _: &Logger,
+            _: SubgraphId,
+            _: DataSource,
+            _: Schema,
+        ) -> Result<Self::Host, Error> {
+            Ok(MockRuntimeHost {})
+        }
+    }
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let subgraph_link = runtime
+        .block_on(future::lazy(move || {
+            add_subgraph_to_ipfs(Arc::new(IpfsClient::default()), "two-datasources")
+        }))
+        .unwrap();
+
+    let attempts = Arc::new(Mutex::new(0));
+    let block_stream_builder = FailingBlockStreamBuilder {
+        attempts: attempts.clone(),
+        failures,
+        deterministic,
+    };
+
+    runtime
+        .block_on(future::lazy(move || {
+            let resolver = Arc::new(IpfsClient::default());
+            let logger = Logger::root(slog::Discard, o!());
+            let manager = SubgraphInstanceManager::new(
+                &logger,
+                Arc::new(FakeStore),
+                MockRuntimeHostBuilder {},
+                block_stream_builder,
+                None,
+            );
+
+            SubgraphManifest::resolve(
+                Link {
+                    link: subgraph_link,
+                },
+                resolver,
+            )
+            .map_err(|e| panic!("subgraph resolve error {:?}", e))
+            .and_then(move |subgraph| {
+                manager
+                    .event_sink()
+                    .send(SubgraphProviderEvent::SubgraphStart(subgraph))
+            })
+            .and_then(move |_| {
+                // Give the subgraph some time to fail and restart
+                Delay::new(Instant::now() + wait).map_err(|_| panic!("time error"))
+            })
+        }))
+        .unwrap();
+
+    attempts.lock().map(|attempts| *attempts).unwrap()
+}
+
+#[test]
+fn subgraph_restarts_after_transient_failures() {
+    // Restarts back off exponentially, starting at a few milliseconds
+    assert_eq!(block_stream_attempts(3, false, Duration::from_secs(2)), 4);
+}
+
+#[test]
+fn subgraph_stays_failed_after_deterministic_failure() {
+    assert_eq!(block_stream_attempts(3, true, Duration::from_secs(2)), 1);
+}
+
+#[test]
+fn subgraph_fails_after_too_many_transient_failures() {
+    // The first attempt and 5 restarts
+    assert_eq!(block_stream_attempts(10, false, Duration::from_secs(2)), 6);
+}
+
+/// Starts the "two-datasources" subgraph with runtime hosts that fail to build
+/// with deterministic or non-deterministic errors, one per data source, and
+/// returns whether the recorded start failure is deterministic.
+fn start_failure_is_deterministic(host_errors: Vec<Option<bool>>) -> bool {
+    #[derive(Debug)]
+    struct MockRuntimeHost {}
+
+    impl RuntimeHost for MockRuntimeHost {
+        fn matches_log(&self, _: &Log) -> bool {
+            true
+        }
+
+        fn process_log(
+            &self,
+            _: &Logger,
+            _: Arc<EthereumBlock>,
+            _: Arc<Transaction>,
+            _: Arc<Log>,
+            _: Vec<EntityOperation>,
+        ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+            unimplemented!();
+        }
+    }
+
+    /// Builds hosts for data sources in order; `Some(deterministic)` fails the
+    /// build with an error of that kind, `None` lets it succeed.
+    #[derive(Clone)]
+    struct MockRuntimeHostBuilder {
+        host_errors: Arc<Mutex<Vec<Option<bool>>>>,
+    }
+
+    impl RuntimeHostBuilder for MockRuntimeHostBuilder {
+        type Host = MockRuntimeHost;
+
+        fn build(
+            &self,
+            _: &Logger,
+            _: SubgraphId,
+            _: DataSource,
+            _: Schema,
+        ) -> Result<Self::Host, Error> {
+            match self.host_errors.lock().unwrap().remove(0) {
+                Some(true) => Err(SubgraphError::new("invalid mapping", true).into()),
+                Some(false) => Err(format_err!("connection refused")),
+                None => Ok(MockRuntimeHost {}),
+            }
+        }
+    }
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let subgraph_link = runtime
+        .block_on(future::lazy(move || {
+            add_subgraph_to_ipfs(Arc::new(IpfsClient::default()), "two-datasources")
+        }))
+        .unwrap();
+
+    let store = Arc::new(MockStore::new(vec![]));
+    let manager_store = store.clone();
+    let subgraph_id = runtime
+        .block_on(future::lazy(move || {
+            let resolver = Arc::new(IpfsClient::default());
+            let logger = Logger::root(slog::Discard, o!());
+            let manager = SubgraphInstanceManager::new(
+                &logger,
+                manager_store,
+                MockRuntimeHostBuilder {
+                    host_errors: Arc::new(Mutex::new(host_errors)),
+                },
+                MockBlockStreamBuilder::new(),
+                None,
+            );
+
+            SubgraphManifest::resolve(
+                Link {
+                    link: subgraph_link,
+                },
+                resolver,
+            )
+            .map_err(|e| panic!("subgraph resolve error {:?}", e))
+            .and_then(move |subgraph| {
+                let id = subgraph.id.clone();
+                manager
+                    .event_sink()
+                    .send(SubgraphProviderEvent::SubgraphStart(subgraph))
+                    .map(move |_| id)
+            })
+            .and_then(move |id| {
+                // Give the subgraph some time to fail
+                Delay::new(Instant::now() + Duration::from_millis(500))
+                    .map(move |_| id)
+                    .map_err(|_| panic!("time error"))
+            })
+        }))
+        .unwrap();
+
+    let subgraph_entity = store
+        .get(EntityKey {
+            subgraph_id: SUBGRAPHS_ID.clone(),
+            entity_type: SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+            entity_id: subgraph_id.to_string(),
+        })
+        .unwrap()
+        .expect("start failure was not recorded");
+    let error_id = match subgraph_entity.get("fatalError") {
+        Some(Value::String(error_id)) => error_id.clone(),
+        value => panic!("unexpected fatal error: {:?}", value),
+    };
+    let error = store
+        .get(EntityKey {
+            subgraph_id: SUBGRAPHS_ID.clone(),
+            entity_type: SUBGRAPH_ERROR_ENTITY_TYPENAME.to_owned(),
+            entity_id: error_id,
+        })
+        .unwrap()
+        .unwrap();
+    match error.get("deterministic") {
+        Some(Value::Bool(deterministic)) => *deterministic,
+        value => panic!("unexpected deterministic flag: {:?}", value),
+    }
+}
+
+#[test]
+fn subgraph_start_failures_are_classified_by_cause() {
+    assert!(start_failure_is_deterministic(vec![Some(true), Some(true)]));
+    assert!(start_failure_is_deterministic(vec![None, Some(true)]));
+    assert!(!start_failure_is_deterministic(vec![Some(false), None]));
+    assert!(!start_failure_is_deterministic(vec![
+        Some(true),
+        Some(false)
+    ]));
+}
@@ -0,0 +1,178 @@
+use futures::future;
+use futures::prelude::*;
+use std::collections::HashMap;
+
+use graph::prelude::{BlockIntegrityChecker as BlockIntegrityCheckerTrait, *};
+use graph::util::env::positive_number_from_env;
+use graph::web3::types::*;
+
+/// Maximum number of blocks that can be checked at once. Every block in the range costs
+/// at least one request to the Ethereum node.
+const MAX_CHECKED_BLOCKS: u64 = 10_000;
+
+/// Checks the blocks in a chain store against the main chain reported by an Ethereum node and
+/// repairs the chain store where necessary.
+pub struct BlockIntegrityChecker<C, E> {
+    chain_store: Arc<C>,
+    eth_adapter: Arc<E>,
+}
+
+impl<C, E> BlockIntegrityChecker<C, E>
+where
+    C: ChainStore,
+    E: EthereumAdapter,
+{
+    pub fn new(chain_store: Arc<C>, eth_adapter: Arc<E>) -> Self {
+        BlockIntegrityChecker {
+            chain_store,
+            eth_adapter,
+        }
+    }
+}
+
+impl<C, E> BlockIntegrityCheckerTrait for BlockIntegrityChecker<C, E>
+where
+    C: ChainStore,
+    E: EthereumAdapter,
+{
+    fn check_blocks(
+        &self,
+        logger: &Logger,
+        from: u64,
+        to: u64,
+    ) -> Box<Future<Item = BlockIntegrityReport, Error = Error> + Send> {
+        let logger = logger.new(o!(
+            "component" => "BlockIntegrityChecker",
+            "from" => from,
+            "to" => to,
+        ));
+
+        if from > to {
+            return Box::new(future::err(format_err!(
+                "invalid block range: {} is greater than {}",
+                from,
+                to
+            )));
+        }
+
+        if to - from >= MAX_CHECKED_BLOCKS {
+            return Box::new(future::err(format_err!(
+                "invalid block range: at most {} blocks can be checked at once, \
+                 but {} to {} contains {}",
+                MAX_CHECKED_BLOCKS,
+                from,
+                to,
+                to - from + 1
+            )));
+        }
+
+        let parallel_requests: usize =
+            match positive_number_from_env("ETHEREUM_BLOCK_CHECK_PARALLEL_REQUESTS", 10) {
+                Ok(parallel_requests) => parallel_requests,
+                Err(e) => return Box::new(future::err(e)),
+            };
+
+        let stored_blocks = match self.chain_store.block_ptrs_in_range(from, to) {
+            Ok(stored_blocks) => stored_blocks,
+            Err(e) => return Box::new(future::err(e)),
+        };
+
+        info!(logger, "Checking blocks in chain store");
+
+        let chain_store = self.chain_store.clone();
+        let eth_adapter = self.eth_adapter.clone();
+
+        // Also look up the parent of the first block, so its parent hash can be checked
+        let first_block_number = if from > 0 { from - 1 } else { 0 };
+
+        let canonical_hashes = {
+            let eth_adapter = eth_adapter.clone();
+            let logger = logger.clone();
+
+            stream::iter_ok(first_block_number..=to)
+                .map(move |number| {
+                    eth_adapter
+                        .block_hash_by_block_number(&logger, number)
+                        .and_then(move |hash_opt| {
+                            hash_opt.map(|hash| (number, hash)).ok_or_else(|| {
+                                format_err!(
+                                    "Ethereum node could not find block with number {}",
+                                    number
+                                )
+                            })
+                        })
+                })
+                .buffered(parallel_requests)
+                .collect()
+                .map(|hashes| hashes.into_iter().collect::<HashMap<u64, H256>>())
+        };
+
+        Box::new(canonical_hashes.and_then(move |canonical_hashes| {
+            let mut report = BlockIntegrityReport {
+                from,
+                to,
+                ..Default::default()
+            };
+
+            let mut stored_blocks_by_number = HashMap::new();
+            for (block_ptr, parent_hash) in stored_blocks {
+                stored_blocks_by_number
+                    .entry(block_ptr.number)
+                    .or_insert_with(Vec::new)
+                    .push((block_ptr, parent_hash));
+            }
+
+            // Compare the stored blocks with the main chain, one block number at a time
+            let mut hashes_to_repair = vec![];
+            for number in from..=to {
+                let canonical_hash = canonical_hashes[&number];
+                let stored_blocks = stored_blocks_by_number.remove(&number).unwrap_or_default();
+
+                let mut found = false;
+                for (block_ptr, parent_hash) in stored_blocks {
+                    if block_ptr.hash != canonical_hash {
+                        report.non_canonical_blocks.push(block_ptr);
+                        continue;
+                    }
+
+                    found = true;
+
+                    // The genesis block has no parent to compare against
+                    if number > 0 && parent_hash != canonical_hashes[&(number - 1)] {
+                        report.parent_hash_mismatches.push(block_ptr);
+                        hashes_to_repair.push(canonical_hash);
+                    }
+                }
+
+                if !found {
+                    report.missing_blocks.push((canonical_hash, number).into());
+                    hashes_to_repair.push(canonical_hash);
+                }
+            }
+
+            info!(
+                logger,
+                "Finished comparing chain store with Ethereum node";
+                "missing_blocks" => report.missing_blocks.len(),
+                "parent_hash_mismatches" => report.parent_hash_mismatches.len(),
+                "non_canonical_blocks" => report.non_canonical_blocks.len()
+            );
+
+            // Re-fetch missing and inconsistent blocks and overwrite them in the chain store
+            report.repaired_blocks = hashes_to_repair.len() as u64;
+            let blocks = stream::iter_ok(hashes_to_repair)
+                .map(move |hash| {
+                    eth_adapter
+                        .block_by_hash(&logger, hash)
+                        .and_then(move |block_opt| {
+                            block_opt.ok_or_else(|| {
+                                format_err!("Ethereum node could not find block {:?}", hash)
+                            })
+                        })
+                })
+                .buffered(parallel_requests);
+
+            chain_store.upsert_blocks(blocks).map(move |()| report)
+        }))
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 use std::time::Instant;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use graph::prelude::*;
 use graph::web3::api::Web3;
@@ -18,7 +19,13 @@ where
     chain_store: Arc<S>,
     web3_transport: T,
     ancestor_count: u64,
+    net_identifiers: EthereumNetworkIdentifier,
+    chain_health: ChainHealth,
+    max_head_age: Duration,
+    max_head_lag: u64,
+    health_check_interval: Duration,
     logger: Logger,
+    metrics_logger: Logger,
     polling_interval: Duration,
 }
 
@@ -29,10 +36,22 @@ where
     <T as Transport>::Out: Send,
     <T as BatchTransport>::Batch: Send,
 {
+    /// Creates a new block ingestor.
+    ///
+    /// Besides ingesting blocks, the ingestor checks the health of the chain head every
+    /// `health_check_interval` and writes the result to `chain_health`. The chain head is considered unhealthy if its
+    /// block is older than `max_head_age`, if it is more than `max_head_lag` blocks behind the
+    /// latest block of the Ethereum node, or if the Ethereum node no longer reports the
+    /// `net_identifiers` it reported at startup.
     pub fn new(
         chain_store: Arc<S>,
         web3_transport: T,
         ancestor_count: u64,
+        net_identifiers: EthereumNetworkIdentifier,
+        chain_health: ChainHealth,
+        max_head_age: Duration,
+        max_head_lag: u64,
+        health_check_interval: Duration,
         logger: Logger,
         polling_interval: Duration,
     ) -> Result<BlockIngestor<S, T>, Error> {
@@ -40,7 +59,13 @@ where
             chain_store,
             web3_transport,
             ancestor_count,
+            net_identifiers,
+            chain_health,
+            max_head_age,
+            max_head_lag,
+            health_check_interval,
             logger: logger.new(o!("component" => "BlockIngestor")),
+            metrics_logger: logger.new(o!("component" => "ChainHeadMetrics")),
             polling_interval,
         })
     }
@@ -50,7 +75,7 @@ where
         let static_self: &'static _ = Box::leak(Box::new(self));
 
         // Create stream that emits at polling interval
-        tokio::timer::Interval::new(Instant::now(), static_self.polling_interval)
+        let polling = tokio::timer::Interval::new(Instant::now(), static_self.polling_interval)
             .map_err(move |e| {
                 error!(static_self.logger, "timer::Interval failed: {:?}", e);
             })
@@ -79,7 +104,167 @@ where
                     // Continue polling even if polling failed
                     future::ok(())
                 })
-            })
+            });
+
+        // Check the health of the chain head at the health check interval
+        let health_checks =
+            tokio::timer::Interval::new(Instant::now(), static_self.health_check_interval)
+                .map_err(move |e| {
+                    error!(static_self.logger, "timer::Interval failed: {:?}", e);
+                })
+                .for_each(move |_| {
+                    static_self.check_health().then(move |result| {
+                        let health = result.unwrap_or_else(|e| ChainHeadHealth {
+                            healthy: false,
+                            problems: vec![format!("chain head health check failed: {}", e)],
+                            ..static_self.chain_health.get()
+                        });
+                        static_self.update_health(health);
+
+                        // Continue checking even if the check failed
+                        future::ok(())
+                    })
+                });
+
+        polling.join(health_checks).map(|_| ())
+    }
+
+    /// Compares the chain head against the wall clock and against the latest block and network
+    /// identifiers reported by the Ethereum node.
+    ///
+    /// This is done periodically by the polling stream; the result is not written to the
+    /// shared chain health.
+    pub fn check_health<'a>(&'a self) -> impl Future<Item = ChainHeadHealth, Error = Error> + 'a {
+        let web3 = Web3::new(self.web3_transport.clone());
+
+        // Look up the chain head block in the chain store
+        let head_block_future =
+            future::result(self.chain_store.chain_head_ptr().and_then(|head_ptr_opt| {
+                match head_ptr_opt {
+                    Some(head_ptr) => self.chain_store.block(head_ptr.hash),
+                    None => Ok(None),
+                }
+            }));
+
+        // Ask the Ethereum node for its latest block number and network identifiers
+        let latest_block_number_future = web3
+            .eth()
+            .block_number()
+            .map_err(|e| format_err!("could not get latest block number from Ethereum: {}", e));
+        let net_version_future = web3
+            .net()
+            .version()
+            .map_err(|e| format_err!("could not get net version from Ethereum: {}", e));
+        let genesis_block_future = web3
+            .eth()
+            .block(BlockNumber::Earliest.into())
+            .map_err(|e| format_err!("could not get genesis block from Ethereum: {}", e));
+
+        head_block_future
+            .join4(
+                latest_block_number_future,
+                net_version_future,
+                genesis_block_future,
+            )
+            .map(
+                move |(head_block_opt, latest_block_number, net_version, genesis_block_opt)| {
+                    let mut problems = vec![];
+
+                    let latest_block_number = latest_block_number.as_u64();
+                    let head_block = head_block_opt.as_ref().map(EthereumBlockPointer::from);
+                    let head_block_lag = head_block
+                        .map(|head_block| latest_block_number.saturating_sub(head_block.number));
+                    let head_block_age = head_block_opt.as_ref().map(|head_block| {
+                        let timestamp =
+                            UNIX_EPOCH + Duration::from_secs(head_block.block.timestamp.as_u64());
+
+                        // Block timestamps may be slightly ahead of the local clock
+                        SystemTime::now()
+                            .duration_since(timestamp)
+                            .unwrap_or_else(|_| Duration::from_secs(0))
+                    });
+
+                    match (head_block, head_block_age, head_block_lag) {
+                        (Some(head_block), Some(head_block_age), Some(head_block_lag)) => {
+                            if head_block_age > self.max_head_age {
+                                problems.push(format!(
+                                    "chain head block #{} is {}s old",
+                                    head_block.number,
+                                    head_block_age.as_secs()
+                                ));
+                            }
+
+                            if head_block_lag > self.max_head_lag {
+                                problems.push(format!(
+                                    "chain head block #{} is {} blocks behind the latest block \
+                                     #{} of the Ethereum node",
+                                    head_block.number, head_block_lag, latest_block_number
+                                ));
+                            }
+                        }
+                        _ => problems.push("there is no chain head block yet".to_owned()),
+                    }
+
+                    if net_version != self.net_identifiers.net_version {
+                        problems.push(format!(
+                            "Ethereum node reports net version {} instead of {}",
+                            net_version, self.net_identifiers.net_version
+                        ));
+                    }
+
+                    match genesis_block_opt.and_then(|genesis_block| genesis_block.hash) {
+                        Some(genesis_block_hash)
+                            if genesis_block_hash != self.net_identifiers.genesis_block_hash =>
+                        {
+                            problems.push(format!(
+                                "Ethereum node is on a different chain, its genesis block is \
+                                 {:?} instead of {:?}",
+                                genesis_block_hash, self.net_identifiers.genesis_block_hash
+                            ));
+                        }
+                        Some(_) => {}
+                        None => problems.push("Ethereum node returned no genesis block".to_owned()),
+                    }
+
+                    ChainHeadHealth {
+                        healthy: problems.is_empty(),
+                        head_block,
+                        head_block_age,
+                        latest_block_number: Some(latest_block_number),
+                        head_block_lag,
+                        unhealthy_checks: 0,
+                        problems,
+                    }
+                },
+            )
+    }
+
+    fn update_health(&self, health: ChainHeadHealth) {
+        let was_healthy = self.chain_health.is_healthy();
+        self.chain_health.set(health);
+        let health = self.chain_health.get();
+
+        // Emit the result of every check as metrics, so that the chain head can be
+        // monitored and alerted on from the logs
+        info!(
+            self.metrics_logger, "Chain head metrics";
+            "healthy" => if health.healthy { 1 } else { 0 },
+            "unhealthy_checks" => health.unhealthy_checks,
+            "head_block_number" => health.head_block.map(|head_block| head_block.number),
+            "head_block_age_secs" => health.head_block_age.map(|age| age.as_secs()),
+            "head_block_lag" => health.head_block_lag,
+            "latest_block_number" => health.latest_block_number
+        );
+
+        if !health.healthy {
+            warn!(
+                self.logger,
+                "Chain head is unhealthy: {}",
+                health.problems.join("; ")
+            );
+        } else if !was_healthy {
+            info!(self.logger, "Chain head is healthy");
+        }
     }
 
     fn do_poll<'a>(&'a self) -> impl Future<Item = (), Error = BlockIngestorError> + 'a {
@@ -13,8 +13,6 @@ use graph::prelude::{
 use graph::util::ethereum::string_to_h256;
 use graph::web3::types::*;
 
-const REORG_THRESHOLD: u64 = 50;
-
 enum BlockStreamState {
     /// The BlockStream is new and has not yet been polled.
     ///
@@ -93,6 +91,8 @@ struct BlockStreamContext<S, C, E> {
     chain_store: Arc<C>,
     eth_adapter: Arc<E>,
     subgraph_id: SubgraphId,
+    reorg_threshold: u64,
+    confirmations: u64,
     logger: Logger,
 }
 
@@ -103,6 +103,8 @@ impl<S, C, E> Clone for BlockStreamContext<S, C, E> {
             chain_store: self.chain_store.clone(),
             eth_adapter: self.eth_adapter.clone(),
             subgraph_id: self.subgraph_id.clone(),
+            reorg_threshold: self.reorg_threshold,
+            confirmations: self.confirmations,
             logger: self.logger.clone(),
         }
     }
@@ -119,7 +121,7 @@ pub struct BlockStream<S, C, E> {
 
 impl<S, C, E> BlockStream<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -129,6 +131,8 @@ where
         eth_adapter: Arc<E>,
         subgraph_id: SubgraphId,
         log_filter: EthereumLogFilter,
+        reorg_threshold: u64,
+        confirmations: u64,
         logger: Logger,
     ) -> Self {
         let logger = logger.new(o!(
@@ -148,6 +152,8 @@ where
                 chain_store,
                 eth_adapter,
                 subgraph_id,
+                reorg_threshold,
+                confirmations,
                 logger,
             },
         }
@@ -156,7 +162,7 @@ where
 
 impl<S, C, E> BlockStreamContext<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -211,7 +217,7 @@ where
         debug!(ctx.logger, "Identify next step");
 
         // Get pointers from database for comparison
-        let head_ptr_opt = ctx.chain_store.chain_head_ptr().unwrap();
+        let head_ptr_opt = ctx.confirmed_head_ptr().unwrap();
         let subgraph_ptr = ctx
             .subgraph_store
             .block_ptr(ctx.subgraph_id.clone())
@@ -227,7 +233,7 @@ where
         let head_ptr = head_ptr_opt.unwrap();
 
         debug!(
-            ctx.logger, "Chain head pointer";
+            ctx.logger, "Confirmed chain head pointer";
             "hash" => format!("{:?}", head_ptr.hash),
             "number" => &head_ptr.number
         );
@@ -237,7 +243,7 @@ where
             "number" => &subgraph_ptr.number
         );
 
-        // Only continue if the subgraph block ptr is behind the head block ptr.
+        // Only continue if the subgraph block ptr is behind the confirmed head block ptr.
         // subgraph_ptr > head_ptr shouldn't happen, but if it does, it's safest to just stop.
         if subgraph_ptr.number >= head_ptr.number {
             return Box::new(future::ok(ReconciliationStep::Done))
@@ -269,16 +275,17 @@ where
         // Accordingly, if the subgraph ptr is really far behind the head ptr, then we can
         // trust that the Ethereum node knows what the real, permanent block is for that block
         // number.
-        // We'll define "really far" to mean "greater than REORG_THRESHOLD blocks".
+        // We'll define "really far" to mean "greater than reorg threshold blocks".
         //
         // If the subgraph ptr is not too far behind the head ptr (i.e. less than
-        // REORG_THRESHOLD blocks behind), then we have to allow for the possibility that the
+        // reorg threshold blocks behind), then we have to allow for the possibility that the
         // block might be on the main chain now, but might become uncled in the future.
         //
         // Most importantly: Our ability to make this assumption (or not) will determine what
         // Ethereum RPC calls can give us accurate data without race conditions.
         // (This is mostly due to some unfortunate API design decisions on the Ethereum side)
-        if (head_ptr.number - subgraph_ptr.number) > REORG_THRESHOLD {
+        let reorg_threshold = ctx.reorg_threshold;
+        if (head_ptr.number - subgraph_ptr.number) > reorg_threshold {
             // Since we are beyond the reorg threshold, the Ethereum node knows what block has
             // been permanently assigned this block number.
             // This allows us to ask the node: does subgraph_ptr point to a block that was
@@ -307,7 +314,7 @@ where
 
                         // End just prior to reorg threshold.
                         // It isn't safe to go any farther due to race conditions.
-                        let to = head_ptr.number - REORG_THRESHOLD;
+                        let to = head_ptr.number - reorg_threshold;
 
                         debug!(ctx.logger, "Finding next blocks with relevant events...");
                         Box::new(
@@ -383,8 +390,9 @@ where
             // change under our feet at any time.
             //
             // Second, due to how the BlockIngestor is designed, we get a helpful guarantee:
-            // the head block and at least its REORG_THRESHOLD most recent ancestors will be
-            // present in the block store.
+            // the head block and at least its reorg threshold most recent ancestors will be
+            // present in the block store (as long as the ingestor's ancestor count covers
+            // both the reorg threshold and the number of confirmations).
             // This allows us to work locally in the block store instead of relying on
             // Ethereum RPC calls, so that we are not subject to the limitations of the RPC
             // API.
@@ -541,10 +549,36 @@ where
         }
     }
 
+    /// Returns the pointer to the most recent block that has received the configured number of
+    /// confirmations, i.e. the block `confirmations` blocks behind the chain head.
+    ///
+    /// Returns `None` if there is no chain head yet or if the confirmed block is not (yet)
+    /// available in the block store.
+    fn confirmed_head_ptr(&self) -> Result<Option<EthereumBlockPointer>, Error> {
+        let head_ptr = match self.chain_store.chain_head_ptr()? {
+            Some(head_ptr) => head_ptr,
+            None => return Ok(None),
+        };
+
+        if self.confirmations == 0 {
+            return Ok(Some(head_ptr));
+        }
+
+        // Not enough blocks on the chain yet to have any confirmed blocks
+        if head_ptr.number < self.confirmations {
+            return Ok(None);
+        }
+
+        Ok(self
+            .chain_store
+            .ancestor_block(head_ptr, self.confirmations)?
+            .map(|block| block.into()))
+    }
+
     /// Write SYNCED to the subgraph entity status field if and only if the subgraph block pointer
-    /// is caught up to the head block pointer.
+    /// is caught up to the confirmed head block pointer.
     fn update_subgraph_synced_status(&self) -> Result<(), Error> {
-        let head_ptr_opt = self.chain_store.chain_head_ptr()?;
+        let head_ptr_opt = self.confirmed_head_ptr()?;
         let subgraph_ptr = self.subgraph_store.block_ptr(self.subgraph_id.clone())?;
 
         if head_ptr_opt != Some(subgraph_ptr) {
@@ -555,7 +589,21 @@ where
             let ops =
                 SubgraphEntity::write_status_operations(&self.subgraph_id, SubgraphStatus::Synced);
             self.subgraph_store
-                .apply_entity_operations(ops, EventSource::None)
+                .apply_entity_operations(ops, EventSource::None)?;
+
+            // If this subgraph is the pending version of a subgraph name, it has now caught up
+            // and can replace the current version
+            if self
+                .subgraph_store
+                .promote_pending_version(&self.subgraph_id)?
+            {
+                info!(
+                    self.logger,
+                    "Promoted pending subgraph version to current version"
+                );
+            }
+
+            Ok(())
         }
     }
 
@@ -657,7 +705,7 @@ where
 
 impl<S, C, E> BlockStreamTrait for BlockStream<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -665,7 +713,7 @@ where
 
 impl<S, C, E> Stream for BlockStream<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -842,7 +890,7 @@ where
 
 impl<S, C, E> EventConsumer<ChainHeadUpdate> for BlockStream<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -859,6 +907,8 @@ pub struct BlockStreamBuilder<S, C, E> {
     subgraph_store: Arc<S>,
     chain_store: Arc<C>,
     eth_adapter: Arc<E>,
+    reorg_threshold: u64,
+    confirmations: u64,
 }
 
 impl<S, C, E> Clone for BlockStreamBuilder<S, C, E> {
@@ -867,28 +917,44 @@ impl<S, C, E> Clone for BlockStreamBuilder<S, C, E> {
             subgraph_store: self.subgraph_store.clone(),
             chain_store: self.chain_store.clone(),
             eth_adapter: self.eth_adapter.clone(),
+            reorg_threshold: self.reorg_threshold,
+            confirmations: self.confirmations,
         }
     }
 }
 
 impl<S, C, E> BlockStreamBuilder<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
-    pub fn new(subgraph_store: Arc<S>, chain_store: Arc<C>, eth_adapter: Arc<E>) -> Self {
+    /// Creates a new block stream builder.
+    ///
+    /// Blocks less than `reorg_threshold` blocks behind the chain head are assumed to be
+    /// subject to chain reorganizations. Subgraphs are kept `confirmations` blocks behind
+    /// the chain head; with `confirmations` set to at least the reorg threshold, subgraphs
+    /// will never have to revert blocks.
+    pub fn new(
+        subgraph_store: Arc<S>,
+        chain_store: Arc<C>,
+        eth_adapter: Arc<E>,
+        reorg_threshold: u64,
+        confirmations: u64,
+    ) -> Self {
         BlockStreamBuilder {
             subgraph_store,
             chain_store,
             eth_adapter,
+            reorg_threshold,
+            confirmations,
         }
     }
 }
 
 impl<S, C, E> BlockStreamBuilderTrait for BlockStreamBuilder<S, C, E>
 where
-    S: Store,
+    S: Store + SubgraphDeploymentStore,
     C: ChainStore,
     E: EthereumAdapter,
 {
@@ -912,6 +978,8 @@ where
             self.eth_adapter.clone(),
             manifest.id.clone(),
             log_filter,
+            self.reorg_threshold,
+            self.confirmations,
             logger,
         );
 
@@ -6,10 +6,13 @@ use std::sync::Arc;
 
 use graph::components::ethereum::{EthereumAdapter as EthereumAdapterTrait, *};
 use graph::prelude::*;
+use graph::serde_json;
 use graph::web3;
 use graph::web3::api::Web3;
+use graph::web3::helpers;
 use graph::web3::transports::batch::Batch;
 use graph::web3::types::{Filter, *};
+use graph::web3::Transport;
 
 #[derive(Clone)]
 pub struct EthereumAdapter<T: web3::Transport> {
@@ -269,6 +272,44 @@ where
                     })
             })
     }
+
+    /// Sends the RPC request `method` (e.g. `eth_getBalance`) for `target`, which is an
+    /// address or a call request, as of the block `block_ptr`. The block is identified by its
+    /// hash as described in EIP-1898.
+    fn state_at_block(
+        &self,
+        logger: &Logger,
+        method: &'static str,
+        target: serde_json::Value,
+        block_ptr: EthereumBlockPointer,
+    ) -> impl Future<Item = serde_json::Value, Error = Error> + Send {
+        let web3 = self.web3.clone();
+
+        retry(format!("{} RPC call", method), logger)
+            .no_limit()
+            .timeout_secs(60)
+            .run(move || {
+                let mut block = serde_json::Map::new();
+                block.insert("blockHash".to_owned(), helpers::serialize(&block_ptr.hash));
+
+                web3.transport()
+                    .execute(
+                        method,
+                        vec![target.clone(), serde_json::Value::Object(block)],
+                    )
+                    .map_err(SyncFailure::new)
+                    .from_err()
+            })
+            .map_err(move |e| {
+                e.into_inner().unwrap_or_else(move || {
+                    format_err!(
+                        "Ethereum node took too long to respond to {} for block {:?}",
+                        method,
+                        block_ptr.hash
+                    )
+                })
+            })
+    }
 }
 
 impl<T> EthereumAdapterTrait for EthereumAdapter<T>
@@ -528,22 +569,11 @@ where
         logger: &Logger,
         call: EthereumContractCall,
     ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
-        // Emit custom error for type mismatches.
-        for (token, kind) in call
-            .args
-            .iter()
-            .zip(call.function.inputs.iter().map(|p| &p.kind))
-        {
-            if !token.type_check(kind) {
-                return Box::new(future::err(EthereumContractCallError::TypeError(
-                    token.clone(),
-                    kind.clone(),
-                )));
-            }
-        }
-
         // Encode the call parameters according to the ABI
-        let call_data = call.function.encode_input(&call.args).unwrap();
+        let call_data = match encode_call_input(&call) {
+            Ok(call_data) => call_data,
+            Err(e) => return Box::new(future::err(e)),
+        };
 
         Box::new(
             // Make the actual function call
@@ -562,4 +592,95 @@ where
             }),
         )
     }
+
+    fn contract_call_at_block_hash(
+        &self,
+        logger: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        let call_data = match encode_call_input(&call) {
+            Ok(call_data) => call_data,
+            Err(e) => return Box::new(future::err(e)),
+        };
+        let request = CallRequest {
+            from: None,
+            to: call.address,
+            gas: None,
+            gas_price: None,
+            value: None,
+            data: Some(Bytes(call_data)),
+        };
+
+        Box::new(
+            self.state_at_block(
+                logger,
+                "eth_call",
+                helpers::serialize(&request),
+                call.block_ptr,
+            )
+            .and_then(|output| serde_json::from_value(output).map_err(Error::from))
+            .map_err(EthereumContractCallError::from)
+            .and_then(move |output: Bytes| {
+                // Decode the return values according to the ABI
+                call.function
+                    .decode_output(&output.0)
+                    .map_err(EthereumContractCallError::from)
+            }),
+        )
+    }
+
+    fn balance(
+        &self,
+        logger: &Logger,
+        address: Address,
+        block_ptr: EthereumBlockPointer,
+    ) -> Box<Future<Item = U256, Error = Error> + Send> {
+        Box::new(
+            self.state_at_block(
+                logger,
+                "eth_getBalance",
+                helpers::serialize(&address),
+                block_ptr,
+            )
+            .and_then(|balance| serde_json::from_value(balance).map_err(Error::from)),
+        )
+    }
+
+    fn code(
+        &self,
+        logger: &Logger,
+        address: Address,
+        block_ptr: EthereumBlockPointer,
+    ) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        Box::new(
+            self.state_at_block(
+                logger,
+                "eth_getCode",
+                helpers::serialize(&address),
+                block_ptr,
+            )
+            .and_then(|code| serde_json::from_value(code).map_err(Error::from))
+            .map(|code: Bytes| code.0),
+        )
+    }
+}
+
+/// Checks the arguments of `call` against the function's inputs and encodes them according
+/// to the ABI.
+fn encode_call_input(call: &EthereumContractCall) -> Result<Vec<u8>, EthereumContractCallError> {
+    // Emit custom error for type mismatches.
+    for (token, kind) in call
+        .args
+        .iter()
+        .zip(call.function.inputs.iter().map(|p| &p.kind))
+    {
+        if !token.type_check(kind) {
+            return Err(EthereumContractCallError::TypeError(
+                token.clone(),
+                kind.clone(),
+            ));
+        }
+    }
+
+    Ok(call.function.encode_input(&call.args).unwrap())
 }
@@ -2,13 +2,18 @@ extern crate failure;
 extern crate futures;
 extern crate graph;
 extern crate jsonrpc_core;
+extern crate serde;
 
+mod block_checker;
 mod block_ingestor;
 mod block_stream;
 mod ethereum_adapter;
+mod replay;
 mod transport;
 
+pub use self::block_checker::BlockIntegrityChecker;
 pub use self::block_ingestor::BlockIngestor;
 pub use self::block_stream::{BlockStream, BlockStreamBuilder};
 pub use self::ethereum_adapter::EthereumAdapter;
+pub use self::replay::{EthereumFixture, RecordedCall, RecordingTransport, ReplayTransport};
 pub use self::transport::{EventLoopHandle, Transport};
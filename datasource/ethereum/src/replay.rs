@@ -0,0 +1,332 @@
+use futures::future;
+use futures::prelude::*;
+use graph::serde_json::{self, Value};
+use jsonrpc_core::types::Call;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs::File;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use graph::prelude::*;
+use graph::web3;
+use graph::web3::helpers::build_request;
+use graph::web3::types::{Block, Transaction, TransactionReceipt};
+use graph::web3::RequestId;
+
+/// A JSON-RPC request sent to an Ethereum node, together with the result the node returned.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedCall {
+    pub method: String,
+    pub params: Vec<Value>,
+    pub result: Value,
+}
+
+impl RecordedCall {
+    fn key(&self) -> String {
+        call_key(&self.method, &self.params)
+    }
+}
+
+/// Ethereum node responses recorded with a `RecordingTransport`, in the order in which they
+/// were received.
+///
+/// Fixtures can be written to and loaded from JSON files and can be replayed with a
+/// `ReplayTransport`.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct EthereumFixture {
+    pub calls: Vec<RecordedCall>,
+}
+
+impl EthereumFixture {
+    /// Loads a fixture from a JSON file.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let file = File::open(path)
+            .map_err(|e| format_err!("failed to open fixture {}: {}", path.display(), e))?;
+        serde_json::from_reader(file)
+            .map_err(|e| format_err!("failed to parse fixture {}: {}", path.display(), e))
+    }
+
+    /// Writes the fixture to a JSON file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let file = File::create(path)
+            .map_err(|e| format_err!("failed to create fixture {}: {}", path.display(), e))?;
+        serde_json::to_writer_pretty(file, self)
+            .map_err(|e| format_err!("failed to write fixture {}: {}", path.display(), e))
+    }
+
+    /// Returns all blocks that were recorded with full transactions, ordered by block number.
+    ///
+    /// Fails if the receipt of any of the transactions in these blocks was not recorded.
+    pub fn blocks(&self) -> Result<Vec<EthereumBlock>, Error> {
+        let mut receipts = HashMap::new();
+        let mut blocks = BTreeMap::new();
+
+        for call in self.calls.iter().filter(|call| !call.result.is_null()) {
+            match call.method.as_str() {
+                "eth_getTransactionReceipt" => {
+                    let receipt: TransactionReceipt = serde_json::from_value(call.result.clone())?;
+                    receipts.insert(receipt.transaction_hash, receipt);
+                }
+                "eth_getBlockByHash" | "eth_getBlockByNumber"
+                    if call.params.get(1) == Some(&Value::Bool(true)) =>
+                {
+                    let block: Block<Transaction> = serde_json::from_value(call.result.clone())?;
+                    let number = block
+                        .number
+                        .ok_or_else(|| format_err!("recorded block is missing a number"))?
+                        .as_u64();
+                    let hash = block
+                        .hash
+                        .ok_or_else(|| format_err!("recorded block is missing a hash"))?;
+                    blocks.insert((number, hash), block);
+                }
+                _ => {}
+            }
+        }
+
+        blocks
+            .into_iter()
+            .map(|(_, block)| {
+                let transaction_receipts = block
+                    .transactions
+                    .iter()
+                    .map(|tx| {
+                        receipts.get(&tx.hash).cloned().ok_or_else(|| {
+                            format_err!("fixture is missing transaction receipt: {}", tx.hash)
+                        })
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+
+                Ok(EthereumBlock {
+                    block,
+                    transaction_receipts,
+                })
+            })
+            .collect()
+    }
+
+    /// Writes all recorded blocks into a chain store and moves the chain head to the
+    /// recorded block with the highest block number.
+    ///
+    /// This allows block streams to be driven from a fixture without running a
+    /// `BlockIngestor` against an Ethereum node.
+    pub fn feed_chain_store<C>(
+        &self,
+        chain_store: Arc<C>,
+        ancestor_count: u64,
+    ) -> impl Future<Item = (), Error = Error> + Send
+    where
+        C: ChainStore,
+    {
+        future::result(self.blocks()).and_then(move |blocks| {
+            chain_store
+                .upsert_blocks(stream::iter_ok(blocks))
+                .and_then(move |()| {
+                    let missing_block_hashes =
+                        chain_store.attempt_chain_head_update(ancestor_count)?;
+                    if missing_block_hashes.is_empty() {
+                        Ok(())
+                    } else {
+                        Err(format_err!(
+                            "fixture is missing ancestor blocks of the chain head: {:?}",
+                            missing_block_hashes
+                        ))
+                    }
+                })
+        })
+    }
+}
+
+fn call_key(method: &str, params: &[Value]) -> String {
+    format!(
+        "{}({})",
+        method,
+        serde_json::to_string(params).expect("failed to serialize JSON-RPC params")
+    )
+}
+
+/// Wraps a web3 transport and records every successful request and its result.
+#[derive(Clone, Debug)]
+pub struct RecordingTransport<T> {
+    transport: T,
+    pending: Arc<Mutex<HashMap<RequestId, (String, Vec<Value>)>>>,
+    fixture: Arc<Mutex<EthereumFixture>>,
+}
+
+impl<T> RecordingTransport<T> {
+    pub fn new(transport: T) -> Self {
+        RecordingTransport {
+            transport,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            fixture: Arc::new(Mutex::new(EthereumFixture::default())),
+        }
+    }
+
+    /// Returns everything that has been recorded so far.
+    pub fn fixture(&self) -> EthereumFixture {
+        self.fixture.lock().unwrap().clone()
+    }
+
+    /// Writes everything that has been recorded so far to a JSON file.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        self.fixture().save(path)
+    }
+
+    fn take_pending(&self, id: RequestId) -> Option<(String, Vec<Value>)> {
+        self.pending.lock().unwrap().remove(&id)
+    }
+}
+
+fn record(fixture: &Mutex<EthereumFixture>, request: Option<(String, Vec<Value>)>, result: &Value) {
+    if let Some((method, params)) = request {
+        fixture.lock().unwrap().calls.push(RecordedCall {
+            method,
+            params,
+            result: result.clone(),
+        });
+    }
+}
+
+impl<T> web3::Transport for RecordingTransport<T>
+where
+    T: web3::Transport,
+    T::Out: Send + 'static,
+{
+    type Out = Box<Future<Item = Value, Error = web3::error::Error> + Send>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let (id, call) = self.transport.prepare(method, params.clone());
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(id, (method.to_owned(), params));
+        (id, call)
+    }
+
+    fn send(&self, id: RequestId, request: Call) -> Self::Out {
+        let recorded_request = self.take_pending(id);
+        let fixture = self.fixture.clone();
+
+        Box::new(self.transport.send(id, request).map(move |result| {
+            record(&fixture, recorded_request, &result);
+            result
+        }))
+    }
+}
+
+impl<T> web3::BatchTransport for RecordingTransport<T>
+where
+    T: web3::BatchTransport,
+    T::Out: Send + 'static,
+    T::Batch: Send + 'static,
+{
+    type Batch = Box<
+        Future<Item = Vec<Result<Value, web3::error::Error>>, Error = web3::error::Error> + Send,
+    >;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        let requests = requests.into_iter().collect::<Vec<_>>();
+        let recorded_requests = requests
+            .iter()
+            .map(|(id, _)| self.take_pending(*id))
+            .collect::<Vec<_>>();
+        let fixture = self.fixture.clone();
+
+        Box::new(self.transport.send_batch(requests).map(move |results| {
+            for (recorded_request, result) in recorded_requests.into_iter().zip(results.iter()) {
+                if let Ok(result) = result {
+                    record(&fixture, recorded_request, result);
+                }
+            }
+            results
+        }))
+    }
+}
+
+/// A web3 transport that answers requests with the results recorded in an `EthereumFixture`,
+/// without connecting to an Ethereum node.
+///
+/// If the same request was recorded several times, the recorded results are returned in
+/// order and the last one is repeated once all others have been returned. This allows e.g.
+/// repeated polling for the latest block to observe the chain progressing the same way it
+/// did during the recording.
+#[derive(Clone, Debug)]
+pub struct ReplayTransport {
+    next_id: Arc<AtomicUsize>,
+    pending: Arc<Mutex<HashMap<RequestId, String>>>,
+    responses: Arc<Mutex<HashMap<String, VecDeque<Value>>>>,
+}
+
+impl ReplayTransport {
+    pub fn new(fixture: EthereumFixture) -> Self {
+        let mut responses = HashMap::new();
+        for call in fixture.calls {
+            responses
+                .entry(call.key())
+                .or_insert_with(VecDeque::new)
+                .push_back(call.result);
+        }
+
+        ReplayTransport {
+            next_id: Arc::new(AtomicUsize::new(1)),
+            pending: Arc::new(Mutex::new(HashMap::new())),
+            responses: Arc::new(Mutex::new(responses)),
+        }
+    }
+
+    fn response(&self, id: RequestId) -> Result<Value, web3::error::Error> {
+        let key = self.pending.lock().unwrap().remove(&id).ok_or_else(|| {
+            web3::error::ErrorKind::Transport(format!("unknown request id: {}", id))
+        })?;
+
+        let mut responses = self.responses.lock().unwrap();
+        let recorded = responses.get_mut(&key).ok_or_else(|| {
+            web3::error::ErrorKind::Transport(format!("no recorded response for {}", key))
+        })?;
+
+        Ok(if recorded.len() > 1 {
+            recorded.pop_front().unwrap()
+        } else {
+            recorded.front().cloned().unwrap()
+        })
+    }
+}
+
+impl web3::Transport for ReplayTransport {
+    type Out = Box<Future<Item = Value, Error = web3::error::Error> + Send>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, Call) {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(id, call_key(method, &params));
+        (id, build_request(id, method, params))
+    }
+
+    fn send(&self, id: RequestId, _request: Call) -> Self::Out {
+        Box::new(future::result(self.response(id)))
+    }
+}
+
+impl web3::BatchTransport for ReplayTransport {
+    type Batch = Box<
+        Future<Item = Vec<Result<Value, web3::error::Error>>, Error = web3::error::Error> + Send,
+    >;
+
+    fn send_batch<I>(&self, requests: I) -> Self::Batch
+    where
+        I: IntoIterator<Item = (RequestId, Call)>,
+    {
+        Box::new(future::ok(
+            requests
+                .into_iter()
+                .map(|(id, _)| self.response(id))
+                .collect(),
+        ))
+    }
+}
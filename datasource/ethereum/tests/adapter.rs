@@ -172,3 +172,45 @@ fn contract_call() {
 
     assert_eq!(call_result[0], Token::Uint(U256::from(100000)));
 }
+
+#[test]
+fn balance_and_code_are_requested_by_block_hash() {
+    let mut transport = TestTransport::default();
+
+    transport.add_response(jsonrpc_core::Value::String("0x64".to_owned()));
+    transport.add_response(jsonrpc_core::Value::String("0x6080".to_owned()));
+
+    let logger = Logger::root(slog::Discard, o!());
+    let adapter = EthereumAdapter::new(transport.clone());
+    let address = Address::from_str("00d04c4b12C4686305bb4F4fC93487CdFBa62580").unwrap();
+    let block_ptr = EthereumBlockPointer::from((H256::from(1), 1 as i64));
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let balance = runtime
+        .block_on(adapter.balance(&logger, address, block_ptr))
+        .unwrap();
+    let code = runtime
+        .block_on(adapter.code(&logger, address, block_ptr))
+        .unwrap();
+
+    assert_eq!(balance, U256::from(100));
+    assert_eq!(code, vec![0x60, 0x80]);
+
+    let block =
+        r#"{"blockHash":"0x0000000000000000000000000000000000000000000000000000000000000001"}"#;
+    transport.assert_request(
+        "eth_getBalance",
+        &[
+            r#""0x00d04c4b12c4686305bb4f4fc93487cdfba62580""#.to_owned(),
+            block.to_owned(),
+        ],
+    );
+    transport.assert_request(
+        "eth_getCode",
+        &[
+            r#""0x00d04c4b12c4686305bb4f4fc93487cdfba62580""#.to_owned(),
+            block.to_owned(),
+        ],
+    );
+    transport.assert_no_more_requests();
+}
@@ -0,0 +1,212 @@
+extern crate futures;
+extern crate graph;
+extern crate graph_datasource_ethereum;
+extern crate graph_mock;
+
+use std::env;
+
+use graph::components::ethereum::{EthereumContractCall, EthereumContractCallError};
+use graph::ethabi::Token;
+use graph::prelude::BlockIntegrityChecker as BlockIntegrityCheckerTrait;
+use graph::prelude::*;
+use graph::web3::types::*;
+use graph_datasource_ethereum::BlockIntegrityChecker;
+use graph_mock::{chain_block, MockChainStore};
+
+fn ptr(block: &EthereumBlock) -> EthereumBlockPointer {
+    block.into()
+}
+
+/// An Ethereum node whose main chain consists of the first `length` blocks of fork 0.
+struct MockEthereumAdapter {
+    length: u64,
+}
+
+impl EthereumAdapter for MockEthereumAdapter {
+    fn net_identifiers(
+        &self,
+        _: &Logger,
+    ) -> Box<Future<Item = EthereumNetworkIdentifier, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn block_by_hash(
+        &self,
+        _: &Logger,
+        block_hash: H256,
+    ) -> Box<Future<Item = Option<EthereumBlock>, Error = Error> + Send> {
+        Box::new(future::ok(
+            (0..self.length)
+                .map(|number| chain_block(number, 0))
+                .find(|block| block.block.hash == Some(block_hash)),
+        ))
+    }
+
+    fn block_hash_by_block_number(
+        &self,
+        _: &Logger,
+        block_number: u64,
+    ) -> Box<Future<Item = Option<H256>, Error = Error> + Send> {
+        Box::new(future::ok(if block_number < self.length {
+            chain_block(block_number, 0).block.hash
+        } else {
+            None
+        }))
+    }
+
+    fn is_on_main_chain(
+        &self,
+        _: &Logger,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = bool, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn find_first_blocks_with_logs(
+        &self,
+        _: &Logger,
+        _: u64,
+        _: u64,
+        _: EthereumLogFilter,
+    ) -> Box<Future<Item = Vec<EthereumBlockPointer>, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn contract_call(
+        &self,
+        _: &Logger,
+        _: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        unimplemented!();
+    }
+    fn contract_call_at_block_hash(
+        &self,
+        _: &Logger,
+        _: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        unimplemented!();
+    }
+
+    fn balance(
+        &self,
+        _: &Logger,
+        _: Address,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = U256, Error = Error> + Send> {
+        unimplemented!();
+    }
+
+    fn code(
+        &self,
+        _: &Logger,
+        _: Address,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = Bytes, Error = Error> + Send> {
+        unimplemented!();
+    }
+}
+
+fn checker(
+    chain_store: Arc<MockChainStore>,
+) -> BlockIntegrityChecker<MockChainStore, MockEthereumAdapter> {
+    BlockIntegrityChecker::new(chain_store, Arc::new(MockEthereumAdapter { length: 6 }))
+}
+
+#[test]
+fn check_blocks_reports_and_repairs_inconsistent_blocks() {
+    let logger = Logger::root(slog::Discard, o!());
+
+    // Block 2 is missing, block 3 was uncled and block 4 was stored with the parent hash
+    // of the uncled block
+    let mut block_4 = chain_block(4, 0);
+    block_4.block.parent_hash = chain_block(3, 1).block.hash.unwrap();
+    let chain_store = Arc::new(MockChainStore::new(vec![
+        chain_block(0, 0),
+        chain_block(1, 0),
+        chain_block(3, 0),
+        chain_block(3, 1),
+        block_4,
+        chain_block(5, 0),
+    ]));
+
+    let report = checker(chain_store.clone())
+        .check_blocks(&logger, 1, 5)
+        .wait()
+        .unwrap();
+
+    assert_eq!(
+        report,
+        BlockIntegrityReport {
+            from: 1,
+            to: 5,
+            missing_blocks: vec![ptr(&chain_block(2, 0))],
+            parent_hash_mismatches: vec![ptr(&chain_block(4, 0))],
+            non_canonical_blocks: vec![ptr(&chain_block(3, 1))],
+            repaired_blocks: 2,
+        }
+    );
+
+    // The missing block was added and the inconsistent one replaced
+    assert!(chain_store
+        .block(chain_block(2, 0).block.hash.unwrap())
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        chain_store
+            .block(chain_block(4, 0).block.hash.unwrap())
+            .unwrap()
+            .unwrap()
+            .block
+            .parent_hash,
+        chain_block(3, 0).block.hash.unwrap()
+    );
+
+    // Checking again finds nothing to repair but the uncled block
+    let report = checker(chain_store)
+        .check_blocks(&logger, 1, 5)
+        .wait()
+        .unwrap();
+    assert_eq!(report.missing_blocks, vec![]);
+    assert_eq!(report.parent_hash_mismatches, vec![]);
+    assert_eq!(report.repaired_blocks, 0);
+
+    // Invalid settings are rejected; this is checked here rather than in a separate test
+    // because tests run in parallel and share the environment
+    env::set_var("ETHEREUM_BLOCK_CHECK_PARALLEL_REQUESTS", "0");
+    let result = checker(Arc::new(MockChainStore::default()))
+        .check_blocks(&logger, 0, 1)
+        .wait();
+    env::remove_var("ETHEREUM_BLOCK_CHECK_PARALLEL_REQUESTS");
+    assert_eq!(
+        result.unwrap_err().to_string(),
+        "ETHEREUM_BLOCK_CHECK_PARALLEL_REQUESTS must be a positive number, \
+         but is set to `0`"
+    );
+}
+
+#[test]
+fn check_blocks_rejects_invalid_ranges() {
+    let logger = Logger::root(slog::Discard, o!());
+    let checker = checker(Arc::new(MockChainStore::default()));
+
+    let error = checker.check_blocks(&logger, 5, 1).wait().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid block range: 5 is greater than 1"
+    );
+
+    let error = checker.check_blocks(&logger, 1, 10_000).wait().unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "invalid block range: at most 10000 blocks can be checked at once, \
+         but 1 to 10000 contains 10000"
+    );
+
+    let error = checker
+        .check_blocks(&logger, 0, u64::max_value())
+        .wait()
+        .unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with("invalid block range: at most 10000 blocks"));
+}
@@ -0,0 +1,247 @@
+extern crate futures;
+extern crate graph;
+extern crate graph_datasource_ethereum;
+extern crate graph_mock;
+extern crate jsonrpc_core;
+
+use futures::finished;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use graph::prelude::*;
+use graph::serde_json;
+use graph::web3::error::Error;
+use graph::web3::helpers::*;
+use graph::web3::types::*;
+use graph::web3::{BatchTransport, RequestId, Transport};
+use graph_datasource_ethereum::BlockIngestor;
+use graph_mock::MockChainHeadUpdateListener;
+
+pub type Result<T> = Box<Future<Item = T, Error = Error> + Send + 'static>;
+
+/// Transport that answers requests with queued responses, in order. Requests beyond the
+/// queued responses fail the test.
+#[derive(Debug, Default, Clone)]
+pub struct TestTransport {
+    responses: Arc<Mutex<VecDeque<jsonrpc_core::Value>>>,
+}
+
+impl Transport for TestTransport {
+    type Out = Result<jsonrpc_core::Value>;
+
+    fn prepare(
+        &self,
+        method: &str,
+        params: Vec<jsonrpc_core::Value>,
+    ) -> (RequestId, jsonrpc_core::Call) {
+        (1, build_request(1, method, params))
+    }
+
+    fn send(&self, id: RequestId, request: jsonrpc_core::Call) -> Result<jsonrpc_core::Value> {
+        match self.responses.lock().unwrap().pop_front() {
+            Some(response) => Box::new(finished(response)),
+            None => panic!("Unexpected request (id: {:?}): {:?}", id, request),
+        }
+    }
+}
+
+impl BatchTransport for TestTransport {
+    type Batch = Result<Vec<::std::result::Result<jsonrpc_core::Value, Error>>>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, jsonrpc_core::Call)>,
+    {
+        Box::new(
+            stream::futures_ordered(
+                requests
+                    .into_iter()
+                    .map(|(id, req)| self.send(id, req).map(|v| Ok(v))),
+            )
+            .collect(),
+        )
+    }
+}
+
+impl TestTransport {
+    /// Queues the responses of the Ethereum node to a health check.
+    fn add_health_check_responses(&self, latest_block_number: u64, net_version: &str) {
+        let mut responses = self.responses.lock().unwrap();
+        responses.push_back(serde_json::to_value(U256::from(latest_block_number)).unwrap());
+        responses.push_back(jsonrpc_core::Value::String(net_version.to_owned()));
+        responses.push_back(serde_json::to_value(block::<H256>(0, 0)).unwrap());
+    }
+}
+
+/// Returns block `number`, created `age` seconds ago.
+fn block<T>(number: u64, age: u64) -> Block<T> {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
+    Block {
+        hash: Some(H256::from(number + 1)),
+        parent_hash: H256::from(number),
+        uncles_hash: H256::default(),
+        author: H160::default(),
+        state_root: H256::default(),
+        transactions_root: H256::default(),
+        receipts_root: H256::default(),
+        number: Some(U128::from(number)),
+        gas_used: U256::from(100),
+        gas_limit: U256::from(1000),
+        extra_data: Bytes(vec![]),
+        logs_bloom: H2048::default(),
+        timestamp: U256::from(now.as_secs() - age),
+        difficulty: U256::from(10),
+        total_difficulty: U256::from(100),
+        seal_fields: vec![],
+        uncles: vec![],
+        transactions: vec![],
+        size: Some(U256::from(10000)),
+    }
+}
+
+/// A chain store that only knows its chain head block.
+struct ChainHeadStore {
+    head: Option<EthereumBlock>,
+}
+
+impl ChainStore for ChainHeadStore {
+    type ChainHeadUpdateListener = MockChainHeadUpdateListener;
+
+    fn genesis_block_ptr(&self) -> ::std::result::Result<EthereumBlockPointer, failure::Error> {
+        unimplemented!()
+    }
+
+    fn upsert_blocks<'a, B, E>(&self, _: B) -> Box<Future<Item = (), Error = E> + Send + 'a>
+    where
+        B: Stream<Item = EthereumBlock, Error = E> + Send + 'a,
+        E: From<failure::Error> + Send + 'a,
+    {
+        unimplemented!()
+    }
+
+    fn attempt_chain_head_update(
+        &self,
+        _: u64,
+    ) -> ::std::result::Result<Vec<H256>, failure::Error> {
+        unimplemented!()
+    }
+
+    fn chain_head_updates(&self) -> Self::ChainHeadUpdateListener {
+        unimplemented!()
+    }
+
+    fn chain_head_ptr(
+        &self,
+    ) -> ::std::result::Result<Option<EthereumBlockPointer>, failure::Error> {
+        Ok(self.head.as_ref().map(EthereumBlockPointer::from))
+    }
+
+    fn block(
+        &self,
+        block_hash: H256,
+    ) -> ::std::result::Result<Option<EthereumBlock>, failure::Error> {
+        Ok(self
+            .head
+            .clone()
+            .filter(|head| head.block.hash == Some(block_hash)))
+    }
+
+    fn block_ptrs_in_range(
+        &self,
+        _: u64,
+        _: u64,
+    ) -> ::std::result::Result<Vec<(EthereumBlockPointer, H256)>, failure::Error> {
+        unimplemented!()
+    }
+
+    fn ancestor_block(
+        &self,
+        _: EthereumBlockPointer,
+        _: u64,
+    ) -> ::std::result::Result<Option<EthereumBlock>, failure::Error> {
+        unimplemented!()
+    }
+}
+
+/// Checks the health of a chain head block `head` against an Ethereum node on network 1,
+/// allowing the head to be at most 60 seconds old and 5 blocks behind.
+fn check_health(head: Option<Block<Transaction>>, transport: TestTransport) -> ChainHeadHealth {
+    let chain_store = ChainHeadStore {
+        head: head.map(|block| EthereumBlock {
+            block,
+            transaction_receipts: vec![],
+        }),
+    };
+    let net_identifiers = EthereumNetworkIdentifier {
+        net_version: "1".to_owned(),
+        genesis_block_hash: block::<H256>(0, 0).hash.unwrap(),
+    };
+
+    let block_ingestor = BlockIngestor::new(
+        Arc::new(chain_store),
+        transport,
+        50,
+        net_identifiers,
+        ChainHealth::new(),
+        Duration::from_secs(60),
+        5,
+        Duration::from_secs(30),
+        Logger::root(slog::Discard, o!()),
+        Duration::from_millis(500),
+    )
+    .unwrap();
+
+    block_ingestor.check_health().wait().unwrap()
+}
+
+#[test]
+fn recent_chain_head_is_healthy() {
+    let transport = TestTransport::default();
+    transport.add_health_check_responses(102, "1");
+
+    let health = check_health(Some(block(100, 10)), transport);
+
+    assert!(health.healthy, "unexpected problems: {:?}", health.problems);
+    assert_eq!(health.head_block.map(|ptr| ptr.number), Some(100));
+    assert_eq!(health.head_block_lag, Some(2));
+    assert_eq!(health.latest_block_number, Some(102));
+    assert!(health.head_block_age.unwrap() < Duration::from_secs(60));
+    assert_eq!(health.problems, Vec::<String>::new());
+}
+
+#[test]
+fn stale_chain_head_on_other_network_is_unhealthy() {
+    let transport = TestTransport::default();
+    transport.add_health_check_responses(120, "3");
+
+    let health = check_health(Some(block(100, 3600)), transport);
+
+    assert!(!health.healthy);
+    assert_eq!(health.head_block_lag, Some(20));
+    assert!(health.head_block_age.unwrap() >= Duration::from_secs(3600));
+    assert_eq!(health.problems.len(), 3, "problems: {:?}", health.problems);
+    assert!(health.problems[0].starts_with("chain head block #100 is 36"));
+    assert_eq!(
+        health.problems[1],
+        "chain head block #100 is 20 blocks behind the latest block #120 of the Ethereum node"
+    );
+    assert_eq!(
+        health.problems[2],
+        "Ethereum node reports net version 3 instead of 1"
+    );
+}
+
+#[test]
+fn missing_chain_head_is_unhealthy() {
+    let transport = TestTransport::default();
+    transport.add_health_check_responses(100, "1");
+
+    let health = check_health(None, transport);
+
+    assert!(!health.healthy);
+    assert_eq!(health.head_block, None);
+    assert_eq!(health.head_block_lag, None);
+    assert_eq!(health.latest_block_number, Some(100));
+    assert_eq!(health.problems, vec!["there is no chain head block yet"]);
+}
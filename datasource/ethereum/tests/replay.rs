@@ -0,0 +1,346 @@
+extern crate futures;
+extern crate graph;
+extern crate graph_datasource_ethereum;
+extern crate graph_mock;
+extern crate jsonrpc_core;
+
+use futures::future;
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+use graph::prelude::EthereumAdapter as EthereumAdapterTrait;
+use graph::prelude::*;
+use graph::serde_json::{self, Value};
+use graph::web3::error::{Error, ErrorKind};
+use graph::web3::helpers::build_request;
+use graph::web3::types::*;
+use graph::web3::{BatchTransport, RequestId, Transport};
+use graph_datasource_ethereum::{
+    EthereumAdapter, EthereumFixture, RecordedCall, RecordingTransport, ReplayTransport,
+};
+use graph_mock::MockChainStore;
+
+fn genesis_block() -> Block<H256> {
+    Block {
+        hash: Some(H256::from(1)),
+        parent_hash: H256::default(),
+        uncles_hash: H256::default(),
+        author: H160::default(),
+        state_root: H256::default(),
+        transactions_root: H256::default(),
+        receipts_root: H256::default(),
+        number: Some(U128::from(0)),
+        gas_used: U256::from(0),
+        gas_limit: U256::from(1000),
+        extra_data: Bytes(vec![]),
+        logs_bloom: H2048::default(),
+        timestamp: U256::from(100000),
+        difficulty: U256::from(10),
+        total_difficulty: U256::from(10),
+        seal_fields: vec![],
+        uncles: vec![],
+        transactions: vec![],
+        size: Some(U256::from(500)),
+    }
+}
+
+fn block_hash(number: u64) -> H256 {
+    H256::from(number + 1)
+}
+
+fn transaction_hash(number: u64) -> H256 {
+    H256::from(number + 1000)
+}
+
+/// Returns block `number` with full transactions, as returned by `eth_getBlockByHash`.
+///
+/// All blocks except for the genesis block contain a single transaction.
+fn full_block(number: u64) -> Value {
+    let mut block = serde_json::to_value(genesis_block()).unwrap();
+    if number > 0 {
+        let transaction = Transaction {
+            hash: transaction_hash(number),
+            nonce: U256::from(0),
+            block_hash: Some(block_hash(number)),
+            block_number: Some(U256::from(number)),
+            transaction_index: Some(U128::from(0)),
+            from: H160::default(),
+            to: Some(H160::default()),
+            value: U256::from(0),
+            gas_price: U256::from(0),
+            gas: U256::from(0),
+            input: Bytes(vec![]),
+        };
+
+        block["hash"] = serde_json::to_value(block_hash(number)).unwrap();
+        block["parentHash"] = serde_json::to_value(block_hash(number - 1)).unwrap();
+        block["number"] = serde_json::to_value(U128::from(number)).unwrap();
+        block["transactions"] = Value::Array(vec![serde_json::to_value(transaction).unwrap()]);
+    }
+    block
+}
+
+/// Returns the receipt of the transaction in block `number`.
+fn receipt(number: u64) -> Value {
+    Value::Object(
+        vec![
+            (
+                "transactionHash",
+                serde_json::to_value(transaction_hash(number)),
+            ),
+            ("transactionIndex", serde_json::to_value(U128::from(0))),
+            ("blockHash", serde_json::to_value(block_hash(number))),
+            ("blockNumber", serde_json::to_value(U256::from(number))),
+            ("cumulativeGasUsed", serde_json::to_value(U256::from(0))),
+            ("gasUsed", serde_json::to_value(U256::from(0))),
+            ("logs", Ok(Value::Array(vec![]))),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.unwrap()))
+        .collect(),
+    )
+}
+
+fn recorded_call(method: &str, params: Vec<Value>, result: Value) -> RecordedCall {
+    RecordedCall {
+        method: method.to_owned(),
+        params,
+        result,
+    }
+}
+
+/// Returns a fixture with the blocks `numbers`, as recorded when fetching them by hash.
+fn chain_fixture(numbers: Vec<u64>) -> EthereumFixture {
+    let mut calls = vec![];
+    for number in numbers {
+        calls.push(recorded_call(
+            "eth_getBlockByHash",
+            vec![
+                serde_json::to_value(block_hash(number)).unwrap(),
+                Value::Bool(true),
+            ],
+            full_block(number),
+        ));
+        if number > 0 {
+            calls.push(recorded_call(
+                "eth_getTransactionReceipt",
+                vec![serde_json::to_value(transaction_hash(number)).unwrap()],
+                receipt(number),
+            ));
+        }
+    }
+    EthereumFixture { calls }
+}
+
+/// Answers requests based on their method only and counts the requests it receives.
+#[derive(Debug, Default, Clone)]
+struct StaticTransport {
+    responses: HashMap<String, Value>,
+    request_count: Arc<Mutex<usize>>,
+}
+
+impl Transport for StaticTransport {
+    type Out = Box<Future<Item = Value, Error = Error> + Send>;
+
+    fn prepare(&self, method: &str, params: Vec<Value>) -> (RequestId, jsonrpc_core::Call) {
+        (1, build_request(1, method, params))
+    }
+
+    fn send(&self, _id: RequestId, request: jsonrpc_core::Call) -> Self::Out {
+        *self.request_count.lock().unwrap() += 1;
+
+        let method = match request {
+            jsonrpc_core::Call::MethodCall(call) => call.method,
+            _ => return Box::new(future::err(ErrorKind::Unreachable.into())),
+        };
+        Box::new(future::result(
+            self.responses
+                .get(&method)
+                .cloned()
+                .ok_or_else(|| ErrorKind::Unreachable.into()),
+        ))
+    }
+}
+
+impl BatchTransport for StaticTransport {
+    type Batch = Box<Future<Item = Vec<Result<Value, Error>>, Error = Error> + Send>;
+
+    fn send_batch<T>(&self, requests: T) -> Self::Batch
+    where
+        T: IntoIterator<Item = (RequestId, jsonrpc_core::Call)>,
+    {
+        Box::new(future::ok(
+            requests
+                .into_iter()
+                .map(|(id, request)| self.send(id, request).wait())
+                .collect(),
+        ))
+    }
+}
+
+#[test]
+fn replays_recorded_net_identifiers() {
+    let logger = Logger::root(slog::Discard, o!());
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+
+    let mut transport = StaticTransport::default();
+    transport
+        .responses
+        .insert("net_version".to_owned(), Value::String("3".to_owned()));
+    transport.responses.insert(
+        "eth_getBlockByNumber".to_owned(),
+        serde_json::to_value(genesis_block()).unwrap(),
+    );
+    let request_count = transport.request_count.clone();
+
+    // Record the net identifiers from the live transport
+    let recording_transport = RecordingTransport::new(transport);
+    let recorded = runtime
+        .block_on(EthereumAdapter::new(recording_transport.clone()).net_identifiers(&logger))
+        .unwrap();
+    assert_eq!(*request_count.lock().unwrap(), 2);
+
+    // Write the fixture to disk and load it again
+    let path = env::temp_dir().join("graph-node-replay-test-fixture.json");
+    recording_transport.save(&path).unwrap();
+    let fixture = EthereumFixture::load(&path).unwrap();
+    assert_eq!(fixture.calls.len(), 2);
+
+    // Replaying the fixture must not hit the live transport
+    let replayed = runtime
+        .block_on(EthereumAdapter::new(ReplayTransport::new(fixture)).net_identifiers(&logger))
+        .unwrap();
+    assert_eq!(*request_count.lock().unwrap(), 2);
+
+    assert_eq!(replayed.net_version, recorded.net_version);
+    assert_eq!(replayed.net_version, "3");
+    assert_eq!(replayed.genesis_block_hash, recorded.genesis_block_hash);
+    assert_eq!(replayed.genesis_block_hash, H256::from(1));
+}
+
+#[test]
+fn replays_repeated_calls_in_order() {
+    let fixture = EthereumFixture {
+        calls: vec!["0x1", "0x2", "0x3"]
+            .into_iter()
+            .map(|number| RecordedCall {
+                method: "eth_blockNumber".to_owned(),
+                params: vec![],
+                result: Value::String(number.to_owned()),
+            })
+            .collect(),
+    };
+    let transport = ReplayTransport::new(fixture);
+
+    let block_number = || {
+        let (id, request) = transport.prepare("eth_blockNumber", vec![]);
+        transport.send(id, request).wait().unwrap()
+    };
+
+    // Recorded results are returned in order, the last one is repeated
+    assert_eq!(block_number(), Value::String("0x1".to_owned()));
+    assert_eq!(block_number(), Value::String("0x2".to_owned()));
+    assert_eq!(block_number(), Value::String("0x3".to_owned()));
+    assert_eq!(block_number(), Value::String("0x3".to_owned()));
+
+    // Requests that were never recorded fail
+    let (id, request) = transport.prepare("eth_gasPrice", vec![]);
+    assert!(transport.send(id, request).wait().is_err());
+}
+
+#[test]
+fn fixture_blocks_are_ordered_and_include_receipts() {
+    let mut fixture = chain_fixture(vec![2, 1]);
+    fixture.calls.extend(vec![
+        // Blocks without full transactions are not part of the chain
+        recorded_call(
+            "eth_getBlockByNumber",
+            vec![Value::String("0x0".to_owned()), Value::Bool(false)],
+            serde_json::to_value(genesis_block()).unwrap(),
+        ),
+        // Neither are blocks that the Ethereum node did not know about
+        recorded_call(
+            "eth_getBlockByHash",
+            vec![
+                serde_json::to_value(block_hash(3)).unwrap(),
+                Value::Bool(true),
+            ],
+            Value::Null,
+        ),
+    ]);
+
+    let blocks = fixture.blocks().unwrap();
+    assert_eq!(
+        blocks
+            .iter()
+            .map(|block| block.block.number.unwrap().as_u64())
+            .collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    for block in blocks {
+        assert_eq!(block.transaction_receipts.len(), 1);
+        assert_eq!(
+            block.transaction_receipts[0].transaction_hash,
+            block.block.transactions[0].hash
+        );
+    }
+}
+
+#[test]
+fn fixture_blocks_require_transaction_receipts() {
+    let mut fixture = chain_fixture(vec![1, 2]);
+    // Drop the receipt of the transaction in block 2
+    fixture.calls.retain(|call| {
+        call.method != "eth_getTransactionReceipt"
+            || call.params[0] == serde_json::to_value(transaction_hash(1)).unwrap()
+    });
+
+    let error = fixture.blocks().unwrap_err();
+    assert!(error
+        .to_string()
+        .contains("fixture is missing transaction receipt"));
+}
+
+#[test]
+fn feeds_chain_store_from_fixture() {
+    let chain_store = Arc::new(MockChainStore::default());
+
+    chain_fixture(vec![0, 1, 2])
+        .feed_chain_store(chain_store.clone(), 2)
+        .wait()
+        .unwrap();
+
+    assert_eq!(
+        chain_store.chain_head_ptr().unwrap(),
+        Some((block_hash(2), 2u64).into())
+    );
+    for number in 0..3 {
+        let block = chain_store.block(block_hash(number)).unwrap().unwrap();
+        assert_eq!(block.block.number, Some(U128::from(number)));
+    }
+    assert_eq!(
+        chain_store
+            .block(block_hash(1))
+            .unwrap()
+            .unwrap()
+            .transaction_receipts[0]
+            .transaction_hash,
+        transaction_hash(1)
+    );
+}
+
+#[test]
+fn feeding_chain_store_requires_ancestor_blocks() {
+    let chain_store = Arc::new(MockChainStore::default());
+
+    let error = chain_fixture(vec![1, 2])
+        .feed_chain_store(chain_store.clone(), 2)
+        .wait()
+        .unwrap_err();
+
+    assert!(error
+        .to_string()
+        .contains("fixture is missing ancestor blocks of the chain head"));
+    assert_eq!(chain_store.chain_head_ptr().unwrap(), None);
+}
@@ -10,6 +10,7 @@ use web3::types::*;
 use super::types::*;
 
 /// A collection of attributes that (kind of) uniquely identify an Ethereum blockchain.
+#[derive(Clone, Debug, PartialEq)]
 pub struct EthereumNetworkIdentifier {
     pub net_version: String,
     pub genesis_block_hash: H256,
@@ -205,4 +206,34 @@ pub trait EthereumAdapter: Send + Sync + 'static {
         logger: &Logger,
         call: EthereumContractCall,
     ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send>;
+
+    /// Like `contract_call`, but requests the block `call.block_ptr` by hash, so the result
+    /// does not depend on which block with that number is on the main chain.
+    fn contract_call_at_block_hash(
+        &self,
+        logger: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send>;
+
+    /// Get the balance of `address` in Wei as of the block `block_ptr`.
+    ///
+    /// The block is requested by hash, so the balance is the same no matter which blocks are
+    /// on the main chain when this is called.
+    fn balance(
+        &self,
+        logger: &Logger,
+        address: Address,
+        block_ptr: EthereumBlockPointer,
+    ) -> Box<Future<Item = U256, Error = Error> + Send>;
+
+    /// Get the code of the contract at `address` as of the block `block_ptr`. The code is empty
+    /// if there is no contract at that address.
+    ///
+    /// Like `balance`, this requests the block by hash.
+    fn code(
+        &self,
+        logger: &Logger,
+        address: Address,
+        block_ptr: EthereumBlockPointer,
+    ) -> Box<Future<Item = Bytes, Error = Error> + Send>;
 }
@@ -0,0 +1,45 @@
+use failure::Error;
+use futures::Future;
+
+use prelude::*;
+
+/// Summary of a block integrity check over a range of block numbers.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BlockIntegrityReport {
+    /// First block number that was checked.
+    pub from: u64,
+
+    /// Last block number that was checked.
+    pub to: u64,
+
+    /// Main chain blocks that were not present in the chain store.
+    pub missing_blocks: Vec<EthereumBlockPointer>,
+
+    /// Main chain blocks whose stored parent hash did not match the hash of the main chain
+    /// block before them.
+    pub parent_hash_mismatches: Vec<EthereumBlockPointer>,
+
+    /// Stored blocks that are not on the main chain (e.g. because they were uncled).
+    pub non_canonical_blocks: Vec<EthereumBlockPointer>,
+
+    /// Number of blocks that were re-fetched from the Ethereum node and written to the chain
+    /// store.
+    pub repaired_blocks: u64,
+}
+
+/// Common trait for components that verify and repair the blocks in a chain store.
+pub trait BlockIntegrityChecker: Send + Sync + 'static {
+    /// Compare the blocks with numbers in the range `[from, to]` in the chain store against
+    /// the main chain according to the Ethereum node and re-fetch main chain blocks that are
+    /// missing or have an inconsistent parent hash.
+    ///
+    /// Careful: block numbers are only reliable for blocks that have received enough
+    /// confirmations to guarantee no further reorgs. Checking blocks closer to the chain
+    /// head may report false positives.
+    fn check_blocks(
+        &self,
+        logger: &Logger,
+        from: u64,
+        to: u64,
+    ) -> Box<Future<Item = BlockIntegrityReport, Error = Error> + Send>;
+}
@@ -0,0 +1,111 @@
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use super::types::EthereumBlockPointer;
+
+/// Result of the most recent chain head health check.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChainHeadHealth {
+    /// `false` if any problems were detected.
+    pub healthy: bool,
+
+    /// The chain head block in the chain store.
+    pub head_block: Option<EthereumBlockPointer>,
+
+    /// Time elapsed since the timestamp of the chain head block.
+    pub head_block_age: Option<Duration>,
+
+    /// Latest block number reported by the Ethereum node.
+    pub latest_block_number: Option<u64>,
+
+    /// Number of blocks the chain head block is behind the latest block of the Ethereum node.
+    pub head_block_lag: Option<u64>,
+
+    /// Number of consecutive health checks, up to and including this one, that detected
+    /// problems. Maintained by `ChainHealth::set`.
+    pub unhealthy_checks: u64,
+
+    /// Human-readable descriptions of the problems that were detected.
+    pub problems: Vec<String>,
+}
+
+impl Default for ChainHeadHealth {
+    fn default() -> Self {
+        ChainHeadHealth {
+            healthy: false,
+            head_block: None,
+            head_block_age: None,
+            latest_block_number: None,
+            head_block_lag: None,
+            unhealthy_checks: 0,
+            problems: vec!["chain head has not been checked yet".to_owned()],
+        }
+    }
+}
+
+/// Shared handle to the chain head health.
+///
+/// Updated by the block ingestor and read by components that report on the health
+/// of the node, e.g. the admin server.
+#[derive(Clone, Debug, Default)]
+pub struct ChainHealth {
+    health: Arc<RwLock<ChainHeadHealth>>,
+}
+
+impl ChainHealth {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the result of the most recent health check.
+    pub fn get(&self) -> ChainHeadHealth {
+        self.health.read().unwrap().clone()
+    }
+
+    /// Replaces the result of the most recent health check and updates the number of
+    /// consecutive unhealthy checks.
+    pub fn set(&self, mut health: ChainHeadHealth) {
+        let mut current = self.health.write().unwrap();
+        health.unhealthy_checks = if health.healthy {
+            0
+        } else {
+            current.unhealthy_checks + 1
+        };
+        *current = health;
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.health.read().unwrap().healthy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn health(healthy: bool) -> ChainHeadHealth {
+        ChainHeadHealth {
+            healthy,
+            problems: vec![],
+            ..ChainHeadHealth::default()
+        }
+    }
+
+    #[test]
+    fn counts_consecutive_unhealthy_checks() {
+        let chain_health = ChainHealth::new();
+        assert!(!chain_health.is_healthy());
+        assert_eq!(chain_health.get().unhealthy_checks, 0);
+
+        chain_health.set(health(false));
+        chain_health.set(health(false));
+        assert_eq!(chain_health.get().unhealthy_checks, 2);
+
+        chain_health.set(health(true));
+        assert!(chain_health.is_healthy());
+        assert_eq!(chain_health.get().unhealthy_checks, 0);
+
+        chain_health.set(health(false));
+        assert_eq!(chain_health.get().unhealthy_checks, 1);
+    }
+}
@@ -1,4 +1,6 @@
 mod adapter;
+mod checker;
+mod health;
 mod listener;
 mod stream;
 mod types;
@@ -8,9 +10,11 @@ pub use self::adapter::{
     EthereumContractStateError, EthereumContractStateRequest, EthereumError, EthereumLogFilter,
     EthereumNetworkIdentifier,
 };
+pub use self::checker::{BlockIntegrityChecker, BlockIntegrityReport};
+pub use self::health::{ChainHeadHealth, ChainHealth};
 pub use self::listener::{ChainHeadUpdate, ChainHeadUpdateListener};
 pub use self::stream::{BlockStream, BlockStreamBuilder};
 pub use self::types::{
     EthereumBlock, EthereumBlockData, EthereumBlockPointer, EthereumEventData,
-    EthereumTransactionData,
+    EthereumTransactionData, EthereumTransactionReceiptData,
 };
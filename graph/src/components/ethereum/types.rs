@@ -13,6 +13,12 @@ impl EthereumBlock {
             .and_then(|hash| self.block.transactions.iter().find(|tx| tx.hash == hash))
             .cloned()
     }
+
+    pub fn transaction_receipt(&self, transaction_hash: H256) -> Option<&TransactionReceipt> {
+        self.transaction_receipts
+            .iter()
+            .find(|receipt| receipt.transaction_hash == transaction_hash)
+    }
 }
 
 /// Ethereum block data.
@@ -32,6 +38,7 @@ pub struct EthereumBlockData {
     pub difficulty: U256,
     pub total_difficulty: U256,
     pub size: Option<U256>,
+    pub uncles: Vec<H256>,
 }
 
 impl<'a, T> From<&'a Block<T>> for EthereumBlockData {
@@ -51,6 +58,7 @@ impl<'a, T> From<&'a Block<T>> for EthereumBlockData {
             difficulty: block.difficulty,
             total_difficulty: block.total_difficulty,
             size: block.size,
+            uncles: block.uncles.clone(),
         }
     }
 }
@@ -65,6 +73,8 @@ pub struct EthereumTransactionData {
     pub value: U256,
     pub gas_used: U256,
     pub gas_price: U256,
+    pub nonce: U256,
+    pub input: Bytes,
 }
 
 impl<'a> From<&'a Transaction> for EthereumTransactionData {
@@ -77,6 +87,40 @@ impl<'a> From<&'a Transaction> for EthereumTransactionData {
             value: tx.value,
             gas_used: tx.gas,
             gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            input: tx.input.clone(),
+        }
+    }
+}
+
+/// Ethereum transaction receipt data.
+#[derive(Clone, Debug)]
+pub struct EthereumTransactionReceiptData {
+    pub transaction_hash: H256,
+    pub transaction_index: U128,
+    pub block_hash: Option<H256>,
+    pub block_number: Option<U256>,
+    pub cumulative_gas_used: U256,
+    pub gas_used: U256,
+    pub contract_address: Option<H160>,
+    pub logs: Vec<Log>,
+    /// `1` if the transaction succeeded, `0` if it failed. Only available for blocks after
+    /// the Byzantium fork.
+    pub status: Option<U128>,
+}
+
+impl<'a> From<&'a TransactionReceipt> for EthereumTransactionReceiptData {
+    fn from(receipt: &'a TransactionReceipt) -> EthereumTransactionReceiptData {
+        EthereumTransactionReceiptData {
+            transaction_hash: receipt.transaction_hash,
+            transaction_index: receipt.transaction_index,
+            block_hash: receipt.block_hash,
+            block_number: receipt.block_number,
+            cumulative_gas_used: receipt.cumulative_gas_used,
+            gas_used: receipt.gas_used,
+            contract_address: receipt.contract_address,
+            logs: receipt.logs.clone(),
+            status: receipt.status.map(|status| U128::from(status.as_u64())),
         }
     }
 }
@@ -91,6 +135,7 @@ pub struct EthereumEventData {
     pub block: EthereumBlockData,
     pub transaction: EthereumTransactionData,
     pub params: Vec<LogParam>,
+    pub receipt: Option<EthereumTransactionReceiptData>,
 }
 
 impl Clone for EthereumEventData {
@@ -110,6 +155,7 @@ impl Clone for EthereumEventData {
                     value: log_param.value.clone(),
                 })
                 .collect(),
+            receipt: self.receipt.clone(),
         }
     }
 }
@@ -1,14 +1,94 @@
 use data::subgraph::Link;
 use failure;
+use hex;
 use ipfs_api;
+use serde_json::{self, Value};
+use slog::{debug, o, warn, Logger};
+use tiny_keccak;
 use tokio::prelude::*;
+use tokio::timer::Delay;
 
-use std::time::Duration;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use util::env::positive_number_from_env;
+
+/// Files larger than this are not downloaded unless configured otherwise (256 MiB).
+pub const DEFAULT_MAX_FILE_BYTES: usize = 256 * 1024 * 1024;
+
+/// Lines of newline-delimited JSON files that are longer than this are rejected (1 MiB).
+pub const DEFAULT_MAX_JSON_LINE_BYTES: usize = 1024 * 1024;
 
 /// Resolves links to subgraph manifests and resources referenced by them.
 pub trait LinkResolver: Send + Sync + 'static {
     /// Fetches the link contents as bytes.
     fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = failure::Error> + Send>;
+
+    /// Fetches the link contents and parses them as newline-delimited JSON, yielding
+    /// one value per non-empty line. Resolvers that can download files incrementally
+    /// should override this so that large files are never held in memory as a whole.
+    fn json_stream(&self, link: &Link) -> Box<Stream<Item = Value, Error = failure::Error> + Send> {
+        Box::new(json_values(
+            self.cat(link).into_stream(),
+            DEFAULT_MAX_JSON_LINE_BYTES,
+        ))
+    }
+}
+
+/// Raised by `json_values` for a line that is longer than the maximum line length.
+#[derive(Debug, Fail)]
+#[fail(display = "JSON line is longer than the maximum of {} bytes", _0)]
+pub struct JsonLineTooLong(usize);
+
+/// Splits a stream of byte chunks into lines and parses every non-empty line as JSON.
+/// Lines that are not valid JSON result in a `serde_json::Error`, lines longer than
+/// `max_line_size` bytes in a `JsonLineTooLong` error.
+pub fn json_values<S, C>(
+    chunks: S,
+    max_line_size: usize,
+) -> impl Stream<Item = Value, Error = failure::Error> + Send
+where
+    S: Stream<Item = C, Error = failure::Error> + Send,
+    C: AsRef<[u8]>,
+{
+    let mut buffer = vec![];
+
+    chunks
+        .map(|chunk| chunk.as_ref().to_vec())
+        // A final newline ensures that the last line is emitted even if the file
+        // does not end with one.
+        .chain(stream::once(Ok(vec![b'\n'])))
+        .and_then(move |chunk| {
+            // What is left in the buffer has no newline, only the new chunk needs searching
+            let mut search_from = buffer.len();
+            buffer.extend_from_slice(&chunk);
+
+            let mut lines = vec![];
+            let mut line_start = 0;
+            while let Some(newline) = buffer[search_from..].iter().position(|b| *b == b'\n') {
+                let line_end = search_from + newline + 1;
+                lines.push(buffer[line_start..line_end].to_vec());
+                line_start = line_end;
+                search_from = line_end;
+            }
+            buffer.drain(..line_start);
+
+            // Lines include their newline, what is left in the buffer is the start of a line
+            if buffer.len() > max_line_size
+                || lines.iter().any(|line| line.len() > max_line_size + 1)
+            {
+                return Err(JsonLineTooLong(max_line_size).into());
+            }
+            Ok(stream::iter_ok::<_, failure::Error>(lines))
+        })
+        .flatten()
+        .filter(|line| line.iter().any(|b| !(*b as char).is_whitespace()))
+        .and_then(|line| serde_json::from_slice(&line).map_err(failure::Error::from))
 }
 
 impl LinkResolver for ipfs_api::IpfsClient {
@@ -26,4 +106,480 @@ impl LinkResolver for ipfs_api::IpfsClient {
                 .map_err(|e| failure::err_msg(e.to_string())),
         )
     }
+
+    fn json_stream(&self, link: &Link) -> Box<Stream<Item = Value, Error = failure::Error> + Send> {
+        let path = link.link.trim_left_matches("/ipfs/");
+
+        Box::new(json_values(
+            self.cat(path).map_err(|e| failure::err_msg(e.to_string())),
+            DEFAULT_MAX_JSON_LINE_BYTES,
+        ))
+    }
+}
+
+/// Raised when a file on IPFS exceeds the maximum size that `IpfsResolver` is willing to
+/// download.
+#[derive(Debug, Fail)]
+#[fail(
+    display = "IPFS file {} is larger than the maximum of {} bytes",
+    _0, _1
+)]
+pub struct IpfsFileTooLarge(String, usize);
+
+/// Fails a stream once `deadline` has passed, no matter how much progress it is making.
+struct Deadline<S> {
+    stream: S,
+    delay: Delay,
+}
+
+impl<S> Deadline<S> {
+    fn new(stream: S, deadline: Instant) -> Self {
+        Deadline {
+            stream,
+            delay: Delay::new(deadline),
+        }
+    }
+}
+
+impl<S> Stream for Deadline<S>
+where
+    S: Stream<Error = failure::Error>,
+{
+    type Item = S::Item;
+    type Error = failure::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, failure::Error> {
+        if self.delay.poll()?.is_ready() {
+            return Err(failure::err_msg("IPFS request timed out"));
+        }
+        self.stream.poll()
+    }
+}
+
+/// Resolves `/ipfs/` links (or plain IPFS hashes) through one or more IPFS nodes.
+///
+/// Nodes are tried in order until one of them returns the file. Downloads are aborted
+/// once they exceed `GRAPH_MAX_IPFS_FILE_BYTES` (default: 256 MiB) or take longer than
+/// `GRAPH_IPFS_TIMEOUT` seconds (default: 10). Since IPFS content never changes, files
+/// are cached in `GRAPH_IPFS_CACHE_DIR`, if set.
+#[derive(Clone)]
+pub struct IpfsResolver {
+    logger: Logger,
+    clients: Vec<Arc<ipfs_api::IpfsClient>>,
+    timeout: Duration,
+    max_file_size: usize,
+    cache_dir: Option<PathBuf>,
+}
+
+impl IpfsResolver {
+    /// Creates a resolver for the given IPFS nodes. Fails if the environment variables
+    /// that configure the resolver are invalid.
+    pub fn new(
+        logger: &Logger,
+        clients: Vec<Arc<ipfs_api::IpfsClient>>,
+    ) -> Result<Self, failure::Error> {
+        let timeout = Duration::from_secs(positive_number_from_env("GRAPH_IPFS_TIMEOUT", 10)?);
+        let max_file_size =
+            positive_number_from_env("GRAPH_MAX_IPFS_FILE_BYTES", DEFAULT_MAX_FILE_BYTES)?;
+        let cache_dir = env::var_os("GRAPH_IPFS_CACHE_DIR").map(PathBuf::from);
+
+        Ok(IpfsResolver {
+            logger: logger.new(o!("component" => "IpfsResolver")),
+            clients,
+            timeout,
+            max_file_size,
+            cache_dir,
+        })
+    }
+
+    /// Path of the cache file for an IPFS path. IPFS paths may contain slashes, so they
+    /// are hashed to obtain a flat file name.
+    fn cache_path(&self, path: &str) -> Option<PathBuf> {
+        self.cache_dir
+            .as_ref()
+            .map(|dir| dir.join(hex::encode(tiny_keccak::keccak256(path.as_bytes()))))
+    }
+
+    /// Reads a file from the cache. Cached files that exceed the size limit, e.g. because
+    /// the limit was lowered since they were cached, are ignored.
+    fn read_cache(&self, path: &str) -> Option<Vec<u8>> {
+        let cache_path = self.cache_path(path)?;
+        let size = fs::metadata(&cache_path).ok()?.len();
+        if size > self.max_file_size as u64 {
+            debug!(
+                self.logger,
+                "Ignoring cached IPFS file that is larger than the maximum of {} bytes",
+                self.max_file_size;
+                "path" => path
+            );
+            return None;
+        }
+        fs::read(cache_path).ok()
+    }
+
+    fn write_cache(&self, path: &str, data: &[u8]) {
+        // Distinguishes temporary files of concurrent writes in this process
+        static WRITES: AtomicUsize = AtomicUsize::new(0);
+
+        if let Some(cache_path) = self.cache_path(path) {
+            // Write to a temporary file first so that readers never see partial files;
+            // every write uses its own file since the same file may be cached concurrently
+            let tmp_path = cache_path.with_extension(format!(
+                "{}-{}.tmp",
+                process::id(),
+                WRITES.fetch_add(1, Ordering::SeqCst)
+            ));
+            if let Err(e) =
+                fs::write(&tmp_path, data).and_then(|()| fs::rename(&tmp_path, &cache_path))
+            {
+                warn!(self.logger, "Failed to cache IPFS file: {}", e; "path" => path);
+                let _ = fs::remove_file(&tmp_path);
+            }
+        }
+    }
+
+    /// Downloads a file from a single IPFS node, enforcing the size limit while streaming.
+    fn cat_from(
+        &self,
+        client: &ipfs_api::IpfsClient,
+        path: String,
+    ) -> impl Future<Item = Vec<u8>, Error = failure::Error> + Send {
+        let max_file_size = self.max_file_size;
+
+        client
+            .cat(&path)
+            .map_err(|e| failure::err_msg(e.to_string()))
+            .fold(vec![], move |mut data, chunk| {
+                if data.len() + chunk.len() > max_file_size {
+                    return Err(IpfsFileTooLarge(path.clone(), max_file_size).into());
+                }
+                data.extend_from_slice(&chunk);
+                Ok(data)
+            })
+            .timeout(self.timeout)
+            .map_err(|e| {
+                e.into_inner()
+                    .unwrap_or_else(|| failure::err_msg("IPFS request timed out"))
+            })
+    }
+
+    /// Streams a file from a single IPFS node, enforcing the size limit. The whole
+    /// download has to finish within the timeout. The returned future resolves once the
+    /// first chunk has arrived, so that a node that cannot provide the file fails before
+    /// any of the file has been consumed.
+    fn cat_stream_from(
+        &self,
+        client: &ipfs_api::IpfsClient,
+        path: String,
+    ) -> impl Future<
+        Item = Box<Stream<Item = Vec<u8>, Error = failure::Error> + Send>,
+        Error = failure::Error,
+    > + Send {
+        let max_file_size = self.max_file_size;
+        let mut size = 0;
+
+        let chunks = client
+            .cat(&path)
+            .map_err(|e| failure::err_msg(e.to_string()))
+            .and_then(move |chunk| {
+                size += chunk.len();
+                if size > max_file_size {
+                    return Err(IpfsFileTooLarge(path.clone(), max_file_size).into());
+                }
+                Ok(chunk.to_vec())
+            });
+
+        Deadline::new(chunks, Instant::now() + self.timeout)
+            .into_future()
+            .map_err(|(e, _)| e)
+            .map(|(first, rest)| -> Box<Stream<Item = _, Error = _> + Send> {
+                match first {
+                    Some(first) => Box::new(stream::once(Ok(first)).chain(rest)),
+                    None => Box::new(stream::empty()),
+                }
+            })
+    }
+
+    /// Sends `request` to the IPFS nodes, in order, until one of them succeeds. Files that
+    /// are too large are not requested again from the other nodes.
+    fn with_fallback<F, R>(
+        &self,
+        path: String,
+        request: F,
+    ) -> Box<Future<Item = R::Item, Error = failure::Error> + Send>
+    where
+        F: Fn(&IpfsResolver, &ipfs_api::IpfsClient, String) -> R + Send + Sync + 'static,
+        R: Future<Error = failure::Error> + Send + 'static,
+    {
+        let request = Arc::new(request);
+
+        let mut clients = self.clients.iter().cloned();
+        let mut response: Box<Future<Item = R::Item, Error = failure::Error> + Send> =
+            match clients.next() {
+                Some(client) => Box::new(request(self, &client, path.clone())),
+                None => {
+                    return Box::new(future::err(failure::err_msg("no IPFS nodes configured")));
+                }
+            };
+
+        for client in clients {
+            let resolver = self.clone();
+            let path = path.clone();
+            let request = request.clone();
+            response = Box::new(response.or_else(
+                move |e| -> Box<Future<Item = _, Error = _> + Send> {
+                    // There is no point in downloading a file that is too large again
+                    if e.downcast_ref::<IpfsFileTooLarge>().is_some() {
+                        return Box::new(future::err(e));
+                    }
+
+                    debug!(
+                        resolver.logger,
+                        "Failed to fetch IPFS file, trying the next IPFS node: {}", e;
+                        "path" => path.as_str()
+                    );
+                    Box::new(request(&resolver, &client, path))
+                },
+            ));
+        }
+
+        response
+    }
+}
+
+impl LinkResolver for IpfsResolver {
+    fn cat(&self, link: &Link) -> Box<Future<Item = Vec<u8>, Error = failure::Error> + Send> {
+        // Discard the `/ipfs/` prefix (if present) to get the hash.
+        let path = link.link.trim_left_matches("/ipfs/").to_owned();
+
+        if let Some(data) = self.read_cache(&path) {
+            return Box::new(future::ok(data));
+        }
+
+        let resolver = self.clone();
+        Box::new(
+            self.with_fallback(path.clone(), |resolver, client, path| {
+                resolver.cat_from(client, path)
+            })
+            .map(move |data| {
+                resolver.write_cache(&path, &data);
+                data
+            }),
+        )
+    }
+
+    /// Streams the file, falling back to the other IPFS nodes like `cat` if a node fails
+    /// before sending any data. Once part of the file has been consumed, errors end the
+    /// stream.
+    fn json_stream(&self, link: &Link) -> Box<Stream<Item = Value, Error = failure::Error> + Send> {
+        let path = link.link.trim_left_matches("/ipfs/").to_owned();
+
+        if let Some(data) = self.read_cache(&path) {
+            return Box::new(json_values(
+                stream::once(Ok(data)),
+                DEFAULT_MAX_JSON_LINE_BYTES,
+            ));
+        }
+
+        let chunks = self
+            .with_fallback(path, |resolver, client, path| {
+                resolver.cat_stream_from(client, path)
+            })
+            .flatten_stream();
+        Box::new(json_values(chunks, DEFAULT_MAX_JSON_LINE_BYTES))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use slog::Discard;
+    use std::net::TcpListener;
+    use tokio::runtime::Runtime;
+    use tokio::timer::Interval;
+
+    /// Answers a single request with `body`, like an IPFS node answering `cat`, and
+    /// returns a client for it.
+    fn serve_once(body: Vec<u8>) -> Arc<ipfs_api::IpfsClient> {
+        let port = ::graph_mock::serve_once("200 OK", body).port();
+        Arc::new(ipfs_api::IpfsClient::new("127.0.0.1", port).unwrap())
+    }
+
+    /// Returns a client for a port that nothing listens on.
+    fn unreachable_client() -> Arc<ipfs_api::IpfsClient> {
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+        Arc::new(ipfs_api::IpfsClient::new("127.0.0.1", port).unwrap())
+    }
+
+    /// Returns an empty cache directory that is unique to `test`.
+    fn cache_dir(test: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("graph-ipfs-cache-{}-{}", test, process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn ipfs_resolver(
+        clients: Vec<Arc<ipfs_api::IpfsClient>>,
+        max_file_size: usize,
+        cache_dir: Option<PathBuf>,
+    ) -> IpfsResolver {
+        IpfsResolver {
+            logger: Logger::root(Discard, o!()),
+            clients,
+            timeout: Duration::from_secs(10),
+            max_file_size,
+            cache_dir,
+        }
+    }
+
+    fn link() -> Link {
+        Link {
+            link: "/ipfs/QmHash".to_owned(),
+        }
+    }
+
+    fn cat(resolver: &IpfsResolver) -> Result<Vec<u8>, failure::Error> {
+        Runtime::new().unwrap().block_on(resolver.cat(&link()))
+    }
+
+    fn json_stream(resolver: &IpfsResolver) -> Result<Vec<Value>, failure::Error> {
+        Runtime::new()
+            .unwrap()
+            .block_on(resolver.json_stream(&link()).collect())
+    }
+
+    #[test]
+    fn ipfs_resolver_falls_back_to_other_nodes() {
+        let resolver = ipfs_resolver(
+            vec![unreachable_client(), serve_once(b"data".to_vec())],
+            DEFAULT_MAX_FILE_BYTES,
+            None,
+        );
+        assert_eq!(cat(&resolver).unwrap(), b"data".to_vec());
+    }
+
+    #[test]
+    fn ipfs_resolver_rejects_files_over_the_size_limit() {
+        let resolver = ipfs_resolver(vec![serve_once(vec![b'x'; 100])], 99, None);
+        let error = cat(&resolver).unwrap_err();
+        assert!(error.downcast_ref::<IpfsFileTooLarge>().is_some());
+
+        let resolver = ipfs_resolver(vec![serve_once(vec![b'x'; 100])], 100, None);
+        assert_eq!(cat(&resolver).unwrap().len(), 100);
+    }
+
+    #[test]
+    fn ipfs_resolver_serves_cached_files() {
+        let dir = cache_dir("serves-cached-files");
+
+        let resolver = ipfs_resolver(
+            vec![serve_once(b"data".to_vec())],
+            DEFAULT_MAX_FILE_BYTES,
+            Some(dir.clone()),
+        );
+        assert_eq!(cat(&resolver).unwrap(), b"data".to_vec());
+
+        // The file is cached without leaving temporary files behind
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        let resolver = ipfs_resolver(
+            vec![unreachable_client()],
+            DEFAULT_MAX_FILE_BYTES,
+            Some(dir.clone()),
+        );
+        assert_eq!(cat(&resolver).unwrap(), b"data".to_vec());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ipfs_resolver_ignores_cached_files_over_the_size_limit() {
+        let dir = cache_dir("ignores-large-cached-files");
+
+        let resolver = ipfs_resolver(vec![serve_once(vec![b'x'; 100])], 100, Some(dir.clone()));
+        assert_eq!(cat(&resolver).unwrap().len(), 100);
+
+        let resolver = ipfs_resolver(vec![serve_once(vec![b'x'; 100])], 99, Some(dir.clone()));
+        let error = cat(&resolver).unwrap_err();
+        assert!(error.downcast_ref::<IpfsFileTooLarge>().is_some());
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn ipfs_resolver_streams_from_other_nodes() {
+        let resolver = ipfs_resolver(
+            vec![unreachable_client(), serve_once(b"1\n2".to_vec())],
+            DEFAULT_MAX_FILE_BYTES,
+            None,
+        );
+        assert_eq!(
+            json_stream(&resolver).unwrap(),
+            vec![Value::from(1), Value::from(2)]
+        );
+    }
+
+    #[test]
+    fn ipfs_resolver_rejects_streams_over_the_size_limit() {
+        let resolver = ipfs_resolver(vec![serve_once(b"1\n2\n3\n".to_vec())], 5, None);
+        let error = json_stream(&resolver).unwrap_err();
+        assert!(error.downcast_ref::<IpfsFileTooLarge>().is_some());
+    }
+
+    #[test]
+    fn deadline_ends_streams_that_are_still_making_progress() {
+        let ticks =
+            Interval::new(Instant::now(), Duration::from_millis(5)).map_err(failure::Error::from);
+        let deadline = Deadline::new(ticks, Instant::now() + Duration::from_millis(50));
+        let error = Runtime::new()
+            .unwrap()
+            .block_on(deadline.collect())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "IPFS request timed out");
+    }
+
+    #[test]
+    fn json_values_split_across_chunks() {
+        let chunks = vec!["{\"id\": ", "1}\n\n  \n\"two\"\n[3", "]"];
+        let values = json_values(stream::iter_ok(chunks), DEFAULT_MAX_JSON_LINE_BYTES)
+            .collect()
+            .wait()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                serde_json::from_str::<Value>("{\"id\": 1}").unwrap(),
+                Value::from("two"),
+                Value::from(vec![3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn json_values_invalid_line() {
+        let chunks = vec!["1\n{\n2\n"];
+        let mut values = json_values(stream::iter_ok(chunks), DEFAULT_MAX_JSON_LINE_BYTES).wait();
+        assert_eq!(values.next().unwrap().unwrap(), Value::from(1));
+        assert!(values.next().unwrap().is_err());
+    }
+
+    #[test]
+    fn json_values_line_too_long() {
+        // Incomplete lines are rejected as soon as they exceed the limit
+        let chunks = vec!["1234\n", "12345"];
+        let mut values = json_values(stream::iter_ok(chunks), 4).wait();
+        assert_eq!(values.next().unwrap().unwrap(), Value::from(1234));
+        let error = values.next().unwrap().unwrap_err();
+        assert!(error.downcast_ref::<JsonLineTooLong>().is_some());
+
+        let chunks = vec!["12345\n"];
+        let mut values = json_values(stream::iter_ok(chunks), 4).wait();
+        let error = values.next().unwrap().unwrap_err();
+        assert!(error.downcast_ref::<JsonLineTooLong>().is_some());
+    }
 }
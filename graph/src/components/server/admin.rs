@@ -1,11 +1,12 @@
 use std::io;
 use std::sync::Arc;
 
+use prelude::ChainHealth;
 use prelude::Logger;
 use prelude::NodeId;
 
 /// Common trait for JSON-RPC admin server implementations.
-pub trait JsonRpcServer<P> {
+pub trait JsonRpcServer<P, C> {
     type Server;
 
     fn serve(
@@ -13,6 +14,8 @@ pub trait JsonRpcServer<P> {
         http_port: u16,
         ws_port: u16,
         provider: Arc<P>,
+        block_checker: Arc<C>,
+        chain_health: ChainHealth,
         node_id: NodeId,
         logger: Logger,
     ) -> Result<Self::Server, io::Error>;
@@ -262,6 +262,12 @@ pub trait Store: Send + Sync + 'static {
     /// Get a pointer to the most recently processed block in the subgraph.
     fn block_ptr(&self, subgraph_id: SubgraphId) -> Result<EthereumBlockPointer, Error>;
 
+    /// Like `block_ptr`, but returns `None` if the subgraph has not been added to the store yet.
+    fn find_block_ptr(
+        &self,
+        subgraph_id: SubgraphId,
+    ) -> Result<Option<EthereumBlockPointer>, Error>;
+
     /// Looks up an entity using the given store key.
     fn get(&self, key: EntityKey) -> Result<Option<Entity>, QueryExecutionError>;
 
@@ -318,15 +324,32 @@ pub trait Store: Send + Sync + 'static {
 
     /// Counts the total number of entities in a subgraph.
     fn count_entities(&self, subgraph: SubgraphId) -> Result<u64, Error>;
+
+    /// Removes all entities of a subgraph, including their history, and moves the subgraph
+    /// block pointer back to `block_ptr`, so that the subgraph can be indexed from scratch.
+    ///
+    /// Must only be called while the subgraph is not being indexed.
+    fn reset_subgraph(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr: EthereumBlockPointer,
+    ) -> Result<(), Error>;
 }
 
 pub trait SubgraphDeploymentStore: Send + Sync + 'static {
-    /// List all deployment names and their associated subgraph IDs for the specified node ID.
+    /// List all deployment names and the IDs of their current and pending subgraph versions
+    /// for the specified node ID, including paused ones.
     fn read_by_node_id(
         &self,
         node_id: NodeId,
     ) -> Result<Vec<(SubgraphDeploymentName, SubgraphId)>, Error>;
 
+    /// Deploy a subgraph version under a name.
+    ///
+    /// The first version deployed under a name becomes the current version right away. Later
+    /// versions become the pending version, replacing any previous pending version, until
+    /// they are promoted with `promote_pending_version`. Deploying the current or pending
+    /// version again only moves it to the given node.
     fn write(
         &self,
         name: SubgraphDeploymentName,
@@ -334,15 +357,51 @@ pub trait SubgraphDeploymentStore: Send + Sync + 'static {
         node_id: NodeId,
     ) -> Result<(), Error>;
 
+    /// Look up the current subgraph version of a name.
     fn read(&self, name: SubgraphDeploymentName) -> Result<Option<(SubgraphId, NodeId)>, Error>;
 
+    /// Remove a name and all of its versions.
     fn remove(&self, name: SubgraphDeploymentName) -> Result<bool, Error>;
 
+    /// List all versions deployed under a name, most recently deployed first.
+    fn read_versions(&self, name: SubgraphDeploymentName) -> Result<Vec<SubgraphVersion>, Error>;
+
+    /// List the current and pending subgraph versions assigned to a node, including paused
+    /// versions.
+    fn read_versions_by_node_id(&self, node_id: NodeId) -> Result<Vec<SubgraphVersion>, Error>;
+
+    /// If the subgraph is the pending version of its name, atomically make it the current
+    /// version and retire the previous current version.
+    ///
+    /// Returns `false` if the subgraph is not a pending version.
+    fn promote_pending_version(&self, subgraph_id: &SubgraphId) -> Result<bool, Error>;
+
+    /// Atomically make a previously deployed version of a name the current version. The
+    /// previous current version and any pending version are retired.
+    ///
+    /// Returns `false` if the subgraph was never deployed under that name.
+    fn rollback(
+        &self,
+        name: SubgraphDeploymentName,
+        subgraph_id: SubgraphId,
+    ) -> Result<bool, Error>;
+
+    /// Assign all versions of a name to a different node.
+    ///
+    /// Returns `false` if the name does not exist.
+    fn reassign(&self, name: SubgraphDeploymentName, node_id: NodeId) -> Result<bool, Error>;
+
+    /// Pause or resume indexing all versions of a name. Paused versions can still be queried.
+    ///
+    /// Returns `false` if the name does not exist.
+    fn set_paused(&self, name: SubgraphDeploymentName, paused: bool) -> Result<bool, Error>;
+
     fn deployment_events(
         &self,
         node_id: NodeId,
     ) -> Box<Stream<Item = DeploymentEvent, Error = Error> + Send>;
 
+    /// Check whether a subgraph is the current or pending version of any name.
     fn is_deployed(&self, id: &SubgraphId) -> Result<bool, Error>;
 
     fn subgraph_schema(&self, subgraph_id: SubgraphId) -> Result<Schema, Error>;
@@ -392,6 +451,16 @@ pub trait ChainStore: Send + Sync + 'static {
     /// Get Some(block) if it is present in the chain store, or None.
     fn block(&self, block_hash: H256) -> Result<Option<EthereumBlock>, Error>;
 
+    /// Get pointers to all blocks in the chain store with numbers in the range `[from, to]`,
+    /// together with their parent hashes, ordered by block number.
+    ///
+    /// There may be more than one block with the same number if some blocks were uncled.
+    fn block_ptrs_in_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(EthereumBlockPointer, H256)>, Error>;
+
     /// Get the `offset`th ancestor of `block_hash`, where offset=0 means the block matching
     /// `block_hash` and offset=1 means its parent. Returns None if unable to complete due to
     /// missing blocks in the chain store.
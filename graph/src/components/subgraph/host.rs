@@ -24,11 +24,13 @@ pub trait RuntimeHost: Send + Sync + Debug {
 pub trait RuntimeHostBuilder: Clone + Send + 'static {
     type Host: RuntimeHost;
 
-    /// Build a new runtime host for a subgraph data source.
+    /// Build a new runtime host for a subgraph data source. Entities written by the
+    /// mapping are validated against `schema`.
     fn build(
         &self,
         logger: &Logger,
         subgraph_id: SubgraphId,
         data_source: DataSource,
+        schema: Schema,
     ) -> Result<Self::Host, Error>;
 }
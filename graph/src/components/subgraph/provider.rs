@@ -1,4 +1,5 @@
 use prelude::*;
+use web3::types::H256;
 
 /// Events emitted by [SubgraphProvider](trait.SubgraphProvider.html) implementations.
 #[derive(Debug, PartialEq)]
@@ -20,6 +21,13 @@ pub trait SubgraphProvider: EventProducer<SubgraphProviderEvent> + Send + Sync +
         &self,
         id: SubgraphId,
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Resolve the manifest of a subgraph and check it for mistakes that would otherwise
+    /// only be discovered while indexing. All problems found are reported at once.
+    fn validate(
+        &self,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
 }
 
 /// Common trait for named subgraph providers.
@@ -37,4 +45,43 @@ pub trait SubgraphProviderWithNames: Send + Sync + 'static {
     ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
 
     fn list(&self) -> Result<Vec<(SubgraphDeploymentName, SubgraphId)>, Error>;
+
+    /// Move all versions of a name to a different indexing node.
+    fn reassign(
+        &self,
+        name: SubgraphDeploymentName,
+        node_id: NodeId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Stop indexing all versions of a name until they are resumed.
+    fn pause(
+        &self,
+        name: SubgraphDeploymentName,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Resume indexing all versions of a paused name.
+    fn resume(
+        &self,
+        name: SubgraphDeploymentName,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// Revert the entity changes of the current version of a name back to an earlier block
+    /// and restart indexing from there.
+    ///
+    /// The block must be an ancestor of the latest block processed by the subgraph.
+    fn rewind(
+        &self,
+        name: SubgraphDeploymentName,
+        block_hash: H256,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
+
+    /// List all versions deployed under a name, most recently deployed first.
+    fn versions(&self, name: SubgraphDeploymentName) -> Result<Vec<SubgraphVersion>, Error>;
+
+    /// Make a previously deployed version the current version of a name.
+    fn rollback(
+        &self,
+        name: SubgraphDeploymentName,
+        id: SubgraphId,
+    ) -> Box<Future<Item = (), Error = SubgraphProviderError> + Send + 'static>;
 }
@@ -1,6 +1,7 @@
 use data::subgraph::*;
 use graphql_parser::{query as q, Pos};
 
+use bigdecimal;
 use failure;
 use hex::FromHexError;
 use num_bigint;
@@ -173,6 +174,12 @@ impl From<num_bigint::ParseBigIntError> for QueryExecutionError {
     }
 }
 
+impl From<bigdecimal::ParseBigDecimalError> for QueryExecutionError {
+    fn from(e: bigdecimal::ParseBigDecimalError) -> Self {
+        QueryExecutionError::ValueParseError("BigDecimal".to_string(), format!("{}", e))
+    }
+}
+
 /// Error caused while processing a [Query](struct.Query.html) request.
 #[derive(Debug)]
 pub enum QueryError {
@@ -23,6 +23,12 @@ impl Schema {
         let document = graphql_parser::parse_schema(&raw)?;
         validate_schema(&document)?;
 
+        Ok(Self::new(id, document))
+    }
+
+    /// Creates a schema from a document without validating it as a subgraph schema. This is
+    /// useful for GraphQL APIs that are not backed by entities.
+    pub fn new(id: SubgraphId, document: schema::Document) -> Self {
         let mut types_for_interface = BTreeMap::<_, Vec<_>>::new();
         for object_type in get_object_type_definitions(&document) {
             for implemented_interface in object_type.implements_interfaces.clone() {
@@ -40,7 +46,7 @@ impl Schema {
         };
         schema.add_subgraph_id_directives(id);
 
-        Ok(schema)
+        schema
     }
 
     pub fn type_for_interface(&self, interface_name: &query::Name) -> Option<&Vec<ObjectType>> {
@@ -7,6 +7,8 @@ use std::iter::FromIterator;
 use std::ops::{Deref, DerefMut};
 use std::str::FromStr;
 
+use data::graphql::validation::get_object_type_definitions;
+use data::schema::Schema;
 use data::subgraph::SubgraphId;
 use graphql_parser::query;
 use graphql_parser::schema;
@@ -122,17 +124,84 @@ impl DeploymentEvent {
     }
 }
 
+/// The role a subgraph version plays for its subgraph name.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SubgraphVersionStatus {
+    /// The version that queries against the subgraph name are served from.
+    Current,
+
+    /// A newer version that is being indexed and will replace the current version once it
+    /// has caught up with the chain head.
+    Pending,
+
+    /// A former version that is no longer indexed but can be rolled back to.
+    Retired,
+}
+
+impl SubgraphVersionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SubgraphVersionStatus::Current => "current",
+            SubgraphVersionStatus::Pending => "pending",
+            SubgraphVersionStatus::Retired => "retired",
+        }
+    }
+}
+
+impl FromStr for SubgraphVersionStatus {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Error> {
+        match s {
+            "current" => Ok(SubgraphVersionStatus::Current),
+            "pending" => Ok(SubgraphVersionStatus::Pending),
+            "retired" => Ok(SubgraphVersionStatus::Retired),
+            _ => Err(format_err!("invalid subgraph version status: {:?}", s)),
+        }
+    }
+}
+
+impl fmt::Display for SubgraphVersionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// A subgraph deployed under a subgraph name.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SubgraphVersion {
+    pub name: SubgraphDeploymentName,
+    pub subgraph_id: SubgraphId,
+    pub node_id: NodeId,
+    pub status: SubgraphVersionStatus,
+
+    /// Paused versions are not indexed but can still be queried.
+    pub paused: bool,
+
+    /// Seconds since the Unix epoch at which the version was deployed.
+    pub created_at: u64,
+}
+
+impl SubgraphVersion {
+    /// Whether the node the version is assigned to should be indexing it.
+    pub fn is_indexed(&self) -> bool {
+        self.status != SubgraphVersionStatus::Retired && !self.paused
+    }
+}
+
 /// An entity attribute name is represented as a string.
 pub type Attribute = String;
 
 pub const ID: &str = "ID";
 pub const BYTES_SCALAR: &str = "Bytes";
 pub const BIG_INT_SCALAR: &str = "BigInt";
+pub const BIG_DECIMAL_SCALAR: &str = "BigDecimal";
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ValueType {
     Boolean,
     BigInt,
+    BigDecimal,
     Bytes,
     Float,
     ID,
@@ -147,6 +216,7 @@ impl FromStr for ValueType {
         match s {
             "Boolean" => Ok(ValueType::Boolean),
             "BigInt" => Ok(ValueType::BigInt),
+            "BigDecimal" => Ok(ValueType::BigDecimal),
             "Bytes" => Ok(ValueType::Bytes),
             "Float" => Ok(ValueType::Float),
             "ID" => Ok(ValueType::ID),
@@ -169,6 +239,7 @@ pub enum Value {
     Null,
     Bytes(scalar::Bytes),
     BigInt(scalar::BigInt),
+    BigDecimal(scalar::BigDecimal),
 }
 
 impl Value {
@@ -201,6 +272,7 @@ impl Value {
                 match n.as_str() {
                     BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_str(e)?),
                     BIG_INT_SCALAR => Value::BigInt(scalar::BigInt::from_str(e)?),
+                    BIG_DECIMAL_SCALAR => Value::BigDecimal(scalar::BigDecimal::from_str(e)?),
                     _ => Value::String(e.clone()),
                 }
             }
@@ -210,9 +282,20 @@ impl Value {
                 match n.as_str() {
                     BYTES_SCALAR => Value::Bytes(scalar::Bytes::from_str(s)?),
                     BIG_INT_SCALAR => Value::BigInt(scalar::BigInt::from_str(s)?),
+                    BIG_DECIMAL_SCALAR => Value::BigDecimal(scalar::BigDecimal::from_str(s)?),
                     _ => Value::String(s.clone()),
                 }
             }
+            // Decimals may also be written as number literals
+            (query::Value::Int(i), NamedType(n)) if n == BIG_DECIMAL_SCALAR => {
+                let i = i.as_i64().ok_or_else(|| {
+                    QueryExecutionError::NamedTypeError(BIG_DECIMAL_SCALAR.to_string())
+                })?;
+                Value::BigDecimal(scalar::BigDecimal::from_str(&i.to_string())?)
+            }
+            (query::Value::Float(f), NamedType(n)) if n == BIG_DECIMAL_SCALAR => {
+                Value::BigDecimal(scalar::BigDecimal::from_str(&f.to_string())?)
+            }
             (query::Value::Int(i), _) => Value::Int(
                 i.to_owned()
                     .as_i64()
@@ -250,6 +333,7 @@ impl fmt::Display for Value {
                     .collect(),
                 Value::Bytes(ref bytes) => bytes.to_string(),
                 Value::BigInt(ref number) => number.to_string(),
+                Value::BigDecimal(ref number) => number.to_string(),
             }
         )
     }
@@ -268,6 +352,7 @@ impl From<Value> for query::Value {
             }
             Value::Bytes(bytes) => query::Value::String(bytes.to_string()),
             Value::BigInt(number) => query::Value::String(number.to_string()),
+            Value::BigDecimal(number) => query::Value::String(number.to_string()),
         }
     }
 }
@@ -290,6 +375,12 @@ impl<'a> From<&'a String> for Value {
     }
 }
 
+impl From<bool> for Value {
+    fn from(value: bool) -> Value {
+        Value::Bool(value)
+    }
+}
+
 impl From<i32> for Value {
     fn from(value: i32) -> Value {
         Value::Int(value)
@@ -326,6 +417,27 @@ where
     }
 }
 
+/// A reason why an entity does not conform to the subgraph schema.
+#[derive(Clone, Debug, Fail, PartialEq)]
+pub enum EntityValidationError {
+    #[fail(display = "entity type `{}` is not defined in the schema", _0)]
+    UnknownEntityType(String),
+    #[fail(display = "entity type `{}` has no field `{}`", _0, _1)]
+    UnknownField(String, String),
+    #[fail(display = "field `{}.{}` is derived and cannot be set", _0, _1)]
+    DerivedField(String, String),
+    #[fail(
+        display = "missing value for non-nullable field `{}.{}` of type `{}`",
+        _0, _1, _2
+    )]
+    MissingValue(String, String, String),
+    #[fail(
+        display = "value `{}` of field `{}.{}` is not of type `{}`",
+        _2, _0, _1, _3
+    )]
+    InvalidValue(String, String, String, String),
+}
+
 /// An entity is represented as a map of attribute names to values.
 #[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
 pub struct Entity(HashMap<Attribute, Value>);
@@ -348,6 +460,114 @@ impl Entity {
             };
         }
     }
+
+    /// Checks that the entity is a complete and valid instance of `entity_type`: every
+    /// attribute must be a field of the type with a value of the field's type, and all
+    /// non-nullable fields must have a value. Derived fields cannot be set.
+    pub fn validate(
+        &self,
+        schema: &Schema,
+        entity_type: &str,
+    ) -> Result<(), EntityValidationError> {
+        use self::EntityValidationError::*;
+
+        let object_type = get_object_type_definitions(&schema.document)
+            .into_iter()
+            .find(|object_type| object_type.name == entity_type)
+            .ok_or_else(|| UnknownEntityType(entity_type.to_owned()))?;
+
+        if let Some(attribute) = self
+            .keys()
+            .find(|attribute| !object_type.fields.iter().any(|f| &&f.name == attribute))
+        {
+            return Err(UnknownField(entity_type.to_owned(), attribute.to_owned()));
+        }
+
+        for field in object_type.fields.iter() {
+            let value = self.get(&field.name).unwrap_or(&Value::Null);
+
+            if field
+                .directives
+                .iter()
+                .any(|directive| directive.name == "derivedFrom")
+            {
+                if value != &Value::Null {
+                    return Err(DerivedField(entity_type.to_owned(), field.name.clone()));
+                }
+                continue;
+            }
+
+            match (value, &field.field_type) {
+                (Value::Null, schema::Type::NonNullType(_)) => {
+                    return Err(MissingValue(
+                        entity_type.to_owned(),
+                        field.name.clone(),
+                        field.field_type.to_string(),
+                    ))
+                }
+                (value, field_type) if !value.is_of_type(schema, field_type) => {
+                    return Err(InvalidValue(
+                        entity_type.to_owned(),
+                        field.name.clone(),
+                        format!("{:?}", value),
+                        field.field_type.to_string(),
+                    ))
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Value {
+    /// Whether the value can be stored in a field of type `ty`. Enum values must be one
+    /// of the values of the enum, references to other entities are stored as their ID.
+    fn is_of_type(&self, schema: &Schema, ty: &schema::Type) -> bool {
+        use self::schema::Type::{ListType, NamedType, NonNullType};
+
+        match (self, ty) {
+            (Value::Null, NonNullType(_)) => false,
+            (Value::Null, _) => true,
+            (value, NonNullType(ty)) => value.is_of_type(schema, ty),
+            (Value::List(values), ListType(ty)) => {
+                values.iter().all(|value| value.is_of_type(schema, ty))
+            }
+            (_, ListType(_)) => false,
+            (value, NamedType(name)) => match (value, name.as_str()) {
+                (Value::Bool(_), "Boolean") => true,
+                (Value::BigInt(_), BIG_INT_SCALAR) => true,
+                (Value::BigDecimal(_), BIG_DECIMAL_SCALAR) => true,
+                (Value::Bytes(_), BYTES_SCALAR) => true,
+                (Value::Float(_), "Float") => true,
+                (Value::Int(_), "Int") => true,
+                (Value::String(_), ID) | (Value::String(_), "String") => true,
+                (Value::String(s), name) => {
+                    schema
+                        .document
+                        .definitions
+                        .iter()
+                        .any(|definition| match definition {
+                            schema::Definition::TypeDefinition(schema::TypeDefinition::Enum(
+                                enum_type,
+                            )) => {
+                                enum_type.name == name
+                                    && enum_type.values.iter().any(|value| &value.name == s)
+                            }
+                            schema::Definition::TypeDefinition(schema::TypeDefinition::Object(
+                                object_type,
+                            )) => object_type.name == name,
+                            schema::Definition::TypeDefinition(
+                                schema::TypeDefinition::Interface(interface_type),
+                            ) => interface_type.name == name,
+                            _ => false,
+                        })
+                }
+                _ => false,
+            },
+        }
+    }
 }
 
 impl Deref for Entity {
@@ -414,3 +634,105 @@ fn value_bigint() {
     );
     assert_eq!(query::Value::from(from_query), graphql_value);
 }
+
+#[test]
+fn value_bigdecimal() {
+    let big_num = "340282366920938463463374607431768211456.0000000000000000001";
+    let graphql_value = query::Value::String(big_num.to_owned());
+    let ty = query::Type::NamedType(BIG_DECIMAL_SCALAR.to_owned());
+    let from_query = Value::from_query_value(&graphql_value, &ty).unwrap();
+    assert_eq!(
+        from_query,
+        Value::BigDecimal(FromStr::from_str(big_num).unwrap())
+    );
+    assert_eq!(query::Value::from(from_query), graphql_value);
+
+    let graphql_value = query::Value::Float(1.5);
+    assert_eq!(
+        Value::from_query_value(&graphql_value, &ty).unwrap(),
+        Value::BigDecimal(scalar::BigDecimal::new(15.into(), -1))
+    );
+}
+
+#[test]
+fn entity_validation() {
+    let schema = Schema::parse(
+        "
+        enum Color { red, green }
+        type Thing @entity {
+            id: ID!
+            name: String!
+            count: Int
+            tags: [String!]!
+            color: Color
+            parent: Thing
+            children: [Thing!] @derivedFrom(field: \"parent\")
+        }",
+        SubgraphId::new("doesntmatter").unwrap(),
+    )
+    .unwrap();
+
+    let thing = |extra: Vec<(&str, Value)>| {
+        let mut thing = Entity::from(vec![
+            ("id", Value::from("t1")),
+            ("name", Value::from("one")),
+            ("tags", Value::List(vec![])),
+        ]);
+        thing.extend(extra.into_iter().map(|(k, v)| (k.to_owned(), v)));
+        thing
+    };
+
+    assert_eq!(thing(vec![]).validate(&schema, "Thing"), Ok(()));
+    assert_eq!(
+        thing(vec![
+            ("count", Value::Int(3)),
+            ("tags", Value::List(vec![Value::from("a")])),
+            ("color", Value::from("red")),
+            ("parent", Value::from("t0")),
+        ])
+        .validate(&schema, "Thing"),
+        Ok(())
+    );
+
+    assert_eq!(
+        thing(vec![]).validate(&schema, "Other"),
+        Err(EntityValidationError::UnknownEntityType("Other".to_owned()))
+    );
+    assert_eq!(
+        thing(vec![("size", Value::Int(1))]).validate(&schema, "Thing"),
+        Err(EntityValidationError::UnknownField(
+            "Thing".to_owned(),
+            "size".to_owned()
+        ))
+    );
+    assert_eq!(
+        thing(vec![("name", Value::Null)]).validate(&schema, "Thing"),
+        Err(EntityValidationError::MissingValue(
+            "Thing".to_owned(),
+            "name".to_owned(),
+            "String!".to_owned()
+        ))
+    );
+    assert_eq!(
+        thing(vec![("count", Value::from("3"))]).validate(&schema, "Thing"),
+        Err(EntityValidationError::InvalidValue(
+            "Thing".to_owned(),
+            "count".to_owned(),
+            "String(\"3\")".to_owned(),
+            "Int".to_owned()
+        ))
+    );
+    assert!(thing(vec![("tags", Value::List(vec![Value::Null]))])
+        .validate(&schema, "Thing")
+        .is_err());
+    assert!(thing(vec![("color", Value::from("blue"))])
+        .validate(&schema, "Thing")
+        .is_err());
+    assert_eq!(
+        thing(vec![("children", Value::List(vec![]))]).validate(&schema, "Thing"),
+        Err(EntityValidationError::DerivedField(
+            "Thing".to_owned(),
+            "children".to_owned()
+        ))
+    );
+}
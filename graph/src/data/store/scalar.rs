@@ -1,3 +1,4 @@
+use bigdecimal;
 use hex;
 use num_bigint;
 use serde::{self, Deserialize, Serialize};
@@ -92,6 +93,12 @@ impl From<u64> for BigInt {
     }
 }
 
+impl From<i64> for BigInt {
+    fn from(i: i64) -> BigInt {
+        BigInt(i.into())
+    }
+}
+
 impl From<U128> for BigInt {
     /// This implementation assumes that U128 represents an unsigned U128,
     /// and not a signed U128 (aka int128 in Solidity). Right now, this is
@@ -172,6 +179,106 @@ impl Rem for BigInt {
     }
 }
 
+/// An arbitrary precision decimal number.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BigDecimal(bigdecimal::BigDecimal);
+
+impl BigDecimal {
+    /// Creates the decimal `digits * 10^exp`.
+    pub fn new(digits: BigInt, exp: i64) -> Self {
+        // `bigdecimal` is built on an older version of `num-bigint` than `BigInt`,
+        // so the digits have to go through their string representation
+        BigDecimal::from_str(&format!("{}e{}", digits, exp))
+            .expect("a BigInt with an exponent is a valid decimal")
+    }
+
+    /// Returns `(digits, exp)` such that the decimal equals `digits * 10^exp`.
+    pub fn as_bigint_and_exponent(&self) -> (BigInt, i64) {
+        let (digits, scale) = self.0.as_bigint_and_exponent();
+        (
+            BigInt::from_str(&digits.to_string()).expect("digits are a valid BigInt"),
+            -scale,
+        )
+    }
+}
+
+impl Display for BigDecimal {
+    fn fmt(&self, f: &mut Formatter) -> Result<(), fmt::Error> {
+        self.0.fmt(f)
+    }
+}
+
+impl From<i32> for BigDecimal {
+    fn from(i: i32) -> BigDecimal {
+        BigDecimal::new(BigInt::from(i), 0)
+    }
+}
+
+impl From<BigInt> for BigDecimal {
+    fn from(n: BigInt) -> BigDecimal {
+        BigDecimal::new(n, 0)
+    }
+}
+
+impl FromStr for BigDecimal {
+    type Err = bigdecimal::ParseBigDecimalError;
+
+    fn from_str(s: &str) -> Result<BigDecimal, Self::Err> {
+        bigdecimal::BigDecimal::from_str(s).map(BigDecimal)
+    }
+}
+
+impl Serialize for BigDecimal {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_string().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BigDecimal {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use serde::de::Error;
+
+        let decimal_string = <String>::deserialize(deserializer)?;
+        BigDecimal::from_str(&decimal_string).map_err(D::Error::custom)
+    }
+}
+
+impl Add for BigDecimal {
+    type Output = BigDecimal;
+
+    fn add(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.add(other.0))
+    }
+}
+
+impl Sub for BigDecimal {
+    type Output = BigDecimal;
+
+    fn sub(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.sub(other.0))
+    }
+}
+
+impl Mul for BigDecimal {
+    type Output = BigDecimal;
+
+    fn mul(self, other: BigDecimal) -> BigDecimal {
+        BigDecimal(self.0.mul(other.0))
+    }
+}
+
+impl Div for BigDecimal {
+    type Output = BigDecimal;
+
+    fn div(self, other: BigDecimal) -> BigDecimal {
+        if other == BigDecimal::from(0) {
+            panic!("Cannot divide by zero-valued `BigDecimal`!")
+        }
+
+        BigDecimal(self.0.div(other.0))
+    }
+}
+
 /// A byte array that's serialized as a hex string prefixed by `0x`.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Bytes(Box<[u8]>);
@@ -1,9 +1,11 @@
+use components::ethereum::EthereumBlockPointer;
 use components::link_resolver::LinkResolver;
 use data::schema::Schema;
 use ethabi::Contract;
 use failure;
 use failure::{Error, SyncFailure};
 use futures::stream;
+use hex;
 use parity_wasm;
 use parity_wasm::elements::Module;
 use serde::de;
@@ -12,12 +14,21 @@ use serde_yaml;
 use std::fmt;
 use std::str::FromStr;
 use std::sync::Arc;
+use tiny_keccak;
 use tokio::prelude::*;
 use web3::types::Address;
 
 /// Rust representation of the GraphQL schema for a `SubgraphManifest`.
 pub mod schema;
 
+/// Consistency checks for resolved subgraph manifests.
+mod validation;
+
+pub use self::validation::{
+    SubgraphManifestValidationError, SubgraphManifestValidationErrors,
+    SUPPORTED_MAPPING_API_VERSIONS,
+};
+
 /// Deserialize an Address (with or without '0x' prefix).
 fn deserialize_address<'de, D>(deserializer: D) -> Result<Address, D::Error>
 where
@@ -30,6 +41,9 @@ where
     Address::from_str(address).map_err(D::Error::custom)
 }
 
+/// Prefix of the IDs of subgraphs whose manifests are not on IPFS.
+const LOCAL_SUBGRAPH_ID_PREFIX: &str = "local";
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct SubgraphId(String);
 
@@ -49,6 +63,25 @@ impl SubgraphId {
 
         Ok(SubgraphId(s))
     }
+
+    /// Returns the ID of the subgraph whose manifest is found at `link`.
+    ///
+    /// Subgraphs on IPFS are identified by the hash of their manifest. Manifests found
+    /// anywhere else, e.g. in local files during development, are identified by a hash
+    /// of the link itself, so that the ID stays the same while the manifest is edited.
+    pub fn for_link(link: &Link) -> Result<Self, ()> {
+        if link.link.starts_with("/ipfs/") {
+            SubgraphId::new(link.link.trim_left_matches("/ipfs/"))
+        } else {
+            let hash = hex::encode(tiny_keccak::keccak256(link.link.as_bytes()));
+            SubgraphId::new(format!("{}{}", LOCAL_SUBGRAPH_ID_PREFIX, &hash[..40]))
+        }
+    }
+
+    /// Whether the manifest of this subgraph is outside of IPFS, e.g. in a local file.
+    pub fn is_local(&self) -> bool {
+        self.0.starts_with(LOCAL_SUBGRAPH_ID_PREFIX)
+    }
 }
 
 impl fmt::Display for SubgraphId {
@@ -114,17 +147,62 @@ impl fmt::Display for SubgraphStatus {
     }
 }
 
+/// A fatal error that made a subgraph fail.
+#[derive(Fail, Debug, Clone, PartialEq)]
+#[fail(display = "{}", message)]
+pub struct SubgraphError {
+    pub message: String,
+    /// The block that was being processed when the error occurred.
+    pub block_ptr: Option<EthereumBlockPointer>,
+    /// The mapping handler that failed, if the error was raised by a mapping.
+    pub handler: Option<String>,
+    /// The data source whose mapping failed, if the error was raised by a mapping.
+    pub data_source: Option<String>,
+    /// Whether processing the same block again would fail the same way.
+    pub deterministic: bool,
+}
+
+impl SubgraphError {
+    /// Creates an error that is not tied to a block or a mapping.
+    pub fn new(message: impl Into<String>, deterministic: bool) -> Self {
+        SubgraphError {
+            message: message.into(),
+            block_ptr: None,
+            handler: None,
+            data_source: None,
+            deterministic,
+        }
+    }
+}
+
 #[derive(Fail, Debug)]
 pub enum SubgraphProviderError {
     #[fail(display = "subgraph resolve error: {}", _0)]
     ResolveError(SubgraphManifestResolveError),
+    /// Occurs when a subgraph manifest is inconsistent with its ABIs, mappings or schema.
+    #[fail(display = "subgraph validation error: {}", _0)]
+    ManifestValidationErrors(SubgraphManifestValidationErrors),
     /// Occurs when attempting to remove a subgraph that's not hosted.
     #[fail(display = "subgraph name not found: {}", _0)]
     NameNotFound(String),
+    /// Occurs when attempting to roll back to a subgraph that was never deployed under a name.
+    #[fail(display = "subgraph {} was never deployed under name {}", _1, _0)]
+    VersionNotFound(String, SubgraphId),
+    /// Occurs when attempting to rewind a subgraph to a block that is not an ancestor of its
+    /// latest processed block.
+    #[fail(display = "invalid rewind target: {}", _0)]
+    InvalidRewindTarget(String),
     #[fail(display = "subgraph with ID {} already running", _0)]
     AlreadyRunning(SubgraphId),
     #[fail(display = "subgraph with ID {} is not running", _0)]
     NotRunning(SubgraphId),
+    /// Occurs when a subgraph was deployed from a local manifest that has not been
+    /// registered with this node since it was started.
+    #[fail(
+        display = "manifest of subgraph {} is a local file that is not available to this node",
+        _0
+    )]
+    LocalManifestUnavailable(SubgraphId),
     /// Occurs when a subgraph's GraphQL schema is invalid.
     #[fail(display = "GraphQL schema error: {}", _0)]
     SchemaValidationError(failure::Error),
@@ -157,12 +235,36 @@ impl From<serde_yaml::Error> for SubgraphManifestResolveError {
 }
 
 /// IPLD link.
-#[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq)]
 pub struct Link {
-    #[serde(rename = "/")]
     pub link: String,
 }
 
+/// Links are either IPLD links of the form `{ "/": "..." }` or plain strings, which is how
+/// manifests that have not been uploaded to IPFS refer to files.
+impl<'de> de::Deserialize<'de> for Link {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawLink {
+            Ipld {
+                #[serde(rename = "/")]
+                link: String,
+            },
+            Plain(String),
+        }
+
+        let link = match <RawLink as de::Deserialize>::deserialize(deserializer)? {
+            RawLink::Ipld { link } => link,
+            RawLink::Plain(link) => link,
+        };
+        Ok(Link { link })
+    }
+}
+
 #[derive(Clone, Debug, Hash, Eq, PartialEq, Deserialize)]
 pub struct SchemaData {
     pub file: Link,
@@ -344,8 +446,8 @@ pub type SubgraphManifest = BaseSubgraphManifest<Schema, DataSource>;
 
 impl SubgraphManifest {
     /// Entry point for resolving a subgraph definition.
-    /// Right now the only supported links are of the form:
-    /// `/ipfs/QmUmg7BZC1YP1ca66rRtWKxpXp77WgVHrnv263JtDuvs2k`
+    /// Links are either of the form `/ipfs/QmUmg7BZC1YP1ca66rRtWKxpXp77WgVHrnv263JtDuvs2k`
+    /// or anything else the link resolver understands, e.g. a path to a local file.
     pub fn resolve(
         link: Link,
         resolver: Arc<impl LinkResolver>,
@@ -362,11 +464,16 @@ impl SubgraphManifest {
                         .as_mapping_mut()
                         .ok_or(SubgraphManifestResolveError::InvalidFormat)?;
 
-                    // Inject the IPFS hash as the ID of the subgraph
-                    // into the definition.
+                    // Inject the ID of the subgraph into the definition
+                    let id = SubgraphId::for_link(&link).map_err(|()| {
+                        SubgraphManifestResolveError::ResolveError(format_err!(
+                            "link does not identify a subgraph: {}",
+                            link.link
+                        ))
+                    })?;
                     raw_mapping.insert(
                         serde_yaml::Value::from("id"),
-                        serde_yaml::Value::from(link.link.trim_left_matches("/ipfs/")),
+                        serde_yaml::Value::from(id.to_string()),
                     );
 
                     // Inject the IPFS link as the location of the data
@@ -422,3 +529,48 @@ impl UnresolvedSubgraphManifest {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn subgraph_id_for_ipfs_link_is_the_hash() {
+        let link = Link {
+            link: "/ipfs/QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco".to_owned(),
+        };
+        let id = SubgraphId::for_link(&link).unwrap();
+        assert_eq!(
+            id,
+            SubgraphId::new("QmXoypizjW3WknFiJnKLwHCnL72vedxjQkDDP1mXWo6uco").unwrap()
+        );
+        assert!(!id.is_local());
+    }
+
+    #[test]
+    fn subgraph_id_for_local_link_is_stable_and_unique() {
+        let link = |link: &str| Link {
+            link: link.to_owned(),
+        };
+        let id = SubgraphId::for_link(&link("/home/user/subgraph/subgraph.yaml")).unwrap();
+
+        assert!(id.is_local());
+        assert_eq!(id.to_string().len(), 45);
+        assert_eq!(
+            SubgraphId::for_link(&link("/home/user/subgraph/subgraph.yaml")).unwrap(),
+            id
+        );
+        assert_ne!(
+            SubgraphId::for_link(&link("/home/user/other/subgraph.yaml")).unwrap(),
+            id
+        );
+    }
+
+    #[test]
+    fn subgraph_id_for_invalid_ipfs_link_fails() {
+        let link = Link {
+            link: "/ipfs/not-a-hash".to_owned(),
+        };
+        assert!(SubgraphId::for_link(&link).is_err());
+    }
+}
@@ -3,7 +3,7 @@
 use super::SubgraphId;
 use components::store::{EntityKey, EntityOperation};
 use data::store::{Entity, Value};
-use data::subgraph::SubgraphStatus;
+use data::subgraph::{SubgraphError, SubgraphStatus};
 use std::collections::HashMap;
 
 /// ID of the subgraph of subgraphs.
@@ -17,6 +17,9 @@ pub const SUBGRAPH_ENTITY_TYPENAME: &str = "Subgraph";
 /// Type name of manifests in the subgraph of subgraphs.
 pub const MANIFEST_ENTITY_TYPENAME: &str = "SubgraphManifest";
 
+/// Type name of fatal subgraph errors in the subgraph of subgraphs.
+pub const SUBGRAPH_ERROR_ENTITY_TYPENAME: &str = "SubgraphError";
+
 #[derive(Debug)]
 pub struct SubgraphEntity {
     id: SubgraphId,
@@ -86,6 +89,42 @@ impl SubgraphEntity {
         )]
     }
 
+    /// Marks the subgraph as failed and records the error that made it fail.
+    pub fn write_failure_operations(id: &SubgraphId, error: SubgraphError) -> Vec<EntityOperation> {
+        let error_id = SubgraphErrorEntity::id(id);
+        let mut ops = SubgraphErrorEntity::from(error).write_operations(&error_id);
+
+        let mut entity = HashMap::new();
+        entity.insert(
+            "status".to_owned(),
+            SubgraphStatus::Failed.to_string().into(),
+        );
+        entity.insert("fatalError".to_owned(), error_id.into());
+        ops.push(set_entity_operation(
+            SUBGRAPH_ENTITY_TYPENAME,
+            id.to_string(),
+            entity,
+        ));
+
+        ops
+    }
+
+    /// Removes the error recorded by the last failure of the subgraph, if there is one.
+    pub fn clear_failure_operations(id: &SubgraphId) -> Vec<EntityOperation> {
+        let mut entity = HashMap::new();
+        entity.insert("fatalError".to_owned(), Value::Null);
+        vec![
+            set_entity_operation(SUBGRAPH_ENTITY_TYPENAME, id.to_string(), entity),
+            EntityOperation::Remove {
+                key: EntityKey {
+                    subgraph_id: SUBGRAPHS_ID.clone(),
+                    entity_type: SUBGRAPH_ERROR_ENTITY_TYPENAME.to_owned(),
+                    entity_id: SubgraphErrorEntity::id(id),
+                },
+            },
+        ]
+    }
+
     pub fn write_ethereum_block_counts_operations(
         id: &SubgraphId,
         processed: u64,
@@ -100,6 +139,62 @@ impl SubgraphEntity {
             entity,
         )]
     }
+
+    /// Resets the status, progress and failure of a subgraph that is indexed again from
+    /// scratch.
+    pub fn reset_operations(id: &SubgraphId) -> Vec<EntityOperation> {
+        let mut ops = Self::write_status_operations(id, SubgraphStatus::Syncing);
+        ops.append(&mut Self::write_ethereum_block_counts_operations(id, 0, 0));
+        ops.append(&mut Self::clear_failure_operations(id));
+        ops
+    }
+}
+
+#[derive(Debug)]
+struct SubgraphErrorEntity {
+    message: String,
+    block_number: Option<u64>,
+    block_hash: Option<String>,
+    handler: Option<String>,
+    data_source: Option<String>,
+    deterministic: bool,
+}
+
+impl SubgraphErrorEntity {
+    fn id(subgraph_id: &SubgraphId) -> String {
+        format!("{}-error", subgraph_id)
+    }
+
+    fn write_operations(self, id: &str) -> Vec<EntityOperation> {
+        let mut entity = HashMap::new();
+        entity.insert("id".to_owned(), id.clone().into());
+        entity.insert("message".to_owned(), self.message.into());
+        entity.insert("blockNumber".to_owned(), self.block_number.into());
+        entity.insert("blockHash".to_owned(), self.block_hash.into());
+        entity.insert("handler".to_owned(), self.handler.into());
+        entity.insert("dataSource".to_owned(), self.data_source.into());
+        entity.insert("deterministic".to_owned(), self.deterministic.into());
+        vec![set_entity_operation(
+            SUBGRAPH_ERROR_ENTITY_TYPENAME,
+            id,
+            entity,
+        )]
+    }
+}
+
+impl From<SubgraphError> for SubgraphErrorEntity {
+    fn from(error: SubgraphError) -> Self {
+        Self {
+            message: error.message,
+            block_number: error.block_ptr.map(|block_ptr| block_ptr.number),
+            block_hash: error
+                .block_ptr
+                .map(|block_ptr| format!("{:?}", block_ptr.hash)),
+            handler: error.handler,
+            data_source: error.data_source,
+            deterministic: error.deterministic,
+        }
+    }
 }
 
 #[derive(Debug)]
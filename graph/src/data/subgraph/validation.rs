@@ -0,0 +1,469 @@
+use graphql_parser::schema::{Definition, Field, Type, TypeDefinition, Value};
+use parity_wasm::elements::{External, Internal};
+use std::fmt;
+
+use super::{DataSource, SubgraphManifest};
+use data::graphql::validation::get_object_type_definitions;
+use util::ethereum::contract_event_with_signature;
+
+/// Mapping API versions this node knows how to run.
+pub const SUPPORTED_MAPPING_API_VERSIONS: &[&str] = &["0.0.1", "0.0.2"];
+
+/// A problem in a subgraph manifest that would otherwise only be discovered while indexing.
+#[derive(Clone, Debug, Fail, PartialEq)]
+pub enum SubgraphManifestValidationError {
+    #[fail(
+        display = "data source `{}` uses unsupported mapping API version `{}`",
+        _0, _1
+    )]
+    UnsupportedApiVersion(String, String),
+    #[fail(
+        display = "data source `{}` uses ABI `{}` for its source, but the mapping has no ABI \
+                   with that name",
+        _0, _1
+    )]
+    SourceAbiNotFound(String, String),
+    #[fail(
+        display = "data source `{}` handles event `{}`, which is not in ABI `{}`",
+        _0, _1, _2
+    )]
+    EventNotInAbi(String, String, String),
+    #[fail(
+        display = "data source `{}` uses handler `{}`, which is not exported by its WASM module",
+        _0, _1
+    )]
+    HandlerNotExported(String, String),
+    #[fail(
+        display = "data source `{}` imports `{}.{}`, which is not available in mapping API \
+                   version `{}`",
+        _0, _1, _2, _3
+    )]
+    UnsupportedImport(String, String, String, String),
+    #[fail(
+        display = "data source `{}` lists entity `{}`, which is not a type in the schema",
+        _0, _1
+    )]
+    UnknownEntity(String, String),
+    #[fail(display = "field `{}.{}` has an invalid @derivedFrom: {}", _0, _1, _2)]
+    InvalidDerivedFrom(String, String, String),
+}
+
+/// All problems found while validating a subgraph manifest.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SubgraphManifestValidationErrors(pub Vec<SubgraphManifestValidationError>);
+
+impl fmt::Display for SubgraphManifestValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let errors = self
+            .0
+            .iter()
+            .map(|e| e.to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        write!(f, "{}", errors)
+    }
+}
+
+impl SubgraphManifest {
+    /// Checks the data sources and schema of the subgraph for consistency.
+    ///
+    /// `provides_import` is called with the API version of the mapping and the module and
+    /// field name of every function it imports, and must return whether the runtime provides
+    /// that function to mappings of that version.
+    pub fn validate<F>(&self, provides_import: F) -> Result<(), SubgraphManifestValidationErrors>
+    where
+        F: Fn(&str, &str, &str) -> bool,
+    {
+        let mut errors = vec![];
+
+        for data_source in self.data_sources.iter() {
+            let api_version_supported = validate_api_version(data_source, &mut errors);
+            validate_event_handlers(data_source, &mut errors);
+            validate_module(
+                data_source,
+                // Imports can only be checked against a known version
+                if api_version_supported {
+                    Some(&provides_import)
+                } else {
+                    None
+                },
+                &mut errors,
+            );
+            validate_entities(self, data_source, &mut errors);
+        }
+        validate_derived_fields(self, &mut errors);
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SubgraphManifestValidationErrors(errors))
+        }
+    }
+}
+
+/// Checks that the mapping API version of the data source is supported and returns whether it is.
+fn validate_api_version(
+    data_source: &DataSource,
+    errors: &mut Vec<SubgraphManifestValidationError>,
+) -> bool {
+    let api_version = &data_source.mapping.api_version;
+    if SUPPORTED_MAPPING_API_VERSIONS.contains(&api_version.as_str()) {
+        true
+    } else {
+        errors.push(SubgraphManifestValidationError::UnsupportedApiVersion(
+            data_source.name.clone(),
+            api_version.clone(),
+        ));
+        false
+    }
+}
+
+/// Checks that every event handled by the data source exists in the ABI of its source contract.
+fn validate_event_handlers(
+    data_source: &DataSource,
+    errors: &mut Vec<SubgraphManifestValidationError>,
+) {
+    let abi = match data_source
+        .mapping
+        .abis
+        .iter()
+        .find(|abi| abi.name == data_source.source.abi)
+    {
+        Some(abi) => abi,
+        None => {
+            errors.push(SubgraphManifestValidationError::SourceAbiNotFound(
+                data_source.name.clone(),
+                data_source.source.abi.clone(),
+            ));
+            return;
+        }
+    };
+
+    for event_handler in data_source.mapping.event_handlers.iter() {
+        if contract_event_with_signature(&abi.contract, &event_handler.event).is_none() {
+            errors.push(SubgraphManifestValidationError::EventNotInAbi(
+                data_source.name.clone(),
+                event_handler.event.clone(),
+                abi.name.clone(),
+            ));
+        }
+    }
+}
+
+/// Checks that the WASM module exports all event handlers and, if `provides_import` is given,
+/// only imports functions that the runtime provides to its mapping API version.
+fn validate_module<F>(
+    data_source: &DataSource,
+    provides_import: Option<&F>,
+    errors: &mut Vec<SubgraphManifestValidationError>,
+) where
+    F: Fn(&str, &str, &str) -> bool,
+{
+    let module = &data_source.mapping.runtime;
+
+    let exported_functions = module
+        .export_section()
+        .map(|section| {
+            section
+                .entries()
+                .iter()
+                .filter(|entry| match entry.internal() {
+                    Internal::Function(_) => true,
+                    _ => false,
+                })
+                .map(|entry| entry.field())
+                .collect::<Vec<_>>()
+        })
+        .unwrap_or(vec![]);
+
+    for event_handler in data_source.mapping.event_handlers.iter() {
+        if !exported_functions.contains(&event_handler.handler.as_str()) {
+            errors.push(SubgraphManifestValidationError::HandlerNotExported(
+                data_source.name.clone(),
+                event_handler.handler.clone(),
+            ));
+        }
+    }
+
+    let provides_import = match provides_import {
+        Some(provides_import) => provides_import,
+        None => return,
+    };
+
+    let api_version = &data_source.mapping.api_version;
+    if let Some(section) = module.import_section() {
+        for entry in section.entries() {
+            if let External::Function(_) = entry.external() {
+                if !provides_import(api_version, entry.module(), entry.field()) {
+                    errors.push(SubgraphManifestValidationError::UnsupportedImport(
+                        data_source.name.clone(),
+                        entry.module().to_owned(),
+                        entry.field().to_owned(),
+                        api_version.clone(),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Checks that the entities a data source claims to write are defined in the schema.
+fn validate_entities(
+    manifest: &SubgraphManifest,
+    data_source: &DataSource,
+    errors: &mut Vec<SubgraphManifestValidationError>,
+) {
+    let entity_types = get_object_type_definitions(&manifest.schema.document)
+        .into_iter()
+        .map(|object_type| object_type.name.as_str())
+        .collect::<Vec<_>>();
+
+    for entity in data_source.mapping.entities.iter() {
+        if !entity_types.contains(&entity.as_str()) {
+            errors.push(SubgraphManifestValidationError::UnknownEntity(
+                data_source.name.clone(),
+                entity.clone(),
+            ));
+        }
+    }
+}
+
+/// Checks that every `@derivedFrom(field: "...")` directive points to an existing field
+/// of the type the derived field refers to.
+fn validate_derived_fields(
+    manifest: &SubgraphManifest,
+    errors: &mut Vec<SubgraphManifestValidationError>,
+) {
+    let document = &manifest.schema.document;
+
+    // Fields of all object and interface types, by type name
+    let type_fields = document
+        .definitions
+        .iter()
+        .filter_map(|definition| match definition {
+            Definition::TypeDefinition(TypeDefinition::Object(t)) => Some((&t.name, &t.fields)),
+            Definition::TypeDefinition(TypeDefinition::Interface(t)) => Some((&t.name, &t.fields)),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+
+    for (type_name, fields) in type_fields.iter() {
+        for field in fields.iter() {
+            let derived_from = match field
+                .directives
+                .iter()
+                .find(|directive| directive.name == "derivedFrom")
+            {
+                Some(directive) => directive,
+                None => continue,
+            };
+
+            let invalid = |reason: String| {
+                SubgraphManifestValidationError::InvalidDerivedFrom(
+                    type_name.to_string(),
+                    field.name.clone(),
+                    reason,
+                )
+            };
+
+            let target_field = match derived_from
+                .arguments
+                .iter()
+                .find(|(name, _)| name == "field")
+            {
+                Some((_, Value::String(target_field))) => target_field,
+                _ => {
+                    errors.push(invalid("the `field` argument must be a string".to_owned()));
+                    continue;
+                }
+            };
+
+            let target_type = base_type_name(field);
+            match type_fields.iter().find(|(name, _)| *name == target_type) {
+                Some((_, target_fields)) => {
+                    if !target_fields.iter().any(|f| &f.name == target_field) {
+                        errors.push(invalid(format!(
+                            "type `{}` has no field `{}`",
+                            target_type, target_field
+                        )));
+                    }
+                }
+                None => errors.push(invalid(format!(
+                    "`{}` is not an entity or interface type",
+                    target_type
+                ))),
+            }
+        }
+    }
+}
+
+/// Returns the name of the type a field refers to, ignoring lists and non-null wrappers.
+fn base_type_name(field: &Field) -> &str {
+    let mut field_type = &field.field_type;
+    loop {
+        match field_type {
+            Type::NamedType(name) => return name,
+            Type::ListType(t) | Type::NonNullType(t) => field_type = &**t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use data::schema::Schema;
+    use data::subgraph::{
+        BaseDataSource, Link, Mapping, MappingABI, MappingEventHandler, Source, SubgraphId,
+    };
+    use ethabi::Contract;
+    use parity_wasm;
+
+    use self::SubgraphManifestValidationError::*;
+
+    const ABI: &str = r#"[{
+        "anonymous": false,
+        "inputs": [{ "indexed": false, "name": "value", "type": "uint256" }],
+        "name": "Transfer",
+        "type": "event"
+    }]"#;
+
+    /// A module that imports `env.abort` and `index.store.set` and exports `handleTransfer`.
+    const MODULE: &[u8] = &[
+        0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00, 0x01, 0x05, 0x01, 0x60, 0x01, 0x7f, 0x00,
+        0x02, 0x1f, 0x02, 0x03, 0x65, 0x6e, 0x76, 0x05, 0x61, 0x62, 0x6f, 0x72, 0x74, 0x00, 0x00,
+        0x05, 0x69, 0x6e, 0x64, 0x65, 0x78, 0x09, 0x73, 0x74, 0x6f, 0x72, 0x65, 0x2e, 0x73, 0x65,
+        0x74, 0x00, 0x00, 0x03, 0x02, 0x01, 0x00, 0x07, 0x12, 0x01, 0x0e, 0x68, 0x61, 0x6e, 0x64,
+        0x6c, 0x65, 0x54, 0x72, 0x61, 0x6e, 0x73, 0x66, 0x65, 0x72, 0x00, 0x02, 0x0a, 0x04, 0x01,
+        0x02, 0x00, 0x0b,
+    ];
+
+    fn manifest(
+        schema: &str,
+        api_version: &str,
+        entities: Vec<&str>,
+        event_handler: (&str, &str),
+    ) -> SubgraphManifest {
+        let id = SubgraphId::new("test").unwrap();
+        let link = Link {
+            link: "/ipfs/test".to_owned(),
+        };
+
+        SubgraphManifest {
+            id: id.clone(),
+            location: link.link.clone(),
+            spec_version: "0.0.1".to_owned(),
+            description: None,
+            repository: None,
+            schema: Schema::parse(schema, id).unwrap(),
+            data_sources: vec![BaseDataSource {
+                kind: "ethereum/contract".to_owned(),
+                network: None,
+                name: "Token".to_owned(),
+                source: Source {
+                    address: Default::default(),
+                    abi: "Token".to_owned(),
+                },
+                mapping: Mapping {
+                    kind: "ethereum/events".to_owned(),
+                    api_version: api_version.to_owned(),
+                    language: "wasm/assemblyscript".to_owned(),
+                    entities: entities.into_iter().map(|e| e.to_owned()).collect(),
+                    abis: vec![MappingABI {
+                        name: "Token".to_owned(),
+                        contract: Contract::load(ABI.as_bytes()).unwrap(),
+                        link: link.clone(),
+                    }],
+                    event_handlers: vec![MappingEventHandler {
+                        event: event_handler.0.to_owned(),
+                        handler: event_handler.1.to_owned(),
+                    }],
+                    runtime: parity_wasm::deserialize_buffer(MODULE).unwrap(),
+                    link,
+                },
+            }],
+        }
+    }
+
+    #[test]
+    fn valid_manifest() {
+        let manifest = manifest(
+            "type Account @entity { id: ID!, transfers: [Transfer!] @derivedFrom(field: \"from\") }
+             type Transfer @entity { id: ID!, from: Account! }",
+            "0.0.1",
+            vec!["Account", "Transfer"],
+            ("Transfer(uint256)", "handleTransfer"),
+        );
+
+        assert_eq!(manifest.validate(|_, _, _| true), Ok(()));
+    }
+
+    #[test]
+    fn all_problems_are_reported() {
+        let manifest = manifest(
+            "type Account @entity { id: ID!, transfers: [Transfer!] @derivedFrom(field: \"to\") }
+             type Transfer @entity { id: ID!, from: Account! }",
+            "0.0.1",
+            vec!["Account", "Approval"],
+            ("Approval(uint256)", "handleApproval"),
+        );
+
+        let errors = manifest
+            .validate(|_, module, field| module == "env" && field == "abort")
+            .unwrap_err();
+
+        assert_eq!(
+            errors.0,
+            vec![
+                EventNotInAbi(
+                    "Token".to_owned(),
+                    "Approval(uint256)".to_owned(),
+                    "Token".to_owned()
+                ),
+                HandlerNotExported("Token".to_owned(), "handleApproval".to_owned()),
+                UnsupportedImport(
+                    "Token".to_owned(),
+                    "index".to_owned(),
+                    "store.set".to_owned(),
+                    "0.0.1".to_owned()
+                ),
+                UnknownEntity("Token".to_owned(), "Approval".to_owned()),
+                InvalidDerivedFrom(
+                    "Account".to_owned(),
+                    "transfers".to_owned(),
+                    "type `Transfer` has no field `to`".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn imports_are_checked_against_the_api_version() {
+        let schema = "type Transfer @entity { id: ID! }";
+        let event_handler = ("Transfer(uint256)", "handleTransfer");
+
+        let errors = manifest(schema, "0.0.1", vec!["Transfer"], event_handler)
+            .validate(|api_version, _, field| api_version == "0.0.2" || field == "abort")
+            .unwrap_err();
+        assert_eq!(
+            errors.0,
+            vec![UnsupportedImport(
+                "Token".to_owned(),
+                "index".to_owned(),
+                "store.set".to_owned(),
+                "0.0.1".to_owned()
+            )]
+        );
+
+        // Imports of unsupported versions are not checked at all
+        let errors = manifest(schema, "0.1.0", vec!["Transfer"], event_handler)
+            .validate(|_, _, _| false)
+            .unwrap_err();
+        assert_eq!(
+            errors.0,
+            vec![UnsupportedApiVersion(
+                "Token".to_owned(),
+                "0.1.0".to_owned()
+            )]
+        );
+    }
+}
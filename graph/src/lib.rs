@@ -1,6 +1,9 @@
 extern crate backtrace;
+extern crate bigdecimal;
 pub extern crate ethabi;
 extern crate futures;
+#[cfg(test)]
+extern crate graph_mock;
 extern crate graphql_parser;
 extern crate hex;
 #[macro_use]
@@ -55,13 +58,16 @@ pub mod prelude {
     pub use tokio::prelude::*;
 
     pub use components::ethereum::{
-        BlockStream, BlockStreamBuilder, ChainHeadUpdate, ChainHeadUpdateListener, EthereumAdapter,
+        BlockIntegrityChecker, BlockIntegrityReport, BlockStream, BlockStreamBuilder,
+        ChainHeadHealth, ChainHeadUpdate, ChainHeadUpdateListener, ChainHealth, EthereumAdapter,
         EthereumBlock, EthereumBlockData, EthereumBlockPointer, EthereumEventData,
         EthereumLogFilter, EthereumNetworkIdentifier, EthereumTransactionData,
+        EthereumTransactionReceiptData,
     };
     pub use components::graphql::{GraphQlRunner, QueryResultFuture, SubscriptionResultFuture};
-    pub use components::link_resolver::LinkResolver;
+    pub use components::link_resolver::{IpfsResolver, LinkResolver};
     pub use components::server::admin::JsonRpcServer;
+    pub use components::server::index_node::IndexNodeServer;
     pub use components::server::query::GraphQLServer;
     pub use components::server::subscription::SubscriptionServer;
     pub use components::store::{
@@ -78,14 +84,15 @@ pub mod prelude {
     pub use data::graphql::SerializableValue;
     pub use data::query::{Query, QueryError, QueryExecutionError, QueryResult, QueryVariables};
     pub use data::schema::Schema;
-    pub use data::store::scalar::{BigInt, BigIntSign};
+    pub use data::store::scalar::{BigDecimal, BigInt, BigIntSign};
     pub use data::store::{
-        Attribute, DeploymentEvent, Entity, NodeId, SubgraphDeploymentName, SubgraphEntityPair,
-        Value, ValueType,
+        Attribute, DeploymentEvent, Entity, EntityValidationError, NodeId, SubgraphDeploymentName,
+        SubgraphEntityPair, SubgraphVersion, SubgraphVersionStatus, Value, ValueType,
     };
     pub use data::subgraph::{
-        DataSource, Link, MappingABI, MappingEventHandler, SubgraphId, SubgraphManifest,
-        SubgraphManifestResolveError, SubgraphProviderError, SubgraphStatus,
+        DataSource, Link, MappingABI, MappingEventHandler, SubgraphError, SubgraphId,
+        SubgraphManifest, SubgraphManifestResolveError, SubgraphManifestValidationError,
+        SubgraphManifestValidationErrors, SubgraphProviderError, SubgraphStatus,
     };
     pub use data::subscription::{
         QueryResultStream, Subscription, SubscriptionError, SubscriptionResult,
@@ -0,0 +1,68 @@
+use failure::Error;
+use std::env;
+use std::str::FromStr;
+
+/// Reads a positive number from the environment variable `name`, or returns `default` if
+/// the variable is not set.
+///
+/// Fails if the variable is set to anything but a positive number.
+pub fn positive_number_from_env<T>(name: &str, default: T) -> Result<T, Error>
+where
+    T: FromStr + PartialOrd + From<u8>,
+{
+    let value = match env::var_os(name) {
+        Some(value) => value,
+        None => return Ok(default),
+    };
+
+    match value.to_str().and_then(|s| s.trim().parse::<T>().ok()) {
+        Some(number) if number > T::from(0) => Ok(number),
+        _ => Err(format_err!(
+            "{} must be a positive number, but is set to `{}`",
+            name,
+            value.to_string_lossy()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positive_number_from_env_falls_back_to_default() {
+        env::remove_var("GRAPH_TEST_UNSET_NUMBER");
+        assert_eq!(
+            positive_number_from_env::<u64>("GRAPH_TEST_UNSET_NUMBER", 10).unwrap(),
+            10
+        );
+    }
+
+    #[test]
+    fn positive_number_from_env_parses_numbers() {
+        env::set_var("GRAPH_TEST_VALID_NUMBER", "25");
+        assert_eq!(
+            positive_number_from_env::<usize>("GRAPH_TEST_VALID_NUMBER", 10).unwrap(),
+            25
+        );
+    }
+
+    #[test]
+    fn positive_number_from_env_rejects_invalid_numbers() {
+        for (name, value) in vec![
+            ("GRAPH_TEST_ZERO_NUMBER", "0"),
+            ("GRAPH_TEST_NEGATIVE_NUMBER", "-1"),
+            ("GRAPH_TEST_TEXT_NUMBER", "ten"),
+        ] {
+            env::set_var(name, value);
+            let error = positive_number_from_env::<u64>(name, 10).unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                format!(
+                    "{} must be a positive number, but is set to `{}`",
+                    name, value
+                )
+            );
+        }
+    }
+}
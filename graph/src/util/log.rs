@@ -4,6 +4,8 @@ use slog::{crit, info, o, Drain, FilterLevel, Logger};
 use slog_async;
 use slog_envlogger;
 use slog_term;
+use std::any::Any;
+use std::cell::Cell;
 use std::sync::Mutex;
 use std::time::Duration;
 use std::{env, panic, process, thread};
@@ -41,16 +43,7 @@ pub fn guarded_logger() -> (Logger, slog_async::AsyncGuard) {
 pub fn register_panic_hook(panic_logger: Logger, shutdown_sender: oneshot::Sender<()>) {
     let shutdown_mutex = Mutex::new(Some(shutdown_sender));
     panic::set_hook(Box::new(move |panic_info| {
-        let panic_payload = panic_info
-            .payload()
-            .downcast_ref::<String>()
-            .cloned()
-            .or_else(|| {
-                panic_info
-                    .payload()
-                    .downcast_ref::<&str>()
-                    .map(|s| s.to_string())
-            });
+        let panic_payload = panic_message(panic_info.payload());
 
         let panic_location = if let Some(location) = panic_info.location() {
             format!("{}:{}", location.file(), location.line().to_string())
@@ -74,6 +67,11 @@ pub fn register_panic_hook(panic_logger: Logger, shutdown_sender: oneshot::Sende
             }
         };
 
+        // Panics caught by `catch_panic` are reported by the caller instead
+        if CATCHING_PANICS.with(|catching| catching.get()) {
+            return;
+        }
+
         // Send a shutdown signal to main which will attempt to cleanly shutdown the runtime
         // After sending shutdown, the thread sleeps for 3 seconds then forces the process to
         // exit because the shutdown is not always able to cleanly exit all workers
@@ -92,3 +90,28 @@ pub fn register_panic_hook(panic_logger: Logger, shutdown_sender: oneshot::Sende
         process::exit(1);
     }));
 }
+
+thread_local! {
+    static CATCHING_PANICS: Cell<bool> = Cell::new(false);
+}
+
+/// Runs `f` and returns the message of the panic if it panics. The panic is
+/// logged by the panic hook like any other, but does not shut down the node.
+pub fn catch_panic<F, R>(f: F) -> Result<R, String>
+where
+    F: FnOnce() -> R,
+{
+    let was_catching = CATCHING_PANICS.with(|catching| catching.replace(true));
+    let result = panic::catch_unwind(panic::AssertUnwindSafe(f));
+    CATCHING_PANICS.with(|catching| catching.set(was_catching));
+    result.map_err(|payload| {
+        panic_message(&*payload).unwrap_or_else(|| String::from("unknown panic"))
+    })
+}
+
+fn panic_message(payload: &(Any + Send)) -> Option<String> {
+    payload
+        .downcast_ref::<String>()
+        .cloned()
+        .or_else(|| payload.downcast_ref::<&str>().map(|s| s.to_string()))
+}
@@ -36,7 +36,18 @@ pub fn api_schema(input_schema: &Document) -> Result<Document, APISchemaError> {
 
 /// Adds built-in GraphQL scalar types (`Int`, `String` etc.) to the schema.
 fn add_builtin_scalar_types(schema: &mut Document) -> Result<(), APISchemaError> {
-    for name in ["Boolean", "ID", "Int", "Float", "String", "Bytes", "BigInt"].into_iter() {
+    for name in [
+        "Boolean",
+        "ID",
+        "Int",
+        "Float",
+        "String",
+        "Bytes",
+        "BigInt",
+        "BigDecimal",
+    ]
+    .into_iter()
+    {
         match ast::get_named_type(schema, &name.to_string()) {
             None => {
                 let typedef = TypeDefinition::Scalar(ScalarType {
@@ -204,6 +215,7 @@ fn field_scalar_filter_input_values(
 ) -> Vec<InputValue> {
     match field_type.name.as_ref() {
         "BigInt" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
+        "BigDecimal" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
         "Boolean" => vec!["", "not", "in", "not_in"],
         "Bytes" => vec!["", "not", "in", "not_in", "contains", "not_contains"],
         "Float" => vec!["", "not", "gt", "lt", "gte", "lte", "in", "not_in"],
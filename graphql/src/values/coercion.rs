@@ -34,6 +34,9 @@ impl MaybeCoercible<ScalarType> for Value {
             ("ID", v @ Value::String(_)) => Some(v.clone()),
             ("Bytes", v @ Value::String(_)) => Some(v.clone()),
             ("BigInt", v @ Value::String(_)) => Some(v.clone()),
+            ("BigDecimal", v @ Value::String(_)) => Some(v.clone()),
+            ("BigDecimal", v @ Value::Int(_)) => Some(v.clone()),
+            ("BigDecimal", v @ Value::Float(_)) => Some(v.clone()),
             _ => None,
         }
     }
@@ -104,7 +107,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use graphql_parser::query::Value;
+    use graphql_parser::query::{Number, Value};
     use graphql_parser::schema::{
         EnumType, EnumValue, InputObjectType, ScalarType, TypeDefinition,
     };
@@ -302,6 +305,32 @@ mod tests {
         );
     }
 
+    #[test]
+    fn coerce_big_decimal_scalar() {
+        let big_decimal_type = TypeDefinition::Scalar(ScalarType::new("BigDecimal".to_string()));
+
+        // We can coerce from Value::String -> TypeDefinition::Scalar(BigDecimal)
+        assert_eq!(
+            Value::String("1234.5678".to_string()).coerce(&big_decimal_type),
+            Some(Value::String("1234.5678".to_string()))
+        );
+
+        // We can coerce from Value::Float -> TypeDefinition::Scalar(BigDecimal)
+        assert_eq!(
+            Value::Float(23.7).coerce(&big_decimal_type),
+            Some(Value::Float(23.7))
+        );
+
+        // We can coerce from Value::Int -> TypeDefinition::Scalar(BigDecimal)
+        assert_eq!(
+            Value::Int(Number::from(17)).coerce(&big_decimal_type),
+            Some(Value::Int(Number::from(17)))
+        );
+
+        // We don't support going from Value::Boolean -> TypeDefinition::Scalar(BigDecimal)
+        assert_eq!(Value::Boolean(true).coerce(&big_decimal_type), None);
+    }
+
     #[test]
     fn coerce_bytes_scalar() {
         let bytes_type = TypeDefinition::Scalar(ScalarType::new("Bytes".to_string()));
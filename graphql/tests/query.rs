@@ -137,6 +137,10 @@ impl Store for TestStore {
         unimplemented!()
     }
 
+    fn find_block_ptr(&self, _: SubgraphId) -> Result<Option<EthereumBlockPointer>, Error> {
+        unimplemented!()
+    }
+
     fn set_block_ptr_with_no_changes(
         &self,
         _: SubgraphId,
@@ -181,6 +185,10 @@ impl Store for TestStore {
         Ok(1)
     }
 
+    fn reset_subgraph(&self, _: SubgraphId, _: EthereumBlockPointer) -> Result<(), Error> {
+        unimplemented!()
+    }
+
     fn get(&self, key: EntityKey) -> Result<Option<Entity>, QueryExecutionError> {
         self.entities
             .iter()
@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use graph::prelude::*;
+use graph::web3::types::*;
+
+use super::MockChainHeadUpdateListener;
+
+/// Returns block `number` of the main chain (`fork` 0) or of a fork that branched off the
+/// main chain before block `number`. Blocks have no transactions.
+pub fn chain_block(number: u64, fork: u64) -> EthereumBlock {
+    EthereumBlock {
+        block: Block {
+            hash: Some(H256::from(fork * 1000 + number + 1)),
+            parent_hash: if number == 0 {
+                H256::default()
+            } else {
+                H256::from(number)
+            },
+            uncles_hash: H256::default(),
+            author: H160::default(),
+            state_root: H256::default(),
+            transactions_root: H256::default(),
+            receipts_root: H256::default(),
+            number: Some(U128::from(number)),
+            gas_used: U256::from(0),
+            gas_limit: U256::from(1000),
+            extra_data: Bytes(vec![]),
+            logs_bloom: H2048::default(),
+            timestamp: U256::from(100000 + number),
+            difficulty: U256::from(10),
+            total_difficulty: U256::from(10 * (number + 1)),
+            seal_fields: vec![],
+            uncles: vec![],
+            transactions: vec![],
+            size: Some(U256::from(500)),
+        },
+        transaction_receipts: vec![],
+    }
+}
+
+/// A chain store that keeps blocks in memory. The genesis block is block 0 of the main
+/// chain built by `chain_block`.
+#[derive(Default)]
+pub struct MockChainStore {
+    blocks: Arc<Mutex<HashMap<H256, EthereumBlock>>>,
+    head: Mutex<Option<EthereumBlockPointer>>,
+}
+
+impl MockChainStore {
+    /// Creates a chain store with `blocks` and no chain head.
+    pub fn new(blocks: Vec<EthereumBlock>) -> Self {
+        MockChainStore {
+            blocks: Arc::new(Mutex::new(
+                blocks
+                    .into_iter()
+                    .map(|block| (block.block.hash.unwrap(), block))
+                    .collect(),
+            )),
+            head: Mutex::new(None),
+        }
+    }
+}
+
+impl ChainStore for MockChainStore {
+    type ChainHeadUpdateListener = MockChainHeadUpdateListener;
+
+    fn genesis_block_ptr(&self) -> Result<EthereumBlockPointer, Error> {
+        Ok((&chain_block(0, 0)).into())
+    }
+
+    fn upsert_blocks<'a, B, E>(&self, blocks: B) -> Box<Future<Item = (), Error = E> + Send + 'a>
+    where
+        B: Stream<Item = EthereumBlock, Error = E> + Send + 'a,
+        E: From<Error> + Send + 'a,
+    {
+        let stored_blocks = self.blocks.clone();
+        Box::new(blocks.for_each(move |block| {
+            stored_blocks
+                .lock()
+                .unwrap()
+                .insert(block.block.hash.unwrap(), block);
+            Ok(())
+        }))
+    }
+
+    /// Makes the block with the highest number the chain head, unless one of its
+    /// `ancestor_count` ancestors is missing.
+    fn attempt_chain_head_update(&self, ancestor_count: u64) -> Result<Vec<H256>, Error> {
+        let blocks = self.blocks.lock().unwrap();
+        let candidate = match blocks.values().max_by_key(|block| block.block.number) {
+            Some(block) => block,
+            None => return Ok(vec![]),
+        };
+
+        // Check that the ancestors of the new head block are present
+        let mut ancestor = candidate;
+        for _ in 0..ancestor_count {
+            if ancestor.block.number == Some(U128::from(0)) {
+                break;
+            }
+            ancestor = match blocks.get(&ancestor.block.parent_hash) {
+                Some(parent) => parent,
+                None => return Ok(vec![ancestor.block.parent_hash]),
+            };
+        }
+
+        *self.head.lock().unwrap() = Some(candidate.into());
+        Ok(vec![])
+    }
+
+    fn chain_head_updates(&self) -> Self::ChainHeadUpdateListener {
+        unimplemented!();
+    }
+
+    fn chain_head_ptr(&self) -> Result<Option<EthereumBlockPointer>, Error> {
+        Ok(self.head.lock().unwrap().clone())
+    }
+
+    fn block(&self, block_hash: H256) -> Result<Option<EthereumBlock>, Error> {
+        Ok(self.blocks.lock().unwrap().get(&block_hash).cloned())
+    }
+
+    fn block_ptrs_in_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(EthereumBlockPointer, H256)>, Error> {
+        let mut block_ptrs = self
+            .blocks
+            .lock()
+            .unwrap()
+            .values()
+            .map(|block| (EthereumBlockPointer::from(block), block.block.parent_hash))
+            .filter(|(block_ptr, _)| block_ptr.number >= from && block_ptr.number <= to)
+            .collect::<Vec<_>>();
+        block_ptrs.sort_by_key(|(block_ptr, _)| block_ptr.number);
+        Ok(block_ptrs)
+    }
+
+    fn ancestor_block(
+        &self,
+        block_ptr: EthereumBlockPointer,
+        offset: u64,
+    ) -> Result<Option<EthereumBlock>, Error> {
+        if offset > block_ptr.number {
+            return Err(format_err!("block offset points to before genesis block"));
+        }
+
+        let blocks = self.blocks.lock().unwrap();
+        let mut block = match blocks.get(&block_ptr.hash) {
+            Some(block) => block,
+            None => return Ok(None),
+        };
+        for _ in 0..offset {
+            block = match blocks.get(&block.block.parent_hash) {
+                Some(parent) => parent,
+                None => return Ok(None),
+            };
+        }
+        Ok(Some(block.clone()))
+    }
+}
@@ -5,11 +5,15 @@ extern crate graph_graphql;
 extern crate graphql_parser;
 
 mod block_stream;
+mod chain_store;
 mod graphql;
+mod http;
 mod server;
 mod store;
 
 pub use self::block_stream::{MockBlockStream, MockBlockStreamBuilder};
+pub use self::chain_store::{chain_block, MockChainStore};
 pub use self::graphql::MockGraphQlRunner;
+pub use self::http::serve_once;
 pub use self::server::MockGraphQLServer;
-pub use self::store::{FakeStore, MockStore};
+pub use self::store::{FakeStore, MockChainHeadUpdateListener, MockStore};
@@ -1,11 +1,15 @@
 use futures::sync::mpsc::{channel, Sender};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
 
 use graph::components::store::*;
+use graph::data::subgraph::schema::{SubgraphEntity, SUBGRAPHS_ID, SUBGRAPH_ENTITY_TYPENAME};
 use graph::prelude::*;
 use graph::web3::types::H256;
 
+use super::MockChainStore;
+
 /// A mock `ChainHeadUpdateListener`
 pub struct MockChainHeadUpdateListener {}
 
@@ -23,9 +27,12 @@ impl EventProducer<ChainHeadUpdate> for MockChainHeadUpdateListener {
 
 pub struct MockStore {
     entities: Vec<Entity>,
+    stored_entities: Mutex<BTreeMap<EntityKey, Entity>>,
     schemas: HashMap<SubgraphId, Schema>,
-    subgraph_deployments: Mutex<Vec<(SubgraphDeploymentName, SubgraphId, NodeId)>>,
+    subgraph_deployments: Mutex<Vec<SubgraphVersion>>,
     subgraph_deployment_event_senders: Mutex<Vec<Sender<DeploymentEvent>>>,
+    block_ptrs: Mutex<HashMap<SubgraphId, EthereumBlockPointer>>,
+    chain_store: MockChainStore,
 }
 
 impl MockStore {
@@ -42,9 +49,79 @@ impl MockStore {
 
         MockStore {
             entities,
+            ..Self::empty(schemas)
+        }
+    }
+
+    /// Creates a new mock `Store` without the test users.
+    pub fn empty(schemas: Vec<(SubgraphId, Schema)>) -> Self {
+        MockStore {
+            entities: vec![],
+            stored_entities: Default::default(),
             schemas: schemas.into_iter().collect(),
             subgraph_deployments: Default::default(),
             subgraph_deployment_event_senders: Default::default(),
+            block_ptrs: Default::default(),
+            chain_store: Default::default(),
+        }
+    }
+
+    /// Returns the entities written with `apply_entity_operations`, ordered by key.
+    pub fn stored_entities(&self) -> Vec<(EntityKey, Entity)> {
+        self.stored_entities
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(key, entity)| (key.clone(), entity.clone()))
+            .collect()
+    }
+
+    /// Updates a subgraph version and emits the deployment events the Postgres store would
+    /// emit for the change.
+    fn update_version<F>(&self, version: &mut SubgraphVersion, f: F)
+    where
+        F: FnOnce(&mut SubgraphVersion),
+    {
+        let old_version = version.clone();
+        f(version);
+
+        let moved = old_version.node_id != version.node_id;
+
+        if old_version.is_indexed() && (!version.is_indexed() || moved) {
+            self.emit_deployment_event(DeploymentEvent::Remove {
+                deployment_name: old_version.name,
+                subgraph_id: old_version.subgraph_id,
+                node_id: old_version.node_id,
+            });
+        }
+        if version.is_indexed() && (!old_version.is_indexed() || moved) {
+            self.emit_deployment_event(DeploymentEvent::Add {
+                deployment_name: version.name.clone(),
+                subgraph_id: version.subgraph_id.clone(),
+                node_id: version.node_id.clone(),
+            });
+        }
+    }
+
+    /// Moves the block pointer of a subgraph from `block_ptr_from` to `block_ptr_to`, and
+    /// fails if the subgraph is not at `block_ptr_from`.
+    fn update_block_ptr(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        let mut block_ptrs = self.block_ptrs.lock().unwrap();
+        match block_ptrs.get_mut(&subgraph_id) {
+            Some(ref mut block_ptr) if **block_ptr == block_ptr_from => {
+                **block_ptr = block_ptr_to;
+                Ok(())
+            }
+            _ => Err(format_err!(
+                "failed to update subgraph block pointer from {:?} to {:?}",
+                block_ptr_from,
+                block_ptr_to
+            )),
         }
     }
 
@@ -69,8 +146,13 @@ impl MockStore {
 
 impl Store for MockStore {
     fn get(&self, key: EntityKey) -> Result<Option<Entity>, QueryExecutionError> {
+        if let Some(entity) = self.stored_entities.lock().unwrap().get(&key) {
+            return Ok(Some(entity.clone()));
+        }
+
         if key.entity_type == "User" {
-            self.entities
+            Ok(self
+                .entities
                 .iter()
                 .find(|entity| {
                     let id = entity.get("id").unwrap();
@@ -79,10 +161,9 @@ impl Store for MockStore {
                         _ => false,
                     }
                 })
-                .map(|entity| Some(entity.clone()))
-                .ok_or_else(|| unimplemented!())
+                .cloned())
         } else {
-            unimplemented!()
+            Ok(None)
         }
     }
 
@@ -90,56 +171,125 @@ impl Store for MockStore {
         Ok(self.entities.clone())
     }
 
-    fn add_subgraph_if_missing(&self, _: SubgraphId, _: EthereumBlockPointer) -> Result<(), Error> {
-        unimplemented!();
+    fn add_subgraph_if_missing(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        self.block_ptrs
+            .lock()
+            .unwrap()
+            .entry(subgraph_id)
+            .or_insert(block_ptr);
+        Ok(())
     }
 
-    fn block_ptr(&self, _: SubgraphId) -> Result<EthereumBlockPointer, Error> {
-        unimplemented!();
+    fn block_ptr(&self, subgraph_id: SubgraphId) -> Result<EthereumBlockPointer, Error> {
+        self.find_block_ptr(subgraph_id.clone())?
+            .ok_or_else(|| format_err!("subgraph {} not found in store", subgraph_id))
+    }
+
+    fn find_block_ptr(
+        &self,
+        subgraph_id: SubgraphId,
+    ) -> Result<Option<EthereumBlockPointer>, Error> {
+        Ok(self.block_ptrs.lock().unwrap().get(&subgraph_id).cloned())
     }
 
     fn set_block_ptr_with_no_changes(
         &self,
-        _: SubgraphId,
-        _: EthereumBlockPointer,
-        _: EthereumBlockPointer,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
     ) -> Result<(), Error> {
-        unimplemented!();
+        self.update_block_ptr(subgraph_id, block_ptr_from, block_ptr_to)
     }
 
     fn transact_block_operations(
         &self,
-        _: SubgraphId,
-        _: EthereumBlockPointer,
-        _: EthereumBlockPointer,
-        _: Vec<EntityOperation>,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
+        operations: Vec<EntityOperation>,
     ) -> Result<(), Error> {
-        unimplemented!();
+        self.update_block_ptr(subgraph_id, block_ptr_from, block_ptr_to)?;
+        self.apply_entity_operations(operations, EventSource::None)
     }
 
     fn apply_entity_operations(
         &self,
-        _: Vec<EntityOperation>,
+        operations: Vec<EntityOperation>,
         _: EventSource,
     ) -> Result<(), Error> {
+        let mut stored_entities = self.stored_entities.lock().unwrap();
+        for operation in operations {
+            let key = operation.entity_key().clone();
+            let existing = stored_entities.remove(&key);
+            if let Some(entity) = operation.apply(existing) {
+                stored_entities.insert(key, entity);
+            }
+        }
         Ok(())
     }
 
     fn revert_block_operations(
         &self,
-        _: SubgraphId,
-        _: EthereumBlockPointer,
-        _: EthereumBlockPointer,
+        subgraph_id: SubgraphId,
+        block_ptr_from: EthereumBlockPointer,
+        block_ptr_to: EthereumBlockPointer,
     ) -> Result<(), Error> {
-        unimplemented!();
+        // Only the block pointer is reverted; entity history is not tracked
+        self.update_block_ptr(subgraph_id, block_ptr_from, block_ptr_to)
     }
 
+    /// Entity changes are not tracked, so subscriptions never receive any.
     fn subscribe(&self, _: Vec<SubgraphEntityPair>) -> EntityChangeStream {
-        unimplemented!();
+        Box::new(stream::empty())
     }
 
-    fn count_entities(&self, _: SubgraphId) -> Result<u64, Error> {
-        unimplemented!();
+    fn count_entities(&self, subgraph_id: SubgraphId) -> Result<u64, Error> {
+        Ok(self
+            .stored_entities
+            .lock()
+            .unwrap()
+            .keys()
+            .filter(|key| key.subgraph_id == subgraph_id)
+            .count() as u64)
+    }
+
+    fn reset_subgraph(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        let subgraph_started = {
+            let mut stored_entities = self.stored_entities.lock().unwrap();
+            let keys = stored_entities
+                .keys()
+                .filter(|key| key.subgraph_id == subgraph_id)
+                .cloned()
+                .collect::<Vec<_>>();
+            for key in keys {
+                stored_entities.remove(&key);
+            }
+
+            stored_entities.contains_key(&EntityKey {
+                subgraph_id: SUBGRAPHS_ID.clone(),
+                entity_type: SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+                entity_id: subgraph_id.to_string(),
+            })
+        };
+        if subgraph_started {
+            self.apply_entity_operations(
+                SubgraphEntity::reset_operations(&subgraph_id),
+                EventSource::None,
+            )?;
+        }
+
+        if let Some(subgraph_block_ptr) = self.block_ptrs.lock().unwrap().get_mut(&subgraph_id) {
+            *subgraph_block_ptr = block_ptr;
+        }
+        Ok(())
     }
 }
 
@@ -153,13 +303,10 @@ impl SubgraphDeploymentStore for MockStore {
             .lock()
             .unwrap()
             .iter()
-            .filter_map(|(name, subgraph_id, node_id)| {
-                if *node_id == by_node_id {
-                    Some((name.to_owned(), subgraph_id.to_owned()))
-                } else {
-                    None
-                }
+            .filter(|version| {
+                version.node_id == by_node_id && version.status != SubgraphVersionStatus::Retired
             })
+            .map(|version| (version.name.clone(), version.subgraph_id.clone()))
             .collect())
     }
 
@@ -171,42 +318,76 @@ impl SubgraphDeploymentStore for MockStore {
     ) -> Result<(), Error> {
         let mut deployments = self.subgraph_deployments.lock().unwrap();
 
-        // Find first deployment with matching name
-        for deployment in deployments.iter_mut() {
-            if deployment.0 == name {
-                // Keep old values for event
-                let old_subgraph_id = deployment.1.to_owned();
-                let old_node_id = deployment.2.to_owned();
-
-                // Update deployment
-                deployment.1 = new_subgraph_id.clone();
-                deployment.2 = new_node_id.clone();
-
-                // Send events
-                self.emit_deployment_event(DeploymentEvent::Remove {
-                    deployment_name: name.clone(),
-                    subgraph_id: old_subgraph_id.clone(),
-                    node_id: old_node_id.clone(),
-                });
-                self.emit_deployment_event(DeploymentEvent::Add {
-                    deployment_name: name.clone(),
-                    subgraph_id: new_subgraph_id.clone(),
-                    node_id: new_node_id.clone(),
-                });
+        let existing_status = deployments
+            .iter()
+            .find(|version| version.name == name && version.subgraph_id == new_subgraph_id)
+            .map(|version| version.status);
+
+        let new_status = match existing_status {
+            // Redeploying the current or pending version keeps its status
+            Some(SubgraphVersionStatus::Retired) => SubgraphVersionStatus::Pending,
+            Some(status) => status,
+            None => {
+                if deployments.iter().any(|version| {
+                    version.name == name && version.status == SubgraphVersionStatus::Current
+                }) {
+                    SubgraphVersionStatus::Pending
+                } else {
+                    SubgraphVersionStatus::Current
+                }
+            }
+        };
+
+        // A new pending version replaces the previous one
+        if new_status == SubgraphVersionStatus::Pending {
+            for version in deployments.iter_mut() {
+                if version.name == name
+                    && version.subgraph_id != new_subgraph_id
+                    && version.status == SubgraphVersionStatus::Pending
+                {
+                    self.update_version(version, |version| {
+                        version.status = SubgraphVersionStatus::Retired;
+                    });
+                }
+            }
+        }
 
-                return Ok(());
+        // A subgraph deployed under several names is indexed by a single node
+        for version in deployments.iter_mut() {
+            if version.subgraph_id == new_subgraph_id && version.node_id != new_node_id {
+                self.update_version(version, |version| version.node_id = new_node_id.clone());
             }
         }
 
+        if let Some(version) = deployments
+            .iter_mut()
+            .find(|version| version.name == name && version.subgraph_id == new_subgraph_id)
+        {
+            self.update_version(version, |version| version.status = new_status);
+            return Ok(());
+        }
+
         // Add deployment
-        deployments.push((name.clone(), new_subgraph_id.clone(), new_node_id.clone()));
+        let version = SubgraphVersion {
+            name,
+            subgraph_id: new_subgraph_id,
+            node_id: new_node_id,
+            status: new_status,
+            paused: false,
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        };
 
         // Send event
         self.emit_deployment_event(DeploymentEvent::Add {
-            deployment_name: name.clone(),
-            subgraph_id: new_subgraph_id.clone(),
-            node_id: new_node_id.clone(),
+            deployment_name: version.name.clone(),
+            subgraph_id: version.subgraph_id.clone(),
+            node_id: version.node_id.clone(),
         });
+
+        deployments.push(version);
         Ok(())
     }
 
@@ -216,32 +397,170 @@ impl SubgraphDeploymentStore for MockStore {
             .lock()
             .unwrap()
             .iter()
-            .find(|(name, _, _)| *name == by_name)
-            .map(|(_, subgraph_id, node_id)| (subgraph_id.to_owned(), node_id.to_owned())))
+            .find(|version| {
+                version.name == by_name && version.status == SubgraphVersionStatus::Current
+            })
+            .map(|version| (version.subgraph_id.clone(), version.node_id.clone())))
     }
 
     fn remove(&self, by_name: SubgraphDeploymentName) -> Result<bool, Error> {
         let mut deployments = self.subgraph_deployments.lock().unwrap();
+        let count_before = deployments.len();
+
+        // Remove all versions with the name
+        let (removed, kept) = deployments
+            .drain(..)
+            .partition::<Vec<_>, _>(|version| version.name == by_name);
+        *deployments = kept;
+
+        // Send events
+        for version in removed.iter() {
+            if version.status != SubgraphVersionStatus::Retired {
+                self.emit_deployment_event(DeploymentEvent::Remove {
+                    deployment_name: version.name.clone(),
+                    subgraph_id: version.subgraph_id.clone(),
+                    node_id: version.node_id.clone(),
+                });
+            }
+        }
+
+        Ok(deployments.len() < count_before)
+    }
+
+    fn read_versions(
+        &self,
+        by_name: SubgraphDeploymentName,
+    ) -> Result<Vec<SubgraphVersion>, Error> {
+        let mut versions = self
+            .subgraph_deployments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|version| version.name == by_name)
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Most recently deployed first
+        versions.reverse();
+        Ok(versions)
+    }
+
+    fn read_versions_by_node_id(&self, by_node_id: NodeId) -> Result<Vec<SubgraphVersion>, Error> {
+        let mut versions = self
+            .subgraph_deployments
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|version| {
+                version.node_id == by_node_id && version.status != SubgraphVersionStatus::Retired
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        // Most recently deployed first
+        versions.reverse();
+        Ok(versions)
+    }
+
+    fn promote_pending_version(&self, subgraph_id: &SubgraphId) -> Result<bool, Error> {
+        let mut deployments = self.subgraph_deployments.lock().unwrap();
 
-        // Find deployment by name
-        let pos_opt = deployments.iter().position(|(name, _, _)| *name == by_name);
+        // The subgraph may be the pending version of several names
+        let names = deployments
+            .iter()
+            .filter(|version| {
+                version.subgraph_id == *subgraph_id
+                    && version.status == SubgraphVersionStatus::Pending
+            })
+            .map(|version| version.name.clone())
+            .collect::<Vec<_>>();
 
-        // If found
-        if let Some(pos) = pos_opt {
-            // Remove the deployment
-            let (deployment_name, subgraph_id, node_id) = deployments.swap_remove(pos);
+        for version in deployments
+            .iter_mut()
+            .filter(|version| names.contains(&version.name))
+        {
+            if version.subgraph_id == *subgraph_id {
+                self.update_version(version, |version| {
+                    version.status = SubgraphVersionStatus::Current;
+                });
+            } else if version.status == SubgraphVersionStatus::Current {
+                self.update_version(version, |version| {
+                    version.status = SubgraphVersionStatus::Retired;
+                });
+            }
+        }
 
-            // Send event
-            self.emit_deployment_event(DeploymentEvent::Remove {
-                deployment_name,
-                subgraph_id,
-                node_id,
-            });
+        Ok(!names.is_empty())
+    }
 
-            Ok(true)
-        } else {
-            Ok(false)
+    fn rollback(
+        &self,
+        name: SubgraphDeploymentName,
+        subgraph_id: SubgraphId,
+    ) -> Result<bool, Error> {
+        let mut deployments = self.subgraph_deployments.lock().unwrap();
+
+        if !deployments
+            .iter()
+            .any(|version| version.name == name && version.subgraph_id == subgraph_id)
+        {
+            return Ok(false);
+        }
+
+        for version in deployments
+            .iter_mut()
+            .filter(|version| version.name == name)
+        {
+            if version.subgraph_id == subgraph_id {
+                self.update_version(version, |version| {
+                    version.status = SubgraphVersionStatus::Current;
+                });
+            } else if version.status != SubgraphVersionStatus::Retired {
+                self.update_version(version, |version| {
+                    version.status = SubgraphVersionStatus::Retired;
+                });
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn reassign(&self, name: SubgraphDeploymentName, node_id: NodeId) -> Result<bool, Error> {
+        let mut deployments = self.subgraph_deployments.lock().unwrap();
+
+        let subgraph_ids = deployments
+            .iter()
+            .filter(|version| version.name == name)
+            .map(|version| version.subgraph_id.clone())
+            .collect::<Vec<_>>();
+
+        // Versions shared with other names move along, so that each subgraph is still
+        // indexed by a single node
+        for version in deployments
+            .iter_mut()
+            .filter(|version| subgraph_ids.contains(&version.subgraph_id))
+        {
+            self.update_version(version, |version| version.node_id = node_id.clone());
+        }
+
+        Ok(!subgraph_ids.is_empty())
+    }
+
+    fn set_paused(&self, name: SubgraphDeploymentName, paused: bool) -> Result<bool, Error> {
+        let mut found = false;
+
+        for version in self
+            .subgraph_deployments
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .filter(|version| version.name == name)
+        {
+            found = true;
+            self.update_version(version, |version| version.paused = paused);
         }
+
+        Ok(found)
     }
 
     fn deployment_events(
@@ -276,39 +595,47 @@ impl ChainStore for MockStore {
     type ChainHeadUpdateListener = MockChainHeadUpdateListener;
 
     fn genesis_block_ptr(&self) -> Result<EthereumBlockPointer, Error> {
-        unimplemented!();
+        self.chain_store.genesis_block_ptr()
     }
 
-    fn upsert_blocks<'a, B, E>(&self, _: B) -> Box<Future<Item = (), Error = E> + Send + 'a>
+    fn upsert_blocks<'a, B, E>(&self, blocks: B) -> Box<Future<Item = (), Error = E> + Send + 'a>
     where
         B: Stream<Item = EthereumBlock, Error = E> + Send + 'a,
         E: From<Error> + Send + 'a,
     {
-        unimplemented!();
+        self.chain_store.upsert_blocks(blocks)
     }
 
-    fn attempt_chain_head_update(&self, _: u64) -> Result<Vec<H256>, Error> {
-        unimplemented!();
+    fn attempt_chain_head_update(&self, ancestor_count: u64) -> Result<Vec<H256>, Error> {
+        self.chain_store.attempt_chain_head_update(ancestor_count)
     }
 
     fn chain_head_updates(&self) -> Self::ChainHeadUpdateListener {
-        unimplemented!();
+        self.chain_store.chain_head_updates()
     }
 
     fn chain_head_ptr(&self) -> Result<Option<EthereumBlockPointer>, Error> {
-        unimplemented!();
+        self.chain_store.chain_head_ptr()
     }
 
-    fn block(&self, _: H256) -> Result<Option<EthereumBlock>, Error> {
-        unimplemented!();
+    fn block(&self, block_hash: H256) -> Result<Option<EthereumBlock>, Error> {
+        self.chain_store.block(block_hash)
+    }
+
+    fn block_ptrs_in_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(EthereumBlockPointer, H256)>, Error> {
+        self.chain_store.block_ptrs_in_range(from, to)
     }
 
     fn ancestor_block(
         &self,
-        _: EthereumBlockPointer,
-        _: u64,
+        block_ptr: EthereumBlockPointer,
+        offset: u64,
     ) -> Result<Option<EthereumBlock>, Error> {
-        unimplemented!();
+        self.chain_store.ancestor_block(block_ptr, offset)
     }
 }
 
@@ -316,7 +643,7 @@ pub struct FakeStore;
 
 impl Store for FakeStore {
     fn get(&self, _: EntityKey) -> Result<Option<Entity>, QueryExecutionError> {
-        unimplemented!();
+        Ok(None)
     }
 
     fn find(&self, _: EntityQuery) -> Result<Vec<Entity>, QueryExecutionError> {
@@ -331,6 +658,10 @@ impl Store for FakeStore {
         unimplemented!();
     }
 
+    fn find_block_ptr(&self, _: SubgraphId) -> Result<Option<EthereumBlockPointer>, Error> {
+        unimplemented!();
+    }
+
     fn set_block_ptr_with_no_changes(
         &self,
         _: SubgraphId,
@@ -374,6 +705,10 @@ impl Store for FakeStore {
     fn count_entities(&self, _: SubgraphId) -> Result<u64, Error> {
         unimplemented!();
     }
+
+    fn reset_subgraph(&self, _: SubgraphId, _: EthereumBlockPointer) -> Result<(), Error> {
+        Ok(())
+    }
 }
 
 impl ChainStore for FakeStore {
@@ -407,6 +742,14 @@ impl ChainStore for FakeStore {
         unimplemented!();
     }
 
+    fn block_ptrs_in_range(
+        &self,
+        _: u64,
+        _: u64,
+    ) -> Result<Vec<(EthereumBlockPointer, H256)>, Error> {
+        unimplemented!();
+    }
+
     fn ancestor_block(
         &self,
         _: EthereumBlockPointer,
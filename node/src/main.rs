@@ -9,6 +9,7 @@ extern crate graph_core;
 extern crate graph_datasource_ethereum;
 extern crate graph_runtime_wasm;
 extern crate graph_server_http;
+extern crate graph_server_index_node;
 extern crate graph_server_json_rpc;
 extern crate graph_server_websocket;
 extern crate graph_store_postgres;
@@ -16,29 +17,40 @@ extern crate http;
 extern crate ipfs_api;
 extern crate url;
 
-use clap::{App, Arg};
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use futures::sync::oneshot;
 use ipfs_api::IpfsClient;
 use itertools::FoldWhile::{Continue, Done};
 use itertools::Itertools;
 use std::env;
-use std::net::ToSocketAddrs;
+use std::fs;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Path, PathBuf};
+use std::process;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime};
 
 use graph::components::forward;
-use graph::prelude::{JsonRpcServer as JsonRpcServerTrait, *};
+use graph::prelude::{
+    IndexNodeServer as IndexNodeServerTrait, JsonRpcServer as JsonRpcServerTrait, *,
+};
+use graph::serde_json;
+use graph::tokio::timer::Interval;
 use graph::tokio_executor;
 use graph::tokio_timer;
 use graph::tokio_timer::timer::Timer;
 use graph::util::log::{guarded_logger, logger, register_panic_hook};
 use graph_core::{
-    ElasticLoggingConfig, SubgraphInstanceManager, SubgraphProvider as IpfsSubgraphProvider,
+    ElasticLoggingConfig, FileLinkResolver, SubgraphInstanceManager,
+    SubgraphProvider as IpfsSubgraphProvider,
     SubgraphProviderWithNames as IpfsSubgraphProviderWithNames,
 };
-use graph_datasource_ethereum::{BlockStreamBuilder, Transport};
-use graph_runtime_wasm::RuntimeHostBuilder as WASMRuntimeHostBuilder;
+use graph_datasource_ethereum::{BlockIntegrityChecker, BlockStreamBuilder, Transport};
+use graph_runtime_wasm::{
+    MappingTestFile, MappingTester, RuntimeHostBuilder as WASMRuntimeHostBuilder,
+};
 use graph_server_http::GraphQLServer as GraphQLQueryServer;
+use graph_server_index_node::IndexNodeServer;
 use graph_server_json_rpc::JsonRpcServer;
 use graph_server_websocket::SubscriptionServer as GraphQLSubscriptionServer;
 use graph_store_postgres::{Store as DieselStore, StoreConfig};
@@ -97,8 +109,8 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             Arg::with_name("subgraph")
                 .takes_value(true)
                 .long("subgraph")
-                .value_name("[NAME:]IPFS_HASH")
-                .help("name and IPFS hash of the subgraph manifest"),
+                .value_name("[NAME:]IPFS_HASH|PATH")
+                .help("name and IPFS hash or local path of the subgraph manifest"),
         )
         .arg(
             Arg::with_name("postgres-url")
@@ -149,8 +161,10 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .takes_value(true)
                 .required(true)
                 .long("ipfs")
+                .multiple(true)
+                .use_delimiter(true)
                 .value_name("HOST:PORT")
-                .help("HTTP address of an IPFS node"),
+                .help("HTTP addresses of IPFS nodes, tried in order"),
         )
         .arg(
             Arg::with_name("http-port")
@@ -173,6 +187,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .value_name("PORT")
                 .help("Port for the JSON-RPC admin server"),
         )
+        .arg(
+            Arg::with_name("index-node-port")
+                .default_value("8030")
+                .long("index-node-port")
+                .value_name("PORT")
+                .help("Port for the index node server"),
+        )
         .arg(
             Arg::with_name("node-id")
                 .default_value("default")
@@ -185,6 +206,15 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .long("debug")
                 .help("Enable debug logging"),
         )
+        .arg(
+            Arg::with_name("watch")
+                .long("watch")
+                .requires("subgraph")
+                .help(
+                    "Reindex the local `--subgraph` manifest from scratch whenever \
+                     files in its directory change",
+                ),
+        )
         .arg(
             Arg::with_name("elasticsearch-url")
                 .long("elasticsearch-url")
@@ -215,11 +245,90 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
                 .env("ETHEREUM_POLLING_INTERVAL")
                 .help("How often to poll the Ethereum node for new blocks"),
         )
+        .arg(
+            Arg::with_name("ethereum-reorg-threshold")
+                .long("ethereum-reorg-threshold")
+                .value_name("BLOCKS")
+                .validator(validate_block_count)
+                .default_value("50")
+                .env("ETHEREUM_REORG_THRESHOLD")
+                .help("Number of blocks behind the chain head after which blocks are considered final"),
+        )
+        .arg(
+            Arg::with_name("ethereum-confirmations")
+                .long("ethereum-confirmations")
+                .value_name("BLOCKS")
+                .validator(validate_block_count)
+                .default_value("0")
+                .env("ETHEREUM_CONFIRMATIONS")
+                .help("Number of blocks subgraphs are kept behind the chain head"),
+        )
+        .arg(
+            Arg::with_name("ethereum-ancestor-count")
+                .long("ethereum-ancestor-count")
+                .value_name("BLOCKS")
+                .validator(validate_block_count)
+                .env("ETHEREUM_ANCESTOR_COUNT")
+                .help(
+                    "Number of ancestors of the chain head block to keep in the block store \
+                     [default: reorg threshold + confirmations]",
+                ),
+        )
+        .arg(
+            Arg::with_name("ethereum-max-head-age")
+                .long("ethereum-max-head-age")
+                .value_name("SECONDS")
+                .default_value("300")
+                .env("ETHEREUM_MAX_HEAD_AGE")
+                .help("Maximum age of the chain head block before the chain is considered unhealthy"),
+        )
+        .arg(
+            Arg::with_name("ethereum-max-head-lag")
+                .long("ethereum-max-head-lag")
+                .value_name("BLOCKS")
+                .default_value("50")
+                .env("ETHEREUM_MAX_HEAD_LAG")
+                .help(
+                    "Maximum number of blocks the chain head may be behind the Ethereum node \
+                     before the chain is considered unhealthy",
+                ),
+        )
+        .arg(
+            Arg::with_name("ethereum-health-check-interval")
+                .long("ethereum-health-check-interval")
+                .value_name("SECONDS")
+                .validator(validate_health_check_interval)
+                .default_value("30")
+                .env("ETHEREUM_HEALTH_CHECK_INTERVAL")
+                .help("How often to check the health of the chain head"),
+        )
+        .subcommand(
+            SubCommand::with_name("test")
+                .about("Runs mapping tests against a local subgraph")
+                .arg(
+                    Arg::with_name("manifest")
+                        .required(true)
+                        .value_name("MANIFEST")
+                        .help("path of the subgraph manifest"),
+                )
+                .arg(
+                    Arg::with_name("tests")
+                        .required(true)
+                        .multiple(true)
+                        .value_name("TEST_FILE")
+                        .help("JSON files with the mapping tests to run"),
+                ),
+        )
+        .setting(AppSettings::SubcommandsNegateReqs)
         .get_matches();
 
     // Set up logger
     let logger = logger(matches.is_present("debug"));
 
+    if let Some(test_matches) = matches.subcommand_matches("test") {
+        process::exit(run_mapping_tests(&logger, test_matches));
+    }
+
     // Safe to unwrap because a value is required by CLI
     let postgres_url = matches.value_of("postgres-url").unwrap().to_string();
 
@@ -227,7 +336,26 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         .expect("Node ID must contain only a-z, A-Z, 0-9, and '_'");
 
     // Obtain subgraph related command-line arguments
-    let subgraph = matches.value_of("subgraph").map(|s| s.to_owned());
+    let subgraph = matches
+        .value_of("subgraph")
+        .map(parse_subgraph_name_and_manifest);
+
+    // A subgraph can also be indexed straight from a manifest in the working tree
+    let subgraph_manifest_path = subgraph
+        .as_ref()
+        .map(|(_, manifest)| Path::new(manifest))
+        .filter(|path| {
+            path.extension()
+                .map_or(false, |extension| extension == "yaml" || extension == "yml")
+        })
+        .map(|path| {
+            path.canonicalize()
+                .unwrap_or_else(|e| panic!("failed to find subgraph manifest {:?}: {}", path, e))
+        });
+    let watch = matches.is_present("watch");
+    if watch && subgraph_manifest_path.is_none() {
+        panic!("--watch requires `--subgraph` to point to a local subgraph manifest");
+    }
 
     // Obtain the Ethereum parameters
     let ethereum_rpc = matches.value_of("ethereum-rpc");
@@ -242,6 +370,58 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             .expect("Ethereum polling interval must be a nonnegative integer"),
     );
 
+    // Obtain the reorg threshold, confirmation depth and ancestor count; the values are
+    // validated by the CLI
+    let reorg_threshold: u64 = matches
+        .value_of("ethereum-reorg-threshold")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let confirmations: u64 = matches
+        .value_of("ethereum-confirmations")
+        .unwrap()
+        .parse()
+        .unwrap();
+    let ancestor_count: u64 = matches
+        .value_of("ethereum-ancestor-count")
+        .map(|s| s.parse().unwrap())
+        .unwrap_or(reorg_threshold + confirmations);
+
+    // Block streams rely on the confirmed head block and its reorg threshold most recent
+    // ancestors being present in the block store
+    if ancestor_count < reorg_threshold + confirmations {
+        clap::Error::with_description(
+            &format!(
+                "Ethereum ancestor count ({}) must be at least the reorg threshold ({}) \
+                 plus the number of confirmations ({})",
+                ancestor_count, reorg_threshold, confirmations
+            ),
+            clap::ErrorKind::ValueValidation,
+        )
+        .exit();
+    }
+
+    // Obtain the chain head health thresholds
+    let max_head_age = Duration::from_secs(
+        matches
+            .value_of("ethereum-max-head-age")
+            .unwrap()
+            .parse()
+            .expect("Ethereum max head age must be a nonnegative integer"),
+    );
+    let max_head_lag: u64 = matches
+        .value_of("ethereum-max-head-lag")
+        .unwrap()
+        .parse()
+        .expect("Ethereum max head lag must be a nonnegative integer");
+    let health_check_interval = Duration::from_secs(
+        matches
+            .value_of("ethereum-health-check-interval")
+            .unwrap()
+            .parse()
+            .unwrap(),
+    );
+
     // Obtain ports to use for the GraphQL server(s)
     let http_port = matches
         .value_of("http-port")
@@ -261,6 +441,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
         .parse()
         .expect("invalid admin port");
 
+    // Obtain index node server port
+    let index_node_port = matches
+        .value_of("index-node-port")
+        .unwrap()
+        .parse()
+        .expect("invalid index node server port");
+
     debug!(logger, "Setting up Sentry");
 
     // Set up Sentry, with release tracking and panic handling;
@@ -278,58 +465,47 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
     sentry::integrations::panic::register_panic_handler();
     info!(logger, "Starting up");
 
-    // Try to create an IPFS client for one of the resolved IPFS addresses
-    let ipfs_address = matches.value_of("ipfs").unwrap();
-    let (ipfs_client, ipfs_address) = match ipfs_address
-        // Resolve the IPFS address into socket addresses
-        .to_socket_addrs()
-        .unwrap_or_else(|e| panic!("failed to resolve IPFS address {}: {}", ipfs_address, e))
-        // Try to create an IPFS client for one of these addresses; collect
-        // errors in case we can't create a client for any of them
-        .fold_while(Err(vec![]), |result, address| {
-            info!(logger, "Trying IPFS node at: {}", address);
-
-            match IpfsClient::new(&format!("{}", address.ip()), address.port()) {
-                Ok(client) => Done(Ok((Arc::new(client), address))),
-                Err(e) => Continue(result.map_err(|mut errors| {
-                    errors.push((address, e));
-                    errors
-                })),
-            }
-        })
-        .into_inner()
-    {
-        Ok((client, address)) => (client, address),
-        Err(errors) => {
-            for (address, e) in errors.iter() {
-                error!(
-                    logger, "Failed to create IPFS client for address: {}", address;
-                    "error" => format!("{}", e),
-                )
-            }
-            panic!("Could not connect to IPFS");
-        }
-    };
+    // Create a client for each IPFS node; nodes listed later are only used if the
+    // nodes before them fail to return a file
+    let ipfs_clients = matches
+        .values_of("ipfs")
+        .unwrap()
+        .map(|ipfs_address| create_ipfs_client(&logger, ipfs_address))
+        .collect::<Vec<_>>();
+
+    // Test the IPFS clients by getting the version from the IPFS daemons
+    for (ipfs_client, ipfs_address) in ipfs_clients.iter() {
+        let ipfs_address = *ipfs_address;
+        let ipfs_ok_logger = logger.clone();
+        let ipfs_err_logger = logger.clone();
+        tokio::spawn(
+            ipfs_client
+                .version()
+                .map_err(move |e| {
+                    error!(
+                        ipfs_err_logger,
+                        "Is there an IPFS node running at \"{}\"?", ipfs_address
+                    );
+                    panic!("Failed to connect to IPFS: {}", e);
+                })
+                .map(move |_| {
+                    info!(
+                        ipfs_ok_logger,
+                        "Successfully connected to IPFS node at: {}", ipfs_address
+                    );
+                }),
+        );
+    }
 
-    // Test the IPFS client by getting the version from the IPFS daemon
-    let ipfs_test = ipfs_client.version();
-    let ipfs_ok_logger = logger.clone();
-    let ipfs_err_logger = logger.clone();
-    tokio::spawn(
-        ipfs_test
-            .map_err(move |e| {
-                error!(
-                    ipfs_err_logger,
-                    "Is there an IPFS node running at \"{}\"?", ipfs_address
-                );
-                panic!("Failed to connect to IPFS: {}", e);
-            })
-            .map(move |_| {
-                info!(
-                    ipfs_ok_logger,
-                    "Successfully connected to IPFS node at: {}", ipfs_address
-                );
-            }),
+    let ipfs_resolver = Arc::new(
+        IpfsResolver::new(
+            &logger,
+            ipfs_clients
+                .into_iter()
+                .map(|(ipfs_client, _)| ipfs_client)
+                .collect(),
+        )
+        .unwrap_or_else(|e| panic!("invalid IPFS configuration: {}", e)),
     );
 
     // Parse the Ethereum URL
@@ -387,7 +563,7 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             network_name: ethereum_network_name.to_owned(),
         },
         &logger,
-        eth_net_identifiers,
+        eth_net_identifiers.clone(),
     ));
     let graphql_runner = Arc::new(graph_core::GraphQlRunner::new(&logger, store.clone()));
     let mut graphql_server = GraphQLQueryServer::new(
@@ -399,11 +575,17 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
     let mut subscription_server =
         GraphQLSubscriptionServer::new(&logger, graphql_runner.clone(), store.clone());
 
-    // Create Ethereum block ingestor
+    // Create Ethereum block ingestor, which also monitors the health of the chain head
+    let chain_health = ChainHealth::new();
     let block_ingestor = graph_datasource_ethereum::BlockIngestor::new(
         store.clone(),
         transport.clone(),
-        50, // ancestor count, which we could make configuable
+        ancestor_count,
+        eth_net_identifiers,
+        chain_health.clone(),
+        max_head_age,
+        max_head_lag,
+        health_check_interval,
         logger.clone(),
         block_polling_interval,
     )
@@ -413,8 +595,13 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
     tokio::spawn(block_ingestor.into_polling_stream());
 
     // Prepare a block stream builder for subgraphs
-    let block_stream_builder =
-        BlockStreamBuilder::new(store.clone(), store.clone(), ethereum.clone());
+    let block_stream_builder = BlockStreamBuilder::new(
+        store.clone(),
+        store.clone(),
+        ethereum.clone(),
+        reorg_threshold,
+        confirmations,
+    );
 
     // Optionally, identify the Elasticsearch logging configuration
     let elastic_config =
@@ -428,7 +615,8 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
 
     // Prepare for hosting WASM runtimes and managing subgraph instances
     let runtime_host_builder =
-        WASMRuntimeHostBuilder::new(ethereum.clone(), ipfs_client.clone(), store.clone());
+        WASMRuntimeHostBuilder::new(ethereum.clone(), ipfs_resolver.clone(), store.clone())
+            .unwrap_or_else(|e| panic!("invalid mapping configuration: {}", e));
     let subgraph_instance_manager = SubgraphInstanceManager::new(
         &logger,
         store.clone(),
@@ -439,15 +627,28 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
 
     // Create IPFS-based subgraph provider
     let mut subgraph_provider =
-        IpfsSubgraphProvider::new(logger.clone(), ipfs_client, store.clone());
+        IpfsSubgraphProvider::new(logger.clone(), ipfs_resolver, store.clone());
+
+    // Identify the CLI subgraph by its IPFS hash or, for local manifests, by its path.
+    // Only local manifests may link to files on this machine or on HTTP servers.
+    let subgraph = subgraph.map(|(name, hash)| {
+        let subgraph_id = match subgraph_manifest_path {
+            Some(ref path) => subgraph_provider
+                .register_local_manifest(path)
+                .expect("Failed to register subgraph manifest"),
+            None => SubgraphId::new(hash).expect("Subgraph hash must be a valid IPFS hash"),
+        };
+        (name, subgraph_id)
+    });
 
     // Forward subgraph events from the subgraph provider to the subgraph instance manager
     tokio::spawn(forward(&mut subgraph_provider, &subgraph_instance_manager).unwrap());
 
     // Create named subgraph provider for resolving subgraph name->ID mappings
+    let subgraph_provider = Arc::new(subgraph_provider);
     let named_subgraph_provider = Arc::new(IpfsSubgraphProviderWithNames::new(
         logger.clone(),
-        Arc::new(subgraph_provider),
+        subgraph_provider.clone(),
         store.clone(),
         node_id.clone(),
     ));
@@ -457,12 +658,17 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             .then(|start_result| Ok(start_result.expect("failed to initialize subgraph provider"))),
     );
 
+    // Prepare a block integrity checker for the admin server
+    let block_checker = Arc::new(BlockIntegrityChecker::new(store.clone(), ethereum.clone()));
+
     // Start admin JSON-RPC server.
     let json_rpc_server = JsonRpcServer::serve(
         json_rpc_port,
         http_port,
         ws_port,
         named_subgraph_provider.clone(),
+        block_checker,
+        chain_health.clone(),
         node_id.clone(),
         logger.clone(),
     )
@@ -472,25 +678,33 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
     std::mem::forget(json_rpc_server);
 
     // Add the CLI subgraph with a REST request to the admin server.
-    if let Some(subgraph) = subgraph {
-        let (name, hash) = if subgraph.contains(':') {
-            let mut split = subgraph.split(':');
-            (split.next().unwrap(), split.next().unwrap().to_owned())
-        } else {
-            ("cli", subgraph)
-        };
-
+    if let Some((name, subgraph_id)) = subgraph {
         let name = SubgraphDeploymentName::new(name)
             .expect("Subgraph name must contain only a-z, A-Z, 0-9, '-' and '_'");
-        let subgraph_id = SubgraphId::new(hash).expect("Subgraph hash must be a valid IPFS hash");
 
         tokio::spawn(
             named_subgraph_provider
-                .deploy(name, subgraph_id, node_id.clone())
+                .deploy(name, subgraph_id.clone(), node_id.clone())
                 .then(|deploy_result| {
                     Ok(deploy_result.expect("Failed to deploy subgraph from `--subgraph` flag"))
                 }),
         );
+
+        // Reindex the subgraph from scratch whenever its build output changes
+        if watch {
+            let manifest_dir = subgraph_manifest_path
+                .as_ref()
+                .and_then(|path| path.parent())
+                .unwrap()
+                .to_owned();
+            tokio::spawn(watch_subgraph(
+                logger.clone(),
+                manifest_dir,
+                subgraph_id,
+                subgraph_provider,
+                store.clone(),
+            ));
+        }
     }
 
     // Serve GraphQL queries over HTTP
@@ -507,9 +721,162 @@ fn async_main() -> impl Future<Item = (), Error = ()> + Send + 'static {
             .expect("Failed to start GraphQL subscription server"),
     );
 
+    // Serve indexing statuses of this node's subgraphs over HTTP
+    let mut index_node_server = IndexNodeServer::new(
+        &logger,
+        store.clone(),
+        chain_health,
+        node_id.clone(),
+        ethereum_network_name.to_owned(),
+    );
+    tokio::spawn(
+        index_node_server
+            .serve(index_node_port)
+            .expect("Failed to start index node server"),
+    );
+
     future::empty()
 }
 
+/// Restarts a subgraph with an empty store whenever files in `dir` change.
+///
+/// The manifest is resolved again when the subgraph is started, so changes to the
+/// manifest, schema, ABIs and mappings all take effect.
+fn watch_subgraph<P, S>(
+    logger: Logger,
+    dir: PathBuf,
+    subgraph_id: SubgraphId,
+    provider: Arc<P>,
+    store: Arc<S>,
+) -> impl Future<Item = (), Error = ()> + Send
+where
+    P: SubgraphProvider,
+    S: Store + ChainStore,
+{
+    let logger = logger.new(o!("component" => "SubgraphWatcher"));
+    let mut fingerprint = dir_fingerprint(&dir);
+
+    info!(logger, "Watching subgraph for changes"; "dir" => dir.display().to_string());
+
+    Interval::new(Instant::now(), Duration::from_secs(1))
+        .map_err(|e| panic!("subgraph watcher interval failed: {}", e))
+        .filter(move |_| {
+            let new_fingerprint = dir_fingerprint(&dir);
+            let changed = new_fingerprint != fingerprint;
+            fingerprint = new_fingerprint;
+            changed
+        })
+        .for_each(move |_| {
+            info!(logger, "Subgraph files changed, reindexing from scratch";
+                  "subgraph" => subgraph_id.to_string());
+
+            let logger = logger.clone();
+            let subgraph_id = subgraph_id.clone();
+            let provider = provider.clone();
+            let store = store.clone();
+
+            provider
+                .stop(subgraph_id.clone())
+                .or_else(|e| match e {
+                    // The subgraph may have failed to start after a previous change
+                    SubgraphProviderError::NotRunning(_) => Ok(()),
+                    e => Err(e.into()),
+                })
+                .and_then(move |()| {
+                    // An in-flight block of the old instance can no longer be written once
+                    // the block pointer has moved back, as it will not match anymore
+                    store
+                        .genesis_block_ptr()
+                        .and_then(|block_ptr| store.reset_subgraph(subgraph_id.clone(), block_ptr))
+                        .map(|()| subgraph_id)
+                })
+                .and_then(move |subgraph_id| provider.start(subgraph_id).map_err(Error::from))
+                .or_else(move |e: Error| {
+                    error!(logger, "Failed to reindex subgraph: {}", e);
+                    Ok(())
+                })
+        })
+}
+
+/// Summarizes the modification times of all files below `dir`, so that changes
+/// can be detected by comparing the results of two calls.
+///
+/// Symbolic links are not followed, so that links pointing back into the tree
+/// cannot make the walk recurse forever; only changes to the links themselves
+/// are detected.
+fn dir_fingerprint(dir: &Path) -> Vec<(PathBuf, Option<SystemTime>)> {
+    let mut fingerprint = vec![];
+    if let Ok(entries) = fs::read_dir(dir) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+
+            // Unlike `Path::is_dir`, the file type of an entry describes a symbolic
+            // link itself rather than its target
+            let is_dir = entry
+                .file_type()
+                .map(|file_type| file_type.is_dir())
+                .unwrap_or(false);
+            if is_dir {
+                fingerprint.extend(dir_fingerprint(&path));
+            } else {
+                let modified = fs::symlink_metadata(&path).and_then(|m| m.modified()).ok();
+                fingerprint.push((path, modified));
+            }
+        }
+    }
+    fingerprint.sort();
+    fingerprint
+}
+
+/// Splits the `--subgraph` argument into the deployment name, which defaults to `cli`,
+/// and the IPFS hash or path of the subgraph manifest.
+fn parse_subgraph_name_and_manifest(s: &str) -> (String, String) {
+    // Paths to manifests may contain ':' themselves
+    if Path::new(s).exists() {
+        return ("cli".to_owned(), s.to_owned());
+    }
+
+    let mut split = s.splitn(2, ':');
+    match (split.next(), split.next()) {
+        (Some(name), Some(manifest)) => (name.to_owned(), manifest.to_owned()),
+        _ => ("cli".to_owned(), s.to_owned()),
+    }
+}
+
+/// Creates an IPFS client for one of the socket addresses an IPFS address resolves to.
+fn create_ipfs_client(logger: &Logger, ipfs_address: &str) -> (Arc<IpfsClient>, SocketAddr) {
+    match ipfs_address
+        // Resolve the IPFS address into socket addresses
+        .to_socket_addrs()
+        .unwrap_or_else(|e| panic!("failed to resolve IPFS address {}: {}", ipfs_address, e))
+        // Try to create an IPFS client for one of these addresses; collect
+        // errors in case we can't create a client for any of them
+        .fold_while(Err(vec![]), |result, address| {
+            info!(logger, "Trying IPFS node at: {}", address);
+
+            match IpfsClient::new(&format!("{}", address.ip()), address.port()) {
+                Ok(client) => Done(Ok((Arc::new(client), address))),
+                Err(e) => Continue(result.map_err(|mut errors| {
+                    errors.push((address, e));
+                    errors
+                })),
+            }
+        })
+        .into_inner()
+    {
+        Ok((client, address)) => (client, address),
+        Err(errors) => {
+            for (address, e) in errors.iter() {
+                error!(
+                    logger, "Failed to create IPFS client for address: {}", address;
+                    "error" => format!("{}", e),
+                )
+            }
+            panic!("Could not connect to IPFS");
+        }
+    }
+}
+
 /// Parses an Ethereum connection string and returns the network name and Ethereum node.
 fn parse_ethereum_network_and_node(s: &str) -> Result<(&str, &str), Error> {
     // Check for common Ethereum node mistakes
@@ -542,3 +909,85 @@ fn parse_ethereum_network_and_node(s: &str) -> Result<(&str, &str), Error> {
 
     Ok((name, loc))
 }
+
+/// Runs the mapping tests of the `test` subcommand and returns the exit code of the process.
+fn run_mapping_tests(logger: &Logger, matches: &ArgMatches) -> i32 {
+    match mapping_tests(logger, matches) {
+        Ok(true) => 0,
+        Ok(false) => 1,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            1
+        }
+    }
+}
+
+/// Runs the mapping tests and reports their results. Returns whether all tests passed, or
+/// an error if the tests could not be run.
+fn mapping_tests(logger: &Logger, matches: &ArgMatches) -> Result<bool, Error> {
+    let manifest_path = Path::new(matches.value_of("manifest").unwrap())
+        .canonicalize()
+        .map_err(|e| format_err!("failed to find subgraph manifest: {}", e))?;
+    let link_resolver = Arc::new(FileLinkResolver::new(manifest_path.parent().unwrap()));
+    let manifest = SubgraphManifest::resolve(
+        Link {
+            link: manifest_path.to_string_lossy().into_owned(),
+        },
+        link_resolver.clone(),
+    )
+    .wait()
+    .map_err(|e| format_err!("failed to resolve subgraph manifest: {}", e))?;
+
+    let mut passed = 0;
+    let mut failed = 0;
+    for path in matches.values_of("tests").unwrap() {
+        let file: MappingTestFile = fs::read(path)
+            .map_err(Error::from)
+            .and_then(|bytes| serde_json::from_slice(&bytes).map_err(Error::from))
+            .map_err(|e| format_err!("failed to read mapping tests from {}: {}", path, e))?;
+        let tester = MappingTester::new(
+            logger,
+            &manifest,
+            file.data_source.as_ref().map(String::as_str),
+            link_resolver.clone(),
+        )
+        .map_err(|e| format_err!("failed to load mapping for {}: {}", path, e))?;
+
+        for test in file.tests.iter() {
+            match tester.run(test) {
+                Ok(()) => {
+                    passed += 1;
+                    println!("test {} ... ok", test.name);
+                }
+                Err(e) => {
+                    failed += 1;
+                    println!("test {} ... FAILED\n{}\n", test.name, e);
+                }
+            }
+        }
+    }
+
+    println!(
+        "\ntest result: {}. {} passed; {} failed",
+        if failed == 0 { "ok" } else { "FAILED" },
+        passed,
+        failed
+    );
+    Ok(failed == 0)
+}
+
+/// Validates that a command-line argument is a number of blocks.
+fn validate_block_count(value: String) -> Result<(), String> {
+    value
+        .parse::<u64>()
+        .map(|_| ())
+        .map_err(|_| format!("`{}` is not a nonnegative integer", value))
+}
+
+/// Validates that a command-line argument is a positive number of seconds.
+fn validate_health_check_interval(value: String) -> Result<(), String> {
+    match value.parse::<u64>() {
+        Ok(seconds) if seconds > 0 => Ok(()),
+        _ => Err(format!("`{}` is not a positive number of seconds", value)),
+    }
+}
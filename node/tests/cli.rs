@@ -9,3 +9,62 @@ fn node_fails_to_start_without_postgres_url() {
         .contains("error: The following required arguments were not provided:")
         .unwrap();
 }
+
+/// Required arguments that let the node get past argument parsing.
+const REQUIRED_ARGS: &[&str] = &[
+    "--postgres-url",
+    "postgresql://localhost/graph-node",
+    "--ethereum-rpc",
+    "mainnet:http://localhost:8545",
+    "--ipfs",
+    "localhost:5001",
+];
+
+#[test]
+fn node_rejects_ancestor_count_below_reorg_threshold_plus_confirmations() {
+    let mut args = REQUIRED_ARGS.to_vec();
+    args.extend(&[
+        "--ethereum-reorg-threshold",
+        "50",
+        "--ethereum-confirmations",
+        "10",
+        "--ethereum-ancestor-count",
+        "55",
+    ]);
+    assert_cli::Assert::main_binary()
+        .with_args(&args)
+        .fails()
+        .and()
+        .stderr()
+        .contains(
+            "Ethereum ancestor count (55) must be at least the reorg threshold (50) \
+             plus the number of confirmations (10)",
+        )
+        .unwrap();
+}
+
+#[test]
+fn node_rejects_invalid_block_counts() {
+    let mut args = REQUIRED_ARGS.to_vec();
+    args.extend(&["--ethereum-confirmations", "many"]);
+    assert_cli::Assert::main_binary()
+        .with_args(&args)
+        .fails()
+        .and()
+        .stderr()
+        .contains("`many` is not a nonnegative integer")
+        .unwrap();
+}
+
+#[test]
+fn node_rejects_zero_health_check_interval() {
+    let mut args = REQUIRED_ARGS.to_vec();
+    args.extend(&["--ethereum-health-check-interval", "0"]);
+    assert_cli::Assert::main_binary()
+        .with_args(&args)
+        .fails()
+        .and()
+        .stderr()
+        .contains("`0` is not a positive number of seconds")
+        .unwrap();
+}
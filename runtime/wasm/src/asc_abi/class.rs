@@ -347,6 +347,7 @@ pub enum StoreValueKind {
     Null,
     Bytes,
     BigInt,
+    BigDecimal,
 }
 
 impl StoreValueKind {
@@ -362,6 +363,7 @@ impl StoreValueKind {
             Value::Null => StoreValueKind::Null,
             Value::Bytes(_) => StoreValueKind::Bytes,
             Value::BigInt(_) => StoreValueKind::BigInt,
+            Value::BigDecimal(_) => StoreValueKind::BigDecimal,
         }
     }
 }
@@ -391,10 +393,21 @@ pub(crate) type Bytes = Uint8Array;
 /// cases correctly.
 pub(crate) type AscBigInt = Uint8Array;
 
+/// Big decimals are represented as `digits * 10^exp`.
+#[repr(C)]
+pub(crate) struct AscBigDecimal {
+    pub digits: AscPtr<AscBigInt>,
+    pub exp: AscPtr<AscBigInt>,
+}
+
+impl AscType for AscBigDecimal {}
+
 pub(crate) type AscAddress = Uint8Array;
 pub(crate) type AscH160 = Uint8Array;
 pub(crate) type AscH256 = Uint8Array;
 
+pub(crate) type AscH256Array = Array<AscPtr<AscH256>>;
+
 pub(crate) type AscLogParamArray = Array<AscPtr<AscLogParam>>;
 
 #[repr(C)]
@@ -413,6 +426,7 @@ pub(crate) struct AscEthereumBlock {
     pub difficulty: AscPtr<AscBigInt>,
     pub total_difficulty: AscPtr<AscBigInt>,
     pub size: AscPtr<AscBigInt>,
+    pub uncles: AscPtr<AscH256Array>,
 }
 
 impl AscType for AscEthereumBlock {}
@@ -426,10 +440,45 @@ pub(crate) struct AscEthereumTransaction {
     pub value: AscPtr<AscBigInt>,
     pub gas_used: AscPtr<AscBigInt>,
     pub gas_price: AscPtr<AscBigInt>,
+    pub nonce: AscPtr<AscBigInt>,
+    pub input: AscPtr<Bytes>,
 }
 
 impl AscType for AscEthereumTransaction {}
 
+#[repr(C)]
+pub(crate) struct AscEthereumLog {
+    pub address: AscPtr<AscAddress>,
+    pub topics: AscPtr<AscH256Array>,
+    pub data: AscPtr<Bytes>,
+    pub block_hash: AscPtr<AscH256>,
+    pub block_number: AscPtr<AscBigInt>,
+    pub transaction_hash: AscPtr<AscH256>,
+    pub transaction_index: AscPtr<AscBigInt>,
+    pub log_index: AscPtr<AscBigInt>,
+    pub transaction_log_index: AscPtr<AscBigInt>,
+    pub log_type: AscPtr<AscString>,
+}
+
+impl AscType for AscEthereumLog {}
+
+pub(crate) type AscEthereumLogArray = Array<AscPtr<AscEthereumLog>>;
+
+#[repr(C)]
+pub(crate) struct AscEthereumTransactionReceipt {
+    pub transaction_hash: AscPtr<AscH256>,
+    pub transaction_index: AscPtr<AscBigInt>,
+    pub block_hash: AscPtr<AscH256>,
+    pub block_number: AscPtr<AscBigInt>,
+    pub cumulative_gas_used: AscPtr<AscBigInt>,
+    pub gas_used: AscPtr<AscBigInt>,
+    pub contract_address: AscPtr<AscAddress>,
+    pub logs: AscPtr<AscEthereumLogArray>,
+    pub status: AscPtr<AscBigInt>,
+}
+
+impl AscType for AscEthereumTransactionReceipt {}
+
 #[repr(C)]
 pub(crate) struct AscEthereumEvent {
     pub address: AscPtr<AscAddress>,
@@ -439,10 +488,64 @@ pub(crate) struct AscEthereumEvent {
     pub block: AscPtr<AscEthereumBlock>,
     pub transaction: AscPtr<AscEthereumTransaction>,
     pub params: AscPtr<AscLogParamArray>,
+    pub receipt: AscPtr<AscEthereumTransactionReceipt>,
 }
 
 impl AscType for AscEthereumEvent {}
 
+/// Ethereum block as seen by mappings with API version 0.0.1, i.e. without uncles.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub(crate) struct AscEthereumBlock_0_0_1 {
+    pub hash: AscPtr<AscH256>,
+    pub parent_hash: AscPtr<AscH256>,
+    pub uncles_hash: AscPtr<AscH256>,
+    pub author: AscPtr<AscH160>,
+    pub state_root: AscPtr<AscH256>,
+    pub transactions_root: AscPtr<AscH256>,
+    pub receipts_root: AscPtr<AscH256>,
+    pub number: AscPtr<AscBigInt>,
+    pub gas_used: AscPtr<AscBigInt>,
+    pub gas_limit: AscPtr<AscBigInt>,
+    pub timestamp: AscPtr<AscBigInt>,
+    pub difficulty: AscPtr<AscBigInt>,
+    pub total_difficulty: AscPtr<AscBigInt>,
+    pub size: AscPtr<AscBigInt>,
+}
+
+impl AscType for AscEthereumBlock_0_0_1 {}
+
+/// Ethereum transaction as seen by mappings with API version 0.0.1, i.e. without nonce
+/// and input.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub(crate) struct AscEthereumTransaction_0_0_1 {
+    pub hash: AscPtr<AscH256>,
+    pub index: AscPtr<AscBigInt>,
+    pub from: AscPtr<AscH160>,
+    pub to: AscPtr<AscH160>,
+    pub value: AscPtr<AscBigInt>,
+    pub gas_used: AscPtr<AscBigInt>,
+    pub gas_price: AscPtr<AscBigInt>,
+}
+
+impl AscType for AscEthereumTransaction_0_0_1 {}
+
+/// Ethereum event as seen by mappings with API version 0.0.1, i.e. without receipt.
+#[repr(C)]
+#[allow(non_camel_case_types)]
+pub(crate) struct AscEthereumEvent_0_0_1 {
+    pub address: AscPtr<AscAddress>,
+    pub log_index: AscPtr<AscBigInt>,
+    pub transaction_log_index: AscPtr<AscBigInt>,
+    pub log_type: AscPtr<AscString>,
+    pub block: AscPtr<AscEthereumBlock_0_0_1>,
+    pub transaction: AscPtr<AscEthereumTransaction_0_0_1>,
+    pub params: AscPtr<AscLogParamArray>,
+}
+
+impl AscType for AscEthereumEvent_0_0_1 {}
+
 #[repr(C)]
 pub(crate) struct AscTypedMapEntry<K, V> {
     pub key: AscPtr<K>,
@@ -1,4 +1,5 @@
 pub use self::asc_ptr::AscPtr;
+use graph::prelude::Error;
 use std::mem::size_of;
 use std::slice;
 use wasmi;
@@ -56,6 +57,15 @@ pub trait AscHeap: Sized {
     {
         T::from_asc_obj(asc_ptr.read_ptr(self), self)
     }
+
+    ///  Like `asc_get`, for types that cannot represent every Asc object of class `C`.
+    fn try_asc_get<T, C>(&self, asc_ptr: AscPtr<C>) -> Result<T, Error>
+    where
+        C: AscType,
+        T: TryFromAscObj<C>,
+    {
+        T::try_from_asc_obj(asc_ptr.read_ptr(self), self)
+    }
 }
 
 /// Type that can be converted to an Asc object of class `C`.
@@ -68,6 +78,11 @@ pub trait FromAscObj<C: AscType> {
     fn from_asc_obj<H: AscHeap>(obj: C, heap: &H) -> Self;
 }
 
+/// Type that can be converted from some, but not all, Asc objects of class `C`.
+pub trait TryFromAscObj<C: AscType>: Sized {
+    fn try_from_asc_obj<H: AscHeap>(obj: C, heap: &H) -> Result<Self, Error>;
+}
+
 // `AscType` is not really public, implementors should live inside the `class` module.
 
 /// A type that has a direct corespondence to an Asc type, which
@@ -6,8 +6,11 @@ use wasmi::{
     ModuleInstance, ModuleRef, NopExternals, RuntimeValue, Signature,
 };
 
+use graph::components::ethereum::{
+    EthereumBlockData, EthereumTransactionData, EthereumTransactionReceiptData,
+};
 use graph::prelude::BigInt;
-use graph::web3::types::{H160, U256};
+use graph::web3::types::{self as web3, Block, Log, Transaction, H160, H2048, H256, U128, U256};
 
 use super::class::*;
 use super::{AscHeap, AscPtr};
@@ -355,50 +358,57 @@ fn abi_store_value() {
         .expect("call returned nothing")
         .try_into()
         .expect("call did not return ptr");
-    let null_value: Value = module.asc_get(null_value_ptr);
+    let null_value: Value = module.try_asc_get(null_value_ptr).unwrap();
     assert_eq!(null_value, Value::Null);
 
     // Value::String
     let string = "some string";
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_string", module.asc_new(string)));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_string", module.asc_new(string)))
+        .unwrap();
     assert_eq!(new_value, Value::from(string));
 
     // Value::Int
     let int = i32::min_value();
-    let new_value: Value =
-        module.asc_get(module.takes_val_returns_ptr("value_from_int", RuntimeValue::from(int)));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr("value_from_int", RuntimeValue::from(int)))
+        .unwrap();
     assert_eq!(new_value, Value::Int(int));
 
     // Value::Float
     let float: f32 = 3.14159001;
     let float_runtime = RuntimeValue::F32(F32::from_float(float));
-    let new_value: Value =
-        module.asc_get(module.takes_val_returns_ptr("value_from_float", float_runtime));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr("value_from_float", float_runtime))
+        .unwrap();
     assert_eq!(new_value, Value::Float(float));
 
     // Value::Bool
     let boolean = true;
-    let new_value: Value = module.asc_get(module.takes_val_returns_ptr(
-        "value_from_bool",
-        RuntimeValue::I32(if boolean { 1 } else { 0 }),
-    ));
+    let new_value: Value = module
+        .try_asc_get(module.takes_val_returns_ptr(
+            "value_from_bool",
+            RuntimeValue::I32(if boolean { 1 } else { 0 }),
+        ))
+        .unwrap();
     assert_eq!(new_value, Value::Bool(boolean));
 
     // Value::List
-    let new_value: Value = module.asc_get(
-        module
-            .module
-            .invoke_export(
-                "array_from_values",
-                &[RuntimeValue::from(module.asc_new(string)), float_runtime],
-                &mut NopExternals,
-            )
-            .expect("call failed")
-            .expect("call returned nothing")
-            .try_into()
-            .expect("call did not return ptr"),
-    );
+    let new_value: Value = module
+        .try_asc_get(
+            module
+                .module
+                .invoke_export(
+                    "array_from_values",
+                    &[RuntimeValue::from(module.asc_new(string)), float_runtime],
+                    &mut NopExternals,
+                )
+                .expect("call failed")
+                .expect("call returned nothing")
+                .try_into()
+                .expect("call did not return ptr"),
+        )
+        .unwrap();
     assert_eq!(
         new_value,
         Value::List(vec![Value::from(string), Value::Float(float)])
@@ -409,8 +419,9 @@ fn abi_store_value() {
         Value::String("bar".to_owned()),
     ];
     let array_ptr = module.asc_new(array);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_array", array_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_array", array_ptr))
+        .unwrap();
     assert_eq!(
         new_value,
         Value::List(vec![
@@ -422,17 +433,125 @@ fn abi_store_value() {
     // Value::Bytes
     let bytes: &[u8] = &[0, 2, 5];
     let bytes_ptr: AscPtr<Bytes> = module.asc_new(bytes);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_bytes", bytes_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_bytes", bytes_ptr))
+        .unwrap();
     assert_eq!(new_value, Value::Bytes(bytes.into()));
 
     // Value::BigInt
     let bytes: &[u8] = &[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1];
     let bytes_ptr: AscPtr<Uint8Array> = module.asc_new(bytes);
-    let new_value: Value =
-        module.asc_get(module.takes_ptr_returns_ptr("value_from_bigint", bytes_ptr));
+    let new_value: Value = module
+        .try_asc_get(module.takes_ptr_returns_ptr("value_from_bigint", bytes_ptr))
+        .unwrap();
     assert_eq!(
         new_value,
         Value::BigInt(::graph::data::store::scalar::BigInt::from_unsigned_bytes_le(bytes))
     );
 }
+
+#[test]
+fn abi_ethereum_block_transaction_and_receipt() {
+    let module = TestModule::new("wasm_test/abi_classes.wasm");
+
+    // Uncles of a block
+    let block = Block::<H256> {
+        hash: Some(H256::from(1)),
+        parent_hash: H256::default(),
+        uncles_hash: H256::default(),
+        author: H160::default(),
+        state_root: H256::default(),
+        transactions_root: H256::default(),
+        receipts_root: H256::default(),
+        number: Some(U128::from(1)),
+        gas_used: U256::from(100),
+        gas_limit: U256::from(1000),
+        extra_data: web3::Bytes(vec![]),
+        logs_bloom: H2048::default(),
+        timestamp: U256::from(100000),
+        difficulty: U256::from(10),
+        total_difficulty: U256::from(100),
+        seal_fields: vec![],
+        uncles: vec![H256::from(2), H256::from(3)],
+        transactions: vec![],
+        size: None,
+    };
+    let block_obj = module
+        .asc_new::<AscEthereumBlock, _>(&EthereumBlockData::from(&block))
+        .read_ptr(&module);
+    let uncles: Vec<H256> = module.asc_get(block_obj.uncles);
+    assert_eq!(uncles, vec![H256::from(2), H256::from(3)]);
+    assert!(block_obj.size.is_null());
+
+    // Nonce and input of a transaction
+    let transaction = Transaction {
+        hash: H256::from(4),
+        nonce: U256::from(7),
+        block_hash: Some(H256::from(1)),
+        block_number: Some(U256::from(1)),
+        transaction_index: Some(U128::from(0)),
+        from: H160::default(),
+        to: None,
+        value: U256::from(0),
+        gas_price: U256::from(20),
+        gas: U256::from(21000),
+        input: web3::Bytes(vec![0xa9, 0x05, 0x9c, 0xbb]),
+    };
+    let transaction_obj = module
+        .asc_new::<AscEthereumTransaction, _>(&EthereumTransactionData::from(&transaction))
+        .read_ptr(&module);
+    let nonce: BigInt = module.asc_get(transaction_obj.nonce);
+    assert_eq!(nonce, BigInt::from(7 as i32));
+    let input: Vec<u8> = module.asc_get(transaction_obj.input);
+    assert_eq!(input, vec![0xa9, 0x05, 0x9c, 0xbb]);
+
+    // Receipt of a failed transaction with one log
+    let log = Log {
+        address: H160::from(5),
+        topics: vec![H256::from(6)],
+        data: web3::Bytes(vec![1, 2]),
+        block_hash: Some(H256::from(1)),
+        block_number: Some(U256::from(1)),
+        transaction_hash: Some(H256::from(4)),
+        transaction_index: Some(U128::from(0)),
+        log_index: Some(U256::from(0)),
+        transaction_log_index: None,
+        log_type: None,
+        removed: None,
+    };
+    let receipt = EthereumTransactionReceiptData {
+        transaction_hash: H256::from(4),
+        transaction_index: U128::from(0),
+        block_hash: Some(H256::from(1)),
+        block_number: Some(U256::from(1)),
+        cumulative_gas_used: U256::from(42000),
+        gas_used: U256::from(21000),
+        contract_address: None,
+        logs: vec![log],
+        status: Some(U128::from(0)),
+    };
+    let receipt_obj = module
+        .asc_new::<AscEthereumTransactionReceipt, _>(&receipt)
+        .read_ptr(&module);
+    let transaction_hash: H256 = module.asc_get(receipt_obj.transaction_hash);
+    assert_eq!(transaction_hash, H256::from(4));
+    let cumulative_gas_used: BigInt = module.asc_get(receipt_obj.cumulative_gas_used);
+    assert_eq!(cumulative_gas_used, BigInt::from(42000 as i32));
+    let gas_used: BigInt = module.asc_get(receipt_obj.gas_used);
+    assert_eq!(gas_used, BigInt::from(21000 as i32));
+    let status: BigInt = module.asc_get(receipt_obj.status);
+    assert_eq!(status, BigInt::from(0 as i32));
+    assert!(receipt_obj.contract_address.is_null());
+
+    let logs = receipt_obj.logs.read_ptr(&module).to_vec(&module);
+    assert_eq!(logs.len(), 1);
+    let log_obj = logs[0].read_ptr(&module);
+    let address: H160 = module.asc_get(log_obj.address);
+    assert_eq!(address, H160::from(5));
+    let topics: Vec<H256> = module.asc_get(log_obj.topics);
+    assert_eq!(topics, vec![H256::from(6)]);
+    let data: Vec<u8> = module.asc_get(log_obj.data);
+    assert_eq!(data, vec![1, 2]);
+    assert!(log_obj.transaction_log_index.is_null());
+    assert!(log_obj.log_type.is_null());
+}
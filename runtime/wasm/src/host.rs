@@ -1,30 +1,42 @@
 use futures::sync::mpsc::{channel, Sender};
-use futures::sync::oneshot;
-use std::thread;
+use futures_cpupool::{Builder as CpuPoolBuilder, CpuPool};
+use parity_wasm;
+use std::fmt;
 
 use graph::components::ethereum::*;
 use graph::components::store::Store;
 use graph::data::subgraph::{DataSource, Source};
-use graph::ethabi::LogParam;
 use graph::ethabi::RawLog;
 use graph::prelude::{
     MappingABI, RuntimeHost as RuntimeHostTrait, RuntimeHostBuilder as RuntimeHostBuilderTrait, *,
 };
 use graph::util;
+use graph::util::env::positive_number_from_env;
 use graph::web3::types::{Log, Transaction};
 
 use super::EventHandlerContext;
-use module::{WasmiModule, WasmiModuleConfig};
+use host_exports::ExternalServiceError;
+use module::{ModuleCache, ValidModule, WasmiModule, WasmiModuleConfig};
 
 pub struct RuntimeHostConfig {
     subgraph_id: SubgraphId,
     data_source: DataSource,
+    schema: Schema,
 }
 
+/// Mapping threads spend most of their time blocked on Ethereum calls, IPFS and the store,
+/// so there are many more of them than there are CPUs.
+const DEFAULT_MAPPING_THREADS: usize = 100;
+
+/// Builds runtime hosts that share one pool of mapping threads and one module cache.
+///
+/// The pool has `GRAPH_MAPPING_THREADS` threads (default: 100).
 pub struct RuntimeHostBuilder<T, L, S> {
     ethereum_adapter: Arc<T>,
     link_resolver: Arc<L>,
     store: Arc<S>,
+    module_cache: Arc<ModuleCache>,
+    pool: CpuPool,
 }
 
 impl<T, L, S> Clone for RuntimeHostBuilder<T, L, S>
@@ -38,6 +50,8 @@ where
             ethereum_adapter: self.ethereum_adapter.clone(),
             link_resolver: self.link_resolver.clone(),
             store: self.store.clone(),
+            module_cache: self.module_cache.clone(),
+            pool: self.pool.clone(),
         }
     }
 }
@@ -48,12 +62,24 @@ where
     L: LinkResolver,
     S: Store,
 {
-    pub fn new(ethereum_adapter: Arc<T>, link_resolver: Arc<L>, store: Arc<S>) -> Self {
-        RuntimeHostBuilder {
+    /// Fails if `GRAPH_MAPPING_THREADS` is set to anything but a positive number.
+    pub fn new(
+        ethereum_adapter: Arc<T>,
+        link_resolver: Arc<L>,
+        store: Arc<S>,
+    ) -> Result<Self, Error> {
+        let threads = positive_number_from_env("GRAPH_MAPPING_THREADS", DEFAULT_MAPPING_THREADS)?;
+
+        Ok(RuntimeHostBuilder {
             ethereum_adapter,
             link_resolver,
             store,
-        }
+            module_cache: Arc::new(ModuleCache::new()),
+            pool: CpuPoolBuilder::new()
+                .name_prefix("mapping-")
+                .pool_size(threads)
+                .create(),
+        })
     }
 }
 
@@ -63,85 +89,86 @@ where
     L: LinkResolver,
     S: Store,
 {
-    type Host = RuntimeHost;
+    type Host = RuntimeHost<T, L, S>;
 
     fn build(
         &self,
         logger: &Logger,
         subgraph_id: SubgraphId,
         data_source: DataSource,
+        schema: Schema,
     ) -> Result<Self::Host, Error> {
         RuntimeHost::new(
             logger,
             self.ethereum_adapter.clone(),
             self.link_resolver.clone(),
             self.store.clone(),
+            &self.module_cache,
+            self.pool.clone(),
             RuntimeHostConfig {
                 subgraph_id,
                 data_source,
+                schema,
             },
         )
     }
 }
 
-type HandleEventResponse = Result<Vec<EntityOperation>, Error>;
+type TaskSender = Sender<Box<Future<Item = (), Error = ()> + Send>>;
 
-#[derive(Debug)]
-struct HandleEventRequest {
-    handler: MappingEventHandler,
+/// Runs the mapping of a data source. Every event is handled by a fresh instance of the
+/// mapping module on a thread of the shared pool.
+pub struct RuntimeHost<T, L, S> {
     logger: Logger,
-    block: Arc<EthereumBlock>,
-    transaction: Arc<Transaction>,
-    log: Arc<Log>,
-    params: Vec<LogParam>,
-    entity_operations: Vec<EntityOperation>,
-    result_sender: oneshot::Sender<HandleEventResponse>,
-}
-
-#[derive(Debug)]
-pub struct RuntimeHost {
     data_source_name: String,
     data_source_contract: Source,
     data_source_contract_abi: MappingABI,
     data_source_event_handlers: Vec<MappingEventHandler>,
-    handle_event_sender: Sender<HandleEventRequest>,
-    _guard: oneshot::Sender<()>,
+    module: Arc<ValidModule>,
+    module_config: WasmiModuleConfig<T, L, S>,
+    pool: CpuPool,
+    task_sender: TaskSender,
 }
 
-impl RuntimeHost {
-    pub fn new<T, L, S>(
+impl<T, L, S> fmt::Debug for RuntimeHost<T, L, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("RuntimeHost")
+            .field("data_source_name", &self.data_source_name)
+            .field("data_source_contract", &self.data_source_contract)
+            .field(
+                "data_source_event_handlers",
+                &self.data_source_event_handlers,
+            )
+            .finish()
+    }
+}
+
+impl<T, L, S> RuntimeHost<T, L, S>
+where
+    T: EthereumAdapter,
+    L: LinkResolver,
+    S: Store,
+{
+    pub fn new(
         logger: &Logger,
         ethereum_adapter: Arc<T>,
         link_resolver: Arc<L>,
         store: Arc<S>,
+        module_cache: &ModuleCache,
+        pool: CpuPool,
         config: RuntimeHostConfig,
-    ) -> Result<Self, Error>
-    where
-        T: EthereumAdapter,
-        L: LinkResolver,
-        S: Store,
-    {
+    ) -> Result<Self, Error> {
         let logger = logger.new(o!(
             "component" => "RuntimeHost",
             "data_source" => config.data_source.name.clone(),
         ));
 
-        // Create channel for canceling the module
-        let (cancel_sender, cancel_receiver) = oneshot::channel();
-
-        // Create channel for event handling requests
-        let (handle_event_sender, handle_event_receiver) = channel(100);
-
-        // wasmi modules are not `Send` therefore they cannot be scheduled by
-        // the regular tokio executor, so we create a dedicated thread.
-        //
-        // This thread can spawn tasks on the runtime by sending them to
-        // `task_receiver`.
+        // wasmi modules are not `Send`, so the mapping threads cannot run futures
+        // on the tokio executor themselves. Instead, they spawn tasks on the
+        // runtime by sending them to `task_receiver`.
         let (task_sender, task_receiver) = channel(100);
         tokio::spawn(task_receiver.for_each(tokio::spawn));
 
-        // Start the WASMI module runtime
-        let module_logger = logger.clone();
         let data_source_name = config.data_source.name.clone();
         let data_source_contract = config.data_source.source.clone();
         let data_source_event_handlers = config.data_source.mapping.event_handlers.clone();
@@ -152,77 +179,43 @@ impl RuntimeHost {
             .iter()
             .find(|abi| abi.name == config.data_source.source.abi)
             .ok_or_else(|| {
-                format_err!(
-                    "No ABI entry found for the main contract of data source \"{}\": {}",
-                    data_source_name,
-                    config.data_source.source.abi,
+                SubgraphError::new(
+                    format!(
+                        "No ABI entry found for the main contract of data source \"{}\": {}",
+                        data_source_name, config.data_source.source.abi,
+                    ),
+                    true,
                 )
             })?
             .clone();
 
-        thread::spawn(move || {
-            debug!(module_logger, "Start WASM runtime");
+        // Validate the mapping, or reuse it if another data source has the same one. Invalid
+        // mappings fail the same way every time they are loaded.
+        let module = module_cache
+            .get_or_validate(&config.data_source)
+            .map_err(|e| SubgraphError::new(e.to_string(), true))?;
 
-            // Load the mapping of the data source as a WASM module
-            let wasmi_config = WasmiModuleConfig {
-                subgraph_id: config.subgraph_id,
-                data_source: config.data_source,
-                ethereum_adapter: ethereum_adapter.clone(),
-                link_resolver: link_resolver.clone(),
-                store: store.clone(),
-            };
-
-            // Start the mapping as a WASM module
-            let mut module = WasmiModule::new(&module_logger, wasmi_config, task_sender)
-                .expect("Failed to load module");
-
-            // Pass incoming events to the WASM module and send entity changes back;
-            // stop when cancelled from the outside
-            handle_event_receiver
-                .map(Some)
-                .select(cancel_receiver.into_stream().map(|_| None).map_err(|_| ()))
-                .for_each(move |request: Option<HandleEventRequest>| {
-                    if let Some(request) = request {
-                        let HandleEventRequest {
-                            handler,
-                            logger,
-                            block,
-                            transaction,
-                            log,
-                            params,
-                            entity_operations,
-                            result_sender,
-                        } = request;
-
-                        let ctx = EventHandlerContext {
-                            logger,
-                            block,
-                            transaction,
-                            entity_operations,
-                        };
-
-                        let result = module.handle_ethereum_event(
-                            ctx,
-                            handler.handler.as_str(),
-                            log,
-                            params,
-                        );
-                        future::result(result_sender.send(result).map_err(|_| ()))
-                    } else {
-                        future::err(())
-                    }
-                })
-                .wait()
-                .ok();
-        });
+        // From here on the validated module is used, there is no need to keep the parsed one
+        let mut data_source = config.data_source;
+        data_source.mapping.runtime = parity_wasm::elements::Module::default();
 
         Ok(RuntimeHost {
+            logger,
             data_source_name,
             data_source_contract,
             data_source_contract_abi,
             data_source_event_handlers,
-            handle_event_sender,
-            _guard: cancel_sender,
+            module,
+            module_config: WasmiModuleConfig {
+                subgraph_id: config.subgraph_id,
+                data_source: Arc::new(data_source),
+                schema: Arc::new(config.schema),
+                ethereum_adapter,
+                link_resolver,
+                store,
+            },
+            pool,
+            task_sender,
         })
     }
 
@@ -260,7 +253,12 @@ impl RuntimeHost {
     }
 }
 
-impl RuntimeHostTrait for RuntimeHost {
+impl<T, L, S> RuntimeHostTrait for RuntimeHost<T, L, S>
+where
+    T: EthereumAdapter,
+    L: LinkResolver,
+    S: Store,
+{
     fn matches_log(&self, log: &Log) -> bool {
         self.matches_log_address(log) && self.matches_log_signature(log)
     }
@@ -273,10 +271,24 @@ impl RuntimeHostTrait for RuntimeHost {
         log: Arc<Log>,
         entity_operations: Vec<EntityOperation>,
     ) -> Box<Future<Item = Vec<EntityOperation>, Error = Error> + Send> {
+        let block_ptr = EthereumBlockPointer::from(&*block);
+        let data_source_name = self.data_source_name.clone();
+
+        // Attributes errors to the block, data source and handler they occurred in
+        let mapping_error = move |e: Error, handler: Option<String>, deterministic: bool| {
+            Error::from(SubgraphError {
+                message: e.to_string(),
+                block_ptr: Some(block_ptr),
+                handler,
+                data_source: Some(data_source_name.clone()),
+                deterministic,
+            })
+        };
+
         // Identify event handler for this log
         let event_handler = match self.event_handler_for_log(&log) {
             Ok(handler) => handler,
-            Err(e) => return Box::new(future::err(e)),
+            Err(e) => return Box::new(future::err(mapping_error(e, None, true))),
         };
 
         // Identify the event ABI in the contract
@@ -286,12 +298,16 @@ impl RuntimeHostTrait for RuntimeHost {
         ) {
             Some(event_abi) => event_abi,
             None => {
-                return Box::new(future::err(format_err!(
-                    "Event with the signature \"{}\" not found in \
-                     contract \"{}\" of data source \"{}\"",
-                    event_handler.event,
-                    self.data_source_contract_abi.name,
-                    self.data_source_name
+                return Box::new(future::err(mapping_error(
+                    format_err!(
+                        "Event with the signature \"{}\" not found in \
+                         contract \"{}\" of data source \"{}\"",
+                        event_handler.event,
+                        self.data_source_contract_abi.name,
+                        self.data_source_name
+                    ),
+                    Some(event_handler.handler.clone()),
+                    true,
                 )))
             }
         };
@@ -303,10 +319,14 @@ impl RuntimeHostTrait for RuntimeHost {
         }) {
             Ok(log) => log.params,
             Err(e) => {
-                return Box::new(future::err(format_err!(
-                    "Failed to parse parameters of event: {}: {}",
-                    event_handler.event,
-                    e
+                return Box::new(future::err(mapping_error(
+                    format_err!(
+                        "Failed to parse parameters of event: {}: {}",
+                        event_handler.event,
+                        e
+                    ),
+                    Some(event_handler.handler.clone()),
+                    true,
                 )))
             }
         };
@@ -317,41 +337,45 @@ impl RuntimeHostTrait for RuntimeHost {
             "handler" => &event_handler.handler
         );
 
-        // Call the event handler and asynchronously wait for the result
-        let (result_sender, result_receiver) = oneshot::channel();
-
-        let before_event_signature = event_handler.event.clone();
-        let event_signature = event_handler.event.clone();
+        // Instantiate the mapping and call the event handler on the pool
+        let ctx = EventHandlerContext {
+            logger: logger.new(o!(
+                "transaction" => format!("{:?}", transaction.hash),
+                "handler" => event_handler.handler.clone(),
+            )),
+            block,
+            transaction,
+            entity_operations,
+        };
+        let module_logger = self.logger.clone();
+        let module = self.module.clone();
+        let module_config = self.module_config.clone();
+        let task_sender = self.task_sender.clone();
+        let handler_name = event_handler.handler.clone();
+        let event_handler_name = event_handler.handler.clone();
 
         Box::new(
-            self.handle_event_sender
-                .clone()
-                .send(HandleEventRequest {
-                    handler: event_handler.clone(),
-                    logger: logger.clone(),
-                    block: block.clone(),
-                    transaction: transaction.clone(),
-                    log: log.clone(),
-                    params,
-                    entity_operations,
-                    result_sender,
-                })
-                .map_err(move |_| {
-                    format_err!(
-                        "Mapping terminated before passing in Ethereum event: {}",
-                        before_event_signature
-                    )
-                })
-                .and_then(|_| {
-                    result_receiver.map_err(move |_| {
-                        format_err!(
-                            "Mapping terminated before finishing to handle \
-                             Ethereum event: {}",
-                            event_signature,
+            self.pool
+                .spawn_fn(move || {
+                    // A panic in the runtime would happen again for the same event, so
+                    // it fails the subgraph instead of taking down the whole node
+                    util::log::catch_panic(|| {
+                        WasmiModule::from_valid_module(
+                            &module_logger,
+                            module,
+                            module_config,
+                            task_sender,
                         )
+                        .and_then(|mut module| {
+                            module.handle_ethereum_event(ctx, handler_name.as_str(), log, params)
+                        })
                     })
+                    .unwrap_or_else(|message| Err(format_err!("Mapping panicked: {}", message)))
                 })
-                .and_then(|result| result),
+                .map_err(move |e| {
+                    let deterministic = e.downcast_ref::<ExternalServiceError>().is_none();
+                    mapping_error(e, Some(event_handler_name), deterministic)
+                }),
         )
     }
 }
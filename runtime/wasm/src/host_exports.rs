@@ -1,12 +1,19 @@
+use ethabi;
+use ethabi::param_type::Reader;
 use ethabi::Token;
-use futures::sync::oneshot;
+use futures::sync::{mpsc, oneshot};
 use graph::components::ethereum::*;
+use graph::components::link_resolver::{IpfsFileTooLarge, JsonLineTooLong};
 use graph::components::store::EntityKey;
 use graph::data::store::scalar;
 use graph::data::subgraph::DataSource;
 use graph::prelude::*;
 use graph::serde_json;
 use graph::web3::types::H160;
+use hex;
+use ripemd160::Ripemd160;
+use secp256k1;
+use sha2::{Digest, Sha256};
 use std::collections::HashMap;
 use std::fmt;
 use std::mem;
@@ -15,12 +22,49 @@ use std::str::FromStr;
 use EventHandlerContext;
 use UnresolvedContractCall;
 
+/// ENS registry contracts of the networks that ENS is deployed on.
+const ENS_REGISTRIES: &[(&str, &str)] = &[
+    ("mainnet", "314159265dd8dbb310642f98f50c066173c1259b"),
+    ("ropsten", "112234455c3a32fd11230c42e7bccd4a84e02010"),
+    ("rinkeby", "e7410170f87102df0055eb195163a03b7f2bff4a"),
+];
+
+/// The functions of the ENS registry and resolvers needed for reverse lookups.
+const ENS_ABI: &str = r#"[
+    {
+        "constant": true,
+        "inputs": [{ "name": "node", "type": "bytes32" }],
+        "name": "resolver",
+        "outputs": [{ "name": "", "type": "address" }],
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{ "name": "node", "type": "bytes32" }],
+        "name": "name",
+        "outputs": [{ "name": "", "type": "string" }],
+        "type": "function"
+    },
+    {
+        "constant": true,
+        "inputs": [{ "name": "node", "type": "bytes32" }],
+        "name": "addr",
+        "outputs": [{ "name": "", "type": "address" }],
+        "type": "function"
+    }
+]"#;
+
+lazy_static! {
+    static ref ENS_CONTRACT: ethabi::Contract =
+        ethabi::Contract::load(ENS_ABI.as_bytes()).expect("invalid ENS ABI");
+}
+
 pub(crate) trait ExportError: fmt::Debug + fmt::Display + Send + Sync + 'static {}
 impl<E> ExportError for E where E: fmt::Debug + fmt::Display + Send + Sync + 'static {}
 
 /// Error raised in host functions.
 #[derive(Debug)]
-pub(crate) struct HostExportError<E>(E);
+pub(crate) struct HostExportError<E>(pub(crate) E);
 
 impl<E: fmt::Display> fmt::Display for HostExportError<E> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -28,9 +72,30 @@ impl<E: fmt::Display> fmt::Display for HostExportError<E> {
     }
 }
 
+impl HostExportError<Error> {
+    /// Returns true if the error was caused by an external service and not by the mapping.
+    pub(crate) fn is_external(&self) -> bool {
+        self.0.downcast_ref::<ExternalServiceError>().is_some()
+    }
+}
+
+/// Error raised when an external service such as IPFS, the Ethereum node or the store fails
+/// while an event is handled. Handling the same event again may succeed.
+#[derive(Debug)]
+pub(crate) struct ExternalServiceError(pub(crate) String);
+
+impl fmt::Display for ExternalServiceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Fail for ExternalServiceError {}
+
 pub(crate) struct HostExports<E, L, S, U> {
     subgraph_id: SubgraphId,
-    data_source: DataSource,
+    data_source: Arc<DataSource>,
+    schema: Arc<Schema>,
     ethereum_adapter: Arc<E>,
     link_resolver: Arc<L>,
     store: Arc<S>,
@@ -47,7 +112,8 @@ where
 {
     pub(crate) fn new(
         subgraph_id: SubgraphId,
-        data_source: DataSource,
+        data_source: Arc<DataSource>,
+        schema: Arc<Schema>,
         ethereum_adapter: Arc<E>,
         link_resolver: Arc<L>,
         store: Arc<S>,
@@ -57,6 +123,7 @@ where
         HostExports {
             subgraph_id,
             data_source,
+            schema,
             ethereum_adapter,
             link_resolver,
             store,
@@ -98,31 +165,80 @@ where
         entity_type: String,
         entity_id: String,
         mut data: HashMap<String, Value>,
-    ) -> Result<(), HostExportError<impl ExportError>> {
+    ) -> Result<(), HostExportError<Error>> {
         // Automatically add an "id" value
         match data.insert("id".to_string(), Value::String(entity_id.clone())) {
             Some(ref v) if v != &Value::String(entity_id.clone()) => {
-                return Err(HostExportError(format!(
+                return Err(HostExportError(format_err!(
                     "Value of {} attribute 'id' conflicts with ID passed to `store.set()`: \
                      {} != {}",
-                    entity_type, v, entity_id,
+                    entity_type,
+                    v,
+                    entity_id,
                 )))
             }
             _ => (),
         }
 
+        let key = EntityKey {
+            subgraph_id: self.subgraph_id.clone(),
+            entity_type: entity_type.clone(),
+            entity_id: entity_id.clone(),
+        };
+        let operation = EntityOperation::Set {
+            key: key.clone(),
+            data: Entity::from(data),
+        };
+
+        // `store.set` only updates the attributes it is given, so it is the entity that
+        // results from merging them into the current entity that has to be valid. Only the
+        // operations after the last removal of the entity matter.
+        {
+            let pending_operations: Vec<_> = self
+                .ctx
+                .as_ref()
+                .map(|ctx| &ctx.entity_operations)
+                .expect("processing event without context")
+                .iter()
+                .filter(|op| op.matches_entity(&key))
+                .collect();
+            let removed = pending_operations.iter().any(|op| op.is_remove());
+            let pending_operations = pending_operations
+                .into_iter()
+                .rev()
+                .take_while(|op| !op.is_remove())
+                .collect::<Vec<_>>();
+            let merge = |entity: Option<Entity>| {
+                let entity = pending_operations
+                    .iter()
+                    .rev()
+                    .fold(entity, |entity, op| op.apply(entity));
+                operation
+                    .apply(entity)
+                    .expect("setting an entity always results in an entity")
+            };
+
+            // Attributes in the store were valid when they were written, so the entity in the
+            // store can only make a difference if the pending operations leave out a required
+            // attribute
+            let mut validation = merge(None).validate(&self.schema, &entity_type);
+            if validation.is_err() && !removed {
+                let stored = self
+                    .store
+                    .get(key)
+                    .map_err(|e| HostExportError(ExternalServiceError(e.to_string()).into()))?;
+                validation = merge(stored).validate(&self.schema, &entity_type);
+            }
+            validation.map_err(|e| {
+                HostExportError(format_err!("Failed to set entity `{}`: {}", entity_id, e))
+            })?;
+        }
+
         self.ctx
             .as_mut()
             .map(|ctx| &mut ctx.entity_operations)
             .expect("processing event without context")
-            .push(EntityOperation::Set {
-                key: EntityKey {
-                    subgraph_id: self.subgraph_id.clone(),
-                    entity_type,
-                    entity_id,
-                },
-                data: Entity::from(data),
-            });
+            .push(operation);
 
         Ok(())
     }
@@ -145,7 +261,7 @@ where
         &self,
         entity_type: String,
         entity_id: String,
-    ) -> Result<Option<Entity>, HostExportError<impl ExportError>> {
+    ) -> Result<Option<Entity>, HostExportError<Error>> {
         let store_key = EntityKey {
             subgraph_id: self.subgraph_id.clone(),
             entity_type,
@@ -189,13 +305,13 @@ where
             .store
             .get(store_key)
             .map(|entity| EntityOperation::apply_all(entity, &matching_operations))
-            .map_err(HostExportError)?)
+            .map_err(|e| HostExportError(ExternalServiceError(e.to_string()).into()))?)
     }
 
     pub(crate) fn ethereum_call(
         &self,
         unresolved_call: UnresolvedContractCall,
-    ) -> Result<Vec<Token>, HostExportError<impl ExportError>> {
+    ) -> Result<Vec<Token>, HostExportError<Error>> {
         let ctx = self.ctx.as_ref().expect("processing event without context");
 
         debug!(ctx.logger, "Call smart contract";
@@ -211,7 +327,7 @@ where
             .iter()
             .find(|abi| abi.name == unresolved_call.contract_name)
             .ok_or_else(|| {
-                HostExportError(format!(
+                HostExportError(format_err!(
                     "Could not find ABI for contract \"{}\", try adding it to the 'abis' section \
                      of the subgraph manifest",
                     unresolved_call.contract_name
@@ -223,9 +339,11 @@ where
         let function = contract
             .function(unresolved_call.function_name.as_str())
             .map_err(|e| {
-                HostExportError(format!(
+                HostExportError(format_err!(
                     "Unknown function \"{}::{}\" called from WASM runtime: {}",
-                    unresolved_call.contract_name, unresolved_call.function_name, e
+                    unresolved_call.contract_name,
+                    unresolved_call.function_name,
+                    e
                 ))
             })?;
 
@@ -241,14 +359,196 @@ where
         let logger = ctx.logger.clone();
         self.block_on(future::lazy(move || {
             eth_adapter.contract_call(&logger, call).map_err(move |e| {
-                HostExportError(format!(
+                let message = format!(
                     "Failed to call function \"{}\" of contract \"{}\": {}",
                     unresolved_call.function_name, unresolved_call.contract_name, e
-                ))
+                );
+
+                // Failing to reach the Ethereum node is not the mapping's fault
+                HostExportError(match e {
+                    EthereumContractCallError::CallError(_)
+                    | EthereumContractCallError::Error(_) => ExternalServiceError(message).into(),
+                    _ => format_err!("{}", message),
+                })
             })
         }))
     }
 
+    /// Encodes a value with the Ethereum ABI, like a single function argument.
+    pub(crate) fn ethereum_encode(&self, token: Token) -> Vec<u8> {
+        ethabi::encode(&[token])
+    }
+
+    /// Decodes ABI-encoded data as a value of the type `types` (e.g. `uint256[]`).
+    ///
+    /// Returns `None` if the data cannot be decoded as that type, so that mappings can
+    /// skip malformed payloads.
+    pub(crate) fn ethereum_decode(
+        &self,
+        types: String,
+        data: Vec<u8>,
+    ) -> Result<Option<Token>, HostExportError<impl ExportError>> {
+        let param_type = Reader::read(&types)
+            .map_err(|e| HostExportError(format!("Failed to parse ABI type `{}`: {}", types, e)))?;
+
+        Ok(ethabi::decode(&[param_type], &data)
+            .ok()
+            .and_then(|tokens| tokens.into_iter().next()))
+    }
+
+    /// Returns the balance of `address` in Wei at the block of the event being handled.
+    pub(crate) fn ethereum_get_balance(
+        &self,
+        address: H160,
+    ) -> Result<BigInt, HostExportError<Error>> {
+        let ctx = self.ctx.as_ref().expect("processing event without context");
+        let block_ptr: EthereumBlockPointer = ctx.block.as_ref().deref().into();
+
+        let eth_adapter = self.ethereum_adapter.clone();
+        let logger = ctx.logger.clone();
+        self.block_on(future::lazy(move || {
+            eth_adapter.balance(&logger, address, block_ptr)
+        }))
+        .map(|balance| BigInt::from_unsigned_u256(&balance))
+        .map_err(|e| {
+            HostExportError(
+                ExternalServiceError(format!("Failed to get balance of {:?}: {}", address, e))
+                    .into(),
+            )
+        })
+    }
+
+    /// Returns the code of the contract at `address` at the block of the event being handled,
+    /// or empty bytes if there is no contract at that address.
+    pub(crate) fn ethereum_get_code(
+        &self,
+        address: H160,
+    ) -> Result<Vec<u8>, HostExportError<Error>> {
+        let ctx = self.ctx.as_ref().expect("processing event without context");
+        let block_ptr: EthereumBlockPointer = ctx.block.as_ref().deref().into();
+
+        let eth_adapter = self.ethereum_adapter.clone();
+        let logger = ctx.logger.clone();
+        self.block_on(future::lazy(move || {
+            eth_adapter.code(&logger, address, block_ptr)
+        }))
+        .map_err(|e| {
+            HostExportError(
+                ExternalServiceError(format!("Failed to get code of {:?}: {}", address, e)).into(),
+            )
+        })
+    }
+
+    /// Looks up the ENS name of `address` in the reverse registrar at the block of the event
+    /// being handled.
+    ///
+    /// Anyone can claim any name in their reverse record, so the name is only returned if it
+    /// also resolves to `address`.
+    pub(crate) fn ens_name_by_address(
+        &self,
+        address: H160,
+    ) -> Result<Option<String>, HostExportError<Error>> {
+        let registry = self.ens_registry()?;
+
+        let reverse_node = ens_namehash(&format!("{}.addr.reverse", hex::encode(address.0)));
+        let name = match self.ens_resolver(registry, reverse_node)? {
+            Some(resolver) => self
+                .ens_call(resolver, "name", reverse_node)?
+                .and_then(|name| match name {
+                    Token::String(ref name) if !name.is_empty() => Some(name.clone()),
+                    _ => None,
+                }),
+            None => None,
+        };
+        let name = match name {
+            Some(name) => name,
+            None => return Ok(None),
+        };
+
+        let node = ens_namehash(&name);
+        let resolved_address = match self.ens_resolver(registry, node)? {
+            Some(resolver) => self
+                .ens_call(resolver, "addr", node)?
+                .and_then(Token::to_address),
+            None => None,
+        };
+
+        Ok(if resolved_address == Some(address) {
+            Some(name)
+        } else {
+            None
+        })
+    }
+
+    /// Returns the address of the ENS registry on the network of the data source.
+    fn ens_registry(&self) -> Result<H160, HostExportError<Error>> {
+        let network = self.data_source.network.as_ref();
+        network
+            .and_then(|network| {
+                ENS_REGISTRIES
+                    .iter()
+                    .find(|(name, _)| *name == network.as_str())
+            })
+            .map(|(_, address)| H160::from_str(address).unwrap())
+            .ok_or_else(|| {
+                HostExportError(format_err!(
+                    "ENS is not available on network `{}`",
+                    network.map(String::as_str).unwrap_or("unknown")
+                ))
+            })
+    }
+
+    /// Returns the resolver of the ENS `node`, or `None` if it has no resolver.
+    fn ens_resolver(
+        &self,
+        registry: H160,
+        node: [u8; 32],
+    ) -> Result<Option<H160>, HostExportError<Error>> {
+        Ok(self
+            .ens_call(registry, "resolver", node)?
+            .and_then(Token::to_address)
+            .filter(|resolver| !resolver.is_zero()))
+    }
+
+    /// Calls the ENS function `function_name` for `node` on the contract at `address`, as of
+    /// the block that is being processed. The block is requested by hash so that ENS records
+    /// of other blocks with the same number are never seen.
+    ///
+    /// Returns `None` if the contract does not implement the function, which is how
+    /// resolvers without a record of that kind behave.
+    fn ens_call(
+        &self,
+        address: H160,
+        function_name: &str,
+        node: [u8; 32],
+    ) -> Result<Option<Token>, HostExportError<Error>> {
+        let ctx = self.ctx.as_ref().expect("processing event without context");
+
+        let call = EthereumContractCall {
+            address,
+            block_ptr: ctx.block.as_ref().deref().into(),
+            function: ENS_CONTRACT.function(function_name).unwrap().clone(),
+            args: vec![Token::FixedBytes(node.to_vec())],
+        };
+
+        let eth_adapter = self.ethereum_adapter.clone();
+        let logger = ctx.logger.clone();
+        match self.block_on(future::lazy(move || {
+            eth_adapter.contract_call_at_block_hash(&logger, call)
+        })) {
+            Ok(tokens) => Ok(tokens.into_iter().next()),
+            Err(EthereumContractCallError::ABIError(_)) => Ok(None),
+            Err(e) => {
+                let message = format!("Failed to call ENS function \"{}\": {}", function_name, e);
+                Err(HostExportError(match e {
+                    EthereumContractCallError::CallError(_)
+                    | EthereumContractCallError::Error(_) => ExternalServiceError(message).into(),
+                    _ => format_err!("{}", message),
+                }))
+            }
+        }
+    }
+
     pub(crate) fn bytes_to_string(
         &self,
         bytes: Vec<u8>,
@@ -313,15 +613,55 @@ where
         serde_json::from_reader(&*bytes).map_err(HostExportError)
     }
 
-    pub(crate) fn ipfs_cat(
+    pub(crate) fn ipfs_cat(&self, link: String) -> Result<Vec<u8>, HostExportError<Error>> {
+        self.block_on(self.link_resolver.cat(&Link { link }).map_err(|e| {
+            // Files that are too large will always be too large
+            if e.downcast_ref::<IpfsFileTooLarge>().is_some() {
+                HostExportError(e)
+            } else {
+                HostExportError(ExternalServiceError(e.to_string()).into())
+            }
+        }))
+    }
+
+    /// Streams the JSON values of a newline-delimited JSON file. The download runs on the
+    /// task sink and values are handed over one at a time, so that the file is not fetched
+    /// faster than the mapping processes it.
+    pub(crate) fn ipfs_map(
         &self,
         link: String,
-    ) -> Result<Vec<u8>, HostExportError<impl ExportError>> {
-        self.block_on(
-            self.link_resolver
-                .cat(&Link { link })
-                .map_err(HostExportError),
-        )
+    ) -> impl Iterator<Item = Result<serde_json::Value, HostExportError<Error>>> {
+        let (sender, receiver) = mpsc::channel(0);
+        let values = self
+            .link_resolver
+            .json_stream(&Link { link: link.clone() })
+            .then(|result| Ok::<_, mpsc::SendError<_>>(result));
+
+        // The download stops when the receiver is dropped, e.g. because a callback failed
+        self.task_sink
+            .clone()
+            .send(Box::new(
+                sender.send_all(values).map(|_| ()).map_err(|_| ()),
+            ))
+            .wait()
+            .map_err(|_| panic!("task receiver dropped"))
+            .unwrap();
+
+        receiver.wait().map(move |value| {
+            value.expect("value receiver failed").map_err(|e| {
+                // Invalid files will always be invalid, and files that are too large will
+                // always be too large
+                if e.downcast_ref::<serde_json::Error>().is_some()
+                    || e.downcast_ref::<JsonLineTooLong>().is_some()
+                {
+                    HostExportError(format_err!("Invalid JSON in file `{}`: {}", link, e))
+                } else if e.downcast_ref::<IpfsFileTooLarge>().is_some() {
+                    HostExportError(e)
+                } else {
+                    HostExportError(ExternalServiceError(e.to_string()).into())
+                }
+            })
+        })
     }
 
     /// Expects a decimal string.
@@ -365,6 +705,61 @@ where
         ::tiny_keccak::keccak256(&input)
     }
 
+    pub(crate) fn crypto_sha256(&self, input: Vec<u8>) -> Vec<u8> {
+        Sha256::digest(&input).to_vec()
+    }
+
+    pub(crate) fn crypto_ripemd160(&self, input: Vec<u8>) -> Vec<u8> {
+        Ripemd160::digest(&input).to_vec()
+    }
+
+    /// Recovers the address that signed `hash`, like the `ecrecover` precompile.
+    /// `v` may be given either as 27/28 or as the raw recovery id 0/1. Returns
+    /// `None` if no public key can be recovered from the signature.
+    pub(crate) fn crypto_ecrecover(
+        &self,
+        hash: Vec<u8>,
+        v: i32,
+        r: Vec<u8>,
+        s: Vec<u8>,
+    ) -> Result<Option<H160>, HostExportError<impl ExportError>> {
+        if hash.len() != 32 || r.len() != 32 || s.len() != 32 {
+            return Err(HostExportError(format!(
+                "ecrecover expects a 32 byte hash, r and s, got {}, {} and {} bytes",
+                hash.len(),
+                r.len(),
+                s.len()
+            )));
+        }
+
+        let recovery_id = match v {
+            0 | 1 => v as u8,
+            27 | 28 => (v - 27) as u8,
+            _ => return Ok(None),
+        };
+
+        let mut message = [0u8; 32];
+        message.copy_from_slice(&hash);
+        let mut signature = [0u8; 64];
+        signature[..32].copy_from_slice(&r);
+        signature[32..].copy_from_slice(&s);
+
+        let public_key = secp256k1::RecoveryId::parse(recovery_id).and_then(|recovery_id| {
+            secp256k1::recover(
+                &secp256k1::Message::parse(&message),
+                &secp256k1::Signature::parse(&signature),
+                &recovery_id,
+            )
+        });
+
+        // The address is the last 20 bytes of the hash of the uncompressed
+        // public key, without its 0x04 prefix.
+        Ok(public_key.ok().map(|public_key| {
+            let hash = ::tiny_keccak::keccak256(&public_key.serialize()[1..]);
+            H160::from_slice(&hash[12..])
+        }))
+    }
+
     pub(crate) fn big_int_plus(&self, x: BigInt, y: BigInt) -> BigInt {
         x + y
     }
@@ -385,6 +780,81 @@ where
         x % y
     }
 
+    pub(crate) fn big_decimal_plus(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x + y
+    }
+
+    pub(crate) fn big_decimal_minus(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x - y
+    }
+
+    pub(crate) fn big_decimal_times(&self, x: BigDecimal, y: BigDecimal) -> BigDecimal {
+        x * y
+    }
+
+    pub(crate) fn big_decimal_divided_by(
+        &self,
+        x: BigDecimal,
+        y: BigDecimal,
+    ) -> Result<BigDecimal, HostExportError<impl ExportError>> {
+        if y == 0.into() {
+            return Err(HostExportError(format!(
+                "Attempted to divide BigDecimal `{}` by zero",
+                x
+            )));
+        }
+        Ok(x / y)
+    }
+
+    pub(crate) fn big_decimal_equals(&self, x: BigDecimal, y: BigDecimal) -> bool {
+        x == y
+    }
+
+    pub(crate) fn big_decimal_to_string(&self, x: BigDecimal) -> String {
+        x.to_string()
+    }
+
+    pub(crate) fn big_decimal_from_string(
+        &self,
+        s: String,
+    ) -> Result<BigDecimal, HostExportError<impl ExportError>> {
+        BigDecimal::from_str(&s)
+            .map_err(|e| HostExportError(format!("Could not parse BigDecimal `{}`: {}", s, e)))
+    }
+
+    /// Writes a message from the mapping to the logger of the event being handled.
+    ///
+    /// The levels match the `LogLevel` enum of the mapping API. Logging a message at the
+    /// critical level aborts the handler.
+    pub(crate) fn log_log(
+        &self,
+        level: i32,
+        message: String,
+    ) -> Result<(), HostExportError<impl ExportError>> {
+        let logger = &self
+            .ctx
+            .as_ref()
+            .expect("processing event without context")
+            .logger;
+
+        match level {
+            0 => {
+                crit!(logger, "{}", message);
+                return Err(HostExportError(format!(
+                    "Critical error logged in mapping: {}",
+                    message
+                )));
+            }
+            1 => error!(logger, "{}", message),
+            2 => warn!(logger, "{}", message),
+            3 => info!(logger, "{}", message),
+            4 => debug!(logger, "{}", message),
+            _ => return Err(HostExportError(format!("Invalid log level: {}", level))),
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn block_on<I: Send + 'static, ER: Send + 'static>(
         &self,
         future: impl Future<Item = I, Error = ER> + Send + 'static,
@@ -402,6 +872,22 @@ where
     }
 }
 
+/// Computes the ENS namehash of `name` as specified in EIP-137. The name is expected to be
+/// normalized already.
+fn ens_namehash(name: &str) -> [u8; 32] {
+    let mut node = [0; 32];
+    if name.is_empty() {
+        return node;
+    }
+
+    for label in name.rsplit('.') {
+        let mut input = node.to_vec();
+        input.extend_from_slice(&::tiny_keccak::keccak256(label.as_bytes()));
+        node = ::tiny_keccak::keccak256(&input);
+    }
+    node
+}
+
 pub(crate) fn string_to_h160(string: &str) -> Result<H160, HostExportError<impl ExportError>> {
     // `H160::from_str` takes a hex string with no leading `0x`.
     let string = string.trim_left_matches("0x");
@@ -416,3 +902,16 @@ fn test_string_to_h160_with_0x() {
         string_to_h160("0xA16081F360e3847006dB660bae1c6d1b2e17eC2A").unwrap()
     )
 }
+
+#[test]
+fn test_ens_namehash() {
+    assert_eq!(ens_namehash(""), [0; 32]);
+    assert_eq!(
+        hex::encode(ens_namehash("eth")),
+        "93cdeb708b7545dc668eb9280176169d1c33cfd8ed6f04690a0bcc88a93fc4ae"
+    );
+    assert_eq!(
+        hex::encode(ens_namehash("foo.eth")),
+        "de9b09fd7c5f901e23a3f19fecc54828e9c848539801e86591bd9801b019f84f"
+    );
+}
@@ -1,7 +1,19 @@
 extern crate ethabi;
 extern crate futures;
+extern crate futures_cpupool;
 extern crate graph;
+extern crate graph_mock;
+extern crate graphql_parser;
 extern crate hex;
+#[macro_use]
+extern crate lazy_static;
+extern crate parity_wasm;
+extern crate ripemd160;
+extern crate secp256k1;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate sha2;
 extern crate tiny_keccak;
 extern crate wasmi;
 
@@ -10,6 +22,9 @@ mod host;
 mod module;
 mod to_from;
 
+/// Harness for unit testing mappings against synthetic events.
+mod mapping_test;
+
 /// Runtime-agnostic implementation of exports to WASM.
 mod host_exports;
 
@@ -17,6 +32,11 @@ use self::graph::prelude::*;
 use self::graph::web3::types::{Address, Transaction};
 
 pub use self::host::{RuntimeHost, RuntimeHostBuilder, RuntimeHostConfig};
+pub use self::mapping_test::{
+    MappingTest, MappingTestFile, MappingTester, TestBlock, TestContractCall, TestEntity,
+    TestEvent, TestOperation, TestTransaction,
+};
+pub use self::module::provides_import;
 
 #[derive(Clone, Debug)]
 pub(crate) struct UnresolvedContractCall {
@@ -0,0 +1,845 @@
+use futures::sync::mpsc::{channel, Sender};
+use graphql_parser::{query, schema};
+use serde::de;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+
+use ethabi::token::{LenientTokenizer, Tokenizer};
+use ethabi::{LogParam, ParamType, Token};
+use graph::components::ethereum::*;
+use graph::data::subgraph::{DataSource, SubgraphManifest};
+use graph::prelude::*;
+use graph::serde_json;
+use graph::util;
+use graph::web3::types::*;
+use graph_mock::MockStore;
+
+use module::{ValidModule, WasmiModule, WasmiModuleConfig};
+use EventHandlerContext;
+
+/// A file with tests for the mapping of one data source of a subgraph.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingTestFile {
+    /// Name of the data source under test; may be omitted if the subgraph has only one.
+    pub data_source: Option<String>,
+    pub tests: Vec<MappingTest>,
+}
+
+/// Feeds one synthetic event to a mapping and describes the outcome the mapping must produce.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MappingTest {
+    pub name: String,
+    /// Entities in the store before the event is handled.
+    #[serde(default)]
+    pub entities: Vec<TestEntity>,
+    /// Responses to the `ethereum.call`s the mapping is expected to make.
+    #[serde(default)]
+    pub calls: Vec<TestContractCall>,
+    pub event: TestEvent,
+    /// The entity operations the handler must return, in order.
+    pub expected_operations: Option<Vec<TestOperation>>,
+    /// All entities in the store after the entity operations have been applied.
+    pub expected_entities: Option<Vec<TestEntity>>,
+}
+
+/// An entity given as a JSON object; values are converted according to the subgraph schema.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestEntity {
+    #[serde(rename = "type")]
+    pub entity_type: String,
+    pub id: String,
+    #[serde(default)]
+    pub data: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum TestOperation {
+    Set {
+        #[serde(rename = "type")]
+        entity_type: String,
+        id: String,
+        #[serde(default)]
+        data: serde_json::Map<String, serde_json::Value>,
+    },
+    Remove {
+        #[serde(rename = "type")]
+        entity_type: String,
+        id: String,
+    },
+}
+
+/// A stubbed response to an `ethereum.call`. Calls match the stub if they are made to the
+/// same address and function and, if `args` is present, with the same arguments.
+#[derive(Clone, Debug, Deserialize)]
+pub struct TestContractCall {
+    #[serde(deserialize_with = "deserialize_address")]
+    pub address: Address,
+    pub function: String,
+    pub args: Option<Vec<serde_json::Value>>,
+    pub result: Vec<serde_json::Value>,
+}
+
+/// An event emitted by the data source contract. Parameters are given either in order or
+/// by name; numbers may be JSON numbers or decimal strings, addresses and bytes hex strings.
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TestEvent {
+    pub signature: String,
+    pub params: serde_json::Value,
+    /// Address of the emitting contract, the data source address by default.
+    #[serde(default, deserialize_with = "deserialize_optional_address")]
+    pub address: Option<Address>,
+    #[serde(default)]
+    pub log_index: u64,
+    #[serde(default)]
+    pub block: TestBlock,
+    #[serde(default)]
+    pub transaction: TestTransaction,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(default)]
+pub struct TestBlock {
+    pub number: u64,
+    pub timestamp: u64,
+}
+
+impl Default for TestBlock {
+    fn default() -> Self {
+        TestBlock {
+            number: 1,
+            timestamp: 0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct TestTransaction {
+    #[serde(deserialize_with = "deserialize_optional_address")]
+    pub from: Option<Address>,
+    #[serde(deserialize_with = "deserialize_optional_address")]
+    pub to: Option<Address>,
+    /// Value in wei, as a decimal string.
+    pub value: Option<String>,
+}
+
+fn deserialize_address<'de, D>(deserializer: D) -> Result<Address, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let s: String = de::Deserialize::deserialize(deserializer)?;
+    Address::from_str(s.trim_left_matches("0x")).map_err(D::Error::custom)
+}
+
+/// Like `deserialize_address`, but also accepts `null`.
+fn deserialize_optional_address<'de, D>(deserializer: D) -> Result<Option<Address>, D::Error>
+where
+    D: de::Deserializer<'de>,
+{
+    use serde::de::Error;
+
+    let s: Option<String> = de::Deserialize::deserialize(deserializer)?;
+    match s {
+        Some(s) => Address::from_str(s.trim_left_matches("0x"))
+            .map(Some)
+            .map_err(D::Error::custom),
+        None => Ok(None),
+    }
+}
+
+type TaskSender = Sender<Box<Future<Item = (), Error = ()> + Send>>;
+
+/// Runs `MappingTest`s against the mapping of a data source, with an in-memory store and
+/// stubbed Ethereum calls.
+pub struct MappingTester<L> {
+    logger: Logger,
+    subgraph_id: SubgraphId,
+    schema: Arc<Schema>,
+    data_source: Arc<DataSource>,
+    module: Arc<ValidModule>,
+    link_resolver: Arc<L>,
+    task_sender: TaskSender,
+
+    // Runs the futures that host exports send to `task_sender`
+    _runtime: tokio::runtime::Runtime,
+}
+
+impl<L> MappingTester<L>
+where
+    L: LinkResolver,
+{
+    /// Prepares the mapping of the data source `data_source_name` of `manifest` for testing.
+    /// The data source name may be omitted if the subgraph has only one data source.
+    pub fn new(
+        logger: &Logger,
+        manifest: &SubgraphManifest,
+        data_source_name: Option<&str>,
+        link_resolver: Arc<L>,
+    ) -> Result<Self, Error> {
+        let data_source = match data_source_name {
+            Some(name) => manifest
+                .data_sources
+                .iter()
+                .find(|data_source| data_source.name == name)
+                .ok_or_else(|| format_err!("Subgraph has no data source named `{}`", name))?,
+            None if manifest.data_sources.len() == 1 => &manifest.data_sources[0],
+            None => {
+                return Err(format_err!(
+                    "Subgraph has {} data sources, the tests must name the one they are for",
+                    manifest.data_sources.len()
+                ))
+            }
+        };
+        let module = Arc::new(ValidModule::new(data_source)?);
+
+        let (task_sender, task_receiver) = channel(100);
+        let mut runtime = tokio::runtime::Runtime::new()?;
+        runtime.spawn(task_receiver.for_each(tokio::spawn));
+
+        Ok(MappingTester {
+            logger: logger.new(o!("component" => "MappingTester")),
+            subgraph_id: manifest.id.clone(),
+            schema: Arc::new(manifest.schema.clone()),
+            data_source: Arc::new(data_source.clone()),
+            module,
+            link_resolver,
+            task_sender,
+            _runtime: runtime,
+        })
+    }
+
+    /// Runs a test with a fresh store. Fails if the handler fails or if its outcome does not
+    /// match the expectations of the test; mismatches are reported as a diff.
+    pub fn run(&self, test: &MappingTest) -> Result<(), Error> {
+        let store = Arc::new(MockStore::empty(vec![(
+            self.subgraph_id.clone(),
+            (*self.schema).clone(),
+        )]));
+        store.apply_entity_operations(
+            test.entities
+                .iter()
+                .map(|entity| {
+                    Ok(EntityOperation::Set {
+                        key: self.entity_key(&entity.entity_type, &entity.id),
+                        data: self.entity(&entity.entity_type, &entity.id, &entity.data)?,
+                    })
+                })
+                .collect::<Result<_, Error>>()?,
+            EventSource::None,
+        )?;
+
+        let operations = self.handle_event(test, store.clone())?;
+
+        if let Some(ref expected) = test.expected_operations {
+            let expected = expected
+                .iter()
+                .map(|operation| self.operation(operation))
+                .collect::<Result<Vec<_>, _>>()?;
+            check(
+                "Entity operations",
+                operation_lines(&expected),
+                operation_lines(&operations),
+            )?;
+        }
+
+        if let Some(ref expected) = test.expected_entities {
+            let mut expected = expected
+                .iter()
+                .map(|entity| {
+                    Ok((
+                        self.entity_key(&entity.entity_type, &entity.id),
+                        self.entity(&entity.entity_type, &entity.id, &entity.data)?,
+                    ))
+                })
+                .collect::<Result<Vec<_>, Error>>()?;
+            expected.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            store.apply_entity_operations(operations, EventSource::None)?;
+            check(
+                "Store entities",
+                entity_lines(&expected),
+                entity_lines(&store.stored_entities()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_event(
+        &self,
+        test: &MappingTest,
+        store: Arc<MockStore>,
+    ) -> Result<Vec<EntityOperation>, Error> {
+        let event = &test.event;
+
+        let handler = self
+            .data_source
+            .mapping
+            .event_handlers
+            .iter()
+            .find(|handler| handler.event == event.signature)
+            .ok_or_else(|| {
+                format_err!(
+                    "Data source `{}` has no handler for event `{}`",
+                    self.data_source.name,
+                    event.signature
+                )
+            })?;
+
+        let contract = &self
+            .data_source
+            .mapping
+            .abis
+            .iter()
+            .find(|abi| abi.name == self.data_source.source.abi)
+            .ok_or_else(|| {
+                format_err!(
+                    "Data source `{}` lacks the ABI `{}`",
+                    self.data_source.name,
+                    self.data_source.source.abi
+                )
+            })?
+            .contract;
+        let event_abi =
+            match util::ethereum::contract_event_with_signature(contract, &event.signature) {
+                Some(event_abi) => event_abi,
+                None => {
+                    return Err(format_err!(
+                        "Event `{}` not found in contract `{}`",
+                        event.signature,
+                        self.data_source.source.abi
+                    ))
+                }
+            };
+
+        // Tokenize the parameters, which may be given in order or by name
+        let params = event_abi
+            .inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| {
+                let value = match event.params {
+                    serde_json::Value::Array(ref values) => values.get(i),
+                    serde_json::Value::Object(ref values) => values.get(&input.name),
+                    _ => return Err(format_err!("Event parameters must be an array or object")),
+                }
+                .ok_or_else(|| format_err!("Missing event parameter `{}`", input.name))?;
+
+                Ok(LogParam {
+                    name: input.name.clone(),
+                    value: tokenize(&input.kind, value).map_err(|e| {
+                        format_err!("Invalid event parameter `{}`: {}", input.name, e)
+                    })?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let value = match event.transaction.value {
+            Some(ref value) => U256::from_dec_str(value)
+                .map_err(|e| format_err!("Invalid transaction value `{}`: {:?}", value, e))?,
+            None => U256::zero(),
+        };
+        let block_hash = H256::from(event.block.number);
+        let transaction = Transaction {
+            hash: H256::zero(),
+            nonce: U256::zero(),
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(event.block.number)),
+            transaction_index: Some(U128::zero()),
+            from: event.transaction.from.unwrap_or_default(),
+            to: event.transaction.to,
+            value,
+            gas_price: U256::zero(),
+            gas: U256::zero(),
+            input: Bytes(vec![]),
+        };
+        let block = EthereumBlock {
+            block: Block {
+                hash: Some(block_hash),
+                parent_hash: H256::from(event.block.number.saturating_sub(1)),
+                uncles_hash: H256::zero(),
+                author: H160::zero(),
+                state_root: H256::zero(),
+                transactions_root: H256::zero(),
+                receipts_root: H256::zero(),
+                number: Some(U128::from(event.block.number)),
+                gas_used: U256::zero(),
+                gas_limit: U256::zero(),
+                extra_data: Bytes(vec![]),
+                logs_bloom: H2048::zero(),
+                timestamp: U256::from(event.block.timestamp),
+                difficulty: U256::zero(),
+                total_difficulty: U256::zero(),
+                seal_fields: vec![],
+                uncles: vec![],
+                transactions: vec![transaction.clone()],
+                size: None,
+            },
+            transaction_receipts: vec![],
+        };
+        let log = Log {
+            address: event.address.unwrap_or(self.data_source.source.address),
+            topics: vec![event_abi.signature()],
+            data: Bytes(vec![]),
+            block_hash: Some(block_hash),
+            block_number: Some(U256::from(event.block.number)),
+            transaction_hash: Some(transaction.hash),
+            transaction_index: Some(U128::zero()),
+            log_index: Some(U256::from(event.log_index)),
+            transaction_log_index: Some(U256::from(event.log_index)),
+            log_type: None,
+            removed: None,
+        };
+
+        let logger = self.logger.new(o!("test" => test.name.clone()));
+        let ctx = EventHandlerContext {
+            logger: logger.clone(),
+            block: Arc::new(block),
+            transaction: Arc::new(transaction),
+            entity_operations: vec![],
+        };
+
+        let mut module = WasmiModule::from_valid_module(
+            &logger,
+            self.module.clone(),
+            WasmiModuleConfig {
+                subgraph_id: self.subgraph_id.clone(),
+                data_source: self.data_source.clone(),
+                schema: self.schema.clone(),
+                ethereum_adapter: Arc::new(StubEthereumAdapter {
+                    calls: test.calls.clone(),
+                }),
+                link_resolver: self.link_resolver.clone(),
+                store,
+            },
+            self.task_sender.clone(),
+        )?;
+        module.handle_ethereum_event(ctx, &handler.handler, Arc::new(log), params)
+    }
+
+    fn entity_key(&self, entity_type: &str, id: &str) -> EntityKey {
+        EntityKey {
+            subgraph_id: self.subgraph_id.clone(),
+            entity_type: entity_type.to_owned(),
+            entity_id: id.to_owned(),
+        }
+    }
+
+    /// Converts JSON attributes into an entity of type `entity_type`, using the schema to
+    /// decide e.g. whether a string is a `String`, `BigInt` or `Bytes`.
+    fn entity(
+        &self,
+        entity_type: &str,
+        id: &str,
+        data: &serde_json::Map<String, serde_json::Value>,
+    ) -> Result<Entity, Error> {
+        let object_type = self
+            .schema
+            .document
+            .definitions
+            .iter()
+            .filter_map(|definition| match definition {
+                schema::Definition::TypeDefinition(schema::TypeDefinition::Object(t)) => Some(t),
+                _ => None,
+            })
+            .find(|object_type| object_type.name == entity_type)
+            .ok_or_else(|| format_err!("Unknown entity type `{}`", entity_type))?;
+
+        let mut entity = Entity::new();
+        entity.insert("id".to_owned(), Value::String(id.to_owned()));
+        for (name, value) in data {
+            let field = object_type
+                .fields
+                .iter()
+                .find(|field| &field.name == name)
+                .ok_or_else(|| {
+                    format_err!("Entity type `{}` has no field `{}`", entity_type, name)
+                })?;
+            let value = Value::from_query_value(&query_value(value), &field.field_type)
+                .map_err(|e| format_err!("Invalid value for `{}.{}`: {}", entity_type, name, e))?;
+            entity.insert(name.to_owned(), value);
+        }
+        Ok(entity)
+    }
+
+    fn operation(&self, operation: &TestOperation) -> Result<EntityOperation, Error> {
+        Ok(match operation {
+            TestOperation::Set {
+                entity_type,
+                id,
+                data,
+            } => EntityOperation::Set {
+                key: self.entity_key(entity_type, id),
+                data: self.entity(entity_type, id, data)?,
+            },
+            TestOperation::Remove { entity_type, id } => EntityOperation::Remove {
+                key: self.entity_key(entity_type, id),
+            },
+        })
+    }
+}
+
+/// Answers `ethereum.call`s with the responses stubbed by a test; other requests fail.
+struct StubEthereumAdapter {
+    calls: Vec<TestContractCall>,
+}
+
+impl StubEthereumAdapter {
+    fn respond(&self, call: &EthereumContractCall) -> Result<Vec<Token>, Error> {
+        for stub in self.calls.iter() {
+            if stub.address != call.address || stub.function != call.function.name {
+                continue;
+            }
+
+            if let Some(ref args) = stub.args {
+                let args = call
+                    .function
+                    .inputs
+                    .iter()
+                    .zip(args.iter())
+                    .map(|(input, arg)| tokenize(&input.kind, arg))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if args != call.args {
+                    continue;
+                }
+            }
+
+            if stub.result.len() != call.function.outputs.len() {
+                return Err(format_err!(
+                    "Stubbed result of `{}` has {} values, but the function returns {}",
+                    stub.function,
+                    stub.result.len(),
+                    call.function.outputs.len()
+                ));
+            }
+            return call
+                .function
+                .outputs
+                .iter()
+                .zip(stub.result.iter())
+                .map(|(output, value)| tokenize(&output.kind, value))
+                .collect();
+        }
+
+        Err(format_err!(
+            "No stubbed response for call of `{}` on {:?} with arguments {:?}",
+            call.function.name,
+            call.address,
+            call.args
+        ))
+    }
+}
+
+impl EthereumAdapter for StubEthereumAdapter {
+    fn net_identifiers(
+        &self,
+        _: &Logger,
+    ) -> Box<Future<Item = EthereumNetworkIdentifier, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "Ethereum network identifiers are not available in mapping tests"
+        )))
+    }
+
+    fn block_by_hash(
+        &self,
+        _: &Logger,
+        _: H256,
+    ) -> Box<Future<Item = Option<EthereumBlock>, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "Ethereum blocks are not available in mapping tests"
+        )))
+    }
+
+    fn block_hash_by_block_number(
+        &self,
+        _: &Logger,
+        _: u64,
+    ) -> Box<Future<Item = Option<H256>, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "Ethereum blocks are not available in mapping tests"
+        )))
+    }
+
+    fn is_on_main_chain(
+        &self,
+        _: &Logger,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = bool, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "Ethereum blocks are not available in mapping tests"
+        )))
+    }
+
+    fn find_first_blocks_with_logs(
+        &self,
+        _: &Logger,
+        _: u64,
+        _: u64,
+        _: EthereumLogFilter,
+    ) -> Box<Future<Item = Vec<EthereumBlockPointer>, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "Ethereum logs are not available in mapping tests"
+        )))
+    }
+
+    fn contract_call(
+        &self,
+        _: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        Box::new(future::result(
+            self.respond(&call).map_err(EthereumContractCallError::from),
+        ))
+    }
+
+    fn contract_call_at_block_hash(
+        &self,
+        logger: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        self.contract_call(logger, call)
+    }
+
+    fn balance(
+        &self,
+        _: &Logger,
+        _: Address,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = U256, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "`ethereum.getBalance` is not supported in mapping tests"
+        )))
+    }
+
+    fn code(
+        &self,
+        _: &Logger,
+        _: Address,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        Box::new(future::err(format_err!(
+            "`ethereum.getCode` is not supported in mapping tests"
+        )))
+    }
+}
+
+/// Converts a JSON value into a token of the given kind.
+fn tokenize(kind: &ParamType, value: &serde_json::Value) -> Result<Token, Error> {
+    match (kind, value) {
+        (ParamType::Array(kind), serde_json::Value::Array(values)) => Ok(Token::Array(
+            values
+                .iter()
+                .map(|value| tokenize(kind, value))
+                .collect::<Result<_, _>>()?,
+        )),
+        (ParamType::FixedArray(kind, len), serde_json::Value::Array(values))
+            if values.len() == *len =>
+        {
+            Ok(Token::FixedArray(
+                values
+                    .iter()
+                    .map(|value| tokenize(kind, value))
+                    .collect::<Result<_, _>>()?,
+            ))
+        }
+        (ParamType::Address, serde_json::Value::String(s))
+        | (ParamType::Bytes, serde_json::Value::String(s))
+        | (ParamType::FixedBytes(_), serde_json::Value::String(s)) => {
+            tokenize_str(kind, s.trim_left_matches("0x"))
+        }
+        (_, serde_json::Value::String(s)) => tokenize_str(kind, s),
+        (_, serde_json::Value::Number(n)) => tokenize_str(kind, &n.to_string()),
+        (_, serde_json::Value::Bool(b)) => tokenize_str(kind, &b.to_string()),
+        _ => Err(format_err!("{} is not a valid {}", value, kind)),
+    }
+}
+
+fn tokenize_str(kind: &ParamType, s: &str) -> Result<Token, Error> {
+    LenientTokenizer::tokenize(kind, s)
+        .map_err(|e| format_err!("`{}` is not a valid {}: {}", s, kind, e))
+}
+
+/// Converts JSON into a GraphQL value that `Value::from_query_value` can coerce to the type
+/// of an entity field. Integers outside of the `Int` range are passed on as strings, so
+/// that they can be stored in `BigInt` and `BigDecimal` fields.
+fn query_value(value: &serde_json::Value) -> query::Value {
+    match value {
+        serde_json::Value::Null => query::Value::Null,
+        serde_json::Value::Bool(b) => query::Value::Boolean(*b),
+        serde_json::Value::Number(n) => match n.as_i64() {
+            Some(i) if i >= i32::min_value() as i64 && i <= i32::max_value() as i64 => {
+                query::Value::Int(query::Number::from(i as i32))
+            }
+            _ if n.is_f64() => query::Value::Float(n.as_f64().unwrap()),
+            _ => query::Value::String(n.to_string()),
+        },
+        serde_json::Value::String(s) => query::Value::String(s.clone()),
+        serde_json::Value::Array(values) => {
+            query::Value::List(values.iter().map(query_value).collect())
+        }
+        serde_json::Value::Object(values) => query::Value::Object(
+            values
+                .iter()
+                .map(|(name, value)| (name.clone(), query_value(value)))
+                .collect::<BTreeMap<_, _>>(),
+        ),
+    }
+}
+
+/// Renders the attributes of an entity, one per line and sorted by name.
+fn attribute_lines(entity: &Entity, lines: &mut Vec<String>) {
+    let mut attributes = entity.iter().collect::<Vec<_>>();
+    attributes.sort_by(|(a, _), (b, _)| a.cmp(b));
+    for (name, value) in attributes {
+        lines.push(format!(
+            "    {}: {}",
+            name,
+            query::Value::from(value.clone())
+        ));
+    }
+}
+
+fn operation_lines(operations: &[EntityOperation]) -> Vec<String> {
+    let mut lines = vec![];
+    for operation in operations {
+        match operation {
+            EntityOperation::Set { key, data } => {
+                lines.push(format!("set {}({})", key.entity_type, key.entity_id));
+                attribute_lines(data, &mut lines);
+            }
+            EntityOperation::Remove { key } => {
+                lines.push(format!("remove {}({})", key.entity_type, key.entity_id));
+            }
+        }
+    }
+    lines
+}
+
+fn entity_lines(entities: &[(EntityKey, Entity)]) -> Vec<String> {
+    let mut lines = vec![];
+    for (key, entity) in entities {
+        lines.push(format!("{}({})", key.entity_type, key.entity_id));
+        attribute_lines(entity, &mut lines);
+    }
+    lines
+}
+
+/// Fails with a line diff between `expected` and `actual` if they differ.
+fn check(what: &str, expected: Vec<String>, actual: Vec<String>) -> Result<(), Error> {
+    if expected == actual {
+        return Ok(());
+    }
+
+    let mut message = format!("{} do not match (- expected, + actual):\n", what);
+    for line in diff(&expected, &actual) {
+        writeln!(message, "{}", line).unwrap();
+    }
+    Err(format_err!("{}", message.trim_right()))
+}
+
+/// Computes a line diff based on the longest common subsequence of `a` and `b`. Lines only
+/// in `a` are prefixed with `- `, lines only in `b` with `+ `.
+fn diff(a: &[String], b: &[String]) -> Vec<String> {
+    // lcs[i][j] is the length of the longest common subsequence of a[i..] and b[j..]
+    let mut lcs = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i][j] = if a[i] == b[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut lines = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(format!("  {}", a[i]));
+            i += 1;
+            j += 1;
+        } else if j == b.len() || (i < a.len() && lcs[i + 1][j] >= lcs[i][j + 1]) {
+            lines.push(format!("- {}", a[i]));
+            i += 1;
+        } else {
+            lines.push(format!("+ {}", b[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+#[test]
+fn diff_marks_changed_lines() {
+    let lines = |s: &str| s.split(' ').map(str::to_owned).collect::<Vec<_>>();
+    assert_eq!(
+        diff(&lines("a b c d"), &lines("a c x d")),
+        vec!["  a", "- b", "  c", "+ x", "  d"]
+    );
+    assert!(diff(&[], &[]).is_empty());
+}
+
+#[test]
+fn stubbed_calls_match_address_function_and_args() {
+    let contract = ethabi::Contract::load(
+        &br#"[{
+            "type": "function",
+            "name": "balanceOf",
+            "constant": true,
+            "inputs": [{ "name": "owner", "type": "address" }],
+            "outputs": [{ "name": "", "type": "uint256" }]
+        }]"#[..],
+    )
+    .unwrap();
+    let adapter = StubEthereumAdapter {
+        calls: serde_json::from_str(
+            r#"[{
+                "address": "0x0123123123012312312301231231230123123123",
+                "function": "balanceOf",
+                "args": ["0xa16081f360e3847006db660bae1c6d1b2e17ec2a"],
+                "result": ["1000"]
+            }]"#,
+        )
+        .unwrap(),
+    };
+    let call = |owner: &str| EthereumContractCall {
+        address: Address::from_str("0123123123012312312301231231230123123123").unwrap(),
+        block_ptr: EthereumBlockPointer {
+            hash: H256::zero(),
+            number: 1,
+        },
+        function: contract.function("balanceOf").unwrap().clone(),
+        args: vec![Token::Address(Address::from_str(owner).unwrap())],
+    };
+
+    assert_eq!(
+        adapter
+            .respond(&call("a16081f360e3847006db660bae1c6d1b2e17ec2a"))
+            .unwrap(),
+        vec![Token::Uint(U256::from(1000))]
+    );
+    assert!(adapter
+        .respond(&call("0000000000000000000000000000000000000001"))
+        .is_err());
+}
+
+#[test]
+fn optional_addresses_may_be_null() {
+    let transaction: TestTransaction = serde_json::from_str(
+        r#"{ "from": "0x0123123123012312312301231231230123123123", "to": null }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        transaction.from,
+        Some(Address::from_str("0123123123012312312301231231230123123123").unwrap())
+    );
+    assert_eq!(transaction.to, None);
+}
@@ -0,0 +1,135 @@
+use std::fmt;
+
+use super::*;
+
+/// A version of the mapping API, i.e. of the host functions that a mapping can import and
+/// of the layouts of the AssemblyScript classes the runtime passes to it.
+///
+/// Versions are ordered, and every version provides all host exports of earlier versions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum MappingApiVersion {
+    /// The original mapping API.
+    V0_0_1,
+    /// Adds `log`, `bigDecimal`, `ens`, `ethereum.encode/decode/getBalance/getCode`,
+    /// `crypto.sha256/ripemd160/ecrecover` and `ipfs.map`, as well as block uncles, transaction
+    /// nonces and inputs, and transaction receipts to Ethereum events.
+    V0_0_2,
+}
+
+/// Host exports available since API version 0.0.1, excluding `env.abort`.
+const EXPORTS_V0_0_1: &[(&str, usize)] = &[
+    // store
+    ("store.set", STORE_SET_FUNC_INDEX),
+    ("store.remove", STORE_REMOVE_FUNC_INDEX),
+    ("store.get", STORE_GET_FUNC_INDEX),
+    // ethereum
+    ("ethereum.call", ETHEREUM_CALL_FUNC_INDEX),
+    // typeConversion
+    (
+        "typeConversion.bytesToString",
+        TYPE_CONVERSION_BYTES_TO_STRING_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.bytesToHex",
+        TYPE_CONVERSION_BYTES_TO_HEX_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.bigIntToString",
+        TYPE_CONVERSION_BIG_INT_TO_STRING_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.bigIntToHex",
+        TYPE_CONVERSION_BIG_INT_TO_HEX_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.stringToH160",
+        TYPE_CONVERSION_STRING_TO_H160_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.i32ToBigInt",
+        TYPE_CONVERSION_I32_TO_BIG_INT_FUNC_INDEX,
+    ),
+    (
+        "typeConversion.bigIntToI32",
+        TYPE_CONVERSION_BIG_INT_TO_I32_FUNC_INDEX,
+    ),
+    // json
+    ("json.fromBytes", JSON_FROM_BYTES_FUNC_INDEX),
+    ("json.toI64", JSON_TO_I64_FUNC_INDEX),
+    ("json.toU64", JSON_TO_U64_FUNC_INDEX),
+    ("json.toF64", JSON_TO_F64_FUNC_INDEX),
+    ("json.toBigInt", JSON_TO_BIG_INT_FUNC_INDEX),
+    // ipfs
+    ("ipfs.cat", IPFS_CAT_FUNC_INDEX),
+    // crypto
+    ("crypto.keccak256", CRYPTO_KECCAK_256_INDEX),
+    // bigInt
+    ("bigInt.plus", BIG_INT_PLUS),
+    ("bigInt.minus", BIG_INT_MINUS),
+    ("bigInt.times", BIG_INT_TIMES),
+    ("bigInt.dividedBy", BIG_INT_DIVIDED_BY),
+    ("bigInt.mod", BIG_INT_MOD),
+];
+
+/// Host exports added in API version 0.0.2.
+const EXPORTS_V0_0_2: &[(&str, usize)] = &[
+    // ethereum
+    ("ethereum.encode", ETHEREUM_ENCODE_FUNC_INDEX),
+    ("ethereum.decode", ETHEREUM_DECODE_FUNC_INDEX),
+    ("ethereum.getBalance", ETHEREUM_GET_BALANCE_FUNC_INDEX),
+    ("ethereum.getCode", ETHEREUM_GET_CODE_FUNC_INDEX),
+    // ens
+    ("ens.nameByAddress", ENS_NAME_BY_ADDRESS_FUNC_INDEX),
+    // ipfs
+    ("ipfs.map", IPFS_MAP_FUNC_INDEX),
+    // crypto
+    ("crypto.sha256", CRYPTO_SHA256_INDEX),
+    ("crypto.ripemd160", CRYPTO_RIPEMD160_INDEX),
+    ("crypto.ecrecover", CRYPTO_ECRECOVER_INDEX),
+    // bigDecimal
+    ("bigDecimal.plus", BIG_DECIMAL_PLUS),
+    ("bigDecimal.minus", BIG_DECIMAL_MINUS),
+    ("bigDecimal.times", BIG_DECIMAL_TIMES),
+    ("bigDecimal.dividedBy", BIG_DECIMAL_DIVIDED_BY),
+    ("bigDecimal.equals", BIG_DECIMAL_EQUALS),
+    ("bigDecimal.toString", BIG_DECIMAL_TO_STRING),
+    ("bigDecimal.fromString", BIG_DECIMAL_FROM_STRING),
+    // log
+    ("log.log", LOG_LOG_FUNC_INDEX),
+];
+
+impl MappingApiVersion {
+    /// Parses the `apiVersion` of a mapping, returning `None` if the version is not supported.
+    pub fn parse(version: &str) -> Option<Self> {
+        match version {
+            "0.0.1" => Some(MappingApiVersion::V0_0_1),
+            "0.0.2" => Some(MappingApiVersion::V0_0_2),
+            _ => None,
+        }
+    }
+
+    /// Returns the index of the host function that mappings of this version import as
+    /// `field_name` from their user module, if there is such a function.
+    pub(crate) fn export_index(self, field_name: &str) -> Option<usize> {
+        let added: &[(&str, usize)] = match self {
+            MappingApiVersion::V0_0_1 => &[],
+            MappingApiVersion::V0_0_2 => EXPORTS_V0_0_2,
+        };
+
+        EXPORTS_V0_0_1
+            .iter()
+            .chain(added.iter())
+            .find(|(name, _)| *name == field_name)
+            .map(|(_, index)| *index)
+    }
+}
+
+impl fmt::Display for MappingApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let version = match self {
+            MappingApiVersion::V0_0_1 => "0.0.1",
+            MappingApiVersion::V0_0_2 => "0.0.2",
+        };
+        write!(f, "{}", version)
+    }
+}
@@ -0,0 +1,66 @@
+use parity_wasm;
+use std::collections::HashMap;
+use std::sync::{Mutex, Weak};
+use tiny_keccak;
+
+use graph::data::subgraph::DataSource;
+use graph::prelude::*;
+
+use super::ValidModule;
+
+/// Validated mappings, keyed by their API version and the hash of their WASM code.
+///
+/// Data sources that share a mapping file, whether in the same subgraph or in different
+/// ones, are validated only once and share a single copy of the module. The cache only
+/// holds weak references, so modules are dropped when the last runtime host using them
+/// goes away.
+#[derive(Default)]
+pub struct ModuleCache {
+    modules: Mutex<HashMap<(String, [u8; 32]), Weak<ValidModule>>>,
+}
+
+impl ModuleCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the validated mapping of the data source, validating it if it is not cached.
+    pub fn get_or_validate(&self, data_source: &DataSource) -> Result<Arc<ValidModule>, Error> {
+        let code = parity_wasm::serialize(data_source.mapping.runtime.clone()).map_err(|e| {
+            format_err!(
+                "Failed to serialize module of data source `{}`: {}",
+                data_source.name,
+                e
+            )
+        })?;
+        let key = (
+            data_source.mapping.api_version.clone(),
+            tiny_keccak::keccak256(&code),
+        );
+
+        if let Some(module) = self
+            .modules
+            .lock()
+            .unwrap()
+            .get(&key)
+            .and_then(Weak::upgrade)
+        {
+            return Ok(module);
+        }
+
+        // Validation is slow, so it runs without holding the lock. If the same module is
+        // validated concurrently, the copy that is cached first wins.
+        let module = Arc::new(ValidModule::new(data_source)?);
+
+        let mut modules = self.modules.lock().unwrap();
+        if let Some(module) = modules.get(&key).and_then(Weak::upgrade) {
+            return Ok(module);
+        }
+
+        // Forget modules that are no longer used by any runtime host
+        modules.retain(|_, module| module.upgrade().is_some());
+
+        modules.insert(key, Arc::downgrade(&module));
+        Ok(module)
+    }
+}
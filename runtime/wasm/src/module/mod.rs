@@ -1,16 +1,17 @@
 use std::fmt;
-use std::ops::Deref;
+use std::mem;
 
 use wasmi::{
     nan_preserving_float::F64, Error, Externals, FuncInstance, FuncRef, HostError, ImportsBuilder,
-    MemoryRef, Module, ModuleImportResolver, ModuleInstance, ModuleRef, NopExternals, RuntimeArgs,
-    RuntimeValue, Signature, Trap,
+    MemoryRef, Module, ModuleImportResolver, ModuleInstance, ModuleRef, NopExternals,
+    NotStartedModuleRef, RuntimeArgs, RuntimeValue, Signature, Trap, ValueType,
 };
 
 use graph::components::ethereum::*;
 use graph::data::subgraph::DataSource;
 use graph::ethabi::LogParam;
 use graph::prelude::{Error as FailureError, *};
+use graph::serde_json;
 use graph::web3::types::{Log, U256};
 use host_exports;
 use EventHandlerContext;
@@ -19,9 +20,17 @@ use asc_abi::asc_ptr::*;
 use asc_abi::class::*;
 use asc_abi::*;
 
+/// Versioned sets of host exports.
+mod api_version;
+/// Cache of validated modules shared by all runtime hosts.
+mod cache;
+
 #[cfg(test)]
 mod test;
 
+pub use self::api_version::MappingApiVersion;
+pub use self::cache::ModuleCache;
+
 /// AssemblyScript-compatible WASM memory heap.
 #[derive(Clone)]
 struct WasmiAscHeap {
@@ -84,44 +93,71 @@ const BIG_INT_MINUS: usize = 20;
 const BIG_INT_TIMES: usize = 21;
 const BIG_INT_DIVIDED_BY: usize = 22;
 const BIG_INT_MOD: usize = 23;
+const LOG_LOG_FUNC_INDEX: usize = 24;
+const BIG_DECIMAL_PLUS: usize = 25;
+const BIG_DECIMAL_MINUS: usize = 26;
+const BIG_DECIMAL_TIMES: usize = 27;
+const BIG_DECIMAL_DIVIDED_BY: usize = 28;
+const BIG_DECIMAL_EQUALS: usize = 29;
+const BIG_DECIMAL_TO_STRING: usize = 30;
+const BIG_DECIMAL_FROM_STRING: usize = 31;
+const ETHEREUM_ENCODE_FUNC_INDEX: usize = 32;
+const ETHEREUM_DECODE_FUNC_INDEX: usize = 33;
+const CRYPTO_SHA256_INDEX: usize = 34;
+const CRYPTO_RIPEMD160_INDEX: usize = 35;
+const CRYPTO_ECRECOVER_INDEX: usize = 36;
+const IPFS_MAP_FUNC_INDEX: usize = 37;
+const ETHEREUM_GET_BALANCE_FUNC_INDEX: usize = 38;
+const ETHEREUM_GET_CODE_FUNC_INDEX: usize = 39;
+const ENS_NAME_BY_ADDRESS_FUNC_INDEX: usize = 40;
 
 pub struct WasmiModuleConfig<T, L, S> {
     pub subgraph_id: SubgraphId,
-    pub data_source: DataSource,
+    pub data_source: Arc<DataSource>,
+    pub schema: Arc<Schema>,
     pub ethereum_adapter: Arc<T>,
     pub link_resolver: Arc<L>,
     pub store: Arc<S>,
 }
 
-/// A WASM module based on wasmi that powers a subgraph runtime.
-pub struct WasmiModule<T, L, S, U> {
-    pub logger: Logger,
-    pub module: ModuleRef,
-    externals: HostExternals<T, L, S, U>,
-    heap: WasmiAscHeap,
+impl<T, L, S> Clone for WasmiModuleConfig<T, L, S> {
+    fn clone(&self) -> Self {
+        WasmiModuleConfig {
+            subgraph_id: self.subgraph_id.clone(),
+            data_source: self.data_source.clone(),
+            schema: self.schema.clone(),
+            ethereum_adapter: self.ethereum_adapter.clone(),
+            link_resolver: self.link_resolver.clone(),
+            store: self.store.clone(),
+        }
+    }
 }
 
-impl<T, L, S, U> WasmiModule<T, L, S, U>
-where
-    T: EthereumAdapter,
-    L: LinkResolver,
-    S: Store + Send + Sync + 'static,
-    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone + 'static,
-{
-    /// Creates a new wasmi module
-    pub fn new(
-        logger: &Logger,
-        config: WasmiModuleConfig<T, L, S>,
-        task_sink: U,
-    ) -> Result<Self, FailureError> {
-        let logger = logger.new(o!("component" => "WasmiModule"));
+/// A mapping that has been validated by wasmi and can be instantiated any number of times.
+pub struct ValidModule {
+    module: Module,
+    api_version: MappingApiVersion,
 
-        let parsed_module = config.data_source.mapping.runtime.clone();
+    // Hack: AS currently puts all user imports in one module, in addition
+    // to the built-in "env" module. The name of that module is not fixed,
+    // to able able to infer the name we allow only one module with imports,
+    // with "env" being optional.
+    user_module: Option<String>,
+}
+
+impl ValidModule {
+    pub fn new(data_source: &DataSource) -> Result<Self, FailureError> {
+        let api_version =
+            MappingApiVersion::parse(&data_source.mapping.api_version).ok_or_else(|| {
+                format_err!(
+                    "Data source `{}` uses unsupported mapping API version `{}`",
+                    data_source.name,
+                    data_source.mapping.api_version
+                )
+            })?;
+
+        let parsed_module = data_source.mapping.runtime.clone();
 
-        // Hack: AS currently puts all user imports in one module, in addition
-        // to the built-in "env" module. The name of that module is not fixed,
-        // to able able to infer the name we allow only one module with imports,
-        // with "env" being optional.
         let mut user_modules = parsed_module
             .import_section()
             .map(|import_section| {
@@ -143,20 +179,33 @@ where
         let module = Module::from_parity_wasm_module(parsed_module).map_err(|e| {
             format_err!(
                 "Wasmi could not interpret module of data source `{}`: {}",
-                config.data_source.name,
+                data_source.name,
                 e
             )
         })?;
 
+        Ok(ValidModule {
+            module,
+            api_version,
+            user_module,
+        })
+    }
+
+    /// Creates a new instance of the module along with a heap for its memory. The instance
+    /// still has to be started.
+    fn instantiate(&self) -> Result<(NotStartedModuleRef, WasmiAscHeap), FailureError> {
         // Build import resolver
+        let module_resolver = ModuleResolver {
+            api_version: self.api_version,
+        };
         let mut imports = ImportsBuilder::new();
         imports.push_resolver("env", &EnvModuleResolver);
-        if let Some(user_module) = user_module {
-            imports.push_resolver(user_module, &ModuleResolver);
+        if let Some(ref user_module) = self.user_module {
+            imports.push_resolver(user_module.as_str(), &module_resolver);
         }
 
         // Instantiate the runtime module using hosted functions and import resolver
-        let module = ModuleInstance::new(&module, &imports)
+        let module = ModuleInstance::new(&self.module, &imports)
             .map_err(|e| format_err!("Failed to instantiate WASM module: {}", e))?;
 
         // Provide access to the WASM runtime linear memory
@@ -171,12 +220,77 @@ where
         // Create a AssemblyScript-compatible WASM memory heap
         let heap = WasmiAscHeap::new(not_started_module, memory);
 
+        Ok((module, heap))
+    }
+}
+
+/// Prepares the event passed to a handler for `log`, including the receipt of the
+/// transaction that emitted it if the block was loaded with receipts.
+fn ethereum_event_data(
+    ctx: &EventHandlerContext,
+    log: &Log,
+    params: Vec<LogParam>,
+) -> EthereumEventData {
+    EthereumEventData {
+        block: EthereumBlockData::from(&ctx.block.block),
+        transaction: EthereumTransactionData::from(&*ctx.transaction),
+        address: log.address,
+        log_index: log.log_index.unwrap_or(U256::zero()),
+        transaction_log_index: log.transaction_log_index.unwrap_or(U256::zero()),
+        log_type: log.log_type.clone(),
+        params,
+        receipt: ctx
+            .block
+            .transaction_receipt(ctx.transaction.hash)
+            .map(EthereumTransactionReceiptData::from),
+    }
+}
+
+/// A WASM module based on wasmi that powers a subgraph runtime.
+pub struct WasmiModule<T, L, S, U> {
+    pub logger: Logger,
+    pub module: ModuleRef,
+    api_version: MappingApiVersion,
+    externals: HostExternals<T, L, S, U>,
+    heap: WasmiAscHeap,
+}
+
+impl<T, L, S, U> WasmiModule<T, L, S, U>
+where
+    T: EthereumAdapter,
+    L: LinkResolver,
+    S: Store + Send + Sync + 'static,
+    U: Sink<SinkItem = Box<Future<Item = (), Error = ()> + Send>> + Clone + 'static,
+{
+    /// Creates a new wasmi module
+    pub fn new(
+        logger: &Logger,
+        config: WasmiModuleConfig<T, L, S>,
+        task_sink: U,
+    ) -> Result<Self, FailureError> {
+        let valid_module = Arc::new(ValidModule::new(&config.data_source)?);
+        Self::from_valid_module(logger, valid_module, config, task_sink)
+    }
+
+    /// Instantiates a module that has already been validated, e.g. one from a `ModuleCache`.
+    pub fn from_valid_module(
+        logger: &Logger,
+        valid_module: Arc<ValidModule>,
+        config: WasmiModuleConfig<T, L, S>,
+        task_sink: U,
+    ) -> Result<Self, FailureError> {
+        let logger = logger.new(o!("component" => "WasmiModule"));
+
+        let (module, heap) = valid_module.instantiate()?;
+
         // Create new instance of externally hosted functions invoker
         let mut externals = HostExternals {
             heap: heap.clone(),
+            valid_module: valid_module.clone(),
             host_exports: host_exports::HostExports::new(
                 config.subgraph_id,
                 config.data_source,
+                config.schema,
                 config.ethereum_adapter.clone(),
                 config.link_resolver.clone(),
                 config.store.clone(),
@@ -192,6 +306,7 @@ where
         Ok(WasmiModule {
             logger,
             module,
+            api_version: valid_module.api_version,
             externals,
             heap,
         })
@@ -207,39 +322,26 @@ where
         self.externals.host_exports.ctx = Some(ctx);
 
         // Prepare an EthereumEvent for the WASM runtime
-        let event = EthereumEventData {
-            block: EthereumBlockData::from(
-                &self
-                    .externals
-                    .host_exports
-                    .ctx
-                    .as_ref()
-                    .unwrap()
-                    .block
-                    .block,
-            ),
-            transaction: EthereumTransactionData::from(
-                self.externals
-                    .host_exports
-                    .ctx
-                    .as_ref()
-                    .unwrap()
-                    .transaction
-                    .deref(),
-            ),
-            address: log.address,
-            log_index: log.log_index.unwrap_or(U256::zero()),
-            transaction_log_index: log.transaction_log_index.unwrap_or(U256::zero()),
-            log_type: log.log_type.clone(),
+        let event = ethereum_event_data(
+            self.externals.host_exports.ctx.as_ref().unwrap(),
+            &log,
             params,
+        );
+
+        // Pass the event to the handler using the class layout of the mapping's API version
+        let event_ptr = match self.api_version {
+            MappingApiVersion::V0_0_1 => {
+                RuntimeValue::from(self.heap.asc_new::<AscEthereumEvent_0_0_1, _>(&event))
+            }
+            MappingApiVersion::V0_0_2 => {
+                RuntimeValue::from(self.heap.asc_new::<AscEthereumEvent, _>(&event))
+            }
         };
 
         // Invoke the event handler
-        let result = self.module.invoke_export(
-            handler_name,
-            &[RuntimeValue::from(self.heap.asc_new(&event))],
-            &mut self.externals,
-        );
+        let result = self
+            .module
+            .invoke_export(handler_name, &[event_ptr], &mut self.externals);
 
         // Return either the collected entity operations or an error
         result
@@ -252,15 +354,28 @@ where
                     .entity_operations
             })
             .map_err(|e| {
-                format_err!(
+                let message = format!(
                     "Failed to handle Ethereum event with handler \"{}\": {}",
-                    handler_name,
-                    e
-                )
+                    handler_name, e
+                );
+
+                // Keep track of whether the handler failed because of an external service
+                if is_external_error(&e) {
+                    host_exports::ExternalServiceError(message).into()
+                } else {
+                    format_err!("{}", message)
+                }
             })
     }
 }
 
+/// Returns true if a call into the WASM module failed because of an external service.
+fn is_external_error(e: &Error) -> bool {
+    e.as_host_error()
+        .and_then(|e| e.downcast_ref::<host_exports::HostExportError<FailureError>>())
+        .map_or(false, |e| e.is_external())
+}
+
 impl<E> HostError for host_exports::HostExportError<E> where
     E: fmt::Debug + fmt::Display + Send + Sync + 'static
 {
@@ -269,6 +384,7 @@ impl<E> HostError for host_exports::HostExportError<E> where
 /// Hosted functions for external use by wasm module
 pub struct HostExternals<T, L, S, U> {
     heap: WasmiAscHeap,
+    valid_module: Arc<ValidModule>,
     host_exports: host_exports::HostExports<T, L, S, U>,
 }
 
@@ -321,7 +437,7 @@ where
         self.host_exports.store_set(
             self.heap.asc_get(entity_ptr),
             self.heap.asc_get(id_ptr),
-            self.heap.asc_get(data_ptr),
+            self.try_asc_get(data_ptr)?,
         )?;
         Ok(None)
     }
@@ -364,6 +480,75 @@ where
         Ok(Some(RuntimeValue::from(self.heap.asc_new(&*result))))
     }
 
+    /// function ethereum.encode(token: ethereum.Value): Bytes
+    fn ethereum_encode(
+        &self,
+        token_ptr: AscPtr<AscEnum<EthereumValueKind>>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let data = self
+            .host_exports
+            .ethereum_encode(self.heap.asc_get(token_ptr));
+        let data_ptr: AscPtr<Uint8Array> = self.heap.asc_new(&*data);
+        Ok(Some(RuntimeValue::from(data_ptr)))
+    }
+
+    /// function ethereum.decode(types: String, data: Bytes): ethereum.Value | null
+    fn ethereum_decode(
+        &self,
+        types_ptr: AscPtr<AscString>,
+        data_ptr: AscPtr<Uint8Array>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let token = self
+            .host_exports
+            .ethereum_decode(self.heap.asc_get(types_ptr), self.heap.asc_get(data_ptr))?;
+
+        Ok(Some(match token {
+            Some(token) => {
+                let token_ptr: AscPtr<AscEnum<EthereumValueKind>> = self.heap.asc_new(&token);
+                RuntimeValue::from(token_ptr)
+            }
+            None => RuntimeValue::from(0),
+        }))
+    }
+
+    /// function ethereum.getBalance(address: Address): BigInt
+    fn ethereum_get_balance(
+        &self,
+        address_ptr: AscPtr<AscH160>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let balance = self
+            .host_exports
+            .ethereum_get_balance(self.heap.asc_get(address_ptr))?;
+        let balance_ptr: AscPtr<AscBigInt> = self.heap.asc_new(&balance);
+        Ok(Some(RuntimeValue::from(balance_ptr)))
+    }
+
+    /// function ethereum.getCode(address: Address): Bytes
+    fn ethereum_get_code(
+        &self,
+        address_ptr: AscPtr<AscH160>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let code = self
+            .host_exports
+            .ethereum_get_code(self.heap.asc_get(address_ptr))?;
+        let code_ptr: AscPtr<Uint8Array> = self.heap.asc_new(code.as_slice());
+        Ok(Some(RuntimeValue::from(code_ptr)))
+    }
+
+    /// function ens.nameByAddress(address: Address): string | null
+    fn ens_name_by_address(
+        &self,
+        address_ptr: AscPtr<AscH160>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let name = self
+            .host_exports
+            .ens_name_by_address(self.heap.asc_get(address_ptr))?;
+        Ok(Some(match name {
+            Some(name) => RuntimeValue::from(self.heap.asc_new(&name)),
+            None => RuntimeValue::from(0),
+        }))
+    }
+
     /// function typeConversion.bytesToString(bytes: Bytes): string
     fn bytes_to_string(&self, bytes_ptr: AscPtr<Uint8Array>) -> Result<Option<RuntimeValue>, Trap> {
         let string = self
@@ -433,6 +618,109 @@ where
         Ok(Some(RuntimeValue::from(bytes_obj)))
     }
 
+    /// function ipfs.map(link: String, callback: String, userData: Value): void
+    ///
+    /// Calls the exported function `callback(value: JSONValue, userData: Value)` once for
+    /// every JSON value in the file, each time in a fresh instance of the module. The
+    /// entity operations of the callbacks only become
+    /// part of the event's operations if all callbacks succeed.
+    fn ipfs_map(
+        &mut self,
+        link_ptr: AscPtr<AscString>,
+        callback_ptr: AscPtr<AscString>,
+        user_data_ptr: AscPtr<AscEnum<StoreValueKind>>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let link: String = self.heap.asc_get(link_ptr);
+        let callback: String = self.heap.asc_get(callback_ptr);
+        let user_data: Value = self.try_asc_get(user_data_ptr)?;
+
+        // Callbacks append to the event's operations, so they see the entities written
+        // before `ipfs.map` was called; their operations are dropped again if one fails
+        let start = self.entity_operations().len();
+        let result = self.ipfs_map_callbacks(link, &callback, user_data);
+        if result.is_err() {
+            self.entity_operations().truncate(start);
+        }
+
+        result?;
+        Ok(None)
+    }
+
+    fn ipfs_map_callbacks(
+        &mut self,
+        link: String,
+        callback: &str,
+        user_data: Value,
+    ) -> Result<(), host_exports::HostExportError<FailureError>> {
+        for value in self.host_exports.ipfs_map(link) {
+            let value = value?;
+
+            // Every callback runs in a fresh instance of the module, so callbacks cannot see
+            // each other's globals and memory. Host exports called by the callback have to
+            // use the heap of that instance until it returns.
+            let valid_module = self.valid_module.clone();
+            let (module, heap) = valid_module
+                .instantiate()
+                .map_err(host_exports::HostExportError)?;
+            let handler_heap = mem::replace(&mut self.heap, heap);
+            let result = self.invoke_ipfs_map_callback(module, callback, &value, &user_data);
+            self.heap = handler_heap;
+
+            result.map_err(|e| {
+                let message = format!("Callback `{}` of ipfs.map failed: {}", callback, e);
+                host_exports::HostExportError(if is_external_error(&e) {
+                    host_exports::ExternalServiceError(message).into()
+                } else {
+                    format_err!("{}", message)
+                })
+            })?;
+        }
+
+        Ok(())
+    }
+
+    fn invoke_ipfs_map_callback(
+        &mut self,
+        module: NotStartedModuleRef,
+        callback: &str,
+        value: &serde_json::Value,
+        user_data: &Value,
+    ) -> Result<(), Error> {
+        let module = module.run_start(self)?;
+        let value_ptr: AscPtr<AscEnum<JsonValueKind>> = self.heap.asc_new(value);
+        let user_data_ptr: AscPtr<AscEnum<StoreValueKind>> = self.heap.asc_new(user_data);
+        module.invoke_export(
+            callback,
+            &[
+                RuntimeValue::from(value_ptr),
+                RuntimeValue::from(user_data_ptr),
+            ],
+            self,
+        )?;
+        Ok(())
+    }
+
+    /// Like `asc_get`, but fails the host export instead of panicking if the object cannot be
+    /// represented on the host.
+    fn try_asc_get<T, C>(&self, asc_ptr: AscPtr<C>) -> Result<T, Trap>
+    where
+        C: AscType,
+        T: TryFromAscObj<C>,
+    {
+        self.heap
+            .try_asc_get(asc_ptr)
+            .map_err(|e| host_exports::HostExportError(e).into())
+    }
+
+    fn entity_operations(&mut self) -> &mut Vec<EntityOperation> {
+        &mut self
+            .host_exports
+            .ctx
+            .as_mut()
+            .expect("processing event without context")
+            .entity_operations
+    }
+
     /// Expects a decimal string.
     /// function json.toI64(json: String): i64
     fn json_to_i64(&self, json_ptr: AscPtr<AscString>) -> Result<Option<RuntimeValue>, Trap> {
@@ -476,6 +764,50 @@ where
         Ok(Some(RuntimeValue::from(hash_ptr)))
     }
 
+    /// function crypto.sha256(input: Bytes): Bytes
+    fn crypto_sha256(&self, input_ptr: AscPtr<Uint8Array>) -> Result<Option<RuntimeValue>, Trap> {
+        let hash = self
+            .host_exports
+            .crypto_sha256(self.heap.asc_get(input_ptr));
+        let hash_ptr: AscPtr<Uint8Array> = self.heap.asc_new(hash.as_slice());
+        Ok(Some(RuntimeValue::from(hash_ptr)))
+    }
+
+    /// function crypto.ripemd160(input: Bytes): Bytes
+    fn crypto_ripemd160(
+        &self,
+        input_ptr: AscPtr<Uint8Array>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let hash = self
+            .host_exports
+            .crypto_ripemd160(self.heap.asc_get(input_ptr));
+        let hash_ptr: AscPtr<Uint8Array> = self.heap.asc_new(hash.as_slice());
+        Ok(Some(RuntimeValue::from(hash_ptr)))
+    }
+
+    /// function crypto.ecrecover(hash: Bytes, v: i32, r: Bytes, s: Bytes): Address | null
+    fn crypto_ecrecover(
+        &self,
+        hash_ptr: AscPtr<Uint8Array>,
+        v: i32,
+        r_ptr: AscPtr<Uint8Array>,
+        s_ptr: AscPtr<Uint8Array>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let address = self.host_exports.crypto_ecrecover(
+            self.heap.asc_get(hash_ptr),
+            v,
+            self.heap.asc_get(r_ptr),
+            self.heap.asc_get(s_ptr),
+        )?;
+        Ok(Some(match address {
+            Some(address) => {
+                let address_ptr: AscPtr<Uint8Array> = self.heap.asc_new(&address);
+                RuntimeValue::from(address_ptr)
+            }
+            None => RuntimeValue::from(0),
+        }))
+    }
+
     /// function bigInt.plus(x: BigInt, y: BigInt): BigInt
     fn big_int_plus(
         &self,
@@ -540,6 +872,104 @@ where
         let result_ptr: AscPtr<AscBigInt> = self.heap.asc_new(&result);
         Ok(Some(RuntimeValue::from(result_ptr)))
     }
+
+    /// function bigDecimal.plus(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_plus(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_plus(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.minus(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_minus(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_minus(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.times(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_times(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_times(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.dividedBy(x: BigDecimal, y: BigDecimal): BigDecimal
+    fn big_decimal_divided_by(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_divided_by(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?)?;
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function bigDecimal.equals(x: BigDecimal, y: BigDecimal): bool
+    fn big_decimal_equals(
+        &self,
+        x_ptr: AscPtr<AscBigDecimal>,
+        y_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let equal = self
+            .host_exports
+            .big_decimal_equals(self.try_asc_get(x_ptr)?, self.try_asc_get(y_ptr)?);
+        Ok(Some(RuntimeValue::I32(if equal { 1 } else { 0 })))
+    }
+
+    /// function bigDecimal.toString(x: BigDecimal): string
+    fn big_decimal_to_string(
+        &self,
+        big_decimal_ptr: AscPtr<AscBigDecimal>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_to_string(self.try_asc_get(big_decimal_ptr)?);
+        Ok(Some(RuntimeValue::from(self.heap.asc_new(&result))))
+    }
+
+    /// function bigDecimal.fromString(x: string): BigDecimal
+    fn big_decimal_from_string(
+        &self,
+        string_ptr: AscPtr<AscString>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        let result = self
+            .host_exports
+            .big_decimal_from_string(self.heap.asc_get(string_ptr))?;
+        let result_ptr: AscPtr<AscBigDecimal> = self.heap.asc_new(&result);
+        Ok(Some(RuntimeValue::from(result_ptr)))
+    }
+
+    /// function log.log(level: i32, message: string): void
+    fn log_log(
+        &self,
+        level: i32,
+        message_ptr: AscPtr<AscString>,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        self.host_exports
+            .log_log(level, self.heap.asc_get(message_ptr))?;
+        Ok(None)
+    }
 }
 
 impl<T, L, S, U> Externals for HostExternals<T, L, S, U>
@@ -571,6 +1001,13 @@ where
                 self.store_remove(args.nth_checked(0)?, args.nth_checked(1)?)
             }
             ETHEREUM_CALL_FUNC_INDEX => self.ethereum_call(args.nth_checked(0)?),
+            ETHEREUM_ENCODE_FUNC_INDEX => self.ethereum_encode(args.nth_checked(0)?),
+            ETHEREUM_DECODE_FUNC_INDEX => {
+                self.ethereum_decode(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            ETHEREUM_GET_BALANCE_FUNC_INDEX => self.ethereum_get_balance(args.nth_checked(0)?),
+            ETHEREUM_GET_CODE_FUNC_INDEX => self.ethereum_get_code(args.nth_checked(0)?),
+            ENS_NAME_BY_ADDRESS_FUNC_INDEX => self.ens_name_by_address(args.nth_checked(0)?),
             TYPE_CONVERSION_BYTES_TO_STRING_FUNC_INDEX => {
                 self.bytes_to_string(args.nth_checked(0)?)
             }
@@ -588,7 +1025,20 @@ where
             JSON_TO_F64_FUNC_INDEX => self.json_to_f64(args.nth_checked(0)?),
             JSON_TO_BIG_INT_FUNC_INDEX => self.json_to_big_int(args.nth_checked(0)?),
             IPFS_CAT_FUNC_INDEX => self.ipfs_cat(args.nth_checked(0)?),
+            IPFS_MAP_FUNC_INDEX => self.ipfs_map(
+                args.nth_checked(0)?,
+                args.nth_checked(1)?,
+                args.nth_checked(2)?,
+            ),
             CRYPTO_KECCAK_256_INDEX => self.crypto_keccak_256(args.nth_checked(0)?),
+            CRYPTO_SHA256_INDEX => self.crypto_sha256(args.nth_checked(0)?),
+            CRYPTO_RIPEMD160_INDEX => self.crypto_ripemd160(args.nth_checked(0)?),
+            CRYPTO_ECRECOVER_INDEX => self.crypto_ecrecover(
+                args.nth_checked(0)?,
+                args.nth_checked(1)?,
+                args.nth_checked(2)?,
+                args.nth_checked(3)?,
+            ),
             BIG_INT_PLUS => self.big_int_plus(args.nth_checked(0)?, args.nth_checked(1)?),
             BIG_INT_MINUS => self.big_int_minus(args.nth_checked(0)?, args.nth_checked(1)?),
             BIG_INT_TIMES => self.big_int_times(args.nth_checked(0)?, args.nth_checked(1)?),
@@ -596,6 +1046,18 @@ where
                 self.big_int_divided_by(args.nth_checked(0)?, args.nth_checked(1)?)
             }
             BIG_INT_MOD => self.big_int_mod(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_PLUS => self.big_decimal_plus(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_MINUS => self.big_decimal_minus(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_TIMES => self.big_decimal_times(args.nth_checked(0)?, args.nth_checked(1)?),
+            BIG_DECIMAL_DIVIDED_BY => {
+                self.big_decimal_divided_by(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            BIG_DECIMAL_EQUALS => {
+                self.big_decimal_equals(args.nth_checked(0)?, args.nth_checked(1)?)
+            }
+            BIG_DECIMAL_TO_STRING => self.big_decimal_to_string(args.nth_checked(0)?),
+            BIG_DECIMAL_FROM_STRING => self.big_decimal_from_string(args.nth_checked(0)?),
+            LOG_LOG_FUNC_INDEX => self.log_log(args.nth_checked(0)?, args.nth_checked(1)?),
             _ => panic!("Unimplemented function at {}", index),
         }
     }
@@ -618,68 +1080,39 @@ impl ModuleImportResolver for EnvModuleResolver {
     }
 }
 
-pub struct ModuleResolver;
+/// Resolves the host functions that a mapping imports from its user module.
+pub struct ModuleResolver {
+    api_version: MappingApiVersion,
+}
 
 impl ModuleImportResolver for ModuleResolver {
     fn resolve_func(&self, field_name: &str, signature: &Signature) -> Result<FuncRef, Error> {
-        let signature = signature.clone();
-        Ok(match field_name {
-            // store
-            "store.set" => FuncInstance::alloc_host(signature, STORE_SET_FUNC_INDEX),
-            "store.remove" => FuncInstance::alloc_host(signature, STORE_REMOVE_FUNC_INDEX),
-            "store.get" => FuncInstance::alloc_host(signature, STORE_GET_FUNC_INDEX),
-
-            // ethereum
-            "ethereum.call" => FuncInstance::alloc_host(signature, ETHEREUM_CALL_FUNC_INDEX),
-
-            // typeConversion
-            "typeConversion.bytesToString" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_BYTES_TO_STRING_FUNC_INDEX)
-            }
-            "typeConversion.bytesToHex" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_BYTES_TO_HEX_FUNC_INDEX)
-            }
-            "typeConversion.bigIntToString" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_BIG_INT_TO_STRING_FUNC_INDEX)
-            }
-            "typeConversion.bigIntToHex" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_BIG_INT_TO_HEX_FUNC_INDEX)
-            }
-            "typeConversion.stringToH160" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_STRING_TO_H160_FUNC_INDEX)
-            }
-            "typeConversion.i32ToBigInt" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_I32_TO_BIG_INT_FUNC_INDEX)
-            }
-            "typeConversion.bigIntToI32" => {
-                FuncInstance::alloc_host(signature, TYPE_CONVERSION_BIG_INT_TO_I32_FUNC_INDEX)
-            }
+        match self.api_version.export_index(field_name) {
+            Some(index) => Ok(FuncInstance::alloc_host(signature.clone(), index)),
+            None => Err(Error::Instantiation(format!(
+                "Export '{}' not found in mapping API version {}",
+                field_name, self.api_version
+            ))),
+        }
+    }
+}
 
-            // json
-            "json.fromBytes" => FuncInstance::alloc_host(signature, JSON_FROM_BYTES_FUNC_INDEX),
-            "json.toI64" => FuncInstance::alloc_host(signature, JSON_TO_I64_FUNC_INDEX),
-            "json.toU64" => FuncInstance::alloc_host(signature, JSON_TO_U64_FUNC_INDEX),
-            "json.toF64" => FuncInstance::alloc_host(signature, JSON_TO_F64_FUNC_INDEX),
-            "json.toBigInt" => FuncInstance::alloc_host(signature, JSON_TO_BIG_INT_FUNC_INDEX),
-
-            // ipfs
-            "ipfs.cat" => FuncInstance::alloc_host(signature, IPFS_CAT_FUNC_INDEX),
-
-            // crypto
-            "crypto.keccak256" => FuncInstance::alloc_host(signature, CRYPTO_KECCAK_256_INDEX),
-
-            // bigInt
-            "bigInt.plus" => FuncInstance::alloc_host(signature, BIG_INT_PLUS),
-            "bigInt.minus" => FuncInstance::alloc_host(signature, BIG_INT_MINUS),
-            "bigInt.times" => FuncInstance::alloc_host(signature, BIG_INT_TIMES),
-            "bigInt.dividedBy" => FuncInstance::alloc_host(signature, BIG_INT_DIVIDED_BY),
-            "bigInt.mod" => FuncInstance::alloc_host(signature, BIG_INT_MOD),
-            _ => {
-                return Err(Error::Instantiation(format!(
-                    "Export '{}' not found",
-                    field_name
-                )))
-            }
-        })
+/// Returns whether a function that a mapping with the given API version imports from `module`
+/// is provided by the runtime.
+pub fn provides_import(api_version: &str, module: &str, field_name: &str) -> bool {
+    let api_version = match MappingApiVersion::parse(api_version) {
+        Some(api_version) => api_version,
+        None => return false,
+    };
+
+    // Host functions are resolved by name only, so any signature will do
+    let signature = Signature::new(Vec::<ValueType>::new(), None);
+    match module {
+        "env" => EnvModuleResolver
+            .resolve_func(field_name, &signature)
+            .is_ok(),
+        _ => ModuleResolver { api_version }
+            .resolve_func(field_name, &signature)
+            .is_ok(),
     }
 }
@@ -1,21 +1,30 @@
-extern crate graph_mock;
 extern crate ipfs_api;
 extern crate parity_wasm;
 
-use self::graph_mock::FakeStore;
-use ethabi::Token;
+use ethabi::{self, Token};
 use failure::Error;
 use futures::sync::mpsc::{channel, Sender};
 use graph::components::ethereum::*;
 use graph::components::store::*;
 use graph::data::store::scalar;
 use graph::data::subgraph::*;
+use graph::serde_json;
 use graph::web3::types::*;
+use graph_mock::FakeStore;
 use hex;
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::str::FromStr;
+use std::sync::Mutex;
 
 use super::*;
+use {MappingTestFile, MappingTester};
+
+/// Account that the mocked ENS resolves `alice.eth` to and from.
+const ALICE: &str = "a16081f360e3847006db660bae1c6d1b2e17ec2a";
+
+/// The only account with code in the mocked chain.
+const CONTRACT: &str = "0123123123012312312301231231230123123123";
 
 #[derive(Default)]
 struct MockEthereumAdapter {}
@@ -69,6 +78,49 @@ impl EthereumAdapter for MockEthereumAdapter {
     ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
         unimplemented!();
     }
+
+    fn contract_call_at_block_hash(
+        &self,
+        _: &Logger,
+        call: EthereumContractCall,
+    ) -> Box<Future<Item = Vec<Token>, Error = EthereumContractCallError> + Send> {
+        // Mocked ENS: all nodes share one resolver, and every reverse record claims
+        // `alice.eth`, which resolves to `ALICE`
+        let token = match call.function.name.as_str() {
+            "resolver" => Token::Address(H160::from_str(CONTRACT).unwrap()),
+            "name" => Token::String("alice.eth".to_owned()),
+            "addr" => Token::Address(H160::from_str(ALICE).unwrap()),
+            _ => unimplemented!(),
+        };
+        Box::new(future::ok(vec![token]))
+    }
+
+    fn balance(
+        &self,
+        _: &Logger,
+        _: Address,
+        block_ptr: EthereumBlockPointer,
+    ) -> Box<Future<Item = U256, Error = Error> + Send> {
+        // Every account earns one Ether per block
+        Box::new(future::ok(
+            U256::from(block_ptr.number) * U256::from(1_000_000_000_000_000_000u64),
+        ))
+    }
+
+    fn code(
+        &self,
+        _: &Logger,
+        address: Address,
+        _: EthereumBlockPointer,
+    ) -> Box<Future<Item = Vec<u8>, Error = Error> + Send> {
+        Box::new(future::ok(
+            if address == H160::from_str(CONTRACT).unwrap() {
+                vec![0x60, 0x80]
+            } else {
+                vec![]
+            },
+        ))
+    }
 }
 
 fn test_module(
@@ -89,7 +141,8 @@ fn test_module(
         &logger,
         WasmiModuleConfig {
             subgraph_id: SubgraphId::new("testsubgraph").unwrap(),
-            data_source,
+            data_source: Arc::new(data_source),
+            schema: Arc::new(mock_schema()),
             ethereum_adapter: mock_ethereum_adapter,
             link_resolver: Arc::new(ipfs_api::IpfsClient::default()),
             store: Arc::new(FakeStore),
@@ -99,6 +152,14 @@ fn test_module(
     .unwrap()
 }
 
+fn mock_schema() -> Schema {
+    Schema::parse(
+        "type Thing @entity { id: ID!, name: String! }",
+        SubgraphId::new("testsubgraph").unwrap(),
+    )
+    .unwrap()
+}
+
 fn mock_data_source(path: &str) -> DataSource {
     let runtime = parity_wasm::deserialize_file(path).expect("Failed to deserialize wasm");
 
@@ -112,7 +173,7 @@ fn mock_data_source(path: &str) -> DataSource {
         },
         mapping: Mapping {
             kind: String::from("ethereum/events"),
-            api_version: String::from("0.1.0"),
+            api_version: String::from("0.0.2"),
             language: String::from("wasm/assemblyscript"),
             entities: vec![],
             abis: vec![],
@@ -125,6 +186,50 @@ fn mock_data_source(path: &str) -> DataSource {
     }
 }
 
+fn mock_context() -> EventHandlerContext {
+    EventHandlerContext {
+        logger: Logger::root(slog::Discard, o!()),
+        block: Arc::new(EthereumBlock {
+            block: Block {
+                hash: Some(H256::default()),
+                parent_hash: H256::default(),
+                uncles_hash: H256::default(),
+                author: H160::default(),
+                state_root: H256::default(),
+                transactions_root: H256::default(),
+                receipts_root: H256::default(),
+                number: Some(U128::from(1)),
+                gas_used: U256::from(100),
+                gas_limit: U256::from(1000),
+                extra_data: Bytes(vec![]),
+                logs_bloom: H2048::default(),
+                timestamp: U256::from(100000),
+                difficulty: U256::from(10),
+                total_difficulty: U256::from(100),
+                seal_fields: vec![],
+                uncles: vec![],
+                transactions: vec![],
+                size: Some(U256::from(10000)),
+            },
+            transaction_receipts: vec![],
+        }),
+        transaction: Arc::new(Transaction {
+            hash: H256::default(),
+            nonce: U256::zero(),
+            block_hash: Some(H256::default()),
+            block_number: Some(U256::from(1)),
+            transaction_index: Some(U128::zero()),
+            from: H160::default(),
+            to: None,
+            value: U256::zero(),
+            gas_price: U256::zero(),
+            gas: U256::zero(),
+            input: Bytes(vec![]),
+        }),
+        entity_operations: vec![],
+    }
+}
+
 impl<T, L, S, U> WasmiModule<T, L, S, U>
 where
     T: EthereumAdapter,
@@ -142,6 +247,66 @@ where
     }
 }
 
+#[test]
+fn module_cache() {
+    let cache = ModuleCache::new();
+    let module = cache
+        .get_or_validate(&mock_data_source("wasm_test/crypto.wasm"))
+        .unwrap();
+
+    // Data sources with the same mapping share one validated module
+    let mut data_source = mock_data_source("wasm_test/crypto.wasm");
+    data_source.name = String::from("other data source");
+    assert!(Arc::ptr_eq(
+        &module,
+        &cache.get_or_validate(&data_source).unwrap()
+    ));
+
+    let other_module = cache
+        .get_or_validate(&mock_data_source("wasm_test/abort.wasm"))
+        .unwrap();
+    assert!(!Arc::ptr_eq(&module, &other_module));
+
+    // Modules are validated separately for each API version
+    let mut data_source = mock_data_source("wasm_test/crypto.wasm");
+    data_source.mapping.api_version = String::from("0.0.1");
+    assert!(!Arc::ptr_eq(
+        &module,
+        &cache.get_or_validate(&data_source).unwrap()
+    ));
+
+    // The cache does not keep modules alive by itself
+    let weak_module = Arc::downgrade(&module);
+    drop(module);
+    assert!(weak_module.upgrade().is_none());
+}
+
+#[test]
+fn mapping_api_versions() {
+    // Every version accepted by manifest validation can be run
+    for version in SUPPORTED_MAPPING_API_VERSIONS {
+        let api_version = MappingApiVersion::parse(version).unwrap();
+        assert_eq!(&api_version.to_string(), version);
+    }
+
+    let mut data_source = mock_data_source("wasm_test/abort.wasm");
+    data_source.mapping.api_version = String::from("0.1.0");
+    assert!(ValidModule::new(&data_source).is_err());
+
+    // Host exports of earlier versions remain available
+    assert!(provides_import("0.0.1", "env", "abort"));
+    assert!(provides_import("0.0.1", "crypto", "crypto.keccak256"));
+    assert!(provides_import("0.0.2", "crypto", "crypto.keccak256"));
+
+    // Newer host exports are only available to mappings of the version that added them
+    assert!(!provides_import("0.0.1", "crypto", "crypto.sha256"));
+    assert!(provides_import("0.0.2", "crypto", "crypto.sha256"));
+    assert!(!provides_import("0.0.1", "log", "log.log"));
+    assert!(provides_import("0.0.2", "log", "log.log"));
+
+    assert!(!provides_import("0.1.0", "crypto", "crypto.keccak256"));
+}
+
 #[test]
 fn json_conversions() {
     let mut module = test_module(mock_data_source("wasm_test/string_to_number.wasm"));
@@ -235,6 +400,118 @@ fn ipfs_cat() {
     assert_eq!(data, "42");
 }
 
+#[test]
+fn ipfs_map() {
+    let mut module = test_module(mock_data_source("wasm_test/ipfs_map.wasm"));
+    let ipfs = Arc::new(ipfs_api::IpfsClient::default());
+
+    let mut runtime = tokio::runtime::Runtime::new().unwrap();
+    let hash = runtime
+        .block_on(ipfs.add(Cursor::new("\"a\"\n\n\"b\"\n")))
+        .unwrap()
+        .hash;
+
+    let expected_operation = |id: &str| {
+        let mut data = Entity::new();
+        data.insert("id".to_owned(), Value::from(id));
+        data.insert("name".to_owned(), Value::from("thing"));
+        EntityOperation::Set {
+            key: EntityKey {
+                subgraph_id: SubgraphId::new("testsubgraph").unwrap(),
+                entity_type: "Thing".to_owned(),
+                entity_id: id.to_owned(),
+            },
+            data,
+        }
+    };
+
+    let ipfs_map = |module: &mut WasmiModule<_, _, _, _>, callback: &str| {
+        let mut ctx = mock_context();
+        ctx.entity_operations.push(expected_operation("existing"));
+        module.externals.host_exports.ctx = Some(ctx);
+
+        let args = [
+            RuntimeValue::from(module.heap.asc_new::<AscString, _>(&hash)),
+            RuntimeValue::from(module.heap.asc_new::<AscString, _>(callback)),
+            RuntimeValue::from(
+                module
+                    .heap
+                    .asc_new::<AscEnum<StoreValueKind>, _>(&Value::from("Thing")),
+            ),
+        ];
+        let result = module
+            .module
+            .invoke_export("ipfsMap", &args, &mut module.externals);
+        let operations = module
+            .externals
+            .host_exports
+            .ctx
+            .take()
+            .unwrap()
+            .entity_operations;
+        (result, operations)
+    };
+
+    // The callback is called once for every JSON value, skipping blank lines, and every
+    // call gets a fresh instance of the module
+    let (result, operations) = ipfs_map(&mut module, "setEntity");
+    assert!(result.is_ok());
+    assert_eq!(
+        operations,
+        vec![
+            expected_operation("existing"),
+            expected_operation("a"),
+            expected_operation("b"),
+        ]
+    );
+
+    // If a callback fails, the operations of all callbacks are discarded
+    let (result, operations) = ipfs_map(&mut module, "setEntityAndFail");
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Callback `setEntityAndFail` of ipfs.map failed"));
+    assert_eq!(operations, vec![expected_operation("existing")]);
+}
+
+#[test]
+fn store_set_validates_the_merged_entity() {
+    let mut module = test_module(mock_data_source("wasm_test/abort.wasm"));
+
+    let mut ctx = mock_context();
+    let mut data = Entity::new();
+    data.insert("id".to_owned(), Value::from("1"));
+    data.insert("name".to_owned(), Value::from("thing"));
+    ctx.entity_operations.push(EntityOperation::Set {
+        key: EntityKey {
+            subgraph_id: SubgraphId::new("testsubgraph").unwrap(),
+            entity_type: "Thing".to_owned(),
+            entity_id: "1".to_owned(),
+        },
+        data,
+    });
+    module.externals.host_exports.ctx = Some(ctx);
+    let host_exports = &mut module.externals.host_exports;
+
+    // Updates only need to include the attributes they change
+    assert!(host_exports
+        .store_set("Thing".to_owned(), "1".to_owned(), HashMap::new())
+        .is_ok());
+
+    // New entities have to be complete
+    let error = host_exports
+        .store_set("Thing".to_owned(), "2".to_owned(), HashMap::new())
+        .unwrap_err();
+    assert!(error.to_string().starts_with("Failed to set entity `2`"));
+
+    // Required attributes cannot be unset
+    let mut data = HashMap::new();
+    data.insert("name".to_owned(), Value::Null);
+    assert!(host_exports
+        .store_set("Thing".to_owned(), "1".to_owned(), data)
+        .is_err());
+}
+
 #[test]
 fn crypto_keccak256() {
     let mut module = test_module(mock_data_source("wasm_test/crypto.wasm"));
@@ -255,6 +532,151 @@ fn crypto_keccak256() {
     );
 }
 
+#[test]
+fn crypto_sha256_and_ripemd160() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_v2.wasm"));
+    let input: &[u8] = "eth".as_ref();
+    let input: AscPtr<Uint8Array> = module.heap.asc_new(input);
+
+    let hash: AscPtr<Uint8Array> =
+        module.takes_val_returns_ptr("sha256", RuntimeValue::from(input));
+    let hash: Vec<u8> = module.heap.asc_get(hash);
+    assert_eq!(
+        hex::encode(hash),
+        "c69ea13227bace6e1f8a06364d93f4a6f04632432b64ca9b1fc4036baea4d34c"
+    );
+
+    let hash: AscPtr<Uint8Array> =
+        module.takes_val_returns_ptr("ripemd160", RuntimeValue::from(input));
+    let hash: Vec<u8> = module.heap.asc_get(hash);
+    assert_eq!(
+        hex::encode(hash),
+        "71ccfeb69d983a6af967c8afe14eeb420fa68f52"
+    );
+}
+
+#[test]
+fn crypto_ecrecover() {
+    let mut module = test_module(mock_data_source("wasm_test/crypto_v2.wasm"));
+    let hash =
+        hex::decode("9e7ae8185ab45ac692fca66526e387fbb53c5753103f546d46f5d4b2ac445bdf").unwrap();
+    let r =
+        hex::decode("544e77a4dbfad982648d8e4c293bc8bb5185e04417b4b073d7155c4ee55a1350").unwrap();
+    let s =
+        hex::decode("35b594c27c995fc0255c75e832497aec920e7b762e1c93a29f1b541caeff828e").unwrap();
+    let hash: AscPtr<Uint8Array> = module.heap.asc_new(hash.as_slice());
+    let r: AscPtr<Uint8Array> = module.heap.asc_new(r.as_slice());
+    let s: AscPtr<Uint8Array> = module.heap.asc_new(s.as_slice());
+
+    let ecrecover = |module: &mut WasmiModule<_, _, _, _>, v: i32| -> Option<H160> {
+        let address: AscPtr<Uint8Array> = module
+            .module
+            .invoke_export(
+                "ecrecover",
+                &[
+                    RuntimeValue::from(hash),
+                    RuntimeValue::from(v),
+                    RuntimeValue::from(r),
+                    RuntimeValue::from(s),
+                ],
+                &mut module.externals,
+            )
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        if address.is_null() {
+            None
+        } else {
+            Some(module.heap.asc_get(address))
+        }
+    };
+
+    let signer = H160::from_str("445a6715bebfe23ba747994715a6531f3b4cb610").unwrap();
+
+    // Both the `27`/`28` and the raw recovery id forms of `v` are accepted
+    assert_eq!(ecrecover(&mut module, 27), Some(signer));
+    assert_eq!(ecrecover(&mut module, 0), Some(signer));
+
+    // The other recovery id yields a different key, and invalid ones nothing
+    assert_ne!(ecrecover(&mut module, 28), Some(signer));
+    assert_eq!(ecrecover(&mut module, 29), None);
+}
+
+#[test]
+fn ethereum_get_balance_and_code() {
+    let mut module = test_module(mock_data_source("wasm_test/chain_state.wasm"));
+    module.externals.host_exports.ctx = Some(mock_context());
+
+    let call = |module: &mut WasmiModule<_, _, _, _>, function: &str, address: &str| {
+        let address = H160::from_str(address).unwrap();
+        let address: AscPtr<AscH160> = module.heap.asc_new(&address);
+        module
+            .module
+            .invoke_export(
+                function,
+                &[RuntimeValue::from(address)],
+                &mut module.externals,
+            )
+            .expect("call failed")
+            .expect("call returned nothing")
+    };
+
+    // The balance is queried at the block of the event, which is block #1
+    let balance: AscPtr<AscBigInt> = call(&mut module, "getBalance", ALICE)
+        .try_into()
+        .expect("call did not return pointer");
+    let balance: BigInt = module.heap.asc_get(balance);
+    assert_eq!(balance, BigInt::from(1_000_000_000_000_000_000u64));
+
+    let code: AscPtr<Uint8Array> = call(&mut module, "getCode", CONTRACT)
+        .try_into()
+        .expect("call did not return pointer");
+    let code: Vec<u8> = module.heap.asc_get(code);
+    assert_eq!(code, vec![0x60, 0x80]);
+
+    let code: AscPtr<Uint8Array> = call(&mut module, "getCode", ALICE)
+        .try_into()
+        .expect("call did not return pointer");
+    let code: Vec<u8> = module.heap.asc_get(code);
+    assert!(code.is_empty());
+}
+
+#[test]
+fn ens_name_by_address() {
+    let mut module = test_module(mock_data_source("wasm_test/chain_state.wasm"));
+    module.externals.host_exports.ctx = Some(mock_context());
+
+    let name_by_address = |module: &mut WasmiModule<_, _, _, _>, address: &str| {
+        let address = H160::from_str(address).unwrap();
+        let address: AscPtr<AscH160> = module.heap.asc_new(&address);
+        let name: AscPtr<AscString> = module
+            .module
+            .invoke_export(
+                "nameByAddress",
+                &[RuntimeValue::from(address)],
+                &mut module.externals,
+            )
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        if name.is_null() {
+            None
+        } else {
+            Some(module.heap.asc_get::<String, _>(name))
+        }
+    };
+
+    assert_eq!(
+        name_by_address(&mut module, ALICE),
+        Some("alice.eth".to_owned())
+    );
+
+    // Reverse records that don't resolve back to the address are ignored
+    assert_eq!(name_by_address(&mut module, CONTRACT), None);
+}
+
 #[test]
 fn token_numeric_conversion() {
     let mut module = test_module(mock_data_source("wasm_test/token_to_numeric.wasm"));
@@ -492,6 +914,171 @@ fn big_int_arithmetic() {
     assert_eq!(result, BigInt::from(1));
 }
 
+#[test]
+fn big_decimal() {
+    let mut module = test_module(mock_data_source("wasm_test/big_decimal.wasm"));
+
+    let decimal = |s: &str| BigDecimal::from_str(s).unwrap();
+    let call = |module: &mut WasmiModule<_, _, _, _>, name: &str, x: &str, y: &str| {
+        let x: AscPtr<AscBigDecimal> = module.heap.asc_new(&decimal(x));
+        let y: AscPtr<AscBigDecimal> = module.heap.asc_new(&decimal(y));
+        module.module.invoke_export(
+            name,
+            &[RuntimeValue::from(x), RuntimeValue::from(y)],
+            &mut module.externals,
+        )
+    };
+    let arithmetic = |module: &mut WasmiModule<_, _, _, _>, name: &str, x: &str, y: &str| {
+        let result_ptr: AscPtr<AscBigDecimal> = call(module, name, x, y)
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        module
+            .heap
+            .try_asc_get::<BigDecimal, _>(result_ptr)
+            .unwrap()
+    };
+
+    assert_eq!(
+        arithmetic(&mut module, "plus", "1.5", "2.25"),
+        decimal("3.75")
+    );
+    assert_eq!(
+        arithmetic(&mut module, "minus", "1", "0.001"),
+        decimal("0.999")
+    );
+    assert_eq!(arithmetic(&mut module, "times", "1.5", "-2"), decimal("-3"));
+    assert_eq!(
+        arithmetic(&mut module, "dividedBy", "1", "4"),
+        decimal("0.25")
+    );
+
+    let err = call(&mut module, "dividedBy", "1", "0").unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Attempted to divide BigDecimal `1` by zero"));
+
+    assert_eq!(
+        call(&mut module, "equals", "1.0", "1").unwrap(),
+        Some(RuntimeValue::I32(1))
+    );
+    assert_eq!(
+        call(&mut module, "equals", "1", "2").unwrap(),
+        Some(RuntimeValue::I32(0))
+    );
+
+    // toString and fromString
+    let number: AscPtr<AscBigDecimal> = module.heap.asc_new(&decimal("-12.5"));
+    let string_ptr: AscPtr<AscString> =
+        module.takes_val_returns_ptr("toString", RuntimeValue::from(number));
+    let string: String = module.heap.asc_get(string_ptr);
+    assert_eq!(string, "-12.5");
+
+    let string: AscPtr<AscString> = module.heap.asc_new("0.125");
+    let number_ptr: AscPtr<AscBigDecimal> =
+        module.takes_val_returns_ptr("fromString", RuntimeValue::from(string));
+    let number: BigDecimal = module.heap.try_asc_get(number_ptr).unwrap();
+    assert_eq!(number, decimal("0.125"));
+
+    let string: AscPtr<AscString> = module.heap.asc_new("not a number");
+    let err = module
+        .module
+        .invoke_export(
+            "fromString",
+            &[RuntimeValue::from(string)],
+            &mut module.externals,
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Could not parse BigDecimal `not a number`"));
+
+    // Decimals with exponents that the store cannot hold are rejected
+    let err = call(&mut module, "plus", "1e131073", "1").unwrap_err();
+    assert!(err.to_string().contains(
+        "BigDecimal exponent `131073` is outside the supported range of -16383 to 131072"
+    ));
+    let err = call(&mut module, "plus", "1", "1e-16384").unwrap_err();
+    assert!(err.to_string().contains("BigDecimal exponent `-16384`"));
+    assert_eq!(
+        arithmetic(&mut module, "times", "1e131072", "1e-16383"),
+        decimal("1e114689")
+    );
+}
+
+/// Returns the receipt of a successful transaction `transaction_hash` in the mocked block.
+fn mock_receipt(transaction_hash: H256) -> TransactionReceipt {
+    serde_json::from_value(serde_json::Value::Object(
+        vec![
+            ("transactionHash", serde_json::to_value(transaction_hash)),
+            ("transactionIndex", serde_json::to_value(U128::from(0))),
+            ("blockHash", serde_json::to_value(H256::default())),
+            ("blockNumber", serde_json::to_value(U256::from(1))),
+            ("cumulativeGasUsed", serde_json::to_value(U256::from(50000))),
+            ("gasUsed", serde_json::to_value(U256::from(21000))),
+            ("contractAddress", Ok(serde_json::Value::Null)),
+            ("logs", Ok(serde_json::Value::Array(vec![]))),
+            ("status", serde_json::to_value(U128::from(1))),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_owned(), value.unwrap()))
+        .collect(),
+    ))
+    .unwrap()
+}
+
+#[test]
+fn ethereum_event_receipt() {
+    let mut ctx = mock_context();
+
+    let mut block = (*ctx.block).clone();
+    block.block.uncles = vec![H256::from(2)];
+    block.transaction_receipts = vec![mock_receipt(H256::from(1)), mock_receipt(H256::from(3))];
+    ctx.block = Arc::new(block);
+
+    let mut transaction = (*ctx.transaction).clone();
+    transaction.hash = H256::from(3);
+    transaction.nonce = U256::from(9);
+    transaction.input = Bytes(vec![1, 2, 3]);
+    ctx.transaction = Arc::new(transaction);
+
+    let log = Log {
+        address: Address::from_str(CONTRACT).unwrap(),
+        topics: vec![],
+        data: Bytes(vec![]),
+        block_hash: Some(H256::default()),
+        block_number: Some(U256::from(1)),
+        transaction_hash: Some(H256::from(3)),
+        transaction_index: Some(U128::from(0)),
+        log_index: Some(U256::from(0)),
+        transaction_log_index: None,
+        log_type: None,
+        removed: None,
+    };
+
+    // Handlers see the uncles of the block, the nonce and input of the transaction and
+    // the receipt of the transaction that emitted the event
+    let event = super::ethereum_event_data(&ctx, &log, vec![]);
+    assert_eq!(event.block.uncles, vec![H256::from(2)]);
+    assert_eq!(event.transaction.nonce, U256::from(9));
+    assert_eq!(event.transaction.input, Bytes(vec![1, 2, 3]));
+
+    let receipt = event.receipt.expect("event without receipt");
+    assert_eq!(receipt.transaction_hash, H256::from(3));
+    assert_eq!(receipt.gas_used, U256::from(21000));
+    assert_eq!(receipt.cumulative_gas_used, U256::from(50000));
+    assert_eq!(receipt.status, Some(U128::from(1)));
+
+    // Blocks loaded without receipts produce events without receipts
+    let mut block = (*ctx.block).clone();
+    block.transaction_receipts = vec![];
+    ctx.block = Arc::new(block);
+    assert!(super::ethereum_event_data(&ctx, &log, vec![])
+        .receipt
+        .is_none());
+}
+
 #[test]
 fn abort() {
     let mut module = test_module(mock_data_source("wasm_test/abort.wasm"));
@@ -501,3 +1088,252 @@ fn abort() {
         .unwrap_err();
     assert_eq!(err.to_string(), "Trap: Trap { kind: Host(HostExportError(\"Mapping aborted at abort.ts, line 6, column 2, with message: not true\")) }");
 }
+
+/// Collects the level and message of every record logged through it.
+#[derive(Clone, Default)]
+struct RecordingDrain(Arc<Mutex<Vec<(slog::Level, String)>>>);
+
+impl slog::Drain for RecordingDrain {
+    type Ok = ();
+    type Err = slog::Never;
+
+    fn log(&self, record: &slog::Record, _: &slog::OwnedKVList) -> Result<(), slog::Never> {
+        self.0
+            .lock()
+            .unwrap()
+            .push((record.level(), record.msg().to_string()));
+        Ok(())
+    }
+}
+
+#[test]
+fn log_log() {
+    let mut module = test_module(mock_data_source("wasm_test/log.wasm"));
+    let drain = RecordingDrain::default();
+    let mut ctx = mock_context();
+    ctx.logger = Logger::root(drain.clone(), o!());
+    module.externals.host_exports.ctx = Some(ctx);
+
+    let log = |module: &mut WasmiModule<_, _, _, _>, level: i32, message: &str| {
+        let message = RuntimeValue::from(module.heap.asc_new::<AscString, _>(message));
+        module.module.invoke_export(
+            "log",
+            &[RuntimeValue::I32(level), message],
+            &mut module.externals,
+        )
+    };
+
+    log(&mut module, 1, "error message").unwrap();
+    log(&mut module, 2, "warning message").unwrap();
+    log(&mut module, 3, "info message").unwrap();
+    log(&mut module, 4, "debug message").unwrap();
+    assert_eq!(
+        *drain.0.lock().unwrap(),
+        vec![
+            (slog::Level::Error, String::from("error message")),
+            (slog::Level::Warning, String::from("warning message")),
+            (slog::Level::Info, String::from("info message")),
+            (slog::Level::Debug, String::from("debug message")),
+        ]
+    );
+
+    // Critical errors are logged and abort the handler
+    let err = log(&mut module, 0, "critical message").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Trap: Trap { kind: Host(HostExportError(\"Critical error logged in mapping: \
+         critical message\")) }"
+    );
+    assert_eq!(
+        drain.0.lock().unwrap().last(),
+        Some(&(slog::Level::Critical, String::from("critical message")))
+    );
+
+    let err = log(&mut module, 5, "message").unwrap_err();
+    assert!(err.to_string().contains("Invalid log level: 5"));
+    assert_eq!(drain.0.lock().unwrap().len(), 5);
+}
+
+#[test]
+fn ethereum_abi_encode_decode() {
+    let mut module = test_module(mock_data_source("wasm_test/ethereum_abi.wasm"));
+
+    let round_trip = |module: &mut WasmiModule<_, _, _, _>, token: Token, types: &str| {
+        let token_ptr: AscPtr<AscEnum<EthereumValueKind>> = module.heap.asc_new(&token);
+        let data_ptr: AscPtr<Uint8Array> = module
+            .module
+            .invoke_export(
+                "ethereumEncode",
+                &[RuntimeValue::from(token_ptr)],
+                &mut module.externals,
+            )
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        let data: Vec<u8> = module.heap.asc_get(data_ptr);
+        assert_eq!(data, ethabi::encode(&[token.clone()]));
+
+        let types_ptr: AscPtr<AscString> = module.heap.asc_new(types);
+        let decoded_ptr: AscPtr<AscEnum<EthereumValueKind>> = module
+            .module
+            .invoke_export(
+                "ethereumDecode",
+                &[RuntimeValue::from(types_ptr), RuntimeValue::from(data_ptr)],
+                &mut module.externals,
+            )
+            .expect("call failed")
+            .expect("call returned nothing")
+            .try_into()
+            .expect("call did not return pointer");
+        let decoded: Token = module.heap.asc_get(decoded_ptr);
+        assert_eq!(decoded, token);
+    };
+
+    round_trip(&mut module, Token::Uint(U256::from(42)), "uint256");
+    round_trip(&mut module, Token::Int(U256::from(7)), "int8");
+    round_trip(&mut module, Token::Bool(true), "bool");
+    round_trip(
+        &mut module,
+        Token::Address(Address::from_str("0123123123012312312301231231230123123123").unwrap()),
+        "address",
+    );
+    round_trip(&mut module, Token::String("graph".to_owned()), "string");
+    round_trip(&mut module, Token::Bytes(vec![1, 2, 3]), "bytes");
+    round_trip(
+        &mut module,
+        Token::Array(vec![Token::Uint(U256::from(1)), Token::Uint(U256::from(2))]),
+        "uint256[]",
+    );
+
+    // Data that cannot be decoded as the requested type results in null
+    let types_ptr: AscPtr<AscString> = module.heap.asc_new("uint256");
+    let data: &[u8] = &[1, 2, 3];
+    let data_ptr: AscPtr<Uint8Array> = module.heap.asc_new(data);
+    let decoded = module
+        .module
+        .invoke_export(
+            "ethereumDecode",
+            &[RuntimeValue::from(types_ptr), RuntimeValue::from(data_ptr)],
+            &mut module.externals,
+        )
+        .expect("call failed")
+        .expect("call returned nothing")
+        .try_into::<u32>()
+        .expect("call did not return pointer");
+    assert_eq!(decoded, 0);
+
+    // Invalid types abort the mapping
+    let types_ptr: AscPtr<AscString> = module.heap.asc_new("uint257");
+    let err = module
+        .module
+        .invoke_export(
+            "ethereumDecode",
+            &[RuntimeValue::from(types_ptr), RuntimeValue::from(data_ptr)],
+            &mut module.externals,
+        )
+        .unwrap_err();
+    assert!(err
+        .to_string()
+        .contains("Failed to parse ABI type `uint257`"));
+}
+
+fn mapping_test_manifest() -> SubgraphManifest {
+    let mut data_source = mock_data_source("wasm_test/mapping_test.wasm");
+    data_source.source.abi = String::from("Things");
+    data_source.mapping.abis = vec![MappingABI {
+        name: String::from("Things"),
+        contract: ethabi::Contract::load(
+            &br#"[{
+                "type": "event",
+                "name": "Removed",
+                "anonymous": false,
+                "inputs": [{ "name": "id", "type": "string", "indexed": false }]
+            }]"#[..],
+        )
+        .unwrap(),
+        link: Link {
+            link: "link".to_owned(),
+        },
+    }];
+    data_source.mapping.event_handlers = vec![MappingEventHandler {
+        event: String::from("Removed(string)"),
+        handler: String::from("handleRemoved"),
+    }];
+
+    SubgraphManifest {
+        id: SubgraphId::new("testsubgraph").unwrap(),
+        location: String::from("/ipfs/testsubgraph"),
+        spec_version: String::from("0.0.1"),
+        description: None,
+        repository: None,
+        schema: mock_schema(),
+        data_sources: vec![data_source],
+    }
+}
+
+#[test]
+fn mapping_test_harness() {
+    let tester = MappingTester::new(
+        &Logger::root(slog::Discard, o!()),
+        &mapping_test_manifest(),
+        None,
+        Arc::new(ipfs_api::IpfsClient::default()),
+    )
+    .unwrap();
+
+    let file: MappingTestFile = serde_json::from_str(
+        r#"{
+            "tests": [
+                {
+                    "name": "removes the thing",
+                    "entities": [
+                        { "type": "Thing", "id": "1", "data": { "name": "one" } },
+                        { "type": "Thing", "id": "2", "data": { "name": "two" } }
+                    ],
+                    "event": { "signature": "Removed(string)", "params": { "id": "1" } },
+                    "expectedOperations": [{ "op": "remove", "type": "Thing", "id": "1" }],
+                    "expectedEntities": [
+                        { "type": "Thing", "id": "2", "data": { "name": "two" } }
+                    ]
+                },
+                {
+                    "name": "keeps the thing",
+                    "entities": [
+                        { "type": "Thing", "id": "1", "data": { "name": "one" } },
+                        { "type": "Thing", "id": "2", "data": { "name": "two" } }
+                    ],
+                    "event": { "signature": "Removed(string)", "params": ["1"] },
+                    "expectedEntities": [
+                        { "type": "Thing", "id": "1", "data": { "name": "one" } },
+                        { "type": "Thing", "id": "2", "data": { "name": "two" } }
+                    ]
+                },
+                {
+                    "name": "unknown event",
+                    "event": { "signature": "Added(string)", "params": ["1"] }
+                }
+            ]
+        }"#,
+    )
+    .unwrap();
+
+    tester.run(&file.tests[0]).unwrap();
+
+    // Mismatches are reported as a diff of the expected and actual store state
+    assert_eq!(
+        tester.run(&file.tests[1]).unwrap_err().to_string(),
+        "Store entities do not match (- expected, + actual):\n\
+         - Thing(1)\n\
+         -     id: \"1\"\n\
+         -     name: \"one\"\n\
+         \x20 Thing(2)\n\
+         \x20     id: \"2\"\n\
+         \x20     name: \"two\""
+    );
+
+    assert_eq!(
+        tester.run(&file.tests[2]).unwrap_err().to_string(),
+        "Data source `example data source` has no handler for event `Added(string)`"
+    );
+}
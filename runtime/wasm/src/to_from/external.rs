@@ -1,14 +1,16 @@
 use ethabi;
 use std::collections::HashMap;
 
-use graph::components::ethereum::{EthereumBlockData, EthereumEventData, EthereumTransactionData};
+use graph::components::ethereum::{
+    EthereumBlockData, EthereumEventData, EthereumTransactionData, EthereumTransactionReceiptData,
+};
 use graph::data::store;
-use graph::prelude::BigInt;
+use graph::prelude::{format_err, BigDecimal, BigInt, Error};
 use graph::serde_json;
 use graph::web3::types as web3;
 
 use asc_abi::class::*;
-use asc_abi::{AscHeap, AscPtr, FromAscObj, ToAscObj};
+use asc_abi::{AscHeap, AscPtr, FromAscObj, ToAscObj, TryFromAscObj};
 
 use UnresolvedContractCall;
 
@@ -58,6 +60,45 @@ impl FromAscObj<AscBigInt> for BigInt {
     }
 }
 
+impl ToAscObj<AscBigDecimal> for BigDecimal {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscBigDecimal {
+        let (digits, exp) = self.as_bigint_and_exponent();
+        AscBigDecimal {
+            digits: heap.asc_new(&digits),
+            exp: heap.asc_new(&BigInt::from(exp)),
+        }
+    }
+}
+
+/// The smallest exponent of a `BigDecimal` passed in from a mapping; this is the largest scale
+/// that a `numeric` column in Postgres supports.
+const BIG_DECIMAL_MIN_EXP: i64 = -16383;
+
+/// The largest exponent of a `BigDecimal` passed in from a mapping; a `numeric` column in
+/// Postgres supports at most this many digits before the decimal point.
+const BIG_DECIMAL_MAX_EXP: i64 = 131072;
+
+impl TryFromAscObj<AscBigDecimal> for BigDecimal {
+    fn try_from_asc_obj<H: AscHeap>(big_decimal: AscBigDecimal, heap: &H) -> Result<Self, Error> {
+        let digits: BigInt = heap.asc_get(big_decimal.digits);
+        let exp: BigInt = heap.asc_get(big_decimal.exp);
+        let exp = exp
+            .to_string()
+            .parse()
+            .ok()
+            .filter(|exp| BIG_DECIMAL_MIN_EXP <= *exp && *exp <= BIG_DECIMAL_MAX_EXP)
+            .ok_or_else(|| {
+                format_err!(
+                    "BigDecimal exponent `{}` is outside the supported range of {} to {}",
+                    exp,
+                    BIG_DECIMAL_MIN_EXP,
+                    BIG_DECIMAL_MAX_EXP
+                )
+            })?;
+        Ok(BigDecimal::new(digits, exp))
+    }
+}
+
 impl ToAscObj<AscEnum<EthereumValueKind>> for ethabi::Token {
     fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEnum<EthereumValueKind> {
         use ethabi::Token::*;
@@ -133,12 +174,15 @@ impl FromAscObj<AscEnum<EthereumValueKind>> for ethabi::Token {
     }
 }
 
-impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
-    fn from_asc_obj<H: AscHeap>(asc_enum: AscEnum<StoreValueKind>, heap: &H) -> Self {
+impl TryFromAscObj<AscEnum<StoreValueKind>> for store::Value {
+    fn try_from_asc_obj<H: AscHeap>(
+        asc_enum: AscEnum<StoreValueKind>,
+        heap: &H,
+    ) -> Result<Self, Error> {
         use self::store::Value;
 
         let payload = asc_enum.payload;
-        match asc_enum.kind {
+        Ok(match asc_enum.kind {
             StoreValueKind::String => {
                 let ptr: AscPtr<AscString> = AscPtr::from(payload);
                 Value::String(heap.asc_get(ptr))
@@ -149,7 +193,7 @@ impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
             StoreValueKind::Bool => Value::Bool(bool::from(payload)),
             StoreValueKind::Array => {
                 let ptr: AscEnumArray<StoreValueKind> = AscPtr::from(payload);
-                Value::List(heap.asc_get(ptr))
+                Value::List(heap.try_asc_get(ptr)?)
             }
             StoreValueKind::Null => Value::Null,
             StoreValueKind::Bytes => {
@@ -162,7 +206,11 @@ impl FromAscObj<AscEnum<StoreValueKind>> for store::Value {
                 let array: Vec<u8> = heap.asc_get(ptr);
                 Value::BigInt(store::scalar::BigInt::from_signed_bytes_le(&array))
             }
-        }
+            StoreValueKind::BigDecimal => {
+                let ptr: AscPtr<AscBigDecimal> = AscPtr::from(payload);
+                Value::BigDecimal(heap.try_asc_get(ptr)?)
+            }
+        })
     }
 }
 
@@ -185,6 +233,10 @@ impl ToAscObj<AscEnum<StoreValueKind>> for store::Value {
                 let bytes_obj: AscPtr<Uint8Array> = heap.asc_new(&*big_int.to_signed_bytes_le());
                 bytes_obj.into()
             }
+            Value::BigDecimal(big_decimal) => {
+                let big_decimal_obj: AscPtr<AscBigDecimal> = heap.asc_new(big_decimal);
+                big_decimal_obj.into()
+            }
         };
 
         AscEnum {
@@ -267,6 +319,7 @@ impl ToAscObj<AscEthereumBlock> for EthereumBlockData {
                 .size
                 .map(|size| heap.asc_new(&BigInt::from_unsigned_u256(&size)))
                 .unwrap_or_else(|| AscPtr::null()),
+            uncles: heap.asc_new(self.uncles.as_slice()),
         }
     }
 }
@@ -284,6 +337,76 @@ impl ToAscObj<AscEthereumTransaction> for EthereumTransactionData {
             value: heap.asc_new(&BigInt::from_unsigned_u256(&self.value)),
             gas_used: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_used)),
             gas_price: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_price)),
+            nonce: heap.asc_new(&BigInt::from_unsigned_u256(&self.nonce)),
+            input: heap.asc_new(self.input.0.as_slice()),
+        }
+    }
+}
+
+impl ToAscObj<AscEthereumLog> for web3::Log {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumLog {
+        AscEthereumLog {
+            address: heap.asc_new(&self.address),
+            topics: heap.asc_new(self.topics.as_slice()),
+            data: heap.asc_new(self.data.0.as_slice()),
+            block_hash: self
+                .block_hash
+                .map(|block_hash| heap.asc_new(&block_hash))
+                .unwrap_or_else(|| AscPtr::null()),
+            block_number: self
+                .block_number
+                .map(|block_number| heap.asc_new(&BigInt::from_unsigned_u256(&block_number)))
+                .unwrap_or_else(|| AscPtr::null()),
+            transaction_hash: self
+                .transaction_hash
+                .map(|transaction_hash| heap.asc_new(&transaction_hash))
+                .unwrap_or_else(|| AscPtr::null()),
+            transaction_index: self
+                .transaction_index
+                .map(|transaction_index| heap.asc_new(&BigInt::from(transaction_index)))
+                .unwrap_or_else(|| AscPtr::null()),
+            log_index: self
+                .log_index
+                .map(|log_index| heap.asc_new(&BigInt::from_unsigned_u256(&log_index)))
+                .unwrap_or_else(|| AscPtr::null()),
+            transaction_log_index: self
+                .transaction_log_index
+                .map(|index| heap.asc_new(&BigInt::from_unsigned_u256(&index)))
+                .unwrap_or_else(|| AscPtr::null()),
+            log_type: self
+                .log_type
+                .clone()
+                .map(|log_type| heap.asc_new(&log_type))
+                .unwrap_or_else(|| AscPtr::null()),
+        }
+    }
+}
+
+impl ToAscObj<AscEthereumTransactionReceipt> for EthereumTransactionReceiptData {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumTransactionReceipt {
+        AscEthereumTransactionReceipt {
+            transaction_hash: heap.asc_new(&self.transaction_hash),
+            transaction_index: heap.asc_new(&BigInt::from(self.transaction_index)),
+            block_hash: self
+                .block_hash
+                .map(|block_hash| heap.asc_new(&block_hash))
+                .unwrap_or_else(|| AscPtr::null()),
+            block_number: self
+                .block_number
+                .map(|block_number| heap.asc_new(&BigInt::from_unsigned_u256(&block_number)))
+                .unwrap_or_else(|| AscPtr::null()),
+            cumulative_gas_used: heap
+                .asc_new(&BigInt::from_unsigned_u256(&self.cumulative_gas_used)),
+            gas_used: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_used)),
+            contract_address: self
+                .contract_address
+                .map(|contract_address| heap.asc_new(&contract_address))
+                .unwrap_or_else(|| AscPtr::null()),
+            logs: heap.asc_new(self.logs.as_slice()),
+            status: self
+                .status
+                .map(|status| heap.asc_new(&BigInt::from(status)))
+                .unwrap_or_else(|| AscPtr::null()),
         }
     }
 }
@@ -303,6 +426,71 @@ impl ToAscObj<AscEthereumEvent> for EthereumEventData {
             block: heap.asc_new(&self.block),
             transaction: heap.asc_new(&self.transaction),
             params: heap.asc_new(self.params.as_slice()),
+            receipt: self
+                .receipt
+                .as_ref()
+                .map(|receipt| heap.asc_new(receipt))
+                .unwrap_or_else(|| AscPtr::null()),
+        }
+    }
+}
+
+impl ToAscObj<AscEthereumBlock_0_0_1> for EthereumBlockData {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumBlock_0_0_1 {
+        AscEthereumBlock_0_0_1 {
+            hash: heap.asc_new(&self.hash),
+            parent_hash: heap.asc_new(&self.parent_hash),
+            uncles_hash: heap.asc_new(&self.uncles_hash),
+            author: heap.asc_new(&self.author),
+            state_root: heap.asc_new(&self.state_root),
+            transactions_root: heap.asc_new(&self.transactions_root),
+            receipts_root: heap.asc_new(&self.receipts_root),
+            number: heap.asc_new(&BigInt::from(self.number)),
+            gas_used: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_used)),
+            gas_limit: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_limit)),
+            timestamp: heap.asc_new(&BigInt::from_unsigned_u256(&self.timestamp)),
+            difficulty: heap.asc_new(&BigInt::from_unsigned_u256(&self.difficulty)),
+            total_difficulty: heap.asc_new(&BigInt::from_unsigned_u256(&self.total_difficulty)),
+            size: self
+                .size
+                .map(|size| heap.asc_new(&BigInt::from_unsigned_u256(&size)))
+                .unwrap_or_else(|| AscPtr::null()),
+        }
+    }
+}
+
+impl ToAscObj<AscEthereumTransaction_0_0_1> for EthereumTransactionData {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumTransaction_0_0_1 {
+        AscEthereumTransaction_0_0_1 {
+            hash: heap.asc_new(&self.hash),
+            index: heap.asc_new(&BigInt::from(self.index)),
+            from: heap.asc_new(&self.from),
+            to: self
+                .to
+                .map(|to| heap.asc_new(&to))
+                .unwrap_or_else(|| AscPtr::null()),
+            value: heap.asc_new(&BigInt::from_unsigned_u256(&self.value)),
+            gas_used: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_used)),
+            gas_price: heap.asc_new(&BigInt::from_unsigned_u256(&self.gas_price)),
+        }
+    }
+}
+
+impl ToAscObj<AscEthereumEvent_0_0_1> for EthereumEventData {
+    fn to_asc_obj<H: AscHeap>(&self, heap: &H) -> AscEthereumEvent_0_0_1 {
+        AscEthereumEvent_0_0_1 {
+            address: heap.asc_new(&self.address),
+            log_index: heap.asc_new(&BigInt::from_unsigned_u256(&self.log_index)),
+            transaction_log_index: heap
+                .asc_new(&BigInt::from_unsigned_u256(&self.transaction_log_index)),
+            log_type: self
+                .log_type
+                .clone()
+                .map(|log_type| heap.asc_new(&log_type))
+                .unwrap_or_else(|| AscPtr::null()),
+            block: heap.asc_new(&self.block),
+            transaction: heap.asc_new(&self.transaction),
+            params: heap.asc_new(self.params.as_slice()),
         }
     }
 }
@@ -2,10 +2,12 @@ use std::collections::HashMap;
 use std::hash::Hash;
 use std::iter::FromIterator;
 
+use graph::prelude::Error;
+
 use asc_abi::class::*;
-use asc_abi::{AscHeap, AscPtr, AscType, AscValue, FromAscObj, ToAscObj};
+use asc_abi::{AscHeap, AscPtr, AscType, AscValue, FromAscObj, ToAscObj, TryFromAscObj};
 
-///! Implementations of `ToAscObj` and `FromAscObj` for Rust types.
+///! Implementations of `ToAscObj`, `FromAscObj` and `TryFromAscObj` for Rust types.
 ///! Standard Rust types go in `mod.rs` and external types in `external.rs`.
 mod external;
 
@@ -88,6 +90,16 @@ impl<C: AscType, T: FromAscObj<C>> FromAscObj<Array<AscPtr<C>>> for Vec<T> {
     }
 }
 
+impl<C: AscType, T: TryFromAscObj<C>> TryFromAscObj<Array<AscPtr<C>>> for Vec<T> {
+    fn try_from_asc_obj<H: AscHeap>(array: Array<AscPtr<C>>, heap: &H) -> Result<Self, Error> {
+        array
+            .to_vec(heap)
+            .into_iter()
+            .map(|x| heap.try_asc_get(x))
+            .collect()
+    }
+}
+
 impl<K: AscType, V: AscType, T: FromAscObj<K>, U: FromAscObj<V>> FromAscObj<AscTypedMapEntry<K, V>>
     for (T, U)
 {
@@ -96,6 +108,20 @@ impl<K: AscType, V: AscType, T: FromAscObj<K>, U: FromAscObj<V>> FromAscObj<AscT
     }
 }
 
+impl<K: AscType, V: AscType, T: FromAscObj<K>, U: TryFromAscObj<V>>
+    TryFromAscObj<AscTypedMapEntry<K, V>> for (T, U)
+{
+    fn try_from_asc_obj<H: AscHeap>(
+        asc_entry: AscTypedMapEntry<K, V>,
+        heap: &H,
+    ) -> Result<Self, Error> {
+        Ok((
+            heap.asc_get(asc_entry.key),
+            heap.try_asc_get(asc_entry.value)?,
+        ))
+    }
+}
+
 impl<'a, 'b, K: AscType, V: AscType, T: ToAscObj<K>, U: ToAscObj<V>>
     ToAscObj<AscTypedMapEntry<K, V>> for (&'a T, &'b U)
 {
@@ -115,3 +141,12 @@ impl<K: AscType, V: AscType, T: FromAscObj<K> + Hash + Eq, U: FromAscObj<V>>
         HashMap::from_iter(entries.into_iter())
     }
 }
+
+impl<K: AscType, V: AscType, T: FromAscObj<K> + Hash + Eq, U: TryFromAscObj<V>>
+    TryFromAscObj<AscTypedMap<K, V>> for HashMap<T, U>
+{
+    fn try_from_asc_obj<H: AscHeap>(asc_map: AscTypedMap<K, V>, heap: &H) -> Result<Self, Error> {
+        let entries: Vec<(T, U)> = heap.try_asc_get(asc_map.entries)?;
+        Ok(HashMap::from_iter(entries.into_iter()))
+    }
+}
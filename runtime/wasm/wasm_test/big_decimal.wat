@@ -0,0 +1,48 @@
+;; Forwards calls to the `bigDecimal.*` host exports.
+;;
+;; Build with `wat2wasm big_decimal.wat -o big_decimal.wasm`.
+(module
+  (import "bigDecimal" "bigDecimal.plus" (func $bigDecimal.plus (param i32 i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.minus" (func $bigDecimal.minus (param i32 i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.times" (func $bigDecimal.times (param i32 i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.dividedBy"
+    (func $bigDecimal.dividedBy (param i32 i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.equals" (func $bigDecimal.equals (param i32 i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.toString" (func $bigDecimal.toString (param i32) (result i32)))
+  (import "bigDecimal" "bigDecimal.fromString"
+    (func $bigDecimal.fromString (param i32) (result i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 8))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "plus") (param $x i32) (param $y i32) (result i32)
+    (call $bigDecimal.plus (local.get $x) (local.get $y)))
+
+  (func (export "minus") (param $x i32) (param $y i32) (result i32)
+    (call $bigDecimal.minus (local.get $x) (local.get $y)))
+
+  (func (export "times") (param $x i32) (param $y i32) (result i32)
+    (call $bigDecimal.times (local.get $x) (local.get $y)))
+
+  (func (export "dividedBy") (param $x i32) (param $y i32) (result i32)
+    (call $bigDecimal.dividedBy (local.get $x) (local.get $y)))
+
+  (func (export "equals") (param $x i32) (param $y i32) (result i32)
+    (call $bigDecimal.equals (local.get $x) (local.get $y)))
+
+  (func (export "toString") (param $x i32) (result i32)
+    (call $bigDecimal.toString (local.get $x)))
+
+  (func (export "fromString") (param $s i32) (result i32)
+    (call $bigDecimal.fromString (local.get $s))))
@@ -0,0 +1,30 @@
+;; Forwards calls to the host exports that read the state of the chain.
+;;
+;; Build with `wat2wasm chain_state.wat -o chain_state.wasm`.
+(module
+  (import "chain" "ethereum.getBalance" (func $ethereum.getBalance (param i32) (result i32)))
+  (import "chain" "ethereum.getCode" (func $ethereum.getCode (param i32) (result i32)))
+  (import "chain" "ens.nameByAddress" (func $ens.nameByAddress (param i32) (result i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 8))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "getBalance") (param $address i32) (result i32)
+    (call $ethereum.getBalance (local.get $address)))
+
+  (func (export "getCode") (param $address i32) (result i32)
+    (call $ethereum.getCode (local.get $address)))
+
+  (func (export "nameByAddress") (param $address i32) (result i32)
+    (call $ens.nameByAddress (local.get $address))))
@@ -0,0 +1,32 @@
+;; Forwards calls to the `crypto.*` host exports added in mapping API version 0.0.2.
+;;
+;; Build with `wat2wasm crypto_v2.wat -o crypto_v2.wasm`.
+(module
+  (import "crypto" "crypto.sha256" (func $crypto.sha256 (param i32) (result i32)))
+  (import "crypto" "crypto.ripemd160" (func $crypto.ripemd160 (param i32) (result i32)))
+  (import "crypto" "crypto.ecrecover"
+    (func $crypto.ecrecover (param i32 i32 i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 8))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "sha256") (param $input i32) (result i32)
+    (call $crypto.sha256 (local.get $input)))
+
+  (func (export "ripemd160") (param $input i32) (result i32)
+    (call $crypto.ripemd160 (local.get $input)))
+
+  (func (export "ecrecover") (param $hash i32) (param $v i32) (param $r i32) (param $s i32)
+    (result i32)
+    (call $crypto.ecrecover (local.get $hash) (local.get $v) (local.get $r) (local.get $s))))
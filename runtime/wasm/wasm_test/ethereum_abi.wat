@@ -0,0 +1,26 @@
+;; Forwards calls to the `ethereum.encode` and `ethereum.decode` host exports.
+;;
+;; Build with `wat2wasm ethereum_abi.wat -o ethereum_abi.wasm`.
+(module
+  (import "ethereum" "ethereum.encode" (func $ethereum.encode (param i32) (result i32)))
+  (import "ethereum" "ethereum.decode" (func $ethereum.decode (param i32 i32) (result i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 8))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "ethereumEncode") (param $token i32) (result i32)
+    (call $ethereum.encode (local.get $token)))
+
+  (func (export "ethereumDecode") (param $types i32) (param $data i32) (result i32)
+    (call $ethereum.decode (local.get $types) (local.get $data))))
@@ -0,0 +1,57 @@
+;; Calls `ipfs.map` with callbacks that store an entity for every JSON value.
+;;
+;; Build with `wat2wasm ipfs_map.wat -o ipfs_map.wasm`.
+(module
+  (import "ipfs_map" "ipfs.map" (func $ipfs.map (param i32 i32 i32)))
+  (import "ipfs_map" "store.set" (func $store.set (param i32 i32 i32)))
+
+  (memory (export "memory") 1)
+
+  ;; The entity `{ name: "thing" }` stored by the callbacks
+  ;; Typed map with the entries array at 16
+  (data (i32.const 8) "\10\00\00\00")
+  ;; Array of one entry, with its buffer at 24
+  (data (i32.const 16) "\18\00\00\00\01\00\00\00")
+  ;; Array buffer holding the pointer to the entry at 40
+  (data (i32.const 24) "\04\00\00\00\00\00\00\00\28\00\00\00")
+  ;; Entry with the key at 48 and the value at 64
+  (data (i32.const 40) "\30\00\00\00\40\00\00\00")
+  ;; "name"
+  (data (i32.const 48) "\04\00\00\00n\00a\00m\00e\00")
+  ;; String value with the string at 80
+  (data (i32.const 64) "\00\00\00\00\00\00\00\00\50\00\00\00\00\00\00\00")
+  ;; "thing"
+  (data (i32.const 80) "\05\00\00\00t\00h\00i\00n\00g\00")
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 96))
+
+  ;; Set once an instance has run a callback
+  (global $called (mut i32) (i32.const 0))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "ipfsMap") (param $hash i32) (param $callback i32) (param $userData i32)
+    (call $ipfs.map (local.get $hash) (local.get $callback) (local.get $userData)))
+
+  ;; Stores the entity with the JSON string `value` as its ID, using the string
+  ;; `userData` as the entity type. Both are enums with the pointer to the string as payload
+  ;; at offset 8. Traps if the instance has already run a callback.
+  (func $setEntity (export "setEntity") (param $value i32) (param $userData i32)
+    (if (global.get $called) (then (unreachable)))
+    (global.set $called (i32.const 1))
+    (call $store.set
+      (i32.load offset=8 (local.get $userData))
+      (i32.load offset=8 (local.get $value))
+      (i32.const 8)))
+
+  (func (export "setEntityAndFail") (param $value i32) (param $userData i32)
+    (call $setEntity (local.get $value) (local.get $userData))
+    (unreachable)))
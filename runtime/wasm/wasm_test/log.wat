@@ -0,0 +1,22 @@
+;; Forwards messages to the `log.log` host export.
+;;
+;; Build with `wat2wasm log.wat -o log.wasm`.
+(module
+  (import "log" "log.log" (func $log.log (param i32 i32)))
+
+  (memory (export "memory") 1)
+
+  ;; Bump allocator used by the host to pass values into the module
+  (global $offset (mut i32) (i32.const 8))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "log") (param $level i32) (param $message i32)
+    (call $log.log (local.get $level) (local.get $message))))
@@ -0,0 +1,36 @@
+;; Mapping for the mapping test harness, with a handler for `Removed(string id)` events
+;; that removes the `Thing` with the ID from the event.
+;;
+;; Build with `wat2wasm mapping_test.wat -o mapping_test.wasm`.
+(module
+  (import "mapping_test" "store.remove" (func $store.remove (param i32 i32)))
+
+  (memory (export "memory") 1)
+
+  ;; The string "Thing": its length followed by UTF-16 code units
+  (data (i32.const 8) "\05\00\00\00T\00h\00i\00n\00g\00")
+
+  ;; Bump allocator used by the host to pass values into the module, starting after the data
+  (global $offset (mut i32) (i32.const 24))
+
+  (func $allocate (export "memory.allocate") (param $size i32) (result i32)
+    (local $ptr i32)
+    (local.set $ptr (global.get $offset))
+    (global.set $offset
+      (i32.and
+        (i32.add (i32.add (global.get $offset) (local.get $size)) (i32.const 7))
+        (i32.const -8)))
+    (local.get $ptr))
+
+  (func (export "handleRemoved") (param $event i32)
+    (call $store.remove
+      (i32.const 8)
+      ;; The string payload of `event.params[0].value`
+      (i32.load offset=8
+        ;; `value` of the parameter
+        (i32.load offset=4
+          ;; First element in the buffer of the array
+          (i32.load offset=8
+            ;; Buffer of `event.params`
+            (i32.load
+              (i32.load offset=24 (local.get $event)))))))))
@@ -0,0 +1,16 @@
+extern crate futures;
+extern crate graph;
+extern crate graph_graphql;
+extern crate graph_server_http;
+extern crate graphql_parser;
+extern crate hyper;
+
+mod resolver;
+mod schema;
+mod server;
+mod service;
+
+pub use self::resolver::IndexNodeResolver;
+pub use self::schema::index_node_schema;
+pub use self::server::IndexNodeServer;
+pub use self::service::{IndexNodeService, IndexNodeServiceResponse};
@@ -0,0 +1,262 @@
+use graphql_parser::{query as q, schema as s};
+use std::collections::HashMap;
+
+use graph::data::subgraph::schema::{
+    SUBGRAPHS_ID, SUBGRAPH_ENTITY_TYPENAME, SUBGRAPH_ERROR_ENTITY_TYPENAME,
+};
+use graph::prelude::*;
+use graph_graphql::prelude::{object_value, Resolver};
+
+fn object_field<'a>(object: &'a Option<q::Value>, field: &str) -> Option<&'a q::Value> {
+    object
+        .as_ref()
+        .and_then(|object| match object {
+            q::Value::Object(ref data) => Some(data),
+            _ => None,
+        })
+        .and_then(|data| data.get(field))
+}
+
+fn string_field(entity: &Entity, field: &str) -> Option<String> {
+    match entity.get(field) {
+        Some(Value::String(s)) => Some(s.clone()),
+        _ => None,
+    }
+}
+
+fn block_object(block_ptr: EthereumBlockPointer) -> q::Value {
+    object_value(vec![
+        ("hash", q::Value::String(format!("{:?}", block_ptr.hash))),
+        ("number", q::Value::String(block_ptr.number.to_string())),
+    ])
+}
+
+/// Resolves indexing statuses of the subgraph versions assigned to this node.
+pub struct IndexNodeResolver<S> {
+    logger: Logger,
+    store: Arc<S>,
+    chain_health: ChainHealth,
+    node_id: NodeId,
+    network_name: String,
+}
+
+impl<S> Clone for IndexNodeResolver<S> {
+    fn clone(&self) -> Self {
+        Self {
+            logger: self.logger.clone(),
+            store: self.store.clone(),
+            chain_health: self.chain_health.clone(),
+            node_id: self.node_id.clone(),
+            network_name: self.network_name.clone(),
+        }
+    }
+}
+
+impl<S> IndexNodeResolver<S>
+where
+    S: Store + ChainStore + SubgraphDeploymentStore,
+{
+    pub fn new(
+        logger: &Logger,
+        store: Arc<S>,
+        chain_health: ChainHealth,
+        node_id: NodeId,
+        network_name: String,
+    ) -> Self {
+        let logger = logger.new(o!("component" => "IndexNodeResolver"));
+
+        IndexNodeResolver {
+            logger,
+            store,
+            chain_health,
+            node_id,
+            network_name,
+        }
+    }
+
+    fn indexing_statuses(
+        &self,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        // Optionally restrict the statuses to a list of subgraph IDs
+        let subgraph_ids = match arguments.get(&String::from("subgraphs")) {
+            Some(q::Value::List(ids)) => Some(
+                ids.iter()
+                    .filter_map(|id| match id {
+                        q::Value::String(id) => Some(id.clone()),
+                        _ => None,
+                    })
+                    .collect::<Vec<_>>(),
+            ),
+            _ => None,
+        };
+
+        let chain_head_block = self
+            .store
+            .chain_head_ptr()
+            .map_err(QueryExecutionError::StoreError)?;
+
+        self.store
+            .read_versions_by_node_id(self.node_id.clone())
+            .map_err(QueryExecutionError::StoreError)?
+            .into_iter()
+            .filter(|version| {
+                subgraph_ids
+                    .as_ref()
+                    .map_or(true, |ids| ids.contains(&version.subgraph_id.to_string()))
+            })
+            .map(|version| self.indexing_status(version, chain_head_block))
+            .collect::<Result<Vec<_>, _>>()
+            .map(q::Value::List)
+    }
+
+    fn indexing_status(
+        &self,
+        version: SubgraphVersion,
+        chain_head_block: Option<EthereumBlockPointer>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        let subgraph = self.store.get(EntityKey {
+            subgraph_id: SUBGRAPHS_ID.clone(),
+            entity_type: SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+            entity_id: version.subgraph_id.to_string(),
+        })?;
+        let subgraph_status = subgraph
+            .as_ref()
+            .and_then(|entity| string_field(entity, "status"));
+        let fatal_error = match subgraph
+            .as_ref()
+            .and_then(|entity| string_field(entity, "fatalError"))
+        {
+            Some(error_id) => self.fatal_error(error_id)?,
+            None => q::Value::Null,
+        };
+
+        let failed = subgraph_status == Some(SubgraphStatus::Failed.to_string());
+        let synced = subgraph_status == Some(SubgraphStatus::Synced.to_string());
+
+        let health = if failed {
+            "failed"
+        } else if !self.chain_health.is_healthy() {
+            "unhealthy"
+        } else {
+            "healthy"
+        };
+
+        // The subgraph has no block pointer until its instance has been started once
+        let latest_block = self
+            .store
+            .find_block_ptr(version.subgraph_id.clone())
+            .map_err(QueryExecutionError::StoreError)?;
+
+        let entity_count = self
+            .store
+            .count_entities(version.subgraph_id.clone())
+            .map_err(QueryExecutionError::StoreError)?;
+
+        debug!(
+            self.logger, "Resolved indexing status";
+            "subgraph" => version.subgraph_id.to_string(),
+            "health" => health,
+        );
+
+        Ok(object_value(vec![
+            (
+                "subgraph",
+                q::Value::String(version.subgraph_id.to_string()),
+            ),
+            ("name", q::Value::String(version.name.to_string())),
+            ("status", q::Value::Enum(version.status.as_str().to_owned())),
+            ("paused", q::Value::Boolean(version.paused)),
+            ("synced", q::Value::Boolean(synced)),
+            ("health", q::Value::Enum(health.to_owned())),
+            ("fatalError", fatal_error),
+            ("network", q::Value::String(self.network_name.clone())),
+            ("node", q::Value::String(version.node_id.to_string())),
+            (
+                "latestBlock",
+                latest_block.map_or(q::Value::Null, block_object),
+            ),
+            (
+                "chainHeadBlock",
+                chain_head_block.map_or(q::Value::Null, block_object),
+            ),
+            ("entityCount", q::Value::String(entity_count.to_string())),
+        ]))
+    }
+
+    fn fatal_error(&self, error_id: String) -> Result<q::Value, QueryExecutionError> {
+        let error = match self.store.get(EntityKey {
+            subgraph_id: SUBGRAPHS_ID.clone(),
+            entity_type: SUBGRAPH_ERROR_ENTITY_TYPENAME.to_owned(),
+            entity_id: error_id,
+        })? {
+            Some(error) => error,
+            None => return Ok(q::Value::Null),
+        };
+
+        let block = match (error.get("blockHash"), error.get("blockNumber")) {
+            (Some(Value::String(hash)), Some(Value::BigInt(number))) => object_value(vec![
+                ("hash", q::Value::String(hash.clone())),
+                ("number", q::Value::String(number.to_string())),
+            ]),
+            _ => q::Value::Null,
+        };
+
+        Ok(object_value(vec![
+            (
+                "message",
+                string_field(&error, "message").map_or(q::Value::Null, q::Value::String),
+            ),
+            ("block", block),
+            (
+                "handler",
+                string_field(&error, "handler").map_or(q::Value::Null, q::Value::String),
+            ),
+            (
+                "dataSource",
+                string_field(&error, "dataSource").map_or(q::Value::Null, q::Value::String),
+            ),
+            (
+                "deterministic",
+                match error.get("deterministic") {
+                    Some(Value::Bool(deterministic)) => q::Value::Boolean(*deterministic),
+                    _ => q::Value::Null,
+                },
+            ),
+        ]))
+    }
+}
+
+impl<S> Resolver for IndexNodeResolver<S>
+where
+    S: Store + ChainStore + SubgraphDeploymentStore,
+{
+    fn resolve_objects(
+        &self,
+        parent: &Option<q::Value>,
+        field: &q::Name,
+        _field_definition: &s::Field,
+        _object_type: &s::ObjectType,
+        arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        match (parent, field.as_str()) {
+            (None, "indexingStatuses") => self.indexing_statuses(arguments),
+            _ => Ok(object_field(parent, field.as_str())
+                .cloned()
+                .unwrap_or(q::Value::Null)),
+        }
+    }
+
+    fn resolve_object(
+        &self,
+        parent: &Option<q::Value>,
+        field: &q::Name,
+        _field_definition: &s::Field,
+        _object_type: &s::ObjectType,
+        _arguments: &HashMap<&q::Name, q::Value>,
+    ) -> Result<q::Value, QueryExecutionError> {
+        Ok(object_field(parent, field.as_str())
+            .cloned()
+            .unwrap_or(q::Value::Null))
+    }
+}
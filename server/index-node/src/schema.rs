@@ -0,0 +1,10 @@
+use graphql_parser;
+
+use graph::prelude::*;
+
+/// Returns the GraphQL schema of the index node server.
+pub fn index_node_schema() -> Schema {
+    let document = graphql_parser::parse_schema(include_str!("./schema.graphql"))
+        .expect("index node schema is invalid");
+    Schema::new(SubgraphId::new("indexnode").unwrap(), document)
+}
@@ -0,0 +1,115 @@
+use hyper;
+use hyper::Server;
+
+use std::error::Error;
+use std::fmt;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+use graph::prelude::{IndexNodeServer as IndexNodeServerTrait, *};
+use resolver::IndexNodeResolver;
+use schema::index_node_schema;
+use service::IndexNodeService;
+
+/// Errors that may occur when starting the server.
+#[derive(Debug)]
+pub enum IndexNodeServeError {
+    BindError(hyper::Error),
+}
+
+impl Error for IndexNodeServeError {
+    fn description(&self) -> &str {
+        "Failed to start the server"
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        None
+    }
+}
+
+impl fmt::Display for IndexNodeServeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IndexNodeServeError::BindError(e) => {
+                write!(f, "Failed to bind index node server: {}", e)
+            }
+        }
+    }
+}
+
+impl From<hyper::Error> for IndexNodeServeError {
+    fn from(err: hyper::Error) -> Self {
+        IndexNodeServeError::BindError(err)
+    }
+}
+
+/// A server for the indexing status API of this node, based on Hyper.
+pub struct IndexNodeServer<S> {
+    logger: Logger,
+    store: Arc<S>,
+    chain_health: ChainHealth,
+    node_id: NodeId,
+    network_name: String,
+}
+
+impl<S> IndexNodeServer<S> {
+    /// Creates a new index node server.
+    pub fn new(
+        logger: &Logger,
+        store: Arc<S>,
+        chain_health: ChainHealth,
+        node_id: NodeId,
+        network_name: String,
+    ) -> Self {
+        IndexNodeServer {
+            logger: logger.new(o!("component" => "IndexNodeServer")),
+            store,
+            chain_health,
+            node_id,
+            network_name,
+        }
+    }
+}
+
+impl<S> IndexNodeServerTrait for IndexNodeServer<S>
+where
+    S: Store + ChainStore + SubgraphDeploymentStore,
+{
+    type ServeError = IndexNodeServeError;
+
+    fn serve(
+        &mut self,
+        port: u16,
+    ) -> Result<Box<Future<Item = (), Error = ()> + Send>, Self::ServeError> {
+        let logger = self.logger.clone();
+
+        info!(
+            logger,
+            "Starting index node server at: http://localhost:{}", port
+        );
+
+        let addr = SocketAddrV4::new(Ipv4Addr::new(0, 0, 0, 0), port);
+
+        // On every incoming request, launch a new service that resolves
+        // indexing statuses from the store
+        let resolver = IndexNodeResolver::new(
+            &logger,
+            self.store.clone(),
+            self.chain_health.clone(),
+            self.node_id.clone(),
+            self.network_name.clone(),
+        );
+        let schema = index_node_schema();
+        let service_logger = logger.clone();
+        let new_service = move || {
+            let service = IndexNodeService::new(&service_logger, resolver.clone(), schema.clone());
+            future::ok::<IndexNodeService<S>, hyper::Error>(service)
+        };
+
+        // Create a task to run the server and handle HTTP requests
+        let task = Server::try_bind(&addr.into())?
+            .serve(new_service)
+            .map_err(move |e| error!(logger, "Server error"; "error" => format!("{}", e)));
+
+        Ok(Box::new(task))
+    }
+}
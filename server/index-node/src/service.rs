@@ -0,0 +1,121 @@
+use graph::components::server::query::GraphQLServerError;
+use graph::prelude::*;
+use graph_graphql::prelude::{execute_query, QueryExecutionOptions};
+use graph_server_http::{GraphQLRequest, GraphQLResponse};
+use hyper::service::Service;
+use hyper::{Body, Method, Request, Response, StatusCode};
+
+use resolver::IndexNodeResolver;
+
+/// An asynchronous response to an index node request.
+pub type IndexNodeServiceResponse =
+    Box<Future<Item = Response<Body>, Error = GraphQLServerError> + Send>;
+
+/// A Hyper Service that serves the indexing status API over a POST /graphql endpoint.
+pub struct IndexNodeService<S> {
+    logger: Logger,
+    resolver: IndexNodeResolver<S>,
+    schema: Schema,
+}
+
+impl<S> Clone for IndexNodeService<S> {
+    fn clone(&self) -> Self {
+        Self {
+            logger: self.logger.clone(),
+            resolver: self.resolver.clone(),
+            schema: self.schema.clone(),
+        }
+    }
+}
+
+impl<S> IndexNodeService<S>
+where
+    S: Store + ChainStore + SubgraphDeploymentStore,
+{
+    /// Creates a new index node service for the index node `schema`.
+    pub fn new(logger: &Logger, resolver: IndexNodeResolver<S>, schema: Schema) -> Self {
+        IndexNodeService {
+            logger: logger.clone(),
+            resolver,
+            schema,
+        }
+    }
+
+    fn index(&self) -> IndexNodeServiceResponse {
+        Box::new(future::ok(
+            Response::builder()
+                .status(200)
+                .body(Body::from(String::from(
+                    "Index node status API. Query indexing statuses at /graphql",
+                )))
+                .unwrap(),
+        ))
+    }
+
+    fn handle_graphql_query(&self, request_body: Body) -> IndexNodeServiceResponse {
+        let service = self.clone();
+        let schema = self.schema.clone();
+
+        Box::new(
+            request_body
+                .concat2()
+                .map_err(|_| GraphQLServerError::from("Failed to read request body"))
+                .and_then(move |body| GraphQLRequest::new(body, schema))
+                .map(move |query| {
+                    execute_query(
+                        &query,
+                        QueryExecutionOptions {
+                            logger: service.logger.clone(),
+                            resolver: service.resolver.clone(),
+                        },
+                    )
+                })
+                .then(|result| GraphQLResponse::new(result)),
+        )
+    }
+
+    // Handles OPTIONS requests
+    fn handle_graphql_options(&self, _request: Request<Body>) -> IndexNodeServiceResponse {
+        Box::new(future::ok(
+            Response::builder()
+                .status(200)
+                .header("Access-Control-Allow-Origin", "*")
+                .header("Access-Control-Allow-Headers", "Content-Type")
+                .header("Access-Control-Allow-Methods", "GET, OPTIONS, POST")
+                .body(Body::from(""))
+                .unwrap(),
+        ))
+    }
+
+    /// Handles 404s.
+    fn handle_not_found(&self) -> IndexNodeServiceResponse {
+        Box::new(future::ok(
+            Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(Body::from("Not found"))
+                .unwrap(),
+        ))
+    }
+}
+
+impl<S> Service for IndexNodeService<S>
+where
+    S: Store + ChainStore + SubgraphDeploymentStore,
+{
+    type ReqBody = Body;
+    type ResBody = Body;
+    type Error = GraphQLServerError;
+    type Future = IndexNodeServiceResponse;
+
+    fn call(&mut self, req: Request<Self::ReqBody>) -> Self::Future {
+        let method = req.method().clone();
+        let path = req.uri().path().to_owned();
+
+        match (method, path.as_str()) {
+            (Method::GET, "/") => self.index(),
+            (Method::POST, "/graphql") => self.handle_graphql_query(req.into_body()),
+            (Method::OPTIONS, "/graphql") => self.handle_graphql_options(req),
+            _ => self.handle_not_found(),
+        }
+    }
+}
@@ -0,0 +1,208 @@
+extern crate graph;
+extern crate graph_graphql;
+extern crate graph_mock;
+extern crate graph_server_index_node;
+extern crate graphql_parser;
+
+use graphql_parser::query as q;
+use std::collections::HashMap;
+
+use graph::data::subgraph::schema::SubgraphEntity;
+use graph::prelude::*;
+use graph::web3::types::H256;
+use graph_graphql::prelude::{execute_query, object_value, QueryExecutionOptions};
+use graph_mock::MockStore;
+use graph_server_index_node::{index_node_schema, IndexNodeResolver};
+
+/// Deploys subgraph "synced" and the failed subgraph "failed" to node "thisnode", and
+/// subgraph "elsewhere" to another node.
+fn test_store() -> Arc<MockStore> {
+    let store = Arc::new(MockStore::empty(vec![]));
+    let node_id = NodeId::new("thisnode").unwrap();
+    let synced = SubgraphId::new("synced").unwrap();
+    let failed = SubgraphId::new("failed").unwrap();
+
+    store
+        .write(
+            SubgraphDeploymentName::new("name1").unwrap(),
+            synced.clone(),
+            node_id.clone(),
+        )
+        .unwrap();
+    store
+        .write(
+            SubgraphDeploymentName::new("name2").unwrap(),
+            failed.clone(),
+            node_id,
+        )
+        .unwrap();
+    store
+        .write(
+            SubgraphDeploymentName::new("name3").unwrap(),
+            SubgraphId::new("elsewhere").unwrap(),
+            NodeId::new("othernode").unwrap(),
+        )
+        .unwrap();
+
+    // Only the synced subgraph has started processing blocks
+    store
+        .add_subgraph_if_missing(synced.clone(), (H256::from(6), 5u64).into())
+        .unwrap();
+
+    let mut ops = SubgraphEntity::write_status_operations(&synced, SubgraphStatus::Synced);
+    ops.append(&mut SubgraphEntity::write_failure_operations(
+        &failed,
+        SubgraphError::new("mapping aborted", true),
+    ));
+    let mut user = Entity::new();
+    user.insert("id".to_owned(), Value::from("1"));
+    ops.push(EntityOperation::Set {
+        key: EntityKey {
+            subgraph_id: synced,
+            entity_type: "User".to_owned(),
+            entity_id: "1".to_owned(),
+        },
+        data: user,
+    });
+    store
+        .apply_entity_operations(ops, EventSource::None)
+        .unwrap();
+
+    store
+}
+
+/// Runs an indexing status query against `store` and returns the statuses by subgraph ID.
+fn indexing_statuses(
+    store: Arc<MockStore>,
+    chain_health: ChainHealth,
+    query: &str,
+) -> HashMap<String, q::Value> {
+    let logger = Logger::root(slog::Discard, o!());
+    let resolver = IndexNodeResolver::new(
+        &logger,
+        store,
+        chain_health,
+        NodeId::new("thisnode").unwrap(),
+        "mainnet".to_owned(),
+    );
+    let query = Query {
+        schema: index_node_schema(),
+        document: graphql_parser::parse_query(query).unwrap(),
+        variables: None,
+    };
+
+    let result = execute_query(&query, QueryExecutionOptions { logger, resolver });
+    assert!(result.errors.is_none(), "errors: {:?}", result.errors);
+
+    let statuses = match result.data {
+        Some(q::Value::Object(mut data)) => data.remove("indexingStatuses"),
+        data => panic!("unexpected query result: {:?}", data),
+    };
+    match statuses {
+        Some(q::Value::List(statuses)) => statuses
+            .into_iter()
+            .map(|status| match status {
+                q::Value::Object(ref fields) => match fields.get("subgraph") {
+                    Some(q::Value::String(id)) => (id.clone(), status.clone()),
+                    _ => panic!("status without subgraph ID: {:?}", fields),
+                },
+                _ => panic!("unexpected status: {:?}", status),
+            })
+            .collect(),
+        statuses => panic!("unexpected indexing statuses: {:?}", statuses),
+    }
+}
+
+fn healthy_chain() -> ChainHealth {
+    let chain_health = ChainHealth::new();
+    chain_health.set(ChainHeadHealth {
+        healthy: true,
+        problems: vec![],
+        ..ChainHeadHealth::default()
+    });
+    chain_health
+}
+
+#[test]
+fn resolves_indexing_statuses_of_this_node() {
+    let statuses = indexing_statuses(
+        test_store(),
+        healthy_chain(),
+        "{
+          indexingStatuses {
+            subgraph name status paused synced health node network entityCount
+            fatalError { message deterministic }
+            latestBlock { hash number }
+            chainHeadBlock { number }
+          }
+        }",
+    );
+
+    assert_eq!(statuses.len(), 2);
+    assert_eq!(
+        statuses["synced"],
+        object_value(vec![
+            ("subgraph", q::Value::String("synced".to_owned())),
+            ("name", q::Value::String("name1".to_owned())),
+            ("status", q::Value::Enum("current".to_owned())),
+            ("paused", q::Value::Boolean(false)),
+            ("synced", q::Value::Boolean(true)),
+            ("health", q::Value::Enum("healthy".to_owned())),
+            ("node", q::Value::String("thisnode".to_owned())),
+            ("network", q::Value::String("mainnet".to_owned())),
+            ("entityCount", q::Value::String("1".to_owned())),
+            ("fatalError", q::Value::Null),
+            (
+                "latestBlock",
+                object_value(vec![
+                    ("hash", q::Value::String(format!("{:?}", H256::from(6)))),
+                    ("number", q::Value::String("5".to_owned())),
+                ]),
+            ),
+            ("chainHeadBlock", q::Value::Null),
+        ])
+    );
+    assert_eq!(
+        statuses["failed"],
+        object_value(vec![
+            ("subgraph", q::Value::String("failed".to_owned())),
+            ("name", q::Value::String("name2".to_owned())),
+            ("status", q::Value::Enum("current".to_owned())),
+            ("paused", q::Value::Boolean(false)),
+            ("synced", q::Value::Boolean(false)),
+            ("health", q::Value::Enum("failed".to_owned())),
+            ("node", q::Value::String("thisnode".to_owned())),
+            ("network", q::Value::String("mainnet".to_owned())),
+            ("entityCount", q::Value::String("0".to_owned())),
+            (
+                "fatalError",
+                object_value(vec![
+                    ("message", q::Value::String("mapping aborted".to_owned())),
+                    ("deterministic", q::Value::Boolean(true)),
+                ]),
+            ),
+            ("latestBlock", q::Value::Null),
+            ("chainHeadBlock", q::Value::Null),
+        ])
+    );
+}
+
+#[test]
+fn filters_indexing_statuses_and_reports_unhealthy_chain() {
+    let statuses = indexing_statuses(
+        test_store(),
+        ChainHealth::new(),
+        "{ indexingStatuses(subgraphs: [\"synced\", \"elsewhere\"]) { subgraph health } }",
+    );
+
+    assert_eq!(
+        statuses.into_iter().collect::<Vec<_>>(),
+        vec![(
+            "synced".to_owned(),
+            object_value(vec![
+                ("subgraph", q::Value::String("synced".to_owned())),
+                ("health", q::Value::Enum("unhealthy".to_owned())),
+            ])
+        )]
+    );
+}
@@ -4,6 +4,7 @@ extern crate serde;
 
 use graph::prelude::{JsonRpcServer as JsonRpcServerTrait, *};
 use graph::serde_json;
+use graph::web3::types::H256;
 use jsonrpc_http_server::{
     jsonrpc_core::{self, Compatibility, IoHandler, Params, Value},
     RestApi, Server, ServerBuilder,
@@ -17,6 +18,12 @@ use std::{fmt, io};
 const JSON_RPC_DEPLOY_ERROR: i64 = 0;
 const JSON_RPC_REMOVE_ERROR: i64 = 1;
 const JSON_RPC_INTERNAL_ERROR: i64 = 3;
+const JSON_RPC_CHECK_BLOCKS_ERROR: i64 = 4;
+const JSON_RPC_ROLLBACK_ERROR: i64 = 5;
+const JSON_RPC_REASSIGN_ERROR: i64 = 6;
+const JSON_RPC_PAUSE_ERROR: i64 = 7;
+const JSON_RPC_RESUME_ERROR: i64 = 8;
+const JSON_RPC_REWIND_ERROR: i64 = 9;
 
 #[derive(Debug, Deserialize)]
 struct SubgraphDeployParams {
@@ -42,15 +49,88 @@ impl fmt::Display for SubgraphRemoveParams {
     }
 }
 
-pub struct JsonRpcServer<P> {
+#[derive(Debug, Deserialize)]
+struct SubgraphReassignParams {
+    name: SubgraphDeploymentName,
+    node_id: NodeId,
+}
+
+impl fmt::Display for SubgraphReassignParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphPauseParams {
+    name: SubgraphDeploymentName,
+}
+
+impl fmt::Display for SubgraphPauseParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphRewindParams {
+    name: SubgraphDeploymentName,
+    block_hash: H256,
+}
+
+impl fmt::Display for SubgraphRewindParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphVersionsParams {
+    name: SubgraphDeploymentName,
+}
+
+impl fmt::Display for SubgraphVersionsParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct SubgraphRollbackParams {
+    name: SubgraphDeploymentName,
+    ipfs_hash: SubgraphId,
+}
+
+impl fmt::Display for SubgraphRollbackParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChainCheckBlocksParams {
+    from: u64,
+    to: u64,
+}
+
+impl fmt::Display for ChainCheckBlocksParams {
+    fn fmt(&self, f: &mut fmt::Formatter) -> Result<(), fmt::Error> {
+        write!(f, "{:?}", self)
+    }
+}
+
+pub struct JsonRpcServer<P, C> {
     provider: Arc<P>,
+    block_checker: Arc<C>,
+    chain_health: ChainHealth,
     logger: Logger,
     node_id: NodeId,
 }
 
-impl<P> JsonRpcServer<P>
+impl<P, C> JsonRpcServer<P, C>
 where
     P: SubgraphProviderWithNames,
+    C: BlockIntegrityChecker,
 {
     /// Handler for the `subgraph_deploy` endpoint.
     fn deploy_handler(
@@ -69,13 +149,26 @@ where
         Box::new(
             self.provider
                 .deploy(params.name, params.ipfs_hash, node_id)
-                .map_err(move |e| {
-                    if let SubgraphProviderError::Unknown(e) = e {
-                        error!(logger, "subgraph_deploy failed: {}", e);
-                        json_rpc_error(JSON_RPC_DEPLOY_ERROR, "internal error".to_owned())
-                    } else {
-                        json_rpc_error(JSON_RPC_DEPLOY_ERROR, e.to_string())
+                .map_err(move |e| match e {
+                    SubgraphProviderError::ManifestValidationErrors(ref errors) => {
+                        // Include every problem individually so tools can list them
+                        let data = errors
+                            .0
+                            .iter()
+                            .map(|error| Value::String(error.to_string()))
+                            .collect();
+                        json_rpc_error_with_data(
+                            JSON_RPC_DEPLOY_ERROR,
+                            e.to_string(),
+                            Value::Array(data),
+                        )
                     }
+                    e => provider_error_to_json_rpc(
+                        &logger,
+                        "subgraph_deploy",
+                        JSON_RPC_DEPLOY_ERROR,
+                        e,
+                    ),
                 })
                 .map(move |_| routes),
         )
@@ -94,12 +187,7 @@ where
             self.provider
                 .remove(params.name)
                 .map_err(move |e| {
-                    if let SubgraphProviderError::Unknown(e) = e {
-                        error!(logger, "subgraph_remove failed: {}", e);
-                        json_rpc_error(JSON_RPC_REMOVE_ERROR, "internal error".to_owned())
-                    } else {
-                        json_rpc_error(JSON_RPC_REMOVE_ERROR, e.to_string())
-                    }
+                    provider_error_to_json_rpc(&logger, "subgraph_remove", JSON_RPC_REMOVE_ERROR, e)
                 })
                 .map(|_| Ok(Value::Null))
                 .flatten(),
@@ -126,11 +214,181 @@ where
 
         Ok(Value::from(serde_json::Map::from_iter(list)))
     }
+
+    /// Handler for the `subgraph_reassign` endpoint.
+    ///
+    /// Moves all versions of a subgraph name to a different indexing node.
+    fn reassign_handler(
+        &self,
+        params: SubgraphReassignParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_reassign request"; "params" => params.to_string());
+
+        Box::new(
+            self.provider
+                .reassign(params.name, params.node_id)
+                .map_err(move |e| {
+                    provider_error_to_json_rpc(
+                        &logger,
+                        "subgraph_reassign",
+                        JSON_RPC_REASSIGN_ERROR,
+                        e,
+                    )
+                })
+                .map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `subgraph_pause` endpoint.
+    fn pause_handler(
+        &self,
+        params: SubgraphPauseParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_pause request"; "params" => params.to_string());
+
+        Box::new(
+            self.provider
+                .pause(params.name)
+                .map_err(move |e| {
+                    provider_error_to_json_rpc(&logger, "subgraph_pause", JSON_RPC_PAUSE_ERROR, e)
+                })
+                .map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `subgraph_resume` endpoint.
+    fn resume_handler(
+        &self,
+        params: SubgraphPauseParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_resume request"; "params" => params.to_string());
+
+        Box::new(
+            self.provider
+                .resume(params.name)
+                .map_err(move |e| {
+                    provider_error_to_json_rpc(&logger, "subgraph_resume", JSON_RPC_RESUME_ERROR, e)
+                })
+                .map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `subgraph_rewind` endpoint.
+    ///
+    /// Reverts the current version of a subgraph name back to an earlier block and restarts
+    /// indexing from there.
+    fn rewind_handler(
+        &self,
+        params: SubgraphRewindParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_rewind request"; "params" => params.to_string());
+
+        Box::new(
+            self.provider
+                .rewind(params.name, params.block_hash)
+                .map_err(move |e| {
+                    provider_error_to_json_rpc(&logger, "subgraph_rewind", JSON_RPC_REWIND_ERROR, e)
+                })
+                .map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `subgraph_versions` endpoint.
+    ///
+    /// Returns all versions deployed under a subgraph name, most recently deployed first.
+    fn versions_handler(
+        &self,
+        params: SubgraphVersionsParams,
+    ) -> Result<Value, jsonrpc_core::Error> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_versions request"; "params" => params.to_string());
+
+        let versions = self
+            .provider
+            .versions(params.name)
+            .map_err(move |e| {
+                error!(logger, "Failed to list subgraph versions: {}", e);
+                json_rpc_error(JSON_RPC_INTERNAL_ERROR, "database error".to_owned())
+            })?
+            .into_iter()
+            .map(subgraph_version_to_value)
+            .collect();
+
+        Ok(Value::Array(versions))
+    }
+
+    /// Handler for the `subgraph_rollback` endpoint.
+    ///
+    /// Makes a previously deployed version the current version of a subgraph name.
+    fn rollback_handler(
+        &self,
+        params: SubgraphRollbackParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received subgraph_rollback request"; "params" => params.to_string());
+
+        Box::new(
+            self.provider
+                .rollback(params.name, params.ipfs_hash)
+                .map_err(move |e| {
+                    provider_error_to_json_rpc(
+                        &logger,
+                        "subgraph_rollback",
+                        JSON_RPC_ROLLBACK_ERROR,
+                        e,
+                    )
+                })
+                .map(|_| Value::Null),
+        )
+    }
+
+    /// Handler for the `chain_check_blocks` endpoint.
+    ///
+    /// Checks the blocks in the given range of the chain store, repairs them where
+    /// necessary and returns a summary of the problems found.
+    fn check_blocks_handler(
+        &self,
+        params: ChainCheckBlocksParams,
+    ) -> Box<Future<Item = Value, Error = jsonrpc_core::Error> + Send> {
+        let logger = self.logger.clone();
+
+        info!(logger, "Received chain_check_blocks request"; "params" => params.to_string());
+
+        Box::new(
+            self.block_checker
+                .check_blocks(&logger, params.from, params.to)
+                .map_err(move |e| {
+                    error!(logger, "chain_check_blocks failed: {}", e);
+                    json_rpc_error(JSON_RPC_CHECK_BLOCKS_ERROR, e.to_string())
+                })
+                .map(block_integrity_report_to_value),
+        )
+    }
+
+    /// Handler for the `chain_health` endpoint.
+    ///
+    /// Returns the result of the most recent chain head health check.
+    fn chain_health_handler(&self) -> Result<Value, jsonrpc_core::Error> {
+        debug!(self.logger, "Received chain_health request");
+
+        Ok(chain_head_health_to_value(self.chain_health.get()))
+    }
 }
 
-impl<P> JsonRpcServerTrait<P> for JsonRpcServer<P>
+impl<P, C> JsonRpcServerTrait<P, C> for JsonRpcServer<P, C>
 where
     P: SubgraphProviderWithNames,
+    C: BlockIntegrityChecker,
 {
     type Server = Server;
 
@@ -139,6 +397,8 @@ where
         http_port: u16,
         ws_port: u16,
         provider: Arc<P>,
+        block_checker: Arc<C>,
+        chain_health: ChainHealth,
         node_id: NodeId,
         logger: Logger,
     ) -> Result<Self::Server, io::Error> {
@@ -155,6 +415,8 @@ where
 
         let arc_self = Arc::new(JsonRpcServer {
             provider,
+            block_checker,
+            chain_health,
             node_id,
             logger,
         });
@@ -182,6 +444,80 @@ where
         let me = arc_self.clone();
         handler.add_method("subgraph_list", move |_| me.list_handler());
 
+        // `subgraph_reassign` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_reassign", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.reassign_handler(params))
+        });
+
+        // `subgraph_pause` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_pause", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.pause_handler(params))
+        });
+
+        // `subgraph_resume` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_resume", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.resume_handler(params))
+        });
+
+        // `subgraph_rewind` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_rewind", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.rewind_handler(params))
+        });
+
+        // `subgraph_versions` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_versions", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.versions_handler(params))
+        });
+
+        // `subgraph_rollback` handler.
+        let me = arc_self.clone();
+        handler.add_method("subgraph_rollback", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.rollback_handler(params))
+        });
+
+        // `chain_check_blocks` handler.
+        let me = arc_self.clone();
+        handler.add_method("chain_check_blocks", move |params: Params| {
+            let me = me.clone();
+            params
+                .parse()
+                .into_future()
+                .and_then(move |params| me.check_blocks_handler(params))
+        });
+
+        // `chain_health` handler.
+        let me = arc_self.clone();
+        handler.add_method("chain_health", move |_| me.chain_health_handler());
+
         ServerBuilder::new(handler)
             // Enable REST API:
             // POST /<method>/<param1>/<param2>
@@ -190,6 +526,23 @@ where
     }
 }
 
+/// Converts a subgraph provider error into a JSON-RPC error for `method`.
+///
+/// The details of unknown errors are logged but not returned to the client.
+fn provider_error_to_json_rpc(
+    logger: &Logger,
+    method: &str,
+    code: i64,
+    e: SubgraphProviderError,
+) -> jsonrpc_core::Error {
+    if let SubgraphProviderError::Unknown(e) = e {
+        error!(logger, "{} failed: {}", method, e);
+        json_rpc_error(code, "internal error".to_owned())
+    } else {
+        json_rpc_error(code, e.to_string())
+    }
+}
+
 fn json_rpc_error(code: i64, message: String) -> jsonrpc_core::Error {
     jsonrpc_core::Error {
         code: jsonrpc_core::ErrorCode::ServerError(code),
@@ -198,6 +551,14 @@ fn json_rpc_error(code: i64, message: String) -> jsonrpc_core::Error {
     }
 }
 
+fn json_rpc_error_with_data(code: i64, message: String, data: Value) -> jsonrpc_core::Error {
+    jsonrpc_core::Error {
+        code: jsonrpc_core::ErrorCode::ServerError(code),
+        message,
+        data: Some(data),
+    }
+}
+
 pub fn parse_response(response: Value) -> Result<(), jsonrpc_core::Error> {
     // serde deserialization of the `id` field to an `Id` struct is somehow
     // incompatible with the `arbitrary-precision` feature which we use, so we
@@ -217,3 +578,88 @@ fn subgraph_routes(name: &SubgraphDeploymentName, http_port: u16, ws_port: u16)
     map.insert("subscriptions", format!(":{}/name/{}", ws_port, name));
     jsonrpc_core::to_value(map).unwrap()
 }
+
+fn subgraph_version_to_value(version: SubgraphVersion) -> Value {
+    let mut map = BTreeMap::new();
+    map.insert("name", Value::from(version.name.to_string()));
+    map.insert("ipfs_hash", Value::from(version.subgraph_id.to_string()));
+    map.insert("node_id", Value::from(version.node_id.to_string()));
+    map.insert("status", Value::from(version.status.as_str()));
+    map.insert("paused", Value::from(version.paused));
+    map.insert("created_at", Value::from(version.created_at));
+    jsonrpc_core::to_value(map).unwrap()
+}
+
+fn block_integrity_report_to_value(report: BlockIntegrityReport) -> Value {
+    let block_ptrs_to_value = |block_ptrs: Vec<EthereumBlockPointer>| {
+        Value::Array(
+            block_ptrs
+                .into_iter()
+                .map(|block_ptr| {
+                    let mut map = BTreeMap::new();
+                    map.insert("number", Value::from(block_ptr.number));
+                    map.insert("hash", Value::from(format!("{:?}", block_ptr.hash)));
+                    jsonrpc_core::to_value(map).unwrap()
+                })
+                .collect(),
+        )
+    };
+
+    let mut map = BTreeMap::new();
+    map.insert("from", Value::from(report.from));
+    map.insert("to", Value::from(report.to));
+    map.insert("missing_blocks", block_ptrs_to_value(report.missing_blocks));
+    map.insert(
+        "parent_hash_mismatches",
+        block_ptrs_to_value(report.parent_hash_mismatches),
+    );
+    map.insert(
+        "non_canonical_blocks",
+        block_ptrs_to_value(report.non_canonical_blocks),
+    );
+    map.insert("repaired_blocks", Value::from(report.repaired_blocks));
+    jsonrpc_core::to_value(map).unwrap()
+}
+
+fn chain_head_health_to_value(health: ChainHeadHealth) -> Value {
+    let mut map = BTreeMap::new();
+    map.insert("healthy", Value::from(health.healthy));
+    map.insert(
+        "head_block_number",
+        health
+            .head_block
+            .map(|head_block| Value::from(head_block.number))
+            .unwrap_or(Value::Null),
+    );
+    map.insert(
+        "head_block_hash",
+        health
+            .head_block
+            .map(|head_block| Value::from(format!("{:?}", head_block.hash)))
+            .unwrap_or(Value::Null),
+    );
+    map.insert(
+        "head_block_age_secs",
+        health
+            .head_block_age
+            .map(|age| Value::from(age.as_secs()))
+            .unwrap_or(Value::Null),
+    );
+    map.insert(
+        "latest_block_number",
+        health
+            .latest_block_number
+            .map(Value::from)
+            .unwrap_or(Value::Null),
+    );
+    map.insert(
+        "head_block_lag",
+        health
+            .head_block_lag
+            .map(Value::from)
+            .unwrap_or(Value::Null),
+    );
+    map.insert("unhealthy_checks", Value::from(health.unhealthy_checks));
+    map.insert("problems", Value::from(health.problems));
+    jsonrpc_core::to_value(map).unwrap()
+}
@@ -0,0 +1,65 @@
+-- Only the current version of each name survives, and each subgraph keeps only one
+-- of its names
+DELETE FROM subgraph_deployments WHERE status <> 'current';
+DELETE FROM subgraph_deployments a
+    USING subgraph_deployments b
+    WHERE a.subgraph_id = b.subgraph_id AND a.deployment_name > b.deployment_name;
+
+DROP INDEX subgraph_deployments_current_version;
+DROP INDEX subgraph_deployments_pending_version;
+
+ALTER TABLE subgraph_deployments DROP COLUMN created_at;
+ALTER TABLE subgraph_deployments DROP COLUMN status;
+
+ALTER TABLE subgraph_deployments DROP CONSTRAINT subgraph_deployments_pkey;
+ALTER TABLE subgraph_deployments ADD PRIMARY KEY (deployment_name);
+ALTER TABLE subgraph_deployments
+    ADD CONSTRAINT subgraph_deployments_subgraph_id_key UNIQUE (subgraph_id);
+
+CREATE OR REPLACE FUNCTION deployment_insert()
+    RETURNS trigger AS
+$$
+BEGIN
+    PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+        'type', 'Add',
+        'deployment_name', NEW.deployment_name,
+        'subgraph_id', NEW.subgraph_id,
+        'node_id', NEW.node_id
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_update()
+    RETURNS trigger AS
+$$
+BEGIN
+    PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+        'type', 'Remove',
+        'deployment_name', OLD.deployment_name,
+        'subgraph_id', OLD.subgraph_id,
+        'node_id', OLD.node_id
+    )::text);
+    PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+        'type', 'Add',
+        'deployment_name', NEW.deployment_name,
+        'subgraph_id', NEW.subgraph_id,
+        'node_id', NEW.node_id
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_delete()
+    RETURNS trigger AS
+$$
+BEGIN
+    PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+        'type', 'Remove',
+        'deployment_name', OLD.deployment_name,
+        'subgraph_id', OLD.subgraph_id,
+        'node_id', OLD.node_id
+    )::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
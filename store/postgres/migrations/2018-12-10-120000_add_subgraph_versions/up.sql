@@ -0,0 +1,89 @@
+-- Allow several subgraph versions per name. Each name has at most one current
+-- version, which queries are served from, and at most one pending version,
+-- which replaces the current version once it has caught up with the chain
+-- head. Retired versions are kept so that they can be rolled back to. The same
+-- subgraph may be deployed under several names.
+ALTER TABLE subgraph_deployments DROP CONSTRAINT subgraph_deployments_pkey;
+ALTER TABLE subgraph_deployments ADD PRIMARY KEY (deployment_name, subgraph_id);
+ALTER TABLE subgraph_deployments DROP CONSTRAINT subgraph_deployments_subgraph_id_key;
+
+ALTER TABLE subgraph_deployments
+    ADD COLUMN status VARCHAR NOT NULL DEFAULT 'current'
+    CHECK (status IN ('current', 'pending', 'retired'));
+ALTER TABLE subgraph_deployments
+    ADD COLUMN created_at BIGINT NOT NULL DEFAULT extract(epoch FROM now())::BIGINT;
+
+CREATE UNIQUE INDEX subgraph_deployments_current_version
+    ON subgraph_deployments (deployment_name)
+    WHERE status = 'current';
+CREATE UNIQUE INDEX subgraph_deployments_pending_version
+    ON subgraph_deployments (deployment_name)
+    WHERE status = 'pending';
+
+-- Current and pending versions are indexed, retired versions are not
+CREATE OR REPLACE FUNCTION deployment_insert()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF NEW.status <> 'retired' THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Promoting a pending version to the current version does not affect indexing,
+-- so only notify about versions that are retired, un-retired or moved to a
+-- different node
+CREATE OR REPLACE FUNCTION deployment_update()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF OLD.status <> 'retired' AND (
+        NEW.status = 'retired' OR
+        OLD.node_id <> NEW.node_id OR
+        OLD.subgraph_id <> NEW.subgraph_id
+    ) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    IF NEW.status <> 'retired' AND (
+        OLD.status = 'retired' OR
+        OLD.node_id <> NEW.node_id OR
+        OLD.subgraph_id <> NEW.subgraph_id
+    ) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_delete()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF OLD.status <> 'retired' THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
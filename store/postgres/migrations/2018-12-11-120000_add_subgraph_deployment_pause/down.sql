@@ -0,0 +1,65 @@
+CREATE OR REPLACE FUNCTION deployment_insert()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF NEW.status <> 'retired' THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_update()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF OLD.status <> 'retired' AND (
+        NEW.status = 'retired' OR
+        OLD.node_id <> NEW.node_id OR
+        OLD.subgraph_id <> NEW.subgraph_id
+    ) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    IF NEW.status <> 'retired' AND (
+        OLD.status = 'retired' OR
+        OLD.node_id <> NEW.node_id OR
+        OLD.subgraph_id <> NEW.subgraph_id
+    ) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_delete()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF OLD.status <> 'retired' THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+ALTER TABLE subgraph_deployments DROP COLUMN paused;
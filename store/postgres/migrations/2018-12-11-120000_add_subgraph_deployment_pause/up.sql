@@ -0,0 +1,64 @@
+-- Paused subgraph versions are not indexed but can still be queried
+ALTER TABLE subgraph_deployments ADD COLUMN paused BOOLEAN NOT NULL DEFAULT false;
+
+CREATE OR REPLACE FUNCTION deployment_insert()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF NEW.status <> 'retired' AND NOT NEW.paused THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+-- Notify about versions that start or stop being indexed or that are moved to
+-- a different node
+CREATE OR REPLACE FUNCTION deployment_update()
+    RETURNS trigger AS
+$$
+DECLARE
+    old_indexed BOOLEAN := OLD.status <> 'retired' AND NOT OLD.paused;
+    new_indexed BOOLEAN := NEW.status <> 'retired' AND NOT NEW.paused;
+    moved BOOLEAN := OLD.node_id <> NEW.node_id OR OLD.subgraph_id <> NEW.subgraph_id;
+BEGIN
+    IF old_indexed AND (NOT new_indexed OR moved) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    IF new_indexed AND (NOT old_indexed OR moved) THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', NEW.node_id), json_build_object(
+            'type', 'Add',
+            'deployment_name', NEW.deployment_name,
+            'subgraph_id', NEW.subgraph_id,
+            'node_id', NEW.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE OR REPLACE FUNCTION deployment_delete()
+    RETURNS trigger AS
+$$
+BEGIN
+    IF OLD.status <> 'retired' AND NOT OLD.paused THEN
+        PERFORM pg_notify(CONCAT('subgraph_deployments_', OLD.node_id), json_build_object(
+            'type', 'Remove',
+            'deployment_name', OLD.deployment_name,
+            'subgraph_id', OLD.subgraph_id,
+            'node_id', OLD.node_id
+        )::text);
+    END IF;
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
@@ -40,9 +40,12 @@ allow_tables_to_appear_in_same_query!(entities, subgraphs);
 joinable!(entities -> subgraphs (subgraph));
 
 table! {
-    subgraph_deployments (deployment_name) {
+    subgraph_deployments (deployment_name, subgraph_id) {
         deployment_name -> Varchar,
         subgraph_id -> Varchar,
         node_id -> Varchar,
+        status -> Varchar,
+        created_at -> BigInt,
+        paused -> Bool,
     }
 }
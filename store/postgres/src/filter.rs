@@ -9,7 +9,7 @@ use std::str::FromStr;
 
 use graph::components::store::EntityFilter;
 use graph::data::store::*;
-use graph::prelude::BigInt;
+use graph::prelude::{BigDecimal as GraphBigDecimal, BigInt};
 use graph::serde_json;
 
 use db_schema::entities;
@@ -89,6 +89,18 @@ impl IntoFilter for BigInt {
     }
 }
 
+impl IntoFilter for GraphBigDecimal {
+    fn into_filter(self, attribute: String, op: &str) -> FilterExpression {
+        Box::new(
+            sql("(data -> ")
+                .bind::<Text, _>(attribute)
+                .sql("->> 'data')::numeric")
+                .sql(op)
+                .bind::<Numeric, _>(BigDecimal::from_str(&self.to_string()).unwrap()),
+        ) as FilterExpression
+    }
+}
+
 trait IntoArrayFilter<T>
 where
     T: 'static,
@@ -171,7 +183,8 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 | Value::Float(_)
                 | Value::Int(_)
                 | Value::Bool(_)
-                | Value::BigInt(_) => {
+                | Value::BigInt(_)
+                | Value::BigDecimal(_) => {
                     return Err(UnsupportedFilter {
                         filter: if contains { "contains" } else { "not_contains" }.to_owned(),
                         value,
@@ -189,6 +202,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
 
             match value {
                 Value::BigInt(n) => Ok(n.into_filter(attribute, op)),
+                Value::BigDecimal(n) => Ok(n.into_filter(attribute, op)),
                 Value::Bool(b) => Ok(b.into_filter(attribute, op)),
                 Value::Bytes(b) => Ok(b.to_string().into_filter(attribute, op)),
                 Value::Float(n) => Ok(n.into_filter(attribute, op)),
@@ -217,6 +231,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
 
             match value {
                 Value::BigInt(n) => Ok(n.into_filter(attribute, op)),
+                Value::BigDecimal(n) => Ok(n.into_filter(attribute, op)),
                 Value::Float(n) => Ok(n.into_filter(attribute, op)),
                 Value::Int(n) => Ok(n.into_filter(attribute, op)),
                 Value::String(s) => Ok(s.into_filter(attribute, op)),
@@ -236,11 +251,8 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
             let op = " = ANY ";
 
             match values[0] {
-                Value::BigInt(_) => Ok(SqlValue::new_array(values).into_array_filter::<Numeric>(
-                    attribute,
-                    op,
-                    "::numeric",
-                )),
+                Value::BigInt(_) | Value::BigDecimal(_) => Ok(SqlValue::new_array(values)
+                    .into_array_filter::<Numeric>(attribute, op, "::numeric")),
                 Value::Bool(_) => Ok(SqlValue::new_array(values).into_array_filter::<Bool>(
                     attribute,
                     op,
@@ -287,6 +299,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 Value::String(s) => Ok(format!("{}%", s).into_filter(attribute, op)),
                 Value::Bool(_)
                 | Value::BigInt(_)
+                | Value::BigDecimal(_)
                 | Value::Bytes(_)
                 | Value::Float(_)
                 | Value::Int(_)
@@ -316,6 +329,7 @@ fn build_filter(filter: EntityFilter) -> Result<FilterExpression, UnsupportedFil
                 Value::String(s) => Ok(format!("%{}", s).into_filter(attribute, op)),
                 Value::Bool(_)
                 | Value::BigInt(_)
+                | Value::BigDecimal(_)
                 | Value::Bytes(_)
                 | Value::Float(_)
                 | Value::Int(_)
@@ -67,7 +67,11 @@ impl ToSql<Numeric, Pg> for SqlValue {
                 &BigDecimal::from_str(&number.to_string()).unwrap(),
                 out,
             ),
-            _ => panic!("Failed to convert attribute value to bigint in SQL"),
+            Value::BigDecimal(ref number) => <BigDecimal as ToSql<Numeric, Pg>>::to_sql(
+                &BigDecimal::from_str(&number.to_string()).unwrap(),
+                out,
+            ),
+            _ => panic!("Failed to convert attribute value to numeric in SQL"),
         }
     }
 }
@@ -4,7 +4,7 @@ use diesel::pg::PgConnection;
 use diesel::prelude::*;
 use diesel::r2d2::{self, ConnectionManager, Pool};
 use diesel::sql_types::Text;
-use diesel::{delete, insert_into, select, update};
+use diesel::{delete, insert_into, select, sql_query, update};
 use filter::store_filter;
 use futures::sync::mpsc::{channel, Sender};
 use lru_time_cache::LruCache;
@@ -15,7 +15,8 @@ use uuid::Uuid;
 
 use graph::components::store::Store as StoreTrait;
 use graph::data::subgraph::schema::{
-    SubgraphManifestEntity, MANIFEST_ENTITY_TYPENAME, SUBGRAPHS_ID,
+    SubgraphEntity, SubgraphManifestEntity, MANIFEST_ENTITY_TYPENAME, SUBGRAPHS_ID,
+    SUBGRAPH_ENTITY_TYPENAME,
 };
 use graph::prelude::*;
 use graph::serde_json;
@@ -514,19 +515,30 @@ impl StoreTrait for Store {
     }
 
     fn block_ptr(&self, subgraph_id: SubgraphId) -> Result<EthereumBlockPointer, Error> {
+        self.find_block_ptr(subgraph_id.clone())?
+            .ok_or_else(|| format_err!("subgraph {} not found in store", subgraph_id))
+    }
+
+    fn find_block_ptr(
+        &self,
+        subgraph_id: SubgraphId,
+    ) -> Result<Option<EthereumBlockPointer>, Error> {
         use db_schema::subgraphs::dsl::*;
 
         subgraphs
             .select((latest_block_hash, latest_block_number))
             .filter(id.eq(subgraph_id.to_string()))
             .first::<(String, i64)>(&*self.conn.get()?)
-            .map(|(hash, number)| {
-                (
-                    hash.parse()
-                        .expect("subgraph block ptr hash in database should be a valid H256"),
-                    number,
-                )
-                    .into()
+            .optional()
+            .map(|row| {
+                row.map(|(hash, number)| {
+                    (
+                        hash.parse()
+                            .expect("subgraph block ptr hash in database should be a valid H256"),
+                        number,
+                    )
+                        .into()
+                })
             })
             .map_err(Error::from)
     }
@@ -568,6 +580,7 @@ impl StoreTrait for Store {
                 .unwrap_or("ASC");
             let cast_type = match value_type {
                 ValueType::BigInt => "::numeric",
+                ValueType::BigDecimal => "::numeric",
                 ValueType::Boolean => "::boolean",
                 ValueType::Bytes => "",
                 ValueType::Float => "::float",
@@ -672,15 +685,28 @@ impl StoreTrait for Store {
             panic!("revert_block_operations must revert a single block only");
         }
 
-        select(revert_block(
-            &block_ptr_from.hash_hex(),
-            block_ptr_from.number as i64,
-            &block_ptr_to.hash_hex(),
-            subgraph_id.to_string(),
-        ))
-        .execute(&*self.conn.get()?)
-        .map_err(|e| format_err!("Error reverting block: {}", e))
-        .map(|_| ())
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            // Moving the block pointer first fails unless it points to the block being
+            // reverted, and locks it against concurrent updates until the revert is done
+            self.update_subgraph_block_pointer(
+                &*conn,
+                subgraph_id.clone(),
+                block_ptr_from,
+                block_ptr_to,
+            )?;
+
+            select(revert_block(
+                &block_ptr_from.hash_hex(),
+                block_ptr_from.number as i64,
+                &block_ptr_to.hash_hex(),
+                subgraph_id.to_string(),
+            ))
+            .execute(&*conn)
+            .map_err(|e| format_err!("Error reverting block: {}", e))
+            .map(|_| ())
+        })
     }
 
     fn subscribe(&self, entities: Vec<SubgraphEntityPair>) -> EntityChangeStream {
@@ -717,6 +743,59 @@ impl StoreTrait for Store {
             .get_result(&*self.conn.get()?)?;
         Ok(count as u64)
     }
+
+    fn reset_subgraph(
+        &self,
+        subgraph_id: SubgraphId,
+        block_ptr: EthereumBlockPointer,
+    ) -> Result<(), Error> {
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            {
+                use db_schema::entities::dsl::*;
+
+                delete(entities.filter(subgraph.eq(subgraph_id.to_string()))).execute(&*conn)?;
+            }
+
+            // Drop the history as well, so that the removed entities can never be
+            // resurrected by reverting blocks
+            sql_query("DELETE FROM entity_history WHERE subgraph = $1")
+                .bind::<Text, _>(subgraph_id.to_string())
+                .execute(&*conn)?;
+
+            {
+                use db_schema::subgraphs::dsl::*;
+
+                update(subgraphs)
+                    .set((
+                        latest_block_hash.eq(block_ptr.hash_hex()),
+                        latest_block_number.eq(block_ptr.number as i64),
+                    ))
+                    .filter(id.eq(subgraph_id.to_string()))
+                    .execute(&*conn)?;
+            }
+
+            // Reset the progress and failure recorded for the subgraph, if it has been
+            // started before
+            let subgraph_entity = self.get_entity(
+                &*conn,
+                &*SUBGRAPHS_ID,
+                &SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+                &subgraph_id.to_string(),
+            )?;
+            if subgraph_entity.is_some() {
+                self.apply_entity_operations_with_conn(
+                    &*conn,
+                    SubgraphEntity::reset_operations(&subgraph_id),
+                    EventSource::None,
+                )?;
+            }
+
+            Ok(())
+        })
+        .map_err(|e: Error| format_err!("Error resetting subgraph {}: {}", subgraph_id, e))
+    }
 }
 
 impl SubgraphDeploymentStore for Store {
@@ -732,6 +811,7 @@ impl SubgraphDeploymentStore for Store {
                 subgraph_deployments::subgraph_id,
             ))
             .filter(subgraph_deployments::node_id.eq(node_id.to_string()))
+            .filter(subgraph_deployments::status.ne(SubgraphVersionStatus::Retired.as_str()))
             .load::<(String, String)>(&*self.conn.get()?)
             .map_err(Error::from)
             .map(|rows| {
@@ -755,21 +835,84 @@ impl SubgraphDeploymentStore for Store {
     ) -> Result<(), Error> {
         use db_schema::subgraph_deployments;
 
-        insert_into(subgraph_deployments::table)
-            .values((
-                subgraph_deployments::deployment_name.eq(name.to_string()),
-                subgraph_deployments::subgraph_id.eq(subgraph_id.to_string()),
-                subgraph_deployments::node_id.eq(node_id.to_string()),
-            ))
-            .on_conflict(subgraph_deployments::deployment_name)
-            .do_update()
-            .set((
-                subgraph_deployments::subgraph_id.eq(subgraph_id.to_string()),
-                subgraph_deployments::node_id.eq(node_id.to_string()),
-            ))
-            .execute(&*self.conn.get()?)
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            let existing_status = subgraph_deployments::table
+                .find((name.to_string(), subgraph_id.to_string()))
+                .select(subgraph_deployments::status)
+                .first::<String>(&*conn)
+                .optional()?;
+
+            let status = match existing_status {
+                // Redeploying the current or pending version keeps its status
+                Some(status) => match status.parse()? {
+                    SubgraphVersionStatus::Retired => SubgraphVersionStatus::Pending,
+                    status => status,
+                },
+
+                None => {
+                    let current_count: i64 = subgraph_deployments::table
+                        .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+                        .filter(
+                            subgraph_deployments::status
+                                .eq(SubgraphVersionStatus::Current.as_str()),
+                        )
+                        .count()
+                        .get_result(&*conn)?;
+
+                    if current_count == 0 {
+                        SubgraphVersionStatus::Current
+                    } else {
+                        SubgraphVersionStatus::Pending
+                    }
+                }
+            };
+
+            // A new pending version replaces the previous one
+            if status == SubgraphVersionStatus::Pending {
+                update(
+                    subgraph_deployments::table
+                        .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+                        .filter(subgraph_deployments::subgraph_id.ne(subgraph_id.to_string()))
+                        .filter(
+                            subgraph_deployments::status
+                                .eq(SubgraphVersionStatus::Pending.as_str()),
+                        ),
+                )
+                .set(subgraph_deployments::status.eq(SubgraphVersionStatus::Retired.as_str()))
+                .execute(&*conn)?;
+            }
+
+            insert_into(subgraph_deployments::table)
+                .values((
+                    subgraph_deployments::deployment_name.eq(name.to_string()),
+                    subgraph_deployments::subgraph_id.eq(subgraph_id.to_string()),
+                    subgraph_deployments::node_id.eq(node_id.to_string()),
+                    subgraph_deployments::status.eq(status.as_str()),
+                ))
+                .on_conflict((
+                    subgraph_deployments::deployment_name,
+                    subgraph_deployments::subgraph_id,
+                ))
+                .do_update()
+                .set((
+                    subgraph_deployments::node_id.eq(node_id.to_string()),
+                    subgraph_deployments::status.eq(status.as_str()),
+                ))
+                .execute(&*conn)?;
+
+            // A subgraph deployed under several names is indexed by a single node
+            update(
+                subgraph_deployments::table
+                    .filter(subgraph_deployments::subgraph_id.eq(subgraph_id.to_string()))
+                    .filter(subgraph_deployments::node_id.ne(node_id.to_string())),
+            )
+            .set(subgraph_deployments::node_id.eq(node_id.to_string()))
+            .execute(&*conn)
             .map_err(Error::from)
             .map(|_| ())
+        })
     }
 
     fn read(&self, name: SubgraphDeploymentName) -> Result<Option<(SubgraphId, NodeId)>, Error> {
@@ -781,6 +924,7 @@ impl SubgraphDeploymentStore for Store {
                 subgraph_deployments::node_id,
             ))
             .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+            .filter(subgraph_deployments::status.eq(SubgraphVersionStatus::Current.as_str()))
             .first::<(String, String)>(&*self.conn.get()?)
             .optional()
             .map_err(Error::from)
@@ -797,14 +941,182 @@ impl SubgraphDeploymentStore for Store {
     fn remove(&self, name: SubgraphDeploymentName) -> Result<bool, Error> {
         use db_schema::subgraph_deployments;
 
-        delete(subgraph_deployments::table.find(name.to_string()))
-            .execute(&*self.conn.get()?)
-            .map(|row_count| match row_count {
-                0 => false,
-                1 => true,
-                _ => unreachable!(),
+        delete(
+            subgraph_deployments::table
+                .filter(subgraph_deployments::deployment_name.eq(name.to_string())),
+        )
+        .execute(&*self.conn.get()?)
+        .map(|row_count| row_count > 0)
+        .map_err(Error::from)
+    }
+
+    fn read_versions(&self, name: SubgraphDeploymentName) -> Result<Vec<SubgraphVersion>, Error> {
+        use db_schema::subgraph_deployments;
+
+        subgraph_deployments::table
+            .select((
+                subgraph_deployments::subgraph_id,
+                subgraph_deployments::node_id,
+                subgraph_deployments::status,
+                subgraph_deployments::paused,
+                subgraph_deployments::created_at,
+            ))
+            .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+            .order(subgraph_deployments::created_at.desc())
+            .load::<(String, String, String, bool, i64)>(&*self.conn.get()?)?
+            .into_iter()
+            .map(|(subgraph_id, node_id, status, paused, created_at)| {
+                Ok(SubgraphVersion {
+                    name: name.clone(),
+                    subgraph_id: SubgraphId::new(subgraph_id)
+                        .expect("invalid subgraph ID found in database"),
+                    node_id: NodeId::new(node_id).expect("invalid node ID found in database"),
+                    status: status.parse()?,
+                    paused,
+                    created_at: created_at as u64,
+                })
             })
-            .map_err(Error::from)
+            .collect()
+    }
+
+    fn read_versions_by_node_id(&self, node_id: NodeId) -> Result<Vec<SubgraphVersion>, Error> {
+        use db_schema::subgraph_deployments;
+
+        subgraph_deployments::table
+            .select((
+                subgraph_deployments::deployment_name,
+                subgraph_deployments::subgraph_id,
+                subgraph_deployments::status,
+                subgraph_deployments::paused,
+                subgraph_deployments::created_at,
+            ))
+            .filter(subgraph_deployments::node_id.eq(node_id.to_string()))
+            .filter(subgraph_deployments::status.ne(SubgraphVersionStatus::Retired.as_str()))
+            .order(subgraph_deployments::created_at.desc())
+            .load::<(String, String, String, bool, i64)>(&*self.conn.get()?)?
+            .into_iter()
+            .map(|(name, subgraph_id, status, paused, created_at)| {
+                Ok(SubgraphVersion {
+                    name: SubgraphDeploymentName::new(name)
+                        .expect("invalid subgraph name found in database"),
+                    subgraph_id: SubgraphId::new(subgraph_id)
+                        .expect("invalid subgraph ID found in database"),
+                    node_id: node_id.clone(),
+                    status: status.parse()?,
+                    paused,
+                    created_at: created_at as u64,
+                })
+            })
+            .collect()
+    }
+
+    fn promote_pending_version(&self, subgraph_id: &SubgraphId) -> Result<bool, Error> {
+        use db_schema::subgraph_deployments;
+
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            // The subgraph may be the pending version of several names
+            let names = subgraph_deployments::table
+                .select(subgraph_deployments::deployment_name)
+                .filter(subgraph_deployments::subgraph_id.eq(subgraph_id.to_string()))
+                .filter(subgraph_deployments::status.eq(SubgraphVersionStatus::Pending.as_str()))
+                .load::<String>(&*conn)?;
+
+            for name in names.iter() {
+                update(
+                    subgraph_deployments::table
+                        .filter(subgraph_deployments::deployment_name.eq(name))
+                        .filter(
+                            subgraph_deployments::status
+                                .eq(SubgraphVersionStatus::Current.as_str()),
+                        ),
+                )
+                .set(subgraph_deployments::status.eq(SubgraphVersionStatus::Retired.as_str()))
+                .execute(&*conn)?;
+
+                update(subgraph_deployments::table.find((name, subgraph_id.to_string())))
+                    .set(subgraph_deployments::status.eq(SubgraphVersionStatus::Current.as_str()))
+                    .execute(&*conn)?;
+            }
+
+            Ok(!names.is_empty())
+        })
+    }
+
+    fn rollback(
+        &self,
+        name: SubgraphDeploymentName,
+        subgraph_id: SubgraphId,
+    ) -> Result<bool, Error> {
+        use db_schema::subgraph_deployments;
+
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            let version_count: i64 = subgraph_deployments::table
+                .find((name.to_string(), subgraph_id.to_string()))
+                .count()
+                .get_result(&*conn)?;
+
+            if version_count == 0 {
+                return Ok(false);
+            }
+
+            update(
+                subgraph_deployments::table
+                    .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+                    .filter(subgraph_deployments::subgraph_id.ne(subgraph_id.to_string()))
+                    .filter(
+                        subgraph_deployments::status.ne(SubgraphVersionStatus::Retired.as_str()),
+                    ),
+            )
+            .set(subgraph_deployments::status.eq(SubgraphVersionStatus::Retired.as_str()))
+            .execute(&*conn)?;
+
+            update(subgraph_deployments::table.find((name.to_string(), subgraph_id.to_string())))
+                .set(subgraph_deployments::status.eq(SubgraphVersionStatus::Current.as_str()))
+                .execute(&*conn)?;
+
+            Ok(true)
+        })
+    }
+
+    fn reassign(&self, name: SubgraphDeploymentName, node_id: NodeId) -> Result<bool, Error> {
+        use db_schema::subgraph_deployments;
+
+        let conn = self.conn.get()?;
+
+        conn.transaction(|| {
+            let subgraph_ids = subgraph_deployments::table
+                .select(subgraph_deployments::subgraph_id)
+                .filter(subgraph_deployments::deployment_name.eq(name.to_string()))
+                .load::<String>(&*conn)?;
+
+            // Versions shared with other names move along, so that each subgraph is still
+            // indexed by a single node
+            update(
+                subgraph_deployments::table
+                    .filter(subgraph_deployments::subgraph_id.eq_any(&subgraph_ids)),
+            )
+            .set(subgraph_deployments::node_id.eq(node_id.to_string()))
+            .execute(&*conn)?;
+
+            Ok(!subgraph_ids.is_empty())
+        })
+    }
+
+    fn set_paused(&self, name: SubgraphDeploymentName, paused: bool) -> Result<bool, Error> {
+        use db_schema::subgraph_deployments;
+
+        update(
+            subgraph_deployments::table
+                .filter(subgraph_deployments::deployment_name.eq(name.to_string())),
+        )
+        .set(subgraph_deployments::paused.eq(paused))
+        .execute(&*self.conn.get()?)
+        .map(|row_count| row_count > 0)
+        .map_err(Error::from)
     }
 
     fn deployment_events(
@@ -857,14 +1169,13 @@ impl SubgraphDeploymentStore for Store {
             return Ok(true);
         }
 
-        let deployment_count = subgraph_deployments
+        // A subgraph can be deployed under several names
+        let deployment_count: i64 = subgraph_deployments
             .filter(subgraph_id.eq(id.to_string()))
-            .execute(&*self.conn.get()?)?;
-        Ok(match deployment_count {
-            0 => false,
-            1 => true,
-            _ => unreachable!(), // The `subgraph_id` column is `UNIQUE`
-        })
+            .filter(status.ne(SubgraphVersionStatus::Retired.as_str()))
+            .count()
+            .get_result(&*self.conn.get()?)?;
+        Ok(deployment_count > 0)
     }
 
     fn subgraph_schema(&self, subgraph_id: SubgraphId) -> Result<Schema, Error> {
@@ -1018,6 +1329,32 @@ impl ChainStore for Store {
             .map_err(Error::from)
     }
 
+    fn block_ptrs_in_range(
+        &self,
+        from: u64,
+        to: u64,
+    ) -> Result<Vec<(EthereumBlockPointer, H256)>, Error> {
+        use db_schema::ethereum_blocks::dsl::*;
+
+        ethereum_blocks
+            .select((hash, number, parent_hash))
+            .filter(network_name.eq(&self.network_name))
+            .filter(number.ge(from as i64))
+            .filter(number.le(to as i64))
+            .order(number.asc())
+            .load::<(String, i64, Option<String>)>(&*self.conn.get()?)?
+            .into_iter()
+            .map(|(block_hash, block_number, block_parent_hash)| {
+                let block_ptr: EthereumBlockPointer =
+                    (block_hash.parse::<H256>()?, block_number).into();
+                let block_parent_hash = block_parent_hash
+                    .ok_or_else(|| format_err!("block {} has no parent hash", block_hash))?
+                    .parse::<H256>()?;
+                Ok((block_ptr, block_parent_hash))
+            })
+            .collect()
+    }
+
     fn ancestor_block(
         &self,
         block_ptr: EthereumBlockPointer,
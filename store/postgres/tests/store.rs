@@ -16,6 +16,7 @@ use std::sync::Mutex;
 
 use graph::components::store::{EntityFilter, EntityKey, EntityOrder, EntityQuery, EntityRange};
 use graph::data::store::scalar;
+use graph::data::subgraph::schema::{SubgraphEntity, SUBGRAPHS_ID, SUBGRAPH_ENTITY_TYPENAME};
 use graph::prelude::*;
 use graph::web3::types::H256;
 use graph_store_postgres::{db_schema, Store as DieselStore, StoreConfig};
@@ -224,6 +225,40 @@ fn create_test_entity(
     }
 }
 
+/// Creates an account entity with a decimal balance.
+fn create_account_entity(id: &str, balance: &str) -> EntityOperation {
+    let mut account = Entity::new();
+
+    account.insert("id".to_owned(), Value::String(id.to_owned()));
+    let balance = scalar::BigDecimal::from_str(balance).unwrap();
+    account.insert("balance".to_owned(), Value::BigDecimal(balance));
+
+    EntityOperation::Set {
+        key: EntityKey {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "account".to_owned(),
+            entity_id: id.to_owned(),
+        },
+        data: account,
+    }
+}
+
+/// Inserts accounts with decimal balances in test block 4.
+fn insert_account_data(store: &DieselStore) {
+    store
+        .transact_block_operations(
+            TEST_SUBGRAPH_ID.clone(),
+            *TEST_BLOCK_3_PTR,
+            *TEST_BLOCK_4_PTR,
+            vec![
+                create_account_entity("1", "67.000000000000000001"),
+                create_account_entity("2", "43.000000000000000001"),
+                create_account_entity("3", "28.000000000000000001"),
+            ],
+        )
+        .unwrap();
+}
+
 /// Removes test data from the database behind the store.
 fn remove_test_data() {
     use db_schema::entities;
@@ -303,6 +338,31 @@ fn get_entity() {
     })
 }
 
+#[test]
+fn get_entity_with_big_decimal() {
+    run_test(|store| -> Result<(), ()> {
+        insert_account_data(&store);
+
+        let key = EntityKey {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "account".to_owned(),
+            entity_id: "1".to_owned(),
+        };
+        let result = store.get(key).unwrap();
+
+        // Decimals are returned without losing precision
+        let mut expected_entity = Entity::new();
+        expected_entity.insert("id".to_owned(), "1".into());
+        expected_entity.insert(
+            "balance".to_owned(),
+            Value::BigDecimal(scalar::BigDecimal::from_str("67.000000000000000001").unwrap()),
+        );
+        assert_eq!(result, Some(expected_entity));
+
+        Ok(())
+    })
+}
+
 #[test]
 fn insert_entity() {
     run_test(|store| -> Result<(), ()> {
@@ -459,6 +519,30 @@ fn test_find(expected_entity_ids: Vec<&str>, query: EntityQuery) {
     })
 }
 
+/// Like `test_find`, but for queries on the accounts inserted by `insert_account_data`.
+fn test_find_accounts(expected_entity_ids: Vec<&str>, query: EntityQuery) {
+    let expected_entity_ids: Vec<String> =
+        expected_entity_ids.into_iter().map(str::to_owned).collect();
+
+    run_test(move |store| -> Result<(), ()> {
+        insert_account_data(&store);
+
+        let entity_ids: Vec<_> = store
+            .find(query)
+            .expect("store.find failed to execute query")
+            .into_iter()
+            .map(|entity| match entity.get("id") {
+                Some(Value::String(id)) => id.to_owned(),
+                _ => panic!("store.find returned account without a string ID"),
+            })
+            .collect();
+
+        assert_eq!(entity_ids, expected_entity_ids);
+
+        Ok(())
+    })
+}
+
 #[test]
 fn find_string_contains() {
     test_find(
@@ -999,6 +1083,49 @@ fn find_int_not_in() {
     )
 }
 
+#[test]
+fn find_big_decimal_greater_than() {
+    test_find_accounts(
+        vec!["1"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "account".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::GreaterThan(
+                "balance".to_owned(),
+                Value::BigDecimal(scalar::BigDecimal::from_str("43.000000000000000001").unwrap()),
+            )])),
+            order_by: None,
+            order_direction: None,
+            range: None,
+        },
+    )
+}
+
+#[test]
+fn find_big_decimal_in() {
+    test_find_accounts(
+        vec!["2", "3"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "account".to_owned(),
+            filter: Some(EntityFilter::And(vec![EntityFilter::In(
+                "balance".to_owned(),
+                vec![
+                    Value::BigDecimal(
+                        scalar::BigDecimal::from_str("43.000000000000000001").unwrap(),
+                    ),
+                    Value::BigDecimal(
+                        scalar::BigDecimal::from_str("28.000000000000000001").unwrap(),
+                    ),
+                ],
+            )])),
+            order_by: Some(("id".to_owned(), ValueType::String)),
+            order_direction: Some(EntityOrder::Ascending),
+            range: None,
+        },
+    )
+}
+
 #[test]
 fn find_bool_equal() {
     test_find(
@@ -1167,6 +1294,21 @@ fn find_order_by_int() {
     );
 }
 
+#[test]
+fn find_order_by_big_decimal() {
+    test_find_accounts(
+        vec!["3", "2", "1"],
+        EntityQuery {
+            subgraph_id: TEST_SUBGRAPH_ID.clone(),
+            entity_type: "account".to_owned(),
+            filter: None,
+            order_by: Some(("balance".to_owned(), ValueType::BigDecimal)),
+            order_direction: Some(EntityOrder::Ascending),
+            range: None,
+        },
+    );
+}
+
 #[test]
 fn find_order_by_string() {
     test_find(
@@ -1252,6 +1394,87 @@ fn revert_block() {
     })
 }
 
+#[test]
+fn revert_block_not_at_subgraph_block_pointer() {
+    run_test(|store| -> Result<(), ()> {
+        // The subgraph has processed block 3, so block 2 can't be reverted
+        let result = store.revert_block_operations(
+            TEST_SUBGRAPH_ID.clone(),
+            *TEST_BLOCK_2_PTR,
+            *TEST_BLOCK_1_PTR,
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            store.block_ptr(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            *TEST_BLOCK_3_PTR
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn reset_subgraph() {
+    run_test(|store| -> Result<(), ()> {
+        // The subgraph failed after processing some blocks
+        let mut ops =
+            SubgraphEntity::write_ethereum_block_counts_operations(&TEST_SUBGRAPH_ID, 3, 5);
+        ops.append(&mut SubgraphEntity::write_failure_operations(
+            &TEST_SUBGRAPH_ID,
+            SubgraphError::new("mapping aborted", true),
+        ));
+        store
+            .apply_entity_operations(ops, EventSource::None)
+            .unwrap();
+
+        store
+            .reset_subgraph(TEST_SUBGRAPH_ID.clone(), *TEST_BLOCK_0_PTR)
+            .unwrap();
+
+        // All entities are gone and the subgraph starts over from the given block
+        assert_eq!(store.count_entities(TEST_SUBGRAPH_ID.clone()).unwrap(), 0);
+        assert_eq!(
+            store.block_ptr(TEST_SUBGRAPH_ID.clone()).unwrap(),
+            *TEST_BLOCK_0_PTR
+        );
+
+        // Its progress and failure are reset as well
+        let subgraph_entity = store
+            .get(EntityKey {
+                subgraph_id: SUBGRAPHS_ID.clone(),
+                entity_type: SUBGRAPH_ENTITY_TYPENAME.to_owned(),
+                entity_id: TEST_SUBGRAPH_ID.to_string(),
+            })
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            subgraph_entity.get("status"),
+            Some(&Value::from(SubgraphStatus::Syncing.to_string()))
+        );
+        assert_eq!(
+            subgraph_entity.get("processedEthereumBlocksCount"),
+            Some(&Value::from(0 as u64))
+        );
+        assert_eq!(
+            subgraph_entity.get("totalEthereumBlocksCount"),
+            Some(&Value::from(0 as u64))
+        );
+        assert_eq!(subgraph_entity.get("fatalError"), None);
+
+        // Reverting blocks can not bring back the removed entities
+        assert!(store
+            .revert_block_operations(
+                TEST_SUBGRAPH_ID.clone(),
+                *TEST_BLOCK_3_PTR,
+                *TEST_BLOCK_2_PTR
+            )
+            .is_err());
+        assert_eq!(store.count_entities(TEST_SUBGRAPH_ID.clone()).unwrap(), 0);
+
+        Ok(())
+    })
+}
+
 #[test]
 fn revert_block_with_delete() {
     run_test(|store| -> Result<(), ()> {
@@ -1525,13 +1748,240 @@ fn write_and_read_deployments() {
             store.read(name2.clone()).unwrap(),
             Some((subgraph_id2.clone(), node_id.clone()))
         );
+        // A new version stays pending until it is promoted
         store
             .write(name1.clone(), subgraph_id3.clone(), node_id.clone())
             .unwrap();
         assert_eq!(
             store.read(name1.clone()).unwrap(),
-            Some((subgraph_id3.clone(), node_id.clone()))
+            Some((subgraph_id1.clone(), node_id.clone()))
+        );
+        assert_eq!(
+            version_statuses(&store, name1.clone()),
+            vec![
+                (subgraph_id1.clone(), SubgraphVersionStatus::Current),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Pending),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        Ok(())
+    })
+}
+
+/// Returns the subgraph IDs and statuses of all versions of a name.
+fn version_statuses(
+    store: &DieselStore,
+    name: SubgraphDeploymentName,
+) -> HashSet<(SubgraphId, SubgraphVersionStatus)> {
+    store
+        .read_versions(name)
+        .unwrap()
+        .into_iter()
+        .map(|version| (version.subgraph_id, version.status))
+        .collect()
+}
+
+#[test]
+fn write_pending_versions() {
+    run_test(|store| -> Result<(), ()> {
+        let name = SubgraphDeploymentName::new("name1").unwrap();
+        let subgraph_id1 = SubgraphId::new("mysubgraph").unwrap();
+        let subgraph_id2 = SubgraphId::new("mysubgraph2").unwrap();
+        let subgraph_id3 = SubgraphId::new("mysubgraph3").unwrap();
+        let node_id = NodeId::new("thisnode").unwrap();
+
+        store
+            .write(name.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name.clone(), subgraph_id2.clone(), node_id.clone())
+            .unwrap();
+
+        // A newer pending version replaces the previous one
+        store
+            .write(name.clone(), subgraph_id3.clone(), node_id.clone())
+            .unwrap();
+        assert_eq!(
+            version_statuses(&store, name.clone()),
+            vec![
+                (subgraph_id1.clone(), SubgraphVersionStatus::Current),
+                (subgraph_id2.clone(), SubgraphVersionStatus::Retired),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Pending),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+
+        // Redeploying the current version keeps it current
+        store
+            .write(name.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+        assert_eq!(
+            store.read(name.clone()).unwrap(),
+            Some((subgraph_id1.clone(), node_id.clone()))
+        );
+
+        // Redeploying a retired version makes it pending again
+        store
+            .write(name.clone(), subgraph_id2.clone(), node_id.clone())
+            .unwrap();
+        assert_eq!(
+            version_statuses(&store, name.clone()),
+            vec![
+                (subgraph_id1.clone(), SubgraphVersionStatus::Current),
+                (subgraph_id2.clone(), SubgraphVersionStatus::Pending),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Retired),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+
+        Ok(())
+    })
+}
+
+#[test]
+fn write_subgraph_under_several_names() {
+    run_test(|store| -> Result<(), ()> {
+        let name1 = SubgraphDeploymentName::new("name1").unwrap();
+        let name2 = SubgraphDeploymentName::new("name2").unwrap();
+        let subgraph_id = SubgraphId::new("mysubgraph").unwrap();
+        let node_id1 = NodeId::new("thisnode").unwrap();
+        let node_id2 = NodeId::new("othernode").unwrap();
+
+        store
+            .write(name1.clone(), subgraph_id.clone(), node_id1.clone())
+            .unwrap();
+        store
+            .write(name2.clone(), subgraph_id.clone(), node_id2.clone())
+            .unwrap();
+
+        // Both names point to the subgraph, which is indexed by the node it was
+        // deployed to last
+        assert_eq!(
+            store.read(name1.clone()).unwrap(),
+            Some((subgraph_id.clone(), node_id2.clone()))
+        );
+        assert_eq!(
+            store.read(name2.clone()).unwrap(),
+            Some((subgraph_id.clone(), node_id2.clone()))
         );
+        assert_eq!(store.read_by_node_id(node_id1.clone()).unwrap(), vec![]);
+        assert!(store.is_deployed(&subgraph_id).unwrap());
+
+        // Removing one name keeps the subgraph deployed under the other
+        assert!(store.remove(name1.clone()).unwrap());
+        assert_eq!(
+            store.read_by_node_id(node_id2.clone()).unwrap(),
+            vec![(name2.clone(), subgraph_id.clone())]
+        );
+        assert!(store.is_deployed(&subgraph_id).unwrap());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn promote_pending_versions() {
+    run_test(|store| -> Result<(), ()> {
+        let name1 = SubgraphDeploymentName::new("name1").unwrap();
+        let name2 = SubgraphDeploymentName::new("name2").unwrap();
+        let subgraph_id1 = SubgraphId::new("mysubgraph").unwrap();
+        let subgraph_id2 = SubgraphId::new("mysubgraph2").unwrap();
+        let subgraph_id3 = SubgraphId::new("mysubgraph3").unwrap();
+        let node_id = NodeId::new("thisnode").unwrap();
+
+        store
+            .write(name1.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name2.clone(), subgraph_id2.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name1.clone(), subgraph_id3.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name2.clone(), subgraph_id3.clone(), node_id.clone())
+            .unwrap();
+
+        // Only pending versions are promoted
+        assert!(!store.promote_pending_version(&subgraph_id1).unwrap());
+
+        // The pending version of both names is promoted at once
+        assert!(store.promote_pending_version(&subgraph_id3).unwrap());
+        assert_eq!(
+            version_statuses(&store, name1.clone()),
+            vec![
+                (subgraph_id1.clone(), SubgraphVersionStatus::Retired),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Current),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        assert_eq!(
+            version_statuses(&store, name2.clone()),
+            vec![
+                (subgraph_id2.clone(), SubgraphVersionStatus::Retired),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Current),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+        assert!(!store.promote_pending_version(&subgraph_id3).unwrap());
+
+        Ok(())
+    })
+}
+
+#[test]
+fn rollback_to_retired_version() {
+    run_test(|store| -> Result<(), ()> {
+        let name1 = SubgraphDeploymentName::new("name1").unwrap();
+        let name2 = SubgraphDeploymentName::new("name2").unwrap();
+        let subgraph_id1 = SubgraphId::new("mysubgraph").unwrap();
+        let subgraph_id2 = SubgraphId::new("mysubgraph2").unwrap();
+        let subgraph_id3 = SubgraphId::new("mysubgraph3").unwrap();
+        let node_id = NodeId::new("thisnode").unwrap();
+
+        store
+            .write(name1.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name1.clone(), subgraph_id2.clone(), node_id.clone())
+            .unwrap();
+        store.promote_pending_version(&subgraph_id2).unwrap();
+        store
+            .write(name1.clone(), subgraph_id3.clone(), node_id.clone())
+            .unwrap();
+        store
+            .write(name2.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+
+        // Versions of other names can't be rolled back to
+        assert!(!store.rollback(name2.clone(), subgraph_id2.clone()).unwrap());
+
+        // Rolling back retires the current and pending versions
+        assert!(store.rollback(name1.clone(), subgraph_id1.clone()).unwrap());
+        assert_eq!(
+            version_statuses(&store, name1.clone()),
+            vec![
+                (subgraph_id1.clone(), SubgraphVersionStatus::Current),
+                (subgraph_id2.clone(), SubgraphVersionStatus::Retired),
+                (subgraph_id3.clone(), SubgraphVersionStatus::Retired),
+            ]
+            .into_iter()
+            .collect::<HashSet<_>>()
+        );
+
+        // Other names deploying the same subgraph are unaffected
+        assert_eq!(
+            version_statuses(&store, name2.clone()),
+            vec![(subgraph_id1.clone(), SubgraphVersionStatus::Current)]
+                .into_iter()
+                .collect::<HashSet<_>>()
+        );
+
         Ok(())
     })
 }
@@ -1632,3 +2082,56 @@ fn receive_deployment_events() {
             })
     })
 }
+
+#[test]
+fn pause_and_resume_deployments() {
+    run_test(|store| {
+        let name1 = SubgraphDeploymentName::new("name1").unwrap();
+        let name2 = SubgraphDeploymentName::new("name2").unwrap();
+        let subgraph_id1 = SubgraphId::new("mysubgraph").unwrap();
+        let node_id = NodeId::new("thisnode").unwrap();
+
+        let event_stream = store.deployment_events(node_id.clone());
+
+        store
+            .write(name1.clone(), subgraph_id1.clone(), node_id.clone())
+            .unwrap();
+        assert!(store.set_paused(name1.clone(), true).unwrap());
+        assert!(!store.set_paused(name2.clone(), true).unwrap());
+
+        // Paused versions are still deployed, but no longer indexed
+        assert_eq!(
+            store.read_by_node_id(node_id.clone()).unwrap(),
+            vec![(name1.clone(), subgraph_id1.clone())]
+        );
+        assert_eq!(
+            store
+                .read_versions(name1.clone())
+                .unwrap()
+                .into_iter()
+                .map(|version| (version.paused, version.is_indexed()))
+                .collect::<Vec<_>>(),
+            vec![(true, false)]
+        );
+
+        // Pausing twice changes nothing
+        assert!(store.set_paused(name1.clone(), true).unwrap());
+        assert!(store.set_paused(name1.clone(), false).unwrap());
+        assert!(store.read_versions(name1.clone()).unwrap()[0].is_indexed());
+
+        let add_event = DeploymentEvent::Add {
+            deployment_name: name1.clone(),
+            subgraph_id: subgraph_id1.clone(),
+            node_id: node_id.clone(),
+        };
+        let remove_event = DeploymentEvent::Remove {
+            deployment_name: name1.clone(),
+            subgraph_id: subgraph_id1.clone(),
+            node_id: node_id.clone(),
+        };
+        event_stream.take(3).collect().and_then(move |events| {
+            assert_eq!(events, vec![add_event.clone(), remove_event, add_event]);
+            Ok(())
+        })
+    })
+}